@@ -0,0 +1,1123 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::cell::{Cell, RefCell};
+
+use crate::{
+    BorrowError, Content, Context, Error, Extend, FillRule, Filter, FontSlant, FontWeight,
+    ImageSurface, LineCap, LineJoin, LinearGradient, Matrix, Operator, Pattern, RadialGradient,
+    RecordingSurface, Rectangle, SolidPattern, SurfacePattern,
+};
+
+// rustdoc-stripper-ignore-next
+/// A color stop of a gradient [`DisplaySource`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorStop {
+    pub offset: f64,
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+    pub alpha: f64,
+}
+
+// rustdoc-stripper-ignore-next
+/// A source recorded in a [`DisplayList`].
+///
+/// Matrices are pattern matrices, stored as `[xx, yx, xy, yy, x0, y0]`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DisplaySource {
+    Solid {
+        red: f64,
+        green: f64,
+        blue: f64,
+        alpha: f64,
+    },
+    Linear {
+        x0: f64,
+        y0: f64,
+        x1: f64,
+        y1: f64,
+        stops: Vec<ColorStop>,
+        extend: Extend,
+        matrix: [f64; 6],
+    },
+    Radial {
+        x0: f64,
+        y0: f64,
+        r0: f64,
+        x1: f64,
+        y1: f64,
+        r1: f64,
+        stops: Vec<ColorStop>,
+        extend: Extend,
+        matrix: [f64; 6],
+    },
+    // rustdoc-stripper-ignore-next
+    /// An image, as tightly packed RGBA pixels with straight alpha.
+    Image {
+        width: i32,
+        height: i32,
+        rgba: Vec<u8>,
+        extend: Extend,
+        filter: Filter,
+        matrix: [f64; 6],
+    },
+}
+
+impl DisplaySource {
+    // rustdoc-stripper-ignore-next
+    /// Captures a solid, gradient or surface pattern.
+    ///
+    /// Surface patterns are only supported for image surfaces, whose pixels are copied. Fails
+    /// with [`Error::PatternTypeMismatch`] for other pattern types and with
+    /// [`Error::SurfaceTypeMismatch`] for other surfaces.
+    pub fn from_pattern(pattern: impl AsRef<Pattern>) -> Result<Self, Error> {
+        let pattern = pattern.as_ref();
+        pattern.status()?;
+        let extend = pattern.extend();
+        let matrix = matrix_to_array(&pattern.matrix());
+
+        if let Ok(solid) = SolidPattern::try_from(pattern.clone()) {
+            let (red, green, blue, alpha) = solid.rgba()?;
+            return Ok(Self::Solid {
+                red,
+                green,
+                blue,
+                alpha,
+            });
+        }
+        if let Ok(linear) = LinearGradient::try_from(pattern.clone()) {
+            let (x0, y0, x1, y1) = linear.linear_points()?;
+            return Ok(Self::Linear {
+                x0,
+                y0,
+                x1,
+                y1,
+                stops: color_stops(&linear)?,
+                extend,
+                matrix,
+            });
+        }
+        if let Ok(radial) = RadialGradient::try_from(pattern.clone()) {
+            let (x0, y0, r0, x1, y1, r1) = radial.radial_circles()?;
+            return Ok(Self::Radial {
+                x0,
+                y0,
+                r0,
+                x1,
+                y1,
+                r1,
+                stops: color_stops(&radial)?,
+                extend,
+                matrix,
+            });
+        }
+        if let Ok(surface) = SurfacePattern::try_from(pattern.clone()) {
+            let image = ImageSurface::try_from(surface.surface()?)
+                .map_err(|_| Error::SurfaceTypeMismatch)?;
+            let rgba = image.to_rgba8_unpremultiplied().map_err(|err| match err {
+                BorrowError::Cairo(err) => err,
+                BorrowError::NonExclusive => unreachable!(),
+            })?;
+            return Ok(Self::Image {
+                width: image.width(),
+                height: image.height(),
+                rgba,
+                extend,
+                filter: pattern.filter(),
+                matrix,
+            });
+        }
+        Err(Error::PatternTypeMismatch)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a cairo pattern for this source.
+    pub fn to_pattern(&self) -> Result<Pattern, Error> {
+        let pattern: Pattern = match self {
+            Self::Solid {
+                red,
+                green,
+                blue,
+                alpha,
+            } => SolidPattern::from_rgba(*red, *green, *blue, *alpha)
+                .as_ref()
+                .clone(),
+            Self::Linear {
+                x0,
+                y0,
+                x1,
+                y1,
+                stops,
+                ..
+            } => {
+                let linear = LinearGradient::new(*x0, *y0, *x1, *y1);
+                add_color_stops(&linear, stops);
+                AsRef::<Pattern>::as_ref(&linear).clone()
+            }
+            Self::Radial {
+                x0,
+                y0,
+                r0,
+                x1,
+                y1,
+                r1,
+                stops,
+                ..
+            } => {
+                let radial = RadialGradient::new(*x0, *y0, *r0, *x1, *y1, *r1);
+                add_color_stops(&radial, stops);
+                AsRef::<Pattern>::as_ref(&radial).clone()
+            }
+            Self::Image {
+                width,
+                height,
+                rgba,
+                filter,
+                ..
+            } => {
+                let image = ImageSurface::from_rgba8_straight(*width, *height, rgba)?;
+                let pattern = SurfacePattern::create(&image);
+                pattern.set_filter(*filter);
+                pattern.as_ref().clone()
+            }
+        };
+        if let Self::Linear { extend, matrix, .. }
+        | Self::Radial { extend, matrix, .. }
+        | Self::Image { extend, matrix, .. } = self
+        {
+            pattern.set_extend(*extend);
+            pattern.set_matrix(matrix_from_array(matrix));
+        }
+        pattern.status()?;
+        Ok(pattern)
+    }
+}
+
+fn color_stops(gradient: &crate::Gradient) -> Result<Vec<ColorStop>, Error> {
+    (0..gradient.color_stop_count()?)
+        .map(|index| {
+            let (offset, red, green, blue, alpha) = gradient.color_stop_rgba(index)?;
+            Ok(ColorStop {
+                offset,
+                red,
+                green,
+                blue,
+                alpha,
+            })
+        })
+        .collect()
+}
+
+fn add_color_stops(gradient: &crate::Gradient, stops: &[ColorStop]) {
+    for stop in stops {
+        gradient.add_color_stop_rgba(stop.offset, stop.red, stop.green, stop.blue, stop.alpha);
+    }
+}
+
+fn matrix_to_array(matrix: &Matrix) -> [f64; 6] {
+    [
+        matrix.xx(),
+        matrix.yx(),
+        matrix.xy(),
+        matrix.yy(),
+        matrix.x0(),
+        matrix.y0(),
+    ]
+}
+
+fn matrix_from_array(m: &[f64; 6]) -> Matrix {
+    Matrix::new(m[0], m[1], m[2], m[3], m[4], m[5])
+}
+
+// rustdoc-stripper-ignore-next
+/// An operation recorded in a [`DisplayList`], corresponding to the [`Context`] method of the
+/// same name.
+///
+/// Matrices are stored as `[xx, yx, xy, yy, x0, y0]`. [`SetMatrix`](Self::SetMatrix) and
+/// [`IdentityMatrix`](Self::IdentityMatrix) are relative to the transformation of the context
+/// the list is replayed onto.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DisplayOp {
+    Save,
+    Restore,
+
+    Translate {
+        tx: f64,
+        ty: f64,
+    },
+    Scale {
+        sx: f64,
+        sy: f64,
+    },
+    Rotate {
+        angle: f64,
+    },
+    Transform([f64; 6]),
+    SetMatrix([f64; 6]),
+    IdentityMatrix,
+
+    NewPath,
+    NewSubPath,
+    ClosePath,
+    MoveTo {
+        x: f64,
+        y: f64,
+    },
+    LineTo {
+        x: f64,
+        y: f64,
+    },
+    CurveTo {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x3: f64,
+        y3: f64,
+    },
+    RelMoveTo {
+        dx: f64,
+        dy: f64,
+    },
+    RelLineTo {
+        dx: f64,
+        dy: f64,
+    },
+    RelCurveTo {
+        dx1: f64,
+        dy1: f64,
+        dx2: f64,
+        dy2: f64,
+        dx3: f64,
+        dy3: f64,
+    },
+    Arc {
+        xc: f64,
+        yc: f64,
+        radius: f64,
+        angle1: f64,
+        angle2: f64,
+    },
+    ArcNegative {
+        xc: f64,
+        yc: f64,
+        radius: f64,
+        angle1: f64,
+        angle2: f64,
+    },
+    Rectangle {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+
+    SetSource(DisplaySource),
+    SetOperator(Operator),
+    SetTolerance(f64),
+    SetFillRule(FillRule),
+    SetLineWidth(f64),
+    SetLineCap(LineCap),
+    SetLineJoin(LineJoin),
+    SetMiterLimit(f64),
+    SetDash {
+        dashes: Vec<f64>,
+        offset: f64,
+    },
+
+    Clip,
+    ClipPreserve,
+    ResetClip,
+    Fill,
+    FillPreserve,
+    Stroke,
+    StrokePreserve,
+    Paint,
+    PaintWithAlpha(f64),
+
+    SelectFontFace {
+        family: String,
+        slant: FontSlant,
+        weight: FontWeight,
+    },
+    SetFontSize(f64),
+    ShowText(String),
+}
+
+impl DisplayOp {
+    // rustdoc-stripper-ignore-next
+    /// Returns `true` for operations that draw, and are therefore reported by
+    /// [`DisplayList::hit_test()`].
+    pub fn is_drawing(&self) -> bool {
+        matches!(
+            self,
+            Self::Fill
+                | Self::FillPreserve
+                | Self::Stroke
+                | Self::StrokePreserve
+                | Self::Paint
+                | Self::PaintWithAlpha(_)
+                | Self::ShowText(_)
+        )
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A list of drawing operations, recorded with a [`DisplayListRecorder`] or built by hand.
+///
+/// Unlike a [`RecordingSurface`], the operations can be inspected, hit-tested, and with the
+/// `serde` feature serialized.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct DisplayList {
+    ops: Vec<DisplayOp>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ops(&self) -> &[DisplayOp] {
+        &self.ops
+    }
+
+    pub fn push(&mut self, op: DisplayOp) {
+        self.ops.push(op);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Replays the operations onto `cr`, relative to its current transformation.
+    ///
+    /// The state of `cr` is saved before and restored after replaying, so the operations can't
+    /// change it, except for the current path. Fails with [`Error::InvalidRestore`] if the list
+    /// restores more states than it saved.
+    pub fn replay(&self, cr: &Context) -> Result<(), Error> {
+        cr.save()?;
+        let mut replay = Replay::new(cr, None);
+        let result = self
+            .ops
+            .iter()
+            .enumerate()
+            .try_for_each(|(index, op)| replay.apply(index, op));
+        for _ in 0..replay.depth {
+            cr.restore()?;
+        }
+        cr.restore()?;
+        result
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the area covered by drawing operations, or `None` if nothing is drawn.
+    ///
+    /// The bounds are computed by replaying the list onto a [`RecordingSurface`], and are
+    /// those of its ink extents.
+    pub fn bounds(&self) -> Result<Option<Rectangle>, Error> {
+        let recording = RecordingSurface::create(Content::ColorAlpha, None)?;
+        let cr = Context::new(&recording)?;
+        self.replay(&cr)?;
+        drop(cr);
+        recording.status()?;
+        let (x, y, width, height) = recording.ink_extents();
+        Ok((width > 0.0 && height > 0.0).then(|| Rectangle::new(x, y, width, height)))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the indices of the drawing operations that cover the point, in drawing order,
+    /// so the topmost one comes last.
+    ///
+    /// Fills and strokes are tested with [`Context::in_fill()`] and [`Context::in_stroke()`],
+    /// paints cover everything inside the clip, and text is tested against its outlines. Drawn
+    /// colors, including transparency, are not taken into account.
+    pub fn hit_test(&self, x: f64, y: f64) -> Result<Vec<usize>, Error> {
+        let scratch = ImageSurface::create(crate::Format::A8, 1, 1)?;
+        let cr = Context::new(&scratch)?;
+        let mut replay = Replay::new(
+            &cr,
+            Some(HitTest {
+                x,
+                y,
+                hits: Vec::new(),
+            }),
+        );
+        for (index, op) in self.ops.iter().enumerate() {
+            replay.apply(index, op)?;
+        }
+        Ok(replay
+            .hit_test
+            .map(|hit_test| hit_test.hits)
+            .unwrap_or_default())
+    }
+}
+
+impl From<Vec<DisplayOp>> for DisplayList {
+    fn from(ops: Vec<DisplayOp>) -> Self {
+        Self { ops }
+    }
+}
+
+impl FromIterator<DisplayOp> for DisplayList {
+    fn from_iter<T: IntoIterator<Item = DisplayOp>>(iter: T) -> Self {
+        Self {
+            ops: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for DisplayList {
+    type Item = DisplayOp;
+    type IntoIter = std::vec::IntoIter<DisplayOp>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.into_iter()
+    }
+}
+
+struct HitTest {
+    // In the space of the list, which is the device space of the scratch context
+    x: f64,
+    y: f64,
+    hits: Vec<usize>,
+}
+
+// Applies operations to a context. When hit-testing, drawing operations are tested against the
+// point instead of being drawn, and sources are ignored.
+struct Replay<'a> {
+    cr: &'a Context,
+    base: Matrix,
+    depth: usize,
+    hit_test: Option<HitTest>,
+}
+
+impl<'a> Replay<'a> {
+    fn new(cr: &'a Context, hit_test: Option<HitTest>) -> Self {
+        Self {
+            cr,
+            base: cr.matrix(),
+            depth: 0,
+            hit_test,
+        }
+    }
+
+    fn apply(&mut self, index: usize, op: &DisplayOp) -> Result<(), Error> {
+        let cr = self.cr;
+        if let Some(hit_test) = &self.hit_test
+            && op.is_drawing()
+        {
+            let (x, y) = cr.device_to_user(hit_test.x, hit_test.y)?;
+            let hit = match op {
+                DisplayOp::Fill | DisplayOp::FillPreserve => cr.in_fill(x, y)?,
+                DisplayOp::Stroke | DisplayOp::StrokePreserve => cr.in_stroke(x, y)?,
+                DisplayOp::ShowText(text) => self.text_hit(text, x, y)?,
+                _ => true,
+            } && cr.in_clip(x, y)?;
+            if matches!(op, DisplayOp::Fill | DisplayOp::Stroke) {
+                cr.new_path();
+            }
+            if hit && let Some(hit_test) = &mut self.hit_test {
+                hit_test.hits.push(index);
+            }
+            return Ok(());
+        }
+
+        match op {
+            DisplayOp::Save => {
+                cr.save()?;
+                self.depth += 1;
+            }
+            DisplayOp::Restore => {
+                if self.depth == 0 {
+                    return Err(Error::InvalidRestore);
+                }
+                cr.restore()?;
+                self.depth -= 1;
+            }
+
+            DisplayOp::Translate { tx, ty } => cr.translate(*tx, *ty),
+            DisplayOp::Scale { sx, sy } => cr.scale(*sx, *sy),
+            DisplayOp::Rotate { angle } => cr.rotate(*angle),
+            DisplayOp::Transform(matrix) => cr.transform(matrix_from_array(matrix)),
+            DisplayOp::SetMatrix(matrix) => {
+                cr.set_matrix(Matrix::multiply(&matrix_from_array(matrix), &self.base))
+            }
+            DisplayOp::IdentityMatrix => cr.set_matrix(self.base),
+
+            DisplayOp::NewPath => cr.new_path(),
+            DisplayOp::NewSubPath => cr.new_sub_path(),
+            DisplayOp::ClosePath => cr.close_path(),
+            DisplayOp::MoveTo { x, y } => cr.move_to(*x, *y),
+            DisplayOp::LineTo { x, y } => cr.line_to(*x, *y),
+            DisplayOp::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+            } => cr.curve_to(*x1, *y1, *x2, *y2, *x3, *y3),
+            DisplayOp::RelMoveTo { dx, dy } => cr.rel_move_to(*dx, *dy),
+            DisplayOp::RelLineTo { dx, dy } => cr.rel_line_to(*dx, *dy),
+            DisplayOp::RelCurveTo {
+                dx1,
+                dy1,
+                dx2,
+                dy2,
+                dx3,
+                dy3,
+            } => cr.rel_curve_to(*dx1, *dy1, *dx2, *dy2, *dx3, *dy3),
+            DisplayOp::Arc {
+                xc,
+                yc,
+                radius,
+                angle1,
+                angle2,
+            } => cr.arc(*xc, *yc, *radius, *angle1, *angle2),
+            DisplayOp::ArcNegative {
+                xc,
+                yc,
+                radius,
+                angle1,
+                angle2,
+            } => cr.arc_negative(*xc, *yc, *radius, *angle1, *angle2),
+            DisplayOp::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => cr.rectangle(*x, *y, *width, *height),
+
+            DisplayOp::SetSource(source) => {
+                if self.hit_test.is_none() {
+                    cr.set_source(source.to_pattern()?)?;
+                }
+            }
+            DisplayOp::SetOperator(operator) => cr.set_operator(*operator),
+            DisplayOp::SetTolerance(tolerance) => cr.set_tolerance(*tolerance),
+            DisplayOp::SetFillRule(fill_rule) => cr.set_fill_rule(*fill_rule),
+            DisplayOp::SetLineWidth(width) => cr.set_line_width(*width),
+            DisplayOp::SetLineCap(cap) => cr.set_line_cap(*cap),
+            DisplayOp::SetLineJoin(join) => cr.set_line_join(*join),
+            DisplayOp::SetMiterLimit(limit) => cr.set_miter_limit(*limit),
+            DisplayOp::SetDash { dashes, offset } => cr.set_dash(dashes, *offset),
+
+            DisplayOp::Clip => cr.clip(),
+            DisplayOp::ClipPreserve => cr.clip_preserve(),
+            DisplayOp::ResetClip => cr.reset_clip(),
+            DisplayOp::Fill => cr.fill()?,
+            DisplayOp::FillPreserve => cr.fill_preserve()?,
+            DisplayOp::Stroke => cr.stroke()?,
+            DisplayOp::StrokePreserve => cr.stroke_preserve()?,
+            DisplayOp::Paint => cr.paint()?,
+            DisplayOp::PaintWithAlpha(alpha) => cr.paint_with_alpha(*alpha)?,
+
+            DisplayOp::SelectFontFace {
+                family,
+                slant,
+                weight,
+            } => cr.select_font_face(family, *slant, *weight),
+            DisplayOp::SetFontSize(size) => cr.set_font_size(*size),
+            DisplayOp::ShowText(text) => cr.show_text(text)?,
+        }
+        cr.status()
+    }
+
+    // Tests the point against the outlines of the text, and moves the current point past the
+    // text like `show_text()` does, leaving the path otherwise untouched.
+    fn text_hit(&self, text: &str, x: f64, y: f64) -> Result<bool, Error> {
+        let cr = self.cr;
+        let path = cr.copy_path()?;
+        let (start_x, start_y) = if cr.has_current_point()? {
+            cr.current_point()?
+        } else {
+            (0.0, 0.0)
+        };
+
+        cr.new_path();
+        cr.move_to(start_x, start_y);
+        cr.text_path(text);
+        cr.save()?;
+        cr.set_fill_rule(FillRule::Winding);
+        let hit = cr.in_fill(x, y)?;
+        cr.restore()?;
+        let (end_x, end_y) = cr.current_point()?;
+
+        cr.new_path();
+        cr.append_path(&path);
+        cr.move_to(end_x, end_y);
+        Ok(hit)
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// Draws onto a [`Context`] while recording the operations into a [`DisplayList`].
+///
+/// The methods correspond to the [`Context`] methods of the same name. Operations done directly
+/// on the context are not recorded.
+#[derive(Debug)]
+pub struct DisplayListRecorder {
+    cr: Context,
+    base: Matrix,
+    depth: Cell<usize>,
+    list: RefCell<DisplayList>,
+}
+
+impl DisplayListRecorder {
+    // rustdoc-stripper-ignore-next
+    /// Creates a recorder drawing onto `cr`. The recorded operations are relative to the current
+    /// transformation of `cr`.
+    pub fn new(cr: &Context) -> Self {
+        Self {
+            cr: cr.clone(),
+            base: cr.matrix(),
+            depth: Cell::new(0),
+            list: RefCell::new(DisplayList::new()),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the context, for example to query the current point or path.
+    pub fn context(&self) -> &Context {
+        &self.cr
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a copy of the operations recorded so far.
+    pub fn display_list(&self) -> DisplayList {
+        self.list.borrow().clone()
+    }
+
+    pub fn finish(self) -> DisplayList {
+        self.list.into_inner()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Applies `op` to the context, and records it if it succeeds.
+    pub fn record(&self, op: DisplayOp) -> Result<(), Error> {
+        let mut replay = Replay {
+            cr: &self.cr,
+            base: self.base,
+            depth: self.depth.get(),
+            hit_test: None,
+        };
+        let index = self.list.borrow().len();
+        replay.apply(index, &op)?;
+        self.depth.set(replay.depth);
+        self.list.borrow_mut().push(op);
+        Ok(())
+    }
+
+    // Records an operation that only fails if the context is already in an error state.
+    fn push(&self, op: DisplayOp) {
+        let _ = self.record(op);
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        self.record(DisplayOp::Save)
+    }
+
+    pub fn restore(&self) -> Result<(), Error> {
+        self.record(DisplayOp::Restore)
+    }
+
+    pub fn translate(&self, tx: f64, ty: f64) {
+        self.push(DisplayOp::Translate { tx, ty });
+    }
+
+    pub fn scale(&self, sx: f64, sy: f64) {
+        self.push(DisplayOp::Scale { sx, sy });
+    }
+
+    pub fn rotate(&self, angle: f64) {
+        self.push(DisplayOp::Rotate { angle });
+    }
+
+    pub fn transform(&self, matrix: Matrix) {
+        self.push(DisplayOp::Transform(matrix_to_array(&matrix)));
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the transformation relative to the one of the context when the recorder was
+    /// created.
+    pub fn set_matrix(&self, matrix: Matrix) {
+        self.push(DisplayOp::SetMatrix(matrix_to_array(&matrix)));
+    }
+
+    pub fn identity_matrix(&self) {
+        self.push(DisplayOp::IdentityMatrix);
+    }
+
+    pub fn new_path(&self) {
+        self.push(DisplayOp::NewPath);
+    }
+
+    pub fn new_sub_path(&self) {
+        self.push(DisplayOp::NewSubPath);
+    }
+
+    pub fn close_path(&self) {
+        self.push(DisplayOp::ClosePath);
+    }
+
+    pub fn move_to(&self, x: f64, y: f64) {
+        self.push(DisplayOp::MoveTo { x, y });
+    }
+
+    pub fn line_to(&self, x: f64, y: f64) {
+        self.push(DisplayOp::LineTo { x, y });
+    }
+
+    pub fn curve_to(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) {
+        self.push(DisplayOp::CurveTo {
+            x1,
+            y1,
+            x2,
+            y2,
+            x3,
+            y3,
+        });
+    }
+
+    pub fn rel_move_to(&self, dx: f64, dy: f64) {
+        self.push(DisplayOp::RelMoveTo { dx, dy });
+    }
+
+    pub fn rel_line_to(&self, dx: f64, dy: f64) {
+        self.push(DisplayOp::RelLineTo { dx, dy });
+    }
+
+    pub fn rel_curve_to(&self, dx1: f64, dy1: f64, dx2: f64, dy2: f64, dx3: f64, dy3: f64) {
+        self.push(DisplayOp::RelCurveTo {
+            dx1,
+            dy1,
+            dx2,
+            dy2,
+            dx3,
+            dy3,
+        });
+    }
+
+    pub fn arc(&self, xc: f64, yc: f64, radius: f64, angle1: f64, angle2: f64) {
+        self.push(DisplayOp::Arc {
+            xc,
+            yc,
+            radius,
+            angle1,
+            angle2,
+        });
+    }
+
+    pub fn arc_negative(&self, xc: f64, yc: f64, radius: f64, angle1: f64, angle2: f64) {
+        self.push(DisplayOp::ArcNegative {
+            xc,
+            yc,
+            radius,
+            angle1,
+            angle2,
+        });
+    }
+
+    pub fn rectangle(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.push(DisplayOp::Rectangle {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    pub fn set_source_rgb(&self, red: f64, green: f64, blue: f64) {
+        self.set_source_rgba(red, green, blue, 1.0);
+    }
+
+    pub fn set_source_rgba(&self, red: f64, green: f64, blue: f64, alpha: f64) {
+        self.push(DisplayOp::SetSource(DisplaySource::Solid {
+            red,
+            green,
+            blue,
+            alpha,
+        }));
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets a solid, gradient or image surface pattern as source.
+    ///
+    /// See [`DisplaySource::from_pattern()`] for the supported patterns.
+    pub fn set_source(&self, source: impl AsRef<Pattern>) -> Result<(), Error> {
+        self.record(DisplayOp::SetSource(DisplaySource::from_pattern(source)?))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets an image surface as source, with its origin at `x`, `y`. The pixels are copied.
+    pub fn set_source_surface(&self, surface: &ImageSurface, x: f64, y: f64) -> Result<(), Error> {
+        let pattern = SurfacePattern::create(surface);
+        let mut matrix = Matrix::identity();
+        matrix.translate(-x, -y);
+        pattern.set_matrix(matrix);
+        self.set_source(&pattern)
+    }
+
+    pub fn set_operator(&self, op: Operator) {
+        self.push(DisplayOp::SetOperator(op));
+    }
+
+    pub fn set_tolerance(&self, tolerance: f64) {
+        self.push(DisplayOp::SetTolerance(tolerance));
+    }
+
+    pub fn set_fill_rule(&self, fill_rule: FillRule) {
+        self.push(DisplayOp::SetFillRule(fill_rule));
+    }
+
+    pub fn set_line_width(&self, width: f64) {
+        self.push(DisplayOp::SetLineWidth(width));
+    }
+
+    pub fn set_line_cap(&self, cap: LineCap) {
+        self.push(DisplayOp::SetLineCap(cap));
+    }
+
+    pub fn set_line_join(&self, join: LineJoin) {
+        self.push(DisplayOp::SetLineJoin(join));
+    }
+
+    pub fn set_miter_limit(&self, limit: f64) {
+        self.push(DisplayOp::SetMiterLimit(limit));
+    }
+
+    pub fn set_dash(&self, dashes: &[f64], offset: f64) {
+        self.push(DisplayOp::SetDash {
+            dashes: dashes.to_vec(),
+            offset,
+        });
+    }
+
+    pub fn clip(&self) {
+        self.push(DisplayOp::Clip);
+    }
+
+    pub fn clip_preserve(&self) {
+        self.push(DisplayOp::ClipPreserve);
+    }
+
+    pub fn reset_clip(&self) {
+        self.push(DisplayOp::ResetClip);
+    }
+
+    pub fn fill(&self) -> Result<(), Error> {
+        self.record(DisplayOp::Fill)
+    }
+
+    pub fn fill_preserve(&self) -> Result<(), Error> {
+        self.record(DisplayOp::FillPreserve)
+    }
+
+    pub fn stroke(&self) -> Result<(), Error> {
+        self.record(DisplayOp::Stroke)
+    }
+
+    pub fn stroke_preserve(&self) -> Result<(), Error> {
+        self.record(DisplayOp::StrokePreserve)
+    }
+
+    pub fn paint(&self) -> Result<(), Error> {
+        self.record(DisplayOp::Paint)
+    }
+
+    pub fn paint_with_alpha(&self, alpha: f64) -> Result<(), Error> {
+        self.record(DisplayOp::PaintWithAlpha(alpha))
+    }
+
+    pub fn select_font_face(&self, family: &str, slant: FontSlant, weight: FontWeight) {
+        self.push(DisplayOp::SelectFontFace {
+            family: family.to_owned(),
+            slant,
+            weight,
+        });
+    }
+
+    pub fn set_font_size(&self, size: f64) {
+        self.push(DisplayOp::SetFontSize(size));
+    }
+
+    pub fn show_text(&self, text: &str) -> Result<(), Error> {
+        self.record(DisplayOp::ShowText(text.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{
+        Format,
+        testing::{Tolerance, compare},
+    };
+
+    fn draw(recorder: &DisplayListRecorder) {
+        recorder.set_source_rgb(1.0, 1.0, 1.0);
+        recorder.paint().unwrap();
+
+        recorder.save().unwrap();
+        recorder.translate(10.0, 10.0);
+        recorder.rectangle(0.0, 0.0, 30.0, 20.0);
+        let gradient = LinearGradient::new(0.0, 0.0, 30.0, 0.0);
+        gradient.add_color_stop_rgb(0.0, 1.0, 0.0, 0.0);
+        gradient.add_color_stop_rgba(1.0, 0.0, 0.0, 1.0, 0.5);
+        recorder.set_source(&gradient).unwrap();
+        recorder.fill().unwrap();
+        recorder.restore().unwrap();
+
+        let image =
+            ImageSurface::from_rgba8_straight(2, 1, &[0, 255, 0, 255, 0, 0, 0, 128]).unwrap();
+        recorder.set_source_surface(&image, 50.0, 5.0).unwrap();
+        recorder.rectangle(50.0, 5.0, 2.0, 1.0);
+        recorder.fill().unwrap();
+
+        recorder.set_source_rgba(0.0, 0.0, 0.0, 0.8);
+        recorder.set_line_width(4.0);
+        recorder.set_dash(&[3.0, 1.0], 0.0);
+        recorder.arc(60.0, 40.0, 10.0, 0.0, 2.0 * PI);
+        recorder.stroke().unwrap();
+    }
+
+    fn record() -> (DisplayList, ImageSurface) {
+        let image = ImageSurface::create(Format::ARgb32, 80, 60).unwrap();
+        let cr = Context::new(&image).unwrap();
+        let recorder = DisplayListRecorder::new(&cr);
+        draw(&recorder);
+        (recorder.finish(), image)
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let (list, drawn) = record();
+        assert_eq!(list.len(), 16);
+        assert_eq!(
+            list.ops()[4],
+            DisplayOp::Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 30.0,
+                height: 20.0
+            }
+        );
+        let DisplayOp::SetSource(DisplaySource::Linear { stops, .. }) = &list.ops()[5] else {
+            panic!("unexpected {:?}", list.ops()[5]);
+        };
+        assert_eq!(stops.len(), 2);
+        assert_eq!(stops[1].alpha, 0.5);
+
+        let replayed = ImageSurface::create(Format::ARgb32, 80, 60).unwrap();
+        let cr = Context::new(&replayed).unwrap();
+        cr.set_line_width(1.0);
+        list.replay(&cr).unwrap();
+        // The replayed operations don't leak into the context
+        assert_eq!(cr.line_width(), 1.0);
+        drop(cr);
+        let comparison = compare(&replayed, &drawn, Tolerance::EXACT).unwrap();
+        assert!(comparison.is_match(), "{comparison}");
+
+        // Replaying is relative to the current transformation
+        let scaled = ImageSurface::create(Format::ARgb32, 160, 120).unwrap();
+        let cr = Context::new(&scaled).unwrap();
+        cr.scale(2.0, 2.0);
+        list.replay(&cr).unwrap();
+    }
+
+    #[test]
+    fn unbalanced_restore() {
+        let list = DisplayList::from(vec![
+            DisplayOp::Save,
+            DisplayOp::Restore,
+            DisplayOp::Restore,
+        ]);
+        let image = ImageSurface::create(Format::ARgb32, 1, 1).unwrap();
+        let cr = Context::new(&image).unwrap();
+        assert_eq!(list.replay(&cr), Err(Error::InvalidRestore));
+        cr.status().unwrap();
+
+        let recorder = DisplayListRecorder::new(&cr);
+        assert_eq!(recorder.restore(), Err(Error::InvalidRestore));
+        assert!(recorder.finish().is_empty());
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(DisplayList::new().bounds().unwrap(), None);
+
+        let list: DisplayList = [
+            DisplayOp::Translate { tx: 5.0, ty: 0.0 },
+            DisplayOp::Rectangle {
+                x: 10.0,
+                y: 20.0,
+                width: 30.0,
+                height: 40.0,
+            },
+            DisplayOp::Fill,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            list.bounds().unwrap(),
+            Some(Rectangle::new(15.0, 20.0, 30.0, 40.0))
+        );
+    }
+
+    #[test]
+    fn hit_test() {
+        let (list, _) = record();
+        // Only the background paint
+        assert_eq!(list.hit_test(75.0, 5.0).unwrap(), [1]);
+        // The gradient rectangle, drawn after translating
+        assert_eq!(list.hit_test(15.0, 15.0).unwrap(), [1, 6]);
+        assert_eq!(list.hit_test(5.0, 15.0).unwrap(), [1]);
+        // The stroke of the circle, but not its inside
+        assert_eq!(list.hit_test(70.0, 40.0).unwrap(), [1, 15]);
+        assert_eq!(list.hit_test(60.0, 40.0).unwrap(), [1]);
+
+        let clipped = DisplayList::from(vec![
+            DisplayOp::Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 10.0,
+                height: 10.0,
+            },
+            DisplayOp::Clip,
+            DisplayOp::Paint,
+            DisplayOp::ResetClip,
+            DisplayOp::MoveTo { x: 20.0, y: 40.0 },
+            DisplayOp::SetFontSize(30.0),
+            DisplayOp::ShowText("II".into()),
+            DisplayOp::LineTo { x: 200.0, y: 40.0 },
+            DisplayOp::Stroke,
+        ]);
+        assert_eq!(clipped.hit_test(5.0, 5.0).unwrap(), [2]);
+        assert_eq!(clipped.hit_test(15.0, 5.0).unwrap(), Vec::<usize>::new());
+        // The stroke starts where the text ended
+        assert_eq!(clipped.hit_test(15.0, 40.0).unwrap(), Vec::<usize>::new());
+        assert_eq!(clipped.hit_test(190.0, 40.0).unwrap(), [8]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let (list, _) = record();
+        let json = serde_json::to_string(&list).unwrap();
+        let deserialized: DisplayList = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, list);
+    }
+}
@@ -115,6 +115,7 @@ impl From<ffi::cairo_antialias_t> for Antialias {
 gvalue_impl!(Antialias, ffi::gobject::cairo_gobject_antialias_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_fill_rule_t")]
 pub enum FillRule {
@@ -123,6 +124,7 @@ pub enum FillRule {
     #[doc(alias = "FILL_RULE_EVEN_ODD")]
     EvenOdd,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -152,6 +154,7 @@ impl From<ffi::cairo_fill_rule_t> for FillRule {
 gvalue_impl!(FillRule, ffi::gobject::cairo_gobject_fill_rule_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_line_cap_t")]
 pub enum LineCap {
@@ -162,6 +165,7 @@ pub enum LineCap {
     #[doc(alias = "LINE_CAP_SQUARE")]
     Square,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -193,6 +197,7 @@ impl From<ffi::cairo_line_cap_t> for LineCap {
 gvalue_impl!(LineCap, ffi::gobject::cairo_gobject_line_cap_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_line_join_t")]
 pub enum LineJoin {
@@ -203,6 +208,7 @@ pub enum LineJoin {
     #[doc(alias = "LINE_JOIN_BEVEL")]
     Bevel,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -234,6 +240,7 @@ impl From<ffi::cairo_line_join_t> for LineJoin {
 gvalue_impl!(LineJoin, ffi::gobject::cairo_gobject_line_join_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_operator_t")]
 pub enum Operator {
@@ -300,6 +307,7 @@ pub enum Operator {
     #[doc(alias = "OPERATOR_HSL_LUMINOSITY")]
     HslLuminosity,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -472,6 +480,7 @@ impl From<ffi::cairo_content_t> for Content {
 gvalue_impl!(Content, ffi::gobject::cairo_gobject_content_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_extend_t")]
 pub enum Extend {
@@ -484,6 +493,7 @@ pub enum Extend {
     #[doc(alias = "EXTEND_PAD")]
     Pad,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -517,6 +527,7 @@ impl From<ffi::cairo_extend_t> for Extend {
 gvalue_impl!(Extend, ffi::gobject::cairo_gobject_extend_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_filter_t")]
 pub enum Filter {
@@ -533,6 +544,7 @@ pub enum Filter {
     #[doc(alias = "FILTER_GAUSSIAN")]
     Gaussian,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -626,6 +638,7 @@ gvalue_impl!(
 );
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_font_slant_t")]
 pub enum FontSlant {
@@ -636,6 +649,7 @@ pub enum FontSlant {
     #[doc(alias = "FONT_SLANT_OBLIQUE")]
     Oblique,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -667,6 +681,7 @@ impl From<ffi::cairo_font_slant_t> for FontSlant {
 gvalue_impl!(FontSlant, ffi::gobject::cairo_gobject_font_slant_get_type);
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[doc(alias = "cairo_font_weight_t")]
 pub enum FontWeight {
@@ -675,6 +690,7 @@ pub enum FontWeight {
     #[doc(alias = "FONT_WEIGHT_BOLD")]
     Bold,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __Unknown(i32),
 }
 
@@ -1167,6 +1183,14 @@ pub enum Format {
     Rgb16_565,
     #[doc(alias = "FORMAT_RGB30")]
     Rgb30,
+    #[cfg(feature = "v1_18")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v1_18")))]
+    #[doc(alias = "FORMAT_RGB96F")]
+    Rgb96f,
+    #[cfg(feature = "v1_18")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v1_18")))]
+    #[doc(alias = "FORMAT_RGBA128F")]
+    Rgba128f,
     #[doc(hidden)]
     __Unknown(i32),
 }
@@ -1182,6 +1206,10 @@ impl From<Format> for ffi::cairo_format_t {
             Format::A1 => ffi::FORMAT_A1,
             Format::Rgb16_565 => ffi::FORMAT_RGB16_565,
             Format::Rgb30 => ffi::FORMAT_RGB30,
+            #[cfg(feature = "v1_18")]
+            Format::Rgb96f => ffi::FORMAT_RGB96F,
+            #[cfg(feature = "v1_18")]
+            Format::Rgba128f => ffi::FORMAT_RGBA128F,
             Format::__Unknown(value) => value,
         }
     }
@@ -1198,6 +1226,10 @@ impl From<ffi::cairo_format_t> for Format {
             ffi::FORMAT_A1 => Self::A1,
             ffi::FORMAT_RGB16_565 => Self::Rgb16_565,
             ffi::FORMAT_RGB30 => Self::Rgb30,
+            #[cfg(feature = "v1_18")]
+            ffi::FORMAT_RGB96F => Self::Rgb96f,
+            #[cfg(feature = "v1_18")]
+            ffi::FORMAT_RGBA128F => Self::Rgba128f,
             value => Self::__Unknown(value),
         }
     }
@@ -1679,4 +1711,20 @@ mod tests {
     fn stride_works() {
         assert_eq!(Format::Rgb24.stride_for_width(1).unwrap(), 4);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_unknown() {
+        assert_eq!(serde_json::to_string(&Operator::Xor).unwrap(), r#""Xor""#);
+        assert_eq!(
+            serde_json::from_str::<Operator>(r#""Xor""#).unwrap(),
+            Operator::Xor
+        );
+
+        // Unknown values are neither serialized nor deserialized
+        assert!(serde_json::to_string(&Operator::__Unknown(1000)).is_err());
+        assert!(serde_json::from_str::<Operator>(r#"{"__Unknown":1000}"#).is_err());
+        assert!(serde_json::from_str::<Extend>(r#"{"__Unknown":-1}"#).is_err());
+        assert!(serde_json::from_str::<Filter>(r#""__Unknown""#).is_err());
+    }
 }
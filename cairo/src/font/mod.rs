@@ -35,9 +35,14 @@ impl TextCluster {
 }
 */
 pub use self::{
-    font_extents::FontExtents, font_face::FontFace, font_options::FontOptions, glyph::Glyph,
-    scaled_font::ScaledFont, text_cluster::TextCluster, text_extents::TextExtents,
-    user_fonts::UserFontFace,
+    font_extents::FontExtents,
+    font_face::FontFace,
+    font_options::FontOptions,
+    glyph::Glyph,
+    scaled_font::ScaledFont,
+    text_cluster::TextCluster,
+    text_extents::TextExtents,
+    user_fonts::{TextGlyphs, UserFont, UserFontFace},
 };
 pub use crate::enums::{
     Antialias, FontSlant, FontType, FontWeight, HintMetrics, HintStyle, SubpixelOrder,
@@ -1,9 +1,9 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
-use std::sync::OnceLock;
+use std::{any::Any, rc::Rc, sync::OnceLock};
 
 use super::{FontExtents, FontFace, ScaledFont, TextCluster, TextClusterFlags, TextExtents};
-use crate::{Context, Error, Glyph, ffi, utils::status_to_result};
+use crate::{Context, Error, Glyph, UserDataKey, ffi, utils::status_to_result};
 
 type BoxInitFunc =
     Box<dyn Fn(&ScaledFont, &Context, &mut FontExtents) -> Result<(), Error> + Send + Sync>;
@@ -251,3 +251,480 @@ impl std::ops::Deref for UserFontFace {
         &self.0
     }
 }
+
+// rustdoc-stripper-ignore-next
+/// A user font implemented in Rust, for [`UserFontFace::from_user_font()`].
+///
+/// Scaled fonts of the face can be used from any thread, which is why implementors must be
+/// `Send` and `Sync`. Mutable state needs interior mutability.
+pub trait UserFont: Any + Send + Sync {
+    // rustdoc-stripper-ignore-next
+    /// Initializes a scaled font of the face, see
+    /// [`UserFontFace::set_init_func()`].
+    fn init(
+        &self,
+        scaled_font: &ScaledFont,
+        cr: &Context,
+        extents: &mut FontExtents,
+    ) -> Result<(), Error> {
+        let _ = (scaled_font, cr, extents);
+        Ok(())
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Draws `glyph` on `cr` in font space and sets its advance in `extents`.
+    ///
+    /// The source of `cr` must not be changed, the glyph is used as a mask.
+    fn render_glyph(
+        &self,
+        scaled_font: &ScaledFont,
+        glyph: libc::c_ulong,
+        cr: &Context,
+        extents: &mut TextExtents,
+    ) -> Result<(), Error>;
+
+    // rustdoc-stripper-ignore-next
+    /// Draws `glyph` on `cr` in color. Returning [`Error::UserFontNotImplemented`], the default,
+    /// uses [`render_glyph()`](Self::render_glyph) instead.
+    fn render_color_glyph(
+        &self,
+        scaled_font: &ScaledFont,
+        glyph: libc::c_ulong,
+        cr: &Context,
+        extents: &mut TextExtents,
+    ) -> Result<(), Error> {
+        let _ = (scaled_font, glyph, cr, extents);
+        Err(Error::UserFontNotImplemented)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Maps a character to a glyph. Returning [`Error::UserFontNotImplemented`], the default,
+    /// uses the code point as glyph index.
+    fn unicode_to_glyph(
+        &self,
+        scaled_font: &ScaledFont,
+        unicode: char,
+    ) -> Result<libc::c_ulong, Error> {
+        let _ = (scaled_font, unicode);
+        Err(Error::UserFontNotImplemented)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Converts a whole text to glyphs and clusters, for example for ligatures. Returning
+    /// [`Error::UserFontNotImplemented`], the default, maps each character with
+    /// [`unicode_to_glyph()`](Self::unicode_to_glyph).
+    fn text_to_glyphs(
+        &self,
+        scaled_font: &ScaledFont,
+        text: &str,
+        output: &mut TextGlyphs,
+    ) -> Result<(), Error> {
+        let _ = (scaled_font, text, output);
+        Err(Error::UserFontNotImplemented)
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// The glyphs and clusters produced by [`UserFont::text_to_glyphs()`].
+#[derive(Debug)]
+pub struct TextGlyphs {
+    text_len: usize,
+    glyphs: Vec<Glyph>,
+    clusters: Vec<TextCluster>,
+    flags: TextClusterFlags,
+    wants_clusters: bool,
+}
+
+impl TextGlyphs {
+    fn new(text_len: usize, wants_clusters: bool) -> Self {
+        Self {
+            text_len,
+            glyphs: Vec::new(),
+            clusters: Vec::new(),
+            flags: TextClusterFlags::None,
+            wants_clusters,
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds a glyph, positioned in font space relative to the origin of the text.
+    pub fn push_glyph(&mut self, index: libc::c_ulong, x: f64, y: f64) -> &mut Self {
+        self.glyphs.push(Glyph::new(index, x, y));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds a cluster mapping the next `num_bytes` of the text to the next `num_glyphs` glyphs.
+    ///
+    /// Clusters must cover the whole text and all glyphs. Without any clusters, a single
+    /// cluster covering everything is used.
+    pub fn push_cluster(&mut self, num_bytes: usize, num_glyphs: usize) -> &mut Self {
+        self.clusters.push(TextCluster::new(
+            num_bytes.try_into().unwrap_or(i32::MAX),
+            num_glyphs.try_into().unwrap_or(i32::MAX),
+        ));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets whether the clusters map the glyphs to the text backwards, for right to left text.
+    pub fn set_backward(&mut self, backward: bool) -> &mut Self {
+        self.flags = if backward {
+            TextClusterFlags::Backward
+        } else {
+            TextClusterFlags::None
+        };
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Whether cairo uses the clusters, so that computing them can be skipped otherwise.
+    pub fn wants_clusters(&self) -> bool {
+        self.wants_clusters
+    }
+
+    pub fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    pub fn clusters(&self) -> &[TextCluster] {
+        &self.clusters
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if !self.wants_clusters {
+            self.clusters.clear();
+            return Ok(());
+        }
+        if self.clusters.is_empty() {
+            self.push_cluster(self.text_len, self.glyphs.len());
+        }
+        let (num_bytes, num_glyphs) = self.clusters.iter().fold((0, 0), |(b, g), c| {
+            (b + c.num_bytes() as usize, g + c.num_glyphs() as usize)
+        });
+        if num_bytes != self.text_len || num_glyphs != self.glyphs.len() {
+            return Err(Error::InvalidClusters);
+        }
+        Ok(())
+    }
+}
+
+static USER_FONT_KEY: UserDataKey<Box<dyn UserFont>> = UserDataKey::new();
+
+// The user font of the face of `scaled_font`, which is kept alive by the face while cairo
+// calls into it.
+unsafe fn user_font<'a>(scaled_font: *mut ffi::cairo_scaled_font_t) -> Option<&'a dyn UserFont> {
+    unsafe {
+        let font_face = ffi::cairo_scaled_font_get_font_face(scaled_font);
+        let ptr = ffi::cairo_font_face_get_user_data(font_face, &USER_FONT_KEY.ffi)
+            as *const Box<dyn UserFont>;
+        ptr.as_ref().map(|font| &**font)
+    }
+}
+
+unsafe extern "C" fn user_font_init(
+    scaled_font: *mut ffi::cairo_scaled_font_t,
+    cr: *mut ffi::cairo_t,
+    extents: *mut ffi::cairo_font_extents_t,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let Some(font) = user_font(scaled_font) else {
+            return Error::UserFontError.into();
+        };
+        match font.init(
+            &ScaledFont::from_raw_none(scaled_font),
+            &Context::from_raw_none(cr),
+            &mut *(extents as *mut FontExtents),
+        ) {
+            Err(err) => err.into(),
+            Ok(()) => ffi::STATUS_SUCCESS,
+        }
+    }
+}
+
+unsafe extern "C" fn user_font_render_glyph(
+    scaled_font: *mut ffi::cairo_scaled_font_t,
+    glyph: libc::c_ulong,
+    cr: *mut ffi::cairo_t,
+    extents: *mut ffi::cairo_text_extents_t,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let Some(font) = user_font(scaled_font) else {
+            return Error::UserFontError.into();
+        };
+        match font.render_glyph(
+            &ScaledFont::from_raw_none(scaled_font),
+            glyph,
+            &Context::from_raw_none(cr),
+            &mut *(extents as *mut TextExtents),
+        ) {
+            Err(err) => err.into(),
+            Ok(()) => ffi::STATUS_SUCCESS,
+        }
+    }
+}
+
+unsafe extern "C" fn user_font_render_color_glyph(
+    scaled_font: *mut ffi::cairo_scaled_font_t,
+    glyph: libc::c_ulong,
+    cr: *mut ffi::cairo_t,
+    extents: *mut ffi::cairo_text_extents_t,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let Some(font) = user_font(scaled_font) else {
+            return Error::UserFontError.into();
+        };
+        match font.render_color_glyph(
+            &ScaledFont::from_raw_none(scaled_font),
+            glyph,
+            &Context::from_raw_none(cr),
+            &mut *(extents as *mut TextExtents),
+        ) {
+            Err(err) => err.into(),
+            Ok(()) => ffi::STATUS_SUCCESS,
+        }
+    }
+}
+
+unsafe extern "C" fn user_font_unicode_to_glyph(
+    scaled_font: *mut ffi::cairo_scaled_font_t,
+    unicode: libc::c_ulong,
+    glyph_index: *mut libc::c_ulong,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let Some(font) = user_font(scaled_font) else {
+            return Error::UserFontError.into();
+        };
+        let Some(unicode) = u32::try_from(unicode).ok().and_then(char::from_u32) else {
+            return Error::UserFontNotImplemented.into();
+        };
+        match font.unicode_to_glyph(&ScaledFont::from_raw_none(scaled_font), unicode) {
+            Err(err) => err.into(),
+            Ok(glyph) => {
+                *glyph_index = glyph;
+                ffi::STATUS_SUCCESS
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn user_font_text_to_glyphs(
+    scaled_font: *mut ffi::cairo_scaled_font_t,
+    utf8: *const libc::c_char,
+    utf8_len: libc::c_int,
+    glyphs: *mut *mut ffi::cairo_glyph_t,
+    num_glyphs: *mut libc::c_int,
+    clusters: *mut *mut ffi::cairo_text_cluster_t,
+    num_clusters: *mut libc::c_int,
+    cluster_flags: *mut ffi::cairo_text_cluster_flags_t,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let Some(font) = user_font(scaled_font) else {
+            return Error::UserFontError.into();
+        };
+        let bytes = if utf8_len >= 0 {
+            std::slice::from_raw_parts(utf8 as *const u8, utf8_len as usize)
+        } else {
+            std::ffi::CStr::from_ptr(utf8).to_bytes()
+        };
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            return Error::InvalidString.into();
+        };
+
+        let mut output = TextGlyphs::new(text.len(), !clusters.is_null());
+        if let Err(err) = font
+            .text_to_glyphs(&ScaledFont::from_raw_none(scaled_font), text, &mut output)
+            .and_then(|()| output.finish())
+        {
+            return err.into();
+        }
+
+        // The arrays passed by cairo may be too small, replace them with ones that cairo frees
+        *num_glyphs = output.glyphs.len() as _;
+        *glyphs = ffi::cairo_glyph_allocate(*num_glyphs);
+        std::ptr::copy_nonoverlapping(
+            output.glyphs.as_ptr(),
+            *glyphs as *mut Glyph,
+            output.glyphs.len(),
+        );
+        if !clusters.is_null() {
+            *num_clusters = output.clusters.len() as _;
+            *clusters = ffi::cairo_text_cluster_allocate(*num_clusters);
+            std::ptr::copy_nonoverlapping(
+                output.clusters.as_ptr(),
+                *clusters as *mut TextCluster,
+                output.clusters.len(),
+            );
+            *cluster_flags = output.flags.into();
+        }
+        ffi::STATUS_SUCCESS
+    }
+}
+
+impl UserFontFace {
+    // rustdoc-stripper-ignore-next
+    /// Creates a user font face backed by `font`, which is stored in the user data of the face.
+    ///
+    /// Unlike the `set_*_func()` methods, this works for any number of faces. The face can be
+    /// used anywhere a [`FontFace`] is expected.
+    pub fn from_user_font(font: impl UserFont) -> Result<Self, Error> {
+        let face = Self::create()?;
+        let font: Box<dyn UserFont> = Box::new(font);
+        face.set_user_data(&USER_FONT_KEY, Rc::new(font))?;
+        unsafe {
+            let ptr = face.to_raw_none();
+            ffi::cairo_user_font_face_set_init_func(ptr, Some(user_font_init));
+            ffi::cairo_user_font_face_set_render_glyph_func(ptr, Some(user_font_render_glyph));
+            ffi::cairo_user_font_face_set_render_color_glyph_func(
+                ptr,
+                Some(user_font_render_color_glyph),
+            );
+            ffi::cairo_user_font_face_set_unicode_to_glyph_func(
+                ptr,
+                Some(user_font_unicode_to_glyph),
+            );
+            ffi::cairo_user_font_face_set_text_to_glyphs_func(ptr, Some(user_font_text_to_glyphs));
+        }
+        Ok(face)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the user font of a face created with
+    /// [`from_user_font()`](Self::from_user_font), if it is a `T`.
+    pub fn user_font<T: UserFont>(&self) -> Option<&T> {
+        unsafe {
+            let ptr = ffi::cairo_font_face_get_user_data(self.to_raw_none(), &USER_FONT_KEY.ffi)
+                as *const Box<dyn UserFont>;
+            let font: &dyn Any = &**ptr.as_ref()?;
+            font.downcast_ref()
+        }
+    }
+}
+
+impl From<UserFontFace> for FontFace {
+    #[inline]
+    fn from(face: UserFontFace) -> Self {
+        face.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{Format, ImageSurface};
+
+    // Draws squares, and a wide glyph for the "ff" ligature
+    #[derive(Default)]
+    struct Squares {
+        rendered: AtomicUsize,
+    }
+
+    const LIGATURE: libc::c_ulong = 0x10000;
+
+    impl UserFont for Squares {
+        fn init(
+            &self,
+            _: &ScaledFont,
+            _: &Context,
+            extents: &mut FontExtents,
+        ) -> Result<(), Error> {
+            extents.set_ascent(1.0);
+            extents.set_height(1.0);
+            Ok(())
+        }
+
+        fn render_glyph(
+            &self,
+            _: &ScaledFont,
+            glyph: libc::c_ulong,
+            cr: &Context,
+            extents: &mut TextExtents,
+        ) -> Result<(), Error> {
+            self.rendered.fetch_add(1, Ordering::Relaxed);
+            let width = if glyph == LIGATURE { 1.5 } else { 1.0 };
+            cr.rectangle(0.0, -1.0, width, 1.0);
+            cr.fill()?;
+            extents.set_x_advance(width);
+            Ok(())
+        }
+
+        fn text_to_glyphs(
+            &self,
+            _: &ScaledFont,
+            text: &str,
+            output: &mut TextGlyphs,
+        ) -> Result<(), Error> {
+            let mut x = 0.0;
+            let mut rest = text;
+            while let Some(c) = rest.chars().next() {
+                let (glyph, len) = if rest.starts_with("ff") {
+                    (LIGATURE, 2)
+                } else {
+                    (libc::c_ulong::from(c), c.len_utf8())
+                };
+                output.push_glyph(glyph, x, 0.0).push_cluster(len, 1);
+                x += if glyph == LIGATURE { 1.5 } else { 1.0 };
+                rest = &rest[len..];
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn trait_user_font() {
+        let face = UserFontFace::from_user_font(Squares::default()).unwrap();
+        let surface = ImageSurface::create(Format::ARgb32, 100, 20).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        cr.set_font_face(&face);
+        cr.set_font_size(10.0);
+
+        let (glyphs, clusters) = cr.scaled_font().text_to_glyphs(0.0, 10.0, "offa").unwrap();
+        assert_eq!(
+            glyphs.iter().map(Glyph::index).collect::<Vec<_>>(),
+            [libc::c_ulong::from('o'), LIGATURE, libc::c_ulong::from('a')]
+        );
+        assert_eq!(glyphs[2].x(), 25.0);
+        assert_eq!(
+            clusters
+                .iter()
+                .map(TextCluster::num_bytes)
+                .collect::<Vec<_>>(),
+            [1, 2, 1]
+        );
+
+        cr.move_to(0.0, 10.0);
+        cr.show_text("off").unwrap();
+        assert!(
+            face.user_font::<Squares>()
+                .unwrap()
+                .rendered
+                .load(Ordering::Relaxed)
+                >= 2
+        );
+        assert!(
+            UserFontFace::create()
+                .unwrap()
+                .user_font::<Squares>()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn text_glyphs_clusters() {
+        let mut output = TextGlyphs::new(3, true);
+        output.push_glyph(1, 0.0, 0.0).push_glyph(2, 1.0, 0.0);
+        output.finish().unwrap();
+        assert_eq!(output.clusters().len(), 1);
+
+        let mut output = TextGlyphs::new(3, true);
+        output.push_glyph(1, 0.0, 0.0).push_cluster(2, 1);
+        assert_eq!(output.finish(), Err(Error::InvalidClusters));
+
+        let mut output = TextGlyphs::new(3, false);
+        output.push_glyph(1, 0.0, 0.0).push_cluster(2, 1);
+        output.finish().unwrap();
+        assert!(output.clusters().is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::{Future, poll_fn},
+    io, mem,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+use gio::prelude::*;
+
+use crate::{Error, Surface, stream::Constructor, stream::StreamWithError};
+
+// rustdoc-stripper-ignore-next
+/// The default size of the chunks that surfaces pass to a [`gio::OutputStream`].
+pub const DEFAULT_OUTPUT_STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+// The maximum number of chunks queued for writing, in addition to the one being filled.
+const MAX_QUEUED_CHUNKS: usize = 4;
+
+struct State {
+    buffer: Vec<u8>,
+    buffer_size: usize,
+    chunks: VecDeque<Vec<u8>>,
+    closed: bool,
+    error: Option<glib::Error>,
+    waker: Option<Waker>,
+}
+
+impl State {
+    fn is_full(&self) -> bool {
+        self.error.is_none() && self.chunks.len() >= MAX_QUEUED_CHUNKS
+    }
+
+    fn queue_buffer(&mut self) {
+        if !self.buffer.is_empty() {
+            let chunk = mem::replace(&mut self.buffer, Vec::with_capacity(self.buffer_size));
+            self.chunks.push_back(chunk);
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.queue_buffer();
+        self.closed = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// The `io::Write` implementation given to cairo. Every time the buffer is full, it is queued
+// for a task on the thread default main context that writes it into the output stream. Cairo
+// writes synchronously from the thread that runs that main context, so when the queue is full
+// the main context is iterated until the task has written a chunk.
+struct OutputStreamWriter {
+    stream: gio::OutputStream,
+    context: glib::MainContext,
+    state: Rc<RefCell<State>>,
+    task: Option<glib::JoinHandle<Result<(), glib::Error>>>,
+}
+
+impl OutputStreamWriter {
+    fn new(stream: gio::OutputStream, buffer_size: usize, io_priority: glib::Priority) -> Self {
+        let buffer_size = buffer_size.max(1);
+        let state = Rc::new(RefCell::new(State {
+            buffer: Vec::with_capacity(buffer_size),
+            buffer_size,
+            chunks: VecDeque::new(),
+            closed: false,
+            error: None,
+            waker: None,
+        }));
+        let context = glib::MainContext::ref_thread_default();
+        let task = context.spawn_local(write_chunks(stream.clone(), state.clone(), io_priority));
+        Self {
+            stream,
+            context,
+            state,
+            task: Some(task),
+        }
+    }
+
+    // Waits until there is room in the queue, or writing failed.
+    fn wait_for_queue(&self) -> io::Result<()> {
+        if !self.state.borrow().is_full() {
+            return Ok(());
+        }
+        let Ok(_guard) = self.context.acquire() else {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "output stream buffer is full and its main context is owned by another thread",
+            ));
+        };
+        while self.state.borrow().is_full() {
+            self.context.iteration(true);
+        }
+        Ok(())
+    }
+}
+
+impl io::Write for OutputStreamWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.wait_for_queue()?;
+        let mut state = self.state.borrow_mut();
+        if let Some(err) = &state.error {
+            return Err(to_io_error(err.clone()));
+        }
+        let len = buf.len().min(state.buffer_size - state.buffer.len());
+        state.buffer.extend_from_slice(&buf[..len]);
+        if state.buffer.len() >= state.buffer_size {
+            state.queue_buffer();
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.state.borrow_mut().queue_buffer();
+        Ok(())
+    }
+}
+
+impl Drop for OutputStreamWriter {
+    fn drop(&mut self) {
+        // Let the task write everything that was queued, even if nobody waits for it
+        self.state.borrow_mut().close();
+    }
+}
+
+async fn write_chunks(
+    stream: gio::OutputStream,
+    state: Rc<RefCell<State>>,
+    io_priority: glib::Priority,
+) -> Result<(), glib::Error> {
+    loop {
+        let chunk = poll_fn(|cx| {
+            let mut state = state.borrow_mut();
+            match state.chunks.pop_front() {
+                Some(chunk) => Poll::Ready(Some(chunk)),
+                None if state.closed => Poll::Ready(None),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await;
+        let Some(chunk) = chunk else {
+            break;
+        };
+
+        let err = match stream.write_all_future(chunk, io_priority).await {
+            Ok((_, _, None)) => continue,
+            Ok((_, _, Some(err))) | Err((_, err)) => err,
+        };
+        let mut state = state.borrow_mut();
+        state.chunks.clear();
+        state.error = Some(err.clone());
+        return Err(err);
+    }
+
+    stream.flush_future(io_priority).await
+}
+
+fn to_io_error(err: glib::Error) -> io::Error {
+    match err.kind::<gio::IOErrorEnum>() {
+        Some(gio::IOErrorEnum::BrokenPipe) => io::Error::new(io::ErrorKind::BrokenPipe, err),
+        Some(gio::IOErrorEnum::PermissionDenied) => {
+            io::Error::new(io::ErrorKind::PermissionDenied, err)
+        }
+        Some(gio::IOErrorEnum::NoSpace) => io::Error::new(io::ErrorKind::StorageFull, err),
+        Some(gio::IOErrorEnum::TimedOut) => io::Error::new(io::ErrorKind::TimedOut, err),
+        _ => io::Error::other(err),
+    }
+}
+
+impl Surface {
+    pub(crate) fn _for_output_stream(
+        constructor: Constructor,
+        width: f64,
+        height: f64,
+        stream: &gio::OutputStream,
+        buffer_size: usize,
+        io_priority: glib::Priority,
+    ) -> Result<Self, Error> {
+        let writer = OutputStreamWriter::new(stream.clone(), buffer_size, io_priority);
+        Self::_for_stream(constructor, width, height, writer)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Finishes a surface created for a [`gio::OutputStream`], for example with
+    /// [`PdfSurface::for_output_stream`](crate::PdfSurface::for_output_stream), and returns a
+    /// future that resolves to the output stream once all bytes are written and flushed.
+    ///
+    /// The surface is finished right away, only writing to the output stream is asynchronous.
+    /// The output stream is not closed.
+    ///
+    /// Errors of writing to the output stream are returned as [`StreamWithError`] with the
+    /// [`gio::OutputStream`] as stream. If the surface was created for another kind of output
+    /// stream, it is returned with an error of kind [`io::ErrorKind::InvalidInput`].
+    ///
+    /// # Panics
+    ///
+    /// In the cases documented for [`Surface::finish_output_stream`].
+    pub fn finish_future(
+        &self,
+    ) -> impl Future<Output = Result<gio::OutputStream, StreamWithError>> + 'static {
+        let (writer, write_error) = match self.finish_output_stream() {
+            Ok(writer) => (writer, None),
+            Err(StreamWithError { stream, error }) => (stream, Some(error)),
+        };
+        let writer = writer.downcast::<OutputStreamWriter>().map(|mut writer| {
+            let task = writer.task.take().expect("output stream already finished");
+            (writer.stream.clone(), task)
+        });
+
+        async move {
+            let (stream, task) = match writer {
+                Ok(writer) => writer,
+                Err(stream) => {
+                    return Err(StreamWithError {
+                        stream,
+                        error: write_error.unwrap_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "surface not created for a gio::OutputStream",
+                            )
+                        }),
+                    });
+                }
+            };
+            let error = match task.await {
+                Ok(Ok(())) => write_error,
+                Ok(Err(err)) => Some(to_io_error(err)),
+                Err(err) => Some(io::Error::other(err.to_string())),
+            };
+            match error {
+                None => Ok(stream),
+                Some(error) => Err(StreamWithError {
+                    stream: Box::new(stream),
+                    error,
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "svg"))]
+mod tests {
+    use super::*;
+    use crate::{Context, SvgSurface};
+
+    fn draw(surface: &Surface) {
+        let cr = Context::new(surface).unwrap();
+        for i in 0..100 {
+            cr.rectangle(f64::from(i), f64::from(i), 10.0, 10.0);
+            cr.fill().unwrap();
+        }
+    }
+
+    #[test]
+    fn memory_output_stream() {
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let stream = gio::MemoryOutputStream::new_resizable();
+                let surface =
+                    SvgSurface::for_output_stream_with_buffer_size(100., 100., &stream, 256)
+                        .unwrap();
+                draw(&surface);
+                let result = context.block_on(surface.finish_future()).unwrap();
+                assert_eq!(&result, stream.upcast_ref::<gio::OutputStream>());
+
+                stream.close(gio::Cancellable::NONE).unwrap();
+                let svg = stream.steal_as_bytes();
+                assert!(svg.starts_with(b"<?xml"));
+                assert!(svg.trim_ascii_end().ends_with(b"</svg>"));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn write_error() {
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let stream = gio::MemoryOutputStream::new_resizable();
+                stream.close(gio::Cancellable::NONE).unwrap();
+                let surface =
+                    SvgSurface::for_output_stream_with_buffer_size(100., 100., &stream, 16)
+                        .unwrap();
+                draw(&surface);
+                let err = context.block_on(surface.finish_future()).unwrap_err();
+                assert!(err.stream.is::<gio::OutputStream>());
+                let err = err
+                    .error
+                    .into_inner()
+                    .unwrap()
+                    .downcast::<glib::Error>()
+                    .unwrap();
+                assert!(err.matches(gio::IOErrorEnum::Closed));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn bounded_queue() {
+        use std::io::Write;
+
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let stream = gio::MemoryOutputStream::new_resizable();
+                let mut writer =
+                    OutputStreamWriter::new(stream.clone().upcast(), 4, glib::Priority::DEFAULT);
+                let data = (0..=255).collect::<Vec<u8>>();
+                for chunk in data.chunks(7) {
+                    writer.write_all(chunk).unwrap();
+                    assert!(writer.state.borrow().chunks.len() <= MAX_QUEUED_CHUNKS);
+                }
+                // Filling the queue made the main context write to the output stream. Only the
+                // queued chunks, the one being written and the one being filled are missing
+                assert!(stream.data_size() >= data.len() - 4 * (MAX_QUEUED_CHUNKS + 2));
+
+                let task = writer.task.take().unwrap();
+                drop(writer);
+                context.block_on(task).unwrap().unwrap();
+                stream.close(gio::Cancellable::NONE).unwrap();
+                assert_eq!(&*stream.steal_as_bytes(), &data[..]);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn other_stream() {
+        let surface = SvgSurface::for_stream(100., 100., Vec::<u8>::new()).unwrap();
+        draw(&surface);
+        let err = glib::MainContext::new()
+            .block_on(surface.finish_future())
+            .unwrap_err();
+        assert_eq!(err.error.kind(), io::ErrorKind::InvalidInput);
+        let svg = err.stream.downcast::<Vec<u8>>().unwrap();
+        assert!(svg.starts_with(b"<?xml"));
+    }
+}
@@ -0,0 +1,289 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+// Pixel format conversions of `ImageSurface`s.
+//
+// Conversions between cairo formats are done by cairo, which uses pixman's SIMD code paths.
+// The loops for converting to and from straight alpha RGBA work on whole rows of plain
+// integers and floats so that the compiler can vectorize them.
+
+use std::sync::OnceLock;
+
+use crate::{BorrowError, Context, Error, Format, ImageSurface, ImageSurfaceData, Operator};
+
+impl Format {
+    // Returns the number of bytes covered by `width` pixels, without row padding.
+    pub(crate) fn row_bytes(self, width: usize) -> Option<usize> {
+        let bits = match self {
+            Format::A1 => 1,
+            Format::A8 => 8,
+            Format::Rgb16_565 => 16,
+            Format::ARgb32 | Format::Rgb24 | Format::Rgb30 => 32,
+            #[cfg(feature = "v1_18")]
+            Format::Rgb96f => 96,
+            #[cfg(feature = "v1_18")]
+            Format::Rgba128f => 128,
+            _ => return None,
+        };
+        Some((width * bits).div_ceil(8))
+    }
+}
+
+impl ImageSurface {
+    // rustdoc-stripper-ignore-next
+    /// Returns a copy of the surface in another format.
+    ///
+    /// Colors are converted the way cairo does when painting: converting to a format without
+    /// alpha channel composites the pixels over black, converting to an alpha-only format keeps
+    /// only the alpha channel, and [`A1`](Format::A1) pixels are opaque if their alpha is at
+    /// least one half.
+    pub fn convert(&self, format: Format) -> Result<ImageSurface, Error> {
+        let converted = ImageSurface::create(format, self.width(), self.height())?;
+        let cr = Context::new(&converted)?;
+        cr.set_operator(Operator::Source);
+        cr.set_source_surface(self, 0.0, 0.0)?;
+        cr.paint()?;
+        drop(cr);
+        converted.flush();
+        Ok(converted)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates an [`ARgb32`](Format::ARgb32) surface from tightly packed RGBA pixels with
+    /// straight alpha, in memory order, as used by most image encoders and GPU APIs.
+    ///
+    /// Fails with [`Error::InvalidSize`] if `data` is not `width * height * 4` bytes long.
+    pub fn from_rgba8_straight(width: i32, height: i32, data: &[u8]) -> Result<Self, Error> {
+        let row_len = usize::try_from(width).map_err(|_| Error::InvalidSize)? * 4;
+        let rows = usize::try_from(height).map_err(|_| Error::InvalidSize)?;
+        if row_len.checked_mul(rows) != Some(data.len()) {
+            return Err(Error::InvalidSize);
+        }
+
+        let mut surface = ImageSurface::create(Format::ARgb32, width, height)?;
+        if data.is_empty() {
+            return Ok(surface);
+        }
+        let mut surface_data = surface.data().map_err(|err| match err {
+            BorrowError::Cairo(err) => err,
+            BorrowError::NonExclusive => unreachable!(),
+        })?;
+        for (out, row) in surface_data.rows_mut().zip(data.chunks_exact(row_len)) {
+            premultiply_row(row, out);
+        }
+        drop(surface_data);
+        Ok(surface)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the pixels as tightly packed RGBA with straight alpha, in memory order.
+    ///
+    /// Surfaces in other formats than [`ARgb32`](Format::ARgb32) are converted with
+    /// [`convert()`](Self::convert) first, except for [`Rgb24`](Format::Rgb24) surfaces.
+    pub fn to_rgba8_unpremultiplied(&self) -> Result<Vec<u8>, BorrowError> {
+        let format = self.format();
+        if !matches!(format, Format::ARgb32 | Format::Rgb24) {
+            return self.convert(Format::ARgb32)?.to_rgba8_unpremultiplied();
+        }
+
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        let mut rgba = vec![0; width * height * 4];
+        if rgba.is_empty() {
+            return Ok(rgba);
+        }
+        let stride = self.stride() as usize;
+        self.with_data(|data| {
+            let rows = data.chunks(stride).map(|row| &row[..width * 4]);
+            for (row, out) in rows.zip(rgba.chunks_exact_mut(width * 4)) {
+                if format == Format::Rgb24 {
+                    opaque_row(row, out);
+                } else {
+                    unpremultiply_row(row, out);
+                }
+            }
+        })?;
+        Ok(rgba)
+    }
+}
+
+impl ImageSurfaceData<'_> {
+    fn row_layout(&self) -> (usize, usize, usize) {
+        let surface = &*self.surface;
+        let row_bytes = surface
+            .format()
+            .row_bytes(surface.width() as usize)
+            .unwrap_or(0);
+        (
+            (surface.stride() as usize).max(1),
+            row_bytes,
+            surface.height() as usize,
+        )
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Iterates over the rows of pixels, without the padding at the end of each row.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &[u8]> + DoubleEndedIterator {
+        let (stride, row_bytes, height) = self.row_layout();
+        self.chunks(stride)
+            .take(height)
+            .map(move |row| &row[..row_bytes])
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Iterates mutably over the rows of pixels, without the padding at the end of each row.
+    pub fn rows_mut(&mut self) -> impl ExactSizeIterator<Item = &mut [u8]> + DoubleEndedIterator {
+        let (stride, row_bytes, height) = self.row_layout();
+        self.chunks_mut(stride)
+            .take(height)
+            .map(move |row| &mut row[..row_bytes])
+    }
+}
+
+// Multiplies by `a / 255`, rounding to the nearest integer.
+#[inline]
+fn premultiply(c: u8, a: u8) -> u8 {
+    let t = u32::from(c) * u32::from(a) + 128;
+    ((t + (t >> 8)) >> 8) as u8
+}
+
+// Multiplies by a factor from `unpremultiply_factors()`, rounding to the nearest integer.
+#[inline]
+fn unpremultiply(c: u8, factor: f32) -> u8 {
+    (f32::from(c) * factor + 0.5).min(255.0) as u8
+}
+
+// `255 / a` for each alpha value, and 0 for fully transparent pixels.
+fn unpremultiply_factors() -> &'static [f32; 256] {
+    static FACTORS: OnceLock<[f32; 256]> = OnceLock::new();
+    FACTORS.get_or_init(|| std::array::from_fn(|a| if a == 0 { 0.0 } else { 255.0 / a as f32 }))
+}
+
+// RGBA with straight alpha to native-endian premultiplied ARGB words.
+fn premultiply_row(rgba: &[u8], argb: &mut [u8]) {
+    for (p, out) in rgba.chunks_exact(4).zip(argb.chunks_exact_mut(4)) {
+        let [r, g, b, a] = [p[0], p[1], p[2], p[3]];
+        let pixel = u32::from(a) << 24
+            | u32::from(premultiply(r, a)) << 16
+            | u32::from(premultiply(g, a)) << 8
+            | u32::from(premultiply(b, a));
+        out.copy_from_slice(&pixel.to_ne_bytes());
+    }
+}
+
+// Native-endian premultiplied ARGB words to RGBA with straight alpha.
+fn unpremultiply_row(argb: &[u8], rgba: &mut [u8]) {
+    let factors = unpremultiply_factors();
+    for (p, out) in argb.chunks_exact(4).zip(rgba.chunks_exact_mut(4)) {
+        let pixel = u32::from_ne_bytes([p[0], p[1], p[2], p[3]]);
+        let a = (pixel >> 24) as u8;
+        let factor = factors[usize::from(a)];
+        let [r, g, b] = [pixel >> 16, pixel >> 8, pixel].map(|c| unpremultiply(c as u8, factor));
+        out.copy_from_slice(&[r, g, b, a]);
+    }
+}
+
+// Native-endian xRGB words to opaque RGBA.
+fn opaque_row(xrgb: &[u8], rgba: &mut [u8]) {
+    for (p, out) in xrgb.chunks_exact(4).zip(rgba.chunks_exact_mut(4)) {
+        let pixel = u32::from_ne_bytes([p[0], p[1], p[2], p[3]]);
+        out.copy_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8, 0xff]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface() -> ImageSurface {
+        ImageSurface::from_rgba8_straight(3, 1, &[255, 0, 0, 255, 0, 255, 0, 128, 10, 20, 30, 0])
+            .unwrap()
+    }
+
+    #[test]
+    fn rgba8_round_trip() {
+        let surface = surface();
+        surface
+            .with_data(|data| {
+                assert_eq!(&data[..4], 0xffff0000u32.to_ne_bytes());
+                assert_eq!(&data[4..8], 0x80008000u32.to_ne_bytes());
+                assert_eq!(&data[8..12], [0; 4]);
+            })
+            .unwrap();
+        assert_eq!(
+            surface.to_rgba8_unpremultiplied().unwrap(),
+            [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            ImageSurface::from_rgba8_straight(2, 2, &[0; 12]).unwrap_err(),
+            Error::InvalidSize
+        );
+
+        for a in 1..=255 {
+            let factor = unpremultiply_factors()[usize::from(a)];
+            for c in 0..=255 {
+                let premultiplied = premultiply(c, a);
+                assert_eq!(
+                    premultiplied,
+                    (f32::from(c) * f32::from(a) / 255.0).round() as u8
+                );
+                assert_eq!(
+                    premultiply(unpremultiply(premultiplied, factor), a),
+                    premultiplied
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn convert() {
+        let surface = surface();
+
+        let rgb = surface.convert(Format::Rgb24).unwrap();
+        assert_eq!(rgb.format(), Format::Rgb24);
+        // Composited over black
+        assert_eq!(
+            rgb.to_rgba8_unpremultiplied().unwrap(),
+            [255, 0, 0, 255, 0, 128, 0, 255, 0, 0, 0, 255]
+        );
+
+        let mut a8 = surface.convert(Format::A8).unwrap();
+        assert_eq!(&*a8.data().unwrap(), [255, 128, 0, 0]);
+        let mut a1 = a8.convert(Format::A1).unwrap();
+        let word = u32::from_ne_bytes(a1.data().unwrap()[..4].try_into().unwrap());
+        let bits = if cfg!(target_endian = "little") {
+            0b011
+        } else {
+            0b110 << 29
+        };
+        assert_eq!(word, bits);
+
+        let rgb565 = surface.convert(Format::Rgb16_565).unwrap();
+        assert_eq!(
+            rgb565.to_rgba8_unpremultiplied().unwrap()[..4],
+            [255, 0, 0, 255]
+        );
+        let rgb30 = surface.convert(Format::Rgb30).unwrap();
+        assert_eq!(
+            rgb30
+                .convert(Format::ARgb32)
+                .unwrap()
+                .to_rgba8_unpremultiplied()
+                .unwrap(),
+            rgb.to_rgba8_unpremultiplied().unwrap()
+        );
+    }
+
+    #[test]
+    fn rows() {
+        let mut surface = ImageSurface::create(Format::A8, 3, 2).unwrap();
+        let mut data = surface.data().unwrap();
+        assert!(data.len() > 6);
+        for (y, row) in data.rows_mut().enumerate() {
+            row.fill(y as u8 + 1);
+        }
+        assert_eq!(data.rows().collect::<Vec<_>>(), [[1; 3], [2; 3]]);
+        assert_eq!(data.rows().next_back(), Some(&[2; 3][..]));
+
+        let mut surface = ImageSurface::create(Format::A1, 33, 1).unwrap();
+        assert_eq!(surface.data().unwrap().rows().next().unwrap().len(), 5);
+    }
+}
@@ -236,7 +236,7 @@ impl DerefMut for ImageSurfaceDataOwned {
 
 #[derive(Debug)]
 pub struct ImageSurfaceData<'a> {
-    surface: &'a mut ImageSurface,
+    pub(crate) surface: &'a mut ImageSurface,
     slice: &'a mut [u8],
     dirty: bool,
 }
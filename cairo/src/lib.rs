@@ -8,9 +8,15 @@ pub use cairo_sys as ffi;
 #[cfg(feature = "freetype")]
 #[cfg_attr(docsrs, doc(cfg(feature = "freetype")))]
 pub use freetype;
+#[cfg(feature = "gio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gio")))]
+pub use gio;
 #[cfg(feature = "use_glib")]
 #[cfg_attr(docsrs, doc(cfg(feature = "use_glib")))]
 pub use glib;
+#[cfg(feature = "image")]
+#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+pub use image;
 
 // Helper macros for our GValue related trait impls
 #[cfg(feature = "use_glib")]
@@ -208,6 +214,12 @@ macro_rules! gvalue_impl_inline {
     };
 }
 
+#[cfg(all(feature = "gio", any(feature = "pdf", feature = "svg", feature = "ps")))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "gio", any(feature = "pdf", feature = "svg", feature = "ps"))))
+)]
+pub use gio_stream::DEFAULT_OUTPUT_STREAM_BUFFER_SIZE;
 #[cfg(feature = "pdf")]
 #[cfg_attr(docsrs, doc(cfg(feature = "pdf")))]
 pub use pdf::PdfSurface;
@@ -220,6 +232,9 @@ pub use ps::PsSurface;
     doc(cfg(any(feature = "pdf", feature = "svg", feature = "ps")))
 )]
 pub use stream::StreamWithError;
+#[cfg(feature = "image")]
+#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+pub use surface_image::ImageSurfacePixel;
 #[cfg(feature = "svg")]
 #[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
 pub use svg::SvgSurface;
@@ -233,23 +248,27 @@ pub use xcb::{
 pub use crate::{
     context::{Context, RectangleList},
     device::Device,
+    display_list::{ColorStop, DisplayList, DisplayListRecorder, DisplayOp, DisplaySource},
     enums::*,
     error::{BorrowError, Error, IoError, Result},
     font::{
         Antialias, FontExtents, FontFace, FontOptions, FontSlant, FontType, FontWeight, Glyph,
-        HintMetrics, HintStyle, ScaledFont, SubpixelOrder, TextCluster, TextExtents, UserFontFace,
+        HintMetrics, HintStyle, ScaledFont, SubpixelOrder, TextCluster, TextExtents, TextGlyphs,
+        UserFont, UserFontFace,
     },
     image_surface::{ImageSurface, ImageSurfaceData, ImageSurfaceDataOwned},
     matrices::Matrix,
-    paths::{Path, PathSegment, PathSegments},
+    paths::{Path, PathBuilder, PathSegment, PathSegments},
     patterns::{
-        Gradient, LinearGradient, Mesh, Pattern, RadialGradient, SolidPattern, SurfacePattern,
+        Gradient, LinearGradient, Mesh, Pattern, RadialGradient, RasterSourcePattern, SolidPattern,
+        SurfacePattern,
     },
     recording_surface::RecordingSurface,
     rectangle::Rectangle,
     rectangle_int::RectangleInt,
     region::Region,
     surface::{MappedImageSurface, Surface},
+    tiles::{Tile, TileOptions},
     user_data::UserDataKey,
 };
 
@@ -263,11 +282,14 @@ mod utils;
 pub use crate::utils::{Version, debug_reset_static_data, version_string};
 mod context;
 mod device;
+mod display_list;
 mod enums;
 mod error;
 mod font;
+mod image_convert;
 mod image_surface;
 mod matrices;
+mod path_data;
 mod paths;
 mod patterns;
 mod recording_surface;
@@ -275,14 +297,22 @@ mod rectangle;
 mod rectangle_int;
 mod region;
 mod surface;
+#[cfg(feature = "image")]
+mod surface_image;
 #[cfg(feature = "png")]
 mod surface_png;
+#[cfg(feature = "v1_16")]
+mod tags;
+pub mod testing;
+mod tiles;
 #[cfg(feature = "xcb")]
 mod xcb;
 
 #[cfg(any(feature = "pdf", feature = "svg", feature = "ps"))]
 #[macro_use]
 mod stream;
+#[cfg(all(feature = "gio", any(feature = "pdf", feature = "svg", feature = "ps")))]
+mod gio_stream;
 #[cfg(feature = "pdf")]
 mod pdf;
 #[cfg(feature = "ps")]
@@ -290,6 +320,10 @@ mod ps;
 #[cfg(feature = "svg")]
 mod svg;
 
+#[cfg(feature = "v1_16")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v1_16")))]
+pub use tags::{Dest, Link, LinkTarget, Structure, StructureElement, TagGuard};
+
 #[cfg(all(target_os = "macos", feature = "quartz-surface"))]
 mod quartz_surface;
 #[cfg(all(target_os = "macos", feature = "quartz-surface"))]
@@ -0,0 +1,413 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+// SVG path data, see https://www.w3.org/TR/SVG11/paths.html#PathData
+
+use std::{
+    f64::consts::{FRAC_PI_2, PI},
+    fmt::Write,
+};
+
+use crate::{Error, Path, PathBuilder, PathSegment};
+
+impl Path {
+    // rustdoc-stripper-ignore-next
+    /// Parses SVG path data, as found in the `d` attribute of a `<path>` element.
+    ///
+    /// Quadratic curves are converted to cubic ones and elliptical arcs are approximated by
+    /// cubic curves. Returns [`Error::InvalidPathData`] if the data is malformed.
+    pub fn from_svg_path_data(data: &str) -> Result<Path, Error> {
+        Parser {
+            data: data.as_bytes(),
+            pos: 0,
+        }
+        .parse()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Serializes the path as SVG path data, using absolute commands only.
+    pub fn to_svg_path_data(&self) -> String {
+        let mut data = String::new();
+        for segment in self.iter() {
+            if !data.is_empty() {
+                data.push(' ');
+            }
+            let _ = match segment {
+                PathSegment::MoveTo((x, y)) => write!(data, "M {x} {y}"),
+                PathSegment::LineTo((x, y)) => write!(data, "L {x} {y}"),
+                PathSegment::CurveTo((x1, y1), (x2, y2), (x, y)) => {
+                    write!(data, "C {x1} {y1} {x2} {y2} {x} {y}")
+                }
+                PathSegment::ClosePath => write!(data, "Z"),
+            };
+        }
+        data
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Path, Error> {
+        let mut builder = PathBuilder::new();
+        let mut current = (0.0, 0.0);
+        let mut start = (0.0, 0.0);
+        // The second control point of the previous segment, for smooth curves
+        let mut last_cubic_control = None;
+        let mut last_quad_control = None;
+        let mut command = None;
+
+        self.skip_whitespace();
+        while let Some(&c) = self.data.get(self.pos) {
+            let cmd = if c.is_ascii_alphabetic() {
+                self.pos += 1;
+                c
+            } else {
+                // Repeated arguments continue the previous command, except that a move turns
+                // into a line
+                match command {
+                    Some(b'M') if self.at_number() => b'L',
+                    Some(b'm') if self.at_number() => b'l',
+                    Some(cmd) if !matches!(cmd, b'Z' | b'z') && self.at_number() => cmd,
+                    _ => return Err(Error::InvalidPathData),
+                }
+            };
+            if command.is_none() && !matches!(cmd, b'M' | b'm') {
+                return Err(Error::InvalidPathData);
+            }
+            command = Some(cmd);
+            self.skip_whitespace();
+
+            let base = if cmd.is_ascii_lowercase() {
+                current
+            } else {
+                (0.0, 0.0)
+            };
+            let (cubic_control, quad_control) = match cmd.to_ascii_uppercase() {
+                b'M' => {
+                    current = self.point(base)?;
+                    start = current;
+                    builder.move_to(current.0, current.1);
+                    (None, None)
+                }
+                b'L' => {
+                    current = self.point(base)?;
+                    builder.line_to(current.0, current.1);
+                    (None, None)
+                }
+                b'H' => {
+                    current.0 = base.0 + self.number()?;
+                    builder.line_to(current.0, current.1);
+                    (None, None)
+                }
+                b'V' => {
+                    current.1 = base.1 + self.number()?;
+                    builder.line_to(current.0, current.1);
+                    (None, None)
+                }
+                b'C' | b'S' => {
+                    let c1 = if cmd.eq_ignore_ascii_case(&b'C') {
+                        self.point(base)?
+                    } else {
+                        reflect(last_cubic_control, current)
+                    };
+                    let c2 = self.point(base)?;
+                    current = self.point(base)?;
+                    builder.curve_to(c1.0, c1.1, c2.0, c2.1, current.0, current.1);
+                    (Some(c2), None)
+                }
+                b'Q' | b'T' => {
+                    let q = if cmd.eq_ignore_ascii_case(&b'Q') {
+                        self.point(base)?
+                    } else {
+                        reflect(last_quad_control, current)
+                    };
+                    let p0 = current;
+                    current = self.point(base)?;
+                    let c1 = (
+                        p0.0 + 2.0 / 3.0 * (q.0 - p0.0),
+                        p0.1 + 2.0 / 3.0 * (q.1 - p0.1),
+                    );
+                    let c2 = (
+                        current.0 + 2.0 / 3.0 * (q.0 - current.0),
+                        current.1 + 2.0 / 3.0 * (q.1 - current.1),
+                    );
+                    builder.curve_to(c1.0, c1.1, c2.0, c2.1, current.0, current.1);
+                    (None, Some(q))
+                }
+                b'A' => {
+                    let rx = self.number()?;
+                    let ry = self.number()?;
+                    let angle = self.number()?;
+                    let large_arc = self.flag()?;
+                    let sweep = self.flag()?;
+                    let p = self.point(base)?;
+                    arc_to(&mut builder, current, (rx, ry), angle, large_arc, sweep, p);
+                    current = p;
+                    (None, None)
+                }
+                b'Z' => {
+                    builder.close_path();
+                    current = start;
+                    (None, None)
+                }
+                _ => return Err(Error::InvalidPathData),
+            };
+            last_cubic_control = cubic_control;
+            last_quad_control = quad_control;
+            self.skip_comma_whitespace();
+        }
+
+        Ok(builder.build())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comma_whitespace(&mut self) {
+        self.skip_whitespace();
+        if self.data.get(self.pos) == Some(&b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn at_number(&self) -> bool {
+        self.data
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, b'.' | b'-' | b'+'))
+    }
+
+    fn number(&mut self) -> Result<f64, Error> {
+        let start = self.pos;
+        let digits = |parser: &mut Self| {
+            let start = parser.pos;
+            while parser.data.get(parser.pos).is_some_and(u8::is_ascii_digit) {
+                parser.pos += 1;
+            }
+            parser.pos > start
+        };
+
+        if matches!(self.data.get(self.pos), Some(b'-' | b'+')) {
+            self.pos += 1;
+        }
+        let mut mantissa = digits(self);
+        if self.data.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            mantissa |= digits(self);
+        }
+        if !mantissa {
+            return Err(Error::InvalidPathData);
+        }
+        if matches!(self.data.get(self.pos), Some(b'e' | b'E')) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if matches!(self.data.get(self.pos), Some(b'-' | b'+')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                self.pos = mantissa_end;
+            }
+        }
+
+        let number = std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse::<f64>().ok())
+            .filter(|number| number.is_finite())
+            .ok_or(Error::InvalidPathData)?;
+        self.skip_comma_whitespace();
+        Ok(number)
+    }
+
+    fn flag(&mut self) -> Result<bool, Error> {
+        let flag = match self.data.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(Error::InvalidPathData),
+        };
+        self.pos += 1;
+        self.skip_comma_whitespace();
+        Ok(flag)
+    }
+
+    fn point(&mut self, base: (f64, f64)) -> Result<(f64, f64), Error> {
+        Ok((base.0 + self.number()?, base.1 + self.number()?))
+    }
+}
+
+// Reflects the previous control point about the current point, or uses the current point if
+// the previous segment was not of the same kind.
+fn reflect(control: Option<(f64, f64)>, current: (f64, f64)) -> (f64, f64) {
+    control.map_or(current, |c| (2.0 * current.0 - c.0, 2.0 * current.1 - c.1))
+}
+
+// Approximates an elliptical arc by cubic curves of at most 90° each, following
+// https://www.w3.org/TR/SVG11/implnote.html#ArcImplementationNotes
+fn arc_to(
+    builder: &mut PathBuilder,
+    p0: (f64, f64),
+    (rx, ry): (f64, f64),
+    angle: f64,
+    large_arc: bool,
+    sweep: bool,
+    p: (f64, f64),
+) {
+    if p0 == p {
+        return;
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 {
+        builder.line_to(p.0, p.1);
+        return;
+    }
+
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let dx = (p0.0 - p.0) / 2.0;
+    let dy = (p0.1 - p.1) / 2.0;
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+
+    // Scale up radii that are too small to reach the end point
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coef = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        coef = -coef;
+    }
+    let cx1 = coef * rx * y1 / ry;
+    let cy1 = -coef * ry * x1 / rx;
+    let cx = cos * cx1 - sin * cy1 + (p0.0 + p.0) / 2.0;
+    let cy = sin * cx1 + cos * cy1 + (p0.1 + p.1) / 2.0;
+
+    let vector_angle = |(ux, uy): (f64, f64), (vx, vy): (f64, f64)| {
+        f64::atan2(ux * vy - uy * vx, ux * vx + uy * vy)
+    };
+    let theta = vector_angle((1.0, 0.0), ((x1 - cx1) / rx, (y1 - cy1) / ry));
+    let mut delta = vector_angle(
+        ((x1 - cx1) / rx, (y1 - cy1) / ry),
+        ((-x1 - cx1) / rx, (-y1 - cy1) / ry),
+    );
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    let point = |t: f64| {
+        let (sin_t, cos_t) = t.sin_cos();
+        (
+            cx + rx * cos_t * cos - ry * sin_t * sin,
+            cy + rx * cos_t * sin + ry * sin_t * cos,
+        )
+    };
+    let derivative = |t: f64| {
+        let (sin_t, cos_t) = t.sin_cos();
+        (
+            -rx * sin_t * cos - ry * cos_t * sin,
+            -rx * sin_t * sin + ry * cos_t * cos,
+        )
+    };
+
+    let n = (delta.abs() / FRAC_PI_2 - 1e-9).ceil().max(1.0) as usize;
+    let step = delta / n as f64;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    for i in 0..n {
+        let t1 = theta + step * i as f64;
+        let t2 = t1 + step;
+        let (e1, d1) = (point(t1), derivative(t1));
+        let (d2, e2) = (derivative(t2), if i + 1 == n { p } else { point(t2) });
+        builder.curve_to(
+            e1.0 + k * d1.0,
+            e1.1 + k * d1.1,
+            e2.0 - k * d2.0,
+            e2.1 - k * d2.1,
+            e2.0,
+            e2.1,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let path = Path::from_svg_path_data("M10,20 l5-5h1V1.5e1 q1 0 1 1 t1 1zm1 1 2 2").unwrap();
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            [
+                PathSegment::MoveTo((10.0, 20.0)),
+                PathSegment::LineTo((15.0, 15.0)),
+                PathSegment::LineTo((16.0, 15.0)),
+                PathSegment::LineTo((16.0, 15.0)),
+                PathSegment::CurveTo(
+                    (16.0 + 2.0 / 3.0, 15.0),
+                    (17.0, 16.0 - 2.0 / 3.0),
+                    (17.0, 16.0)
+                ),
+                PathSegment::CurveTo(
+                    (17.0, 16.0 + 2.0 / 3.0),
+                    (18.0 - 2.0 / 3.0, 17.0),
+                    (18.0, 17.0)
+                ),
+                PathSegment::ClosePath,
+                PathSegment::MoveTo((11.0, 21.0)),
+                PathSegment::LineTo((13.0, 23.0)),
+            ]
+        );
+
+        for invalid in [
+            "L 1 1",
+            "M 1",
+            "M 1 1 Z 2 2",
+            "M 1 1 X",
+            "M 1 1 A 1 1 0 2 0 3 3",
+        ] {
+            assert_eq!(
+                Path::from_svg_path_data(invalid).unwrap_err(),
+                Error::InvalidPathData,
+                "{invalid}"
+            );
+        }
+        assert!(
+            Path::from_svg_path_data(" ")
+                .unwrap()
+                .iter()
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn arc() {
+        // A full circle of radius 10 from two half circles, with compact flags
+        let path = Path::from_svg_path_data("M 0 10 A 10 10 0 1120 10 a10 10 0 1 1 -20 0").unwrap();
+        assert!((path.length() - 20.0 * PI).abs() < 0.1);
+        let (x1, y1, x2, y2) = path.extents().unwrap();
+        assert!((x1 - 0.0).abs() < 1e-3 && (x2 - 20.0).abs() < 1e-3);
+        assert!((y1 - 0.0).abs() < 0.1 && (y2 - 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn serialize() {
+        let data = "M 1 2 L 3.5 -4 C 1 2 3 4 5 6 Z M 1 2";
+        let path = Path::from_svg_path_data(data).unwrap();
+        assert_eq!(path.to_svg_path_data(), data);
+    }
+}
@@ -1,8 +1,11 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
-use std::{iter::FusedIterator, ptr};
+use std::{alloc, iter::FusedIterator, ptr};
 
-use crate::{PathDataType, ffi};
+use crate::{Matrix, PathDataType, ffi};
+
+// Cairo's default tolerance, used for measuring curves.
+const DEFAULT_TOLERANCE: f64 = 0.1;
 
 #[derive(Debug)]
 #[doc(alias = "cairo_path_t")]
@@ -44,6 +47,272 @@ impl Path {
     }
 }
 
+impl Path {
+    // rustdoc-stripper-ignore-next
+    /// Creates a builder for constructing a path from segments.
+    pub fn builder() -> PathBuilder {
+        PathBuilder::new()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a copy of the path with all curves replaced by straight lines that deviate from
+    /// them by at most `tolerance`.
+    pub fn flatten(&self, tolerance: f64) -> Path {
+        let mut builder = PathBuilder::new();
+        let mut current = None;
+        let mut start = None;
+        for segment in self.iter() {
+            match segment {
+                PathSegment::CurveTo(c1, c2, p) => {
+                    let p0 = current.unwrap_or(c1);
+                    if current.is_none() {
+                        builder.move_to(c1.0, c1.1);
+                        start = Some(c1);
+                    }
+                    flatten_curve(p0, c1, c2, p, tolerance, 0, &mut |p| {
+                        builder.line_to(p.0, p.1);
+                    });
+                    current = Some(p);
+                }
+                segment => {
+                    track_current_point(segment, &mut current, &mut start);
+                    builder.segment(segment);
+                }
+            }
+        }
+        builder.build()
+    }
+
+    // Returns the straight pieces the path consists of after flattening, including the ones
+    // added by closing subpaths.
+    fn lines(&self) -> Vec<((f64, f64), (f64, f64))> {
+        let mut lines = Vec::new();
+        let mut current = None;
+        let mut start = None;
+        for segment in self.iter() {
+            match segment {
+                PathSegment::MoveTo(_) => (),
+                PathSegment::LineTo(p) => {
+                    if let Some(p0) = current {
+                        lines.push((p0, p));
+                    }
+                }
+                PathSegment::CurveTo(c1, c2, p) => {
+                    let mut p0 = current.unwrap_or(c1);
+                    flatten_curve(p0, c1, c2, p, DEFAULT_TOLERANCE, 0, &mut |p| {
+                        lines.push((p0, p));
+                        p0 = p;
+                    });
+                }
+                PathSegment::ClosePath => {
+                    if let (Some(p0), Some(p)) = (current, start) {
+                        lines.push((p0, p));
+                    }
+                }
+            }
+            track_current_point(segment, &mut current, &mut start);
+        }
+        lines
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the total length of the path, including the lines closing subpaths.
+    ///
+    /// Curves are measured with cairo's default tolerance of 0.1.
+    pub fn length(&self) -> f64 {
+        self.lines()
+            .into_iter()
+            .map(|(p0, p1)| distance(p0, p1))
+            .sum()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the point at `distance` along the path and the unit tangent vector of the path
+    /// at that point.
+    ///
+    /// Returns `None` if the path has no length or `distance` is not between 0 and
+    /// [`length()`](Self::length).
+    pub fn point_and_tangent_at(&self, distance: f64) -> Option<((f64, f64), (f64, f64))> {
+        if distance < 0.0 {
+            return None;
+        }
+        let mut remaining = distance;
+        let mut last = None;
+        for (p0, p1) in self.lines() {
+            let length = self::distance(p0, p1);
+            if length == 0.0 {
+                continue;
+            }
+            let tangent = ((p1.0 - p0.0) / length, (p1.1 - p0.1) / length);
+            if remaining <= length {
+                let point = (p0.0 + tangent.0 * remaining, p0.1 + tangent.1 * remaining);
+                return Some((point, tangent));
+            }
+            remaining -= length;
+            last = Some((p1, tangent));
+        }
+        // Allow for rounding errors when asking for the end of the path
+        last.filter(|_| remaining <= f64::EPSILON * distance.max(1.0) * 4.0)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the exact bounding box `(x1, y1, x2, y2)` of the path.
+    ///
+    /// Like [`Context::path_extents()`](crate::Context::path_extents), points that are only
+    /// moved to are ignored. Returns `None` if nothing is drawn by the path.
+    pub fn extents(&self) -> Option<(f64, f64, f64, f64)> {
+        let mut extents: Option<(f64, f64, f64, f64)> = None;
+        let mut add = |(x, y): (f64, f64)| {
+            extents = Some(match extents {
+                Some((x1, y1, x2, y2)) => (x1.min(x), y1.min(y), x2.max(x), y2.max(y)),
+                None => (x, y, x, y),
+            });
+        };
+        let mut current = None;
+        let mut start = None;
+        for segment in self.iter() {
+            match segment {
+                PathSegment::MoveTo(_) => (),
+                PathSegment::LineTo(p) => {
+                    current.into_iter().for_each(&mut add);
+                    add(p);
+                }
+                PathSegment::CurveTo(c1, c2, p) => {
+                    let p0 = current.unwrap_or(c1);
+                    add(p0);
+                    add(p);
+                    for t in curve_extrema(p0.0, c1.0, c2.0, p.0)
+                        .into_iter()
+                        .chain(curve_extrema(p0.1, c1.1, c2.1, p.1))
+                        .flatten()
+                    {
+                        add(curve_point(p0, c1, c2, p, t));
+                    }
+                }
+                PathSegment::ClosePath => current.into_iter().for_each(&mut add),
+            }
+            track_current_point(segment, &mut current, &mut start);
+        }
+        extents
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a copy of the path with all points transformed by `matrix`.
+    pub fn transform(&self, matrix: &Matrix) -> Path {
+        let transform = |(x, y): (f64, f64)| matrix.transform_point(x, y);
+        self.iter()
+            .map(|segment| match segment {
+                PathSegment::MoveTo(p) => PathSegment::MoveTo(transform(p)),
+                PathSegment::LineTo(p) => PathSegment::LineTo(transform(p)),
+                PathSegment::CurveTo(c1, c2, p) => {
+                    PathSegment::CurveTo(transform(c1), transform(c2), transform(p))
+                }
+                PathSegment::ClosePath => PathSegment::ClosePath,
+            })
+            .collect()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a copy of the path that is traversed in the opposite direction.
+    ///
+    /// The order of the subpaths is reversed as well. Closed subpaths stay closed.
+    pub fn reverse(&self) -> Path {
+        struct Subpath {
+            start: (f64, f64),
+            // Each segment with the point it starts at
+            segments: Vec<((f64, f64), PathSegment)>,
+            closed: bool,
+        }
+
+        let mut subpaths: Vec<Subpath> = Vec::new();
+        let mut current = None;
+        let mut start = None;
+        for segment in self.iter() {
+            let open = subpaths.last().is_some_and(|s| !s.closed);
+            match segment {
+                PathSegment::MoveTo(p) => subpaths.push(Subpath {
+                    start: p,
+                    segments: Vec::new(),
+                    closed: false,
+                }),
+                PathSegment::ClosePath => {
+                    if open {
+                        subpaths.last_mut().unwrap().closed = true;
+                    }
+                }
+                PathSegment::LineTo(p) | PathSegment::CurveTo(_, _, p) => {
+                    let p0 = match segment {
+                        PathSegment::CurveTo(c1, ..) => current.unwrap_or(c1),
+                        _ => current.unwrap_or(p),
+                    };
+                    if !open {
+                        subpaths.push(Subpath {
+                            start: p0,
+                            segments: Vec::new(),
+                            closed: false,
+                        });
+                    }
+                    subpaths.last_mut().unwrap().segments.push((p0, segment));
+                }
+            }
+            track_current_point(segment, &mut current, &mut start);
+        }
+
+        let mut builder = PathBuilder::new();
+        for subpath in subpaths.iter().rev() {
+            let end = subpath
+                .segments
+                .last()
+                .map_or(subpath.start, |(_, s)| match s {
+                    PathSegment::LineTo(p) | PathSegment::CurveTo(_, _, p) => *p,
+                    _ => unreachable!(),
+                });
+            if subpath.closed {
+                // Walk the closing line first, it ends where the reversed segments begin
+                builder.move_to(subpath.start.0, subpath.start.1);
+                if end != subpath.start {
+                    builder.line_to(end.0, end.1);
+                }
+            } else {
+                builder.move_to(end.0, end.1);
+            }
+            for (i, (p0, segment)) in subpath.segments.iter().enumerate().rev() {
+                match *segment {
+                    PathSegment::CurveTo(c1, c2, _) => {
+                        builder.curve_to(c2.0, c2.1, c1.0, c1.1, p0.0, p0.1);
+                    }
+                    // Closing the subpath draws the line back to its start
+                    _ if i == 0 && subpath.closed => (),
+                    _ => {
+                        builder.line_to(p0.0, p0.1);
+                    }
+                }
+            }
+            if subpath.closed {
+                builder.close_path();
+            }
+        }
+        builder.build()
+    }
+}
+
+impl FromIterator<PathSegment> for Path {
+    fn from_iter<T: IntoIterator<Item = PathSegment>>(iter: T) -> Self {
+        let mut builder = PathBuilder::new();
+        builder.extend(iter);
+        builder.build()
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = PathSegment;
+    type IntoIter = PathSegments<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl Drop for Path {
     #[inline]
     fn drop(&mut self) {
@@ -61,6 +330,226 @@ pub enum PathSegment {
     ClosePath,
 }
 
+// rustdoc-stripper-ignore-next
+/// A builder for [`Path`]s.
+///
+/// The built path can be drawn with [`Context::append_path()`](crate::Context::append_path).
+///
+/// ```no_run
+/// # fn main() -> Result<(), cairo::Error> {
+/// let path = cairo::Path::builder()
+///     .move_to(0.0, 0.0)
+///     .line_to(10.0, 0.0)
+///     .curve_to(15.0, 0.0, 15.0, 10.0, 10.0, 10.0)
+///     .close_path()
+///     .build();
+///
+/// let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 20, 20)?;
+/// let cr = cairo::Context::new(&surface)?;
+/// cr.append_path(&path);
+/// cr.fill()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PathBuilder {
+    segments: Vec<PathSegment>,
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.segment(PathSegment::MoveTo((x, y)))
+    }
+
+    pub fn line_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.segment(PathSegment::LineTo((x, y)))
+    }
+
+    pub fn curve_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) -> &mut Self {
+        self.segment(PathSegment::CurveTo((x1, y1), (x2, y2), (x3, y3)))
+    }
+
+    pub fn close_path(&mut self) -> &mut Self {
+        self.segment(PathSegment::ClosePath)
+    }
+
+    pub fn segment(&mut self, segment: PathSegment) -> &mut Self {
+        self.segments.push(segment);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new path from the segments added so far.
+    pub fn build(&self) -> Path {
+        let num_data = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::MoveTo(_) | PathSegment::LineTo(_) => 2,
+                PathSegment::CurveTo(..) => 4,
+                PathSegment::ClosePath => 1,
+            })
+            .sum::<usize>();
+
+        let header = |data_type: PathDataType, length| ffi::cairo_path_data {
+            header: ffi::cairo_path_data_header {
+                data_type: data_type.into(),
+                length,
+            },
+        };
+        let point = |(x, y): (f64, f64)| ffi::cairo_path_data { point: [x, y] };
+        let mut data = Vec::with_capacity(num_data);
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::MoveTo(p) => data.extend([header(PathDataType::MoveTo, 2), point(p)]),
+                PathSegment::LineTo(p) => data.extend([header(PathDataType::LineTo, 2), point(p)]),
+                PathSegment::CurveTo(c1, c2, p) => data.extend([
+                    header(PathDataType::CurveTo, 4),
+                    point(c1),
+                    point(c2),
+                    point(p),
+                ]),
+                PathSegment::ClosePath => data.push(header(PathDataType::ClosePath, 1)),
+            }
+        }
+
+        // `cairo_path_destroy()` frees the path and its data with `free()`
+        unsafe {
+            let path = malloc::<ffi::cairo_path_t>(1);
+            let path_data = malloc::<ffi::cairo_path_data>(num_data);
+            ptr::copy_nonoverlapping(data.as_ptr(), path_data, num_data);
+            path.write(ffi::cairo_path_t {
+                status: ffi::STATUS_SUCCESS,
+                data: path_data,
+                num_data: num_data as _,
+            });
+            Path::from_raw_full(path)
+        }
+    }
+}
+
+impl Extend<PathSegment> for PathBuilder {
+    fn extend<T: IntoIterator<Item = PathSegment>>(&mut self, iter: T) {
+        self.segments.extend(iter);
+    }
+}
+
+unsafe fn malloc<T>(n: usize) -> *mut T {
+    let layout = alloc::Layout::array::<T>(n.max(1)).expect("path too large");
+    let ptr = unsafe { libc::malloc(layout.size()) } as *mut T;
+    if ptr.is_null() {
+        alloc::handle_alloc_error(layout);
+    }
+    ptr
+}
+
+// Updates the current point and the start of the current subpath like cairo does.
+fn track_current_point(
+    segment: PathSegment,
+    current: &mut Option<(f64, f64)>,
+    start: &mut Option<(f64, f64)>,
+) {
+    match segment {
+        PathSegment::MoveTo(p) => {
+            *current = Some(p);
+            *start = Some(p);
+        }
+        PathSegment::LineTo(p) | PathSegment::CurveTo(_, _, p) => {
+            if current.is_none() {
+                *start = Some(match segment {
+                    PathSegment::CurveTo(c1, ..) => c1,
+                    _ => p,
+                });
+            }
+            *current = Some(p);
+        }
+        PathSegment::ClosePath => *current = *start,
+    }
+}
+
+fn distance(p0: (f64, f64), p1: (f64, f64)) -> f64 {
+    (p1.0 - p0.0).hypot(p1.1 - p0.1)
+}
+
+fn curve_point(
+    p0: (f64, f64),
+    c1: (f64, f64),
+    c2: (f64, f64),
+    p: (f64, f64),
+    t: f64,
+) -> (f64, f64) {
+    let mt = 1.0 - t;
+    let a = mt * mt * mt;
+    let b = 3.0 * mt * mt * t;
+    let c = 3.0 * mt * t * t;
+    let d = t * t * t;
+    (
+        a * p0.0 + b * c1.0 + c * c2.0 + d * p.0,
+        a * p0.1 + b * c1.1 + c * c2.1 + d * p.1,
+    )
+}
+
+// Returns the parameters in (0, 1) where one coordinate of a cubic Bézier curve has an extremum.
+fn curve_extrema(p0: f64, c1: f64, c2: f64, p: f64) -> [Option<f64>; 2] {
+    // Roots of the derivative a t² + b t + c
+    let a = 3.0 * (-p0 + 3.0 * c1 - 3.0 * c2 + p);
+    let b = 6.0 * (p0 - 2.0 * c1 + c2);
+    let c = 3.0 * (c1 - p0);
+    let in_range = |t: f64| (t > 0.0 && t < 1.0).then_some(t);
+    if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return [None, None];
+        }
+        return [in_range(-c / b), None];
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return [None, None];
+    }
+    let root = discriminant.sqrt();
+    [
+        in_range((-b + root) / (2.0 * a)),
+        in_range((-b - root) / (2.0 * a)),
+    ]
+}
+
+// Subdivides a cubic Bézier curve until it is flat enough, and emits the end points of the
+// resulting lines.
+fn flatten_curve(
+    p0: (f64, f64),
+    c1: (f64, f64),
+    c2: (f64, f64),
+    p: (f64, f64),
+    tolerance: f64,
+    depth: u32,
+    emit: &mut impl FnMut((f64, f64)),
+) {
+    // Bounds the distance between the curve and its chord
+    let ux = 3.0 * c1.0 - 2.0 * p0.0 - p.0;
+    let uy = 3.0 * c1.1 - 2.0 * p0.1 - p.1;
+    let vx = 3.0 * c2.0 - p0.0 - 2.0 * p.0;
+    let vy = 3.0 * c2.1 - p0.1 - 2.0 * p.1;
+    let flatness = (ux * ux).max(vx * vx) + (uy * uy).max(vy * vy);
+    if depth >= 16 || flatness <= 16.0 * tolerance * tolerance {
+        emit(p);
+        return;
+    }
+
+    let mid = |a: (f64, f64), b: (f64, f64)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    let p01 = mid(p0, c1);
+    let p12 = mid(c1, c2);
+    let p23 = mid(c2, p);
+    let p012 = mid(p01, p12);
+    let p123 = mid(p12, p23);
+    let split = mid(p012, p123);
+    flatten_curve(p0, p01, p012, split, tolerance, depth + 1, emit);
+    flatten_curve(split, p123, p23, p, tolerance, depth + 1, emit);
+}
+
 pub struct PathSegments<'a> {
     data: &'a [ffi::cairo_path_data],
     i: usize,
@@ -106,6 +595,13 @@ mod tests {
     use super::*;
     use crate::{context::*, enums::Format, image_surface::*};
 
+    fn assert_close(a: (f64, f64), b: (f64, f64)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9,
+            "{a:?} != {b:?}"
+        );
+    }
+
     fn make_cr() -> Context {
         let surface = ImageSurface::create(Format::Rgb24, 1, 1).unwrap();
 
@@ -214,4 +710,109 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn builder() {
+        let segments = [
+            PathSegment::MoveTo((1.0, 2.0)),
+            PathSegment::CurveTo((3.0, 4.0), (5.0, 6.0), (7.0, 8.0)),
+            PathSegment::ClosePath,
+            PathSegment::MoveTo((1.0, 2.0)),
+            PathSegment::LineTo((9.0, 10.0)),
+        ];
+        let path = segments.iter().copied().collect::<Path>();
+        assert_path_equals_segments(&path, &segments);
+
+        let cr = make_cr();
+        cr.append_path(&path);
+        let copy = cr.copy_path().expect("Invalid path");
+        assert_path_equals_segments(&copy, &segments);
+
+        let empty = Path::builder().build();
+        assert!(empty.iter().next().is_none());
+        assert_eq!(empty.extents(), None);
+        assert_eq!(empty.point_and_tangent_at(0.0), None);
+    }
+
+    #[test]
+    fn measure() {
+        let square = Path::builder()
+            .move_to(0.0, 0.0)
+            .line_to(10.0, 0.0)
+            .line_to(10.0, 10.0)
+            .line_to(0.0, 10.0)
+            .close_path()
+            .build();
+        assert_eq!(square.length(), 40.0);
+        assert_eq!(square.extents(), Some((0.0, 0.0, 10.0, 10.0)));
+
+        let (point, tangent) = square.point_and_tangent_at(15.0).unwrap();
+        assert_close(point, (10.0, 5.0));
+        assert_close(tangent, (0.0, 1.0));
+        // The closing line is part of the path
+        let (point, tangent) = square.point_and_tangent_at(40.0).unwrap();
+        assert_close(point, (0.0, 0.0));
+        assert_close(tangent, (0.0, -1.0));
+        assert_eq!(square.point_and_tangent_at(40.5), None);
+
+        // The extents of curves are exact and don't include the control points
+        let curve = Path::builder()
+            .move_to(0.0, 0.0)
+            .curve_to(0.0, 10.0, 10.0, 10.0, 10.0, 0.0)
+            .build();
+        let (x1, y1, x2, y2) = curve.extents().unwrap();
+        assert_close((x1, y1), (0.0, 0.0));
+        assert_close((x2, y2), (10.0, 7.5));
+
+        let flat = curve.flatten(0.01);
+        assert!(
+            flat.iter()
+                .skip(1)
+                .all(|s| matches!(s, PathSegment::LineTo(_)))
+        );
+        assert!(flat.iter().count() > 10);
+        assert!((flat.length() - curve.length()).abs() < 0.1);
+    }
+
+    #[test]
+    fn transform_and_reverse() {
+        let path = Path::builder()
+            .move_to(0.0, 0.0)
+            .line_to(1.0, 0.0)
+            .curve_to(2.0, 0.0, 2.0, 1.0, 1.0, 1.0)
+            .close_path()
+            .move_to(5.0, 5.0)
+            .line_to(6.0, 5.0)
+            .build();
+
+        let mut matrix = Matrix::identity();
+        matrix.translate(10.0, 0.0);
+        matrix.scale(2.0, 2.0);
+        assert_path_equals_segments(
+            &path.transform(&matrix),
+            &[
+                PathSegment::MoveTo((10.0, 0.0)),
+                PathSegment::LineTo((12.0, 0.0)),
+                PathSegment::CurveTo((14.0, 0.0), (14.0, 2.0), (12.0, 2.0)),
+                PathSegment::ClosePath,
+                PathSegment::MoveTo((20.0, 10.0)),
+                PathSegment::LineTo((22.0, 10.0)),
+            ],
+        );
+
+        let reversed = path.reverse();
+        assert_path_equals_segments(
+            &reversed,
+            &[
+                PathSegment::MoveTo((6.0, 5.0)),
+                PathSegment::LineTo((5.0, 5.0)),
+                PathSegment::MoveTo((0.0, 0.0)),
+                PathSegment::LineTo((1.0, 1.0)),
+                PathSegment::CurveTo((2.0, 1.0), (2.0, 0.0), (1.0, 0.0)),
+                PathSegment::ClosePath,
+            ],
+        );
+        assert_path_equals_segments(&reversed.reverse(), &path.iter().collect::<Vec<_>>());
+        assert_eq!(reversed.length(), path.length());
+    }
 }
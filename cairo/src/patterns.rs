@@ -1,12 +1,12 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
-use std::{ops::Deref, ptr};
+use std::{cell::RefCell, mem, ops::Deref, ptr, rc::Rc};
 
-use libc::{c_double, c_int, c_uint};
+use libc::{c_double, c_int, c_uint, c_void};
 
 use crate::{
-    Error, Extend, Filter, Matrix, MeshCorner, Path, PatternType, Surface, ffi,
-    utils::status_to_result,
+    Content, Error, Extend, Filter, ImageSurface, Matrix, MeshCorner, Path, PatternType,
+    RectangleInt, Surface, ffi, utils::status_to_result,
 };
 
 // See https://cairographics.org/manual/bindings-patterns.html for more info
@@ -546,6 +546,219 @@ impl Default for Mesh {
     }
 }
 
+type AcquireFunc = Rc<dyn Fn(Option<&Surface>, RectangleInt) -> Result<ImageSurface, Error>>;
+type ReleaseFunc = Rc<dyn Fn(ImageSurface)>;
+type StatusFunc = Rc<dyn Fn() -> Result<(), Error>>;
+
+// The callback data of a raster source pattern. It is shared between the pattern and all its
+// copies, each of them holds a strong reference that is released by the finish callback.
+#[derive(Default)]
+struct RasterSourceFuncs {
+    acquire: RefCell<Option<AcquireFunc>>,
+    release: RefCell<Option<ReleaseFunc>>,
+    snapshot: RefCell<Option<StatusFunc>>,
+    copy: RefCell<Option<StatusFunc>>,
+    finish: RefCell<Option<Box<dyn FnOnce()>>>,
+}
+
+impl Drop for RasterSourceFuncs {
+    fn drop(&mut self) {
+        if let Some(finish) = self.finish.get_mut().take() {
+            finish();
+        }
+    }
+}
+
+pattern_type!(RasterSourcePattern);
+
+impl RasterSourcePattern {
+    // rustdoc-stripper-ignore-next
+    /// Creates a pattern whose pixels are supplied on demand by the
+    /// [acquire function](Self::set_acquire).
+    ///
+    /// `width` and `height` give the size of the pattern source, drawing outside of it is
+    /// governed by the pattern's [`Extend`] mode. The callbacks of the pattern are shared with
+    /// copies of it that cairo makes internally, e.g. when the pattern is recorded by a
+    /// [`RecordingSurface`](crate::RecordingSurface).
+    #[doc(alias = "cairo_pattern_create_raster_source")]
+    pub fn new(content: Content, width: i32, height: i32) -> Self {
+        let funcs = Rc::into_raw(Rc::new(RasterSourceFuncs::default()));
+        unsafe {
+            let pattern = Pattern::from_raw_full(ffi::cairo_pattern_create_raster_source(
+                funcs as *mut c_void,
+                content.into(),
+                width,
+                height,
+            ));
+            if pattern.status().is_ok() {
+                ffi::cairo_raster_source_pattern_set_finish(
+                    pattern.to_raw_none(),
+                    Some(raster_source_finish_trampoline),
+                );
+                ffi::cairo_raster_source_pattern_set_snapshot(
+                    pattern.to_raw_none(),
+                    Some(raster_source_snapshot_trampoline),
+                );
+                ffi::cairo_raster_source_pattern_set_copy(
+                    pattern.to_raw_none(),
+                    Some(raster_source_copy_trampoline),
+                );
+            } else {
+                // The nil pattern never calls the finish function
+                drop(Rc::from_raw(funcs));
+            }
+            Self(pattern)
+        }
+    }
+
+    fn funcs(&self) -> Option<&RasterSourceFuncs> {
+        unsafe {
+            let funcs = ffi::cairo_raster_source_pattern_get_callback_data(self.to_raw_none());
+            (funcs as *const RasterSourceFuncs).as_ref()
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the function that supplies the pixels of the pattern when it is drawn.
+    ///
+    /// `acquire` is called with the surface being drawn to, if any, and the extents of the
+    /// pattern that are needed, and returns an image covering those extents. Once cairo is done
+    /// with the image, it is passed to the `release` function, or dropped if there is none.
+    ///
+    /// If `acquire` fails, nothing is drawn.
+    #[doc(alias = "cairo_raster_source_pattern_set_acquire")]
+    pub fn set_acquire<F, R>(&self, acquire: F, release: Option<R>)
+    where
+        F: Fn(Option<&Surface>, RectangleInt) -> Result<ImageSurface, Error> + 'static,
+        R: Fn(ImageSurface) + 'static,
+    {
+        let Some(funcs) = self.funcs() else {
+            return;
+        };
+        *funcs.acquire.borrow_mut() = Some(Rc::new(acquire));
+        *funcs.release.borrow_mut() = release.map(|release| Rc::new(release) as ReleaseFunc);
+        unsafe {
+            ffi::cairo_raster_source_pattern_set_acquire(
+                self.to_raw_none(),
+                Some(raster_source_acquire_trampoline),
+                Some(raster_source_release_trampoline),
+            );
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the function that is called when the current contents of the pattern have to be
+    /// preserved, e.g. because the pattern is recorded to be drawn later.
+    ///
+    /// If the source of the pixels may change afterwards, the function has to keep a copy of
+    /// the current contents and supply them from then on.
+    #[doc(alias = "cairo_raster_source_pattern_set_snapshot")]
+    pub fn set_snapshot<F: Fn() -> Result<(), Error> + 'static>(&self, snapshot: F) {
+        if let Some(funcs) = self.funcs() {
+            *funcs.snapshot.borrow_mut() = Some(Rc::new(snapshot));
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the function that is called when cairo makes a copy of the pattern.
+    ///
+    /// The copy shares the callbacks with the pattern. Returning an error makes the copy fail.
+    #[doc(alias = "cairo_raster_source_pattern_set_copy")]
+    pub fn set_copy<F: Fn() -> Result<(), Error> + 'static>(&self, copy: F) {
+        if let Some(funcs) = self.funcs() {
+            *funcs.copy.borrow_mut() = Some(Rc::new(copy));
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the function that is called once the pattern and all copies of it are destroyed.
+    #[doc(alias = "cairo_raster_source_pattern_set_finish")]
+    pub fn set_finish<F: FnOnce() + 'static>(&self, finish: F) {
+        if let Some(funcs) = self.funcs() {
+            *funcs.finish.borrow_mut() = Some(Box::new(finish));
+        }
+    }
+}
+
+unsafe extern "C" fn raster_source_acquire_trampoline(
+    _pattern: *mut ffi::cairo_pattern_t,
+    callback_data: *mut c_void,
+    target: *mut ffi::cairo_surface_t,
+    extents: *const ffi::cairo_rectangle_int_t,
+) -> *mut ffi::cairo_surface_t {
+    unsafe {
+        let funcs = &*(callback_data as *const RasterSourceFuncs);
+        let Some(acquire) = funcs.acquire.borrow().clone() else {
+            return ptr::null_mut();
+        };
+        let target = (!target.is_null()).then(|| Surface::from_raw_borrow(target));
+        let extents = &*extents;
+        let extents = RectangleInt::new(extents.x, extents.y, extents.width, extents.height);
+        match acquire(target.as_deref(), extents) {
+            // Ownership is passed back to the release function
+            Ok(surface) => mem::ManuallyDrop::new(surface).to_raw_none(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+}
+
+unsafe extern "C" fn raster_source_release_trampoline(
+    _pattern: *mut ffi::cairo_pattern_t,
+    callback_data: *mut c_void,
+    surface: *mut ffi::cairo_surface_t,
+) {
+    unsafe {
+        let funcs = &*(callback_data as *const RasterSourceFuncs);
+        let Ok(surface) = ImageSurface::from_raw_full(surface) else {
+            return;
+        };
+        let release = funcs.release.borrow().clone();
+        if let Some(release) = release {
+            release(surface);
+        }
+    }
+}
+
+unsafe extern "C" fn raster_source_snapshot_trampoline(
+    _pattern: *mut ffi::cairo_pattern_t,
+    callback_data: *mut c_void,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let funcs = &*(callback_data as *const RasterSourceFuncs);
+        let snapshot = funcs.snapshot.borrow().clone();
+        match snapshot.map_or(Ok(()), |snapshot| snapshot()) {
+            Err(err) => err.into(),
+            Ok(()) => ffi::STATUS_SUCCESS,
+        }
+    }
+}
+
+unsafe extern "C" fn raster_source_copy_trampoline(
+    _pattern: *mut ffi::cairo_pattern_t,
+    callback_data: *mut c_void,
+    _other: *const ffi::cairo_pattern_t,
+) -> ffi::cairo_status_t {
+    unsafe {
+        let funcs = &*(callback_data as *const RasterSourceFuncs);
+        let copy = funcs.copy.borrow().clone();
+        match copy.map_or(Ok(()), |copy| copy()) {
+            Err(err) => err.into(),
+            Ok(()) => {
+                // A failed copy is never finished, so only take the reference on success
+                Rc::increment_strong_count(callback_data as *const RasterSourceFuncs);
+                ffi::STATUS_SUCCESS
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn raster_source_finish_trampoline(
+    _pattern: *mut ffi::cairo_pattern_t,
+    callback_data: *mut c_void,
+) {
+    unsafe { drop(Rc::from_raw(callback_data as *const RasterSourceFuncs)) }
+}
+
 #[test]
 fn try_from() {
     let linear = LinearGradient::new(0., 0., 1., 1.);
@@ -555,3 +768,57 @@ fn try_from() {
     assert!(LinearGradient::try_from(gradient).is_ok());
     assert!(LinearGradient::try_from(pattern).is_ok());
 }
+
+#[test]
+fn raster_source() {
+    use std::cell::Cell;
+
+    use crate::{Context, Format};
+
+    let acquired = Rc::new(RefCell::new(Vec::new()));
+    let released = Rc::new(Cell::new(0));
+    let finished = Rc::new(Cell::new(false));
+
+    let pattern = RasterSourcePattern::new(Content::Color, 8, 8);
+    pattern.set_acquire(
+        {
+            let acquired = acquired.clone();
+            move |target: Option<&Surface>, extents: RectangleInt| {
+                assert!(target.is_some());
+                acquired.borrow_mut().push(extents);
+                let image = ImageSurface::create(Format::Rgb24, extents.width(), extents.height())?;
+                let cr = Context::new(&image)?;
+                cr.set_source_rgb(1.0, 0.0, 0.0);
+                cr.paint()?;
+                drop(cr);
+                Ok(image)
+            }
+        },
+        Some({
+            let released = released.clone();
+            move |_: ImageSurface| released.set(released.get() + 1)
+        }),
+    );
+    pattern.set_finish({
+        let finished = finished.clone();
+        move || finished.set(true)
+    });
+
+    let mut target = ImageSurface::create(Format::Rgb24, 4, 4).unwrap();
+    let cr = Context::new(&target).unwrap();
+    cr.set_source(&pattern).unwrap();
+    cr.paint().unwrap();
+    drop(cr);
+    assert!(!acquired.borrow().is_empty());
+    assert_eq!(released.get(), acquired.borrow().len());
+
+    let stride = target.stride() as usize;
+    let data = target.data().unwrap();
+    let pixel = u32::from_ne_bytes(data[stride..stride + 4].try_into().unwrap());
+    assert_eq!(pixel & 0x00ff_ffff, 0x00ff_0000);
+    drop(data);
+
+    assert!(!finished.get());
+    drop(pattern);
+    assert!(finished.get());
+}
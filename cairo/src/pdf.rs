@@ -13,7 +13,7 @@ use glib::translate::*;
 
 use crate::{Error, PdfVersion, Surface, SurfaceType, ffi};
 #[cfg(all(feature = "pdf", feature = "v1_16"))]
-use crate::{PdfMetadata, PdfOutline};
+use crate::{Link, PdfMetadata, PdfOutline};
 
 impl PdfVersion {
     pub fn as_str(self) -> Option<&'static str> {
@@ -154,6 +154,23 @@ impl PdfSurface {
         self.status()?;
         Ok(res)
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds an outline item pointing to `link`, like [`add_outline()`](Self::add_outline).
+    ///
+    /// The rectangles of the link are not used for outlines.
+    #[cfg(all(feature = "pdf", feature = "v1_16"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "pdf", feature = "v1_16"))))]
+    pub fn add_outline_link(
+        &self,
+        parent_id: i32,
+        name: &str,
+        link: &Link,
+        flags: PdfOutline,
+    ) -> Result<i32, Error> {
+        let link = Link::new(link.target().clone());
+        self.add_outline(parent_id, name, &link.attributes()?, flags)
+    }
 }
 
 #[cfg(test)]
@@ -35,6 +35,51 @@ macro_rules! for_stream_constructors {
             )?))
         }
 
+        /// Writes into a [`gio::OutputStream`] without blocking, from a task spawned on the
+        /// thread default main context.
+        ///
+        /// Output is passed to the output stream in chunks of
+        /// [`DEFAULT_OUTPUT_STREAM_BUFFER_SIZE`] bytes. A few chunks are buffered while they
+        /// wait to be written. When that buffer is full, drawing iterates the thread default
+        /// main context until a chunk was written, so other sources of the main context must not
+        /// use the surface. If the main context is owned by another thread, drawing fails
+        /// instead. Use [`Surface::finish_future`] to wait until everything is written.
+        ///
+        /// [`DEFAULT_OUTPUT_STREAM_BUFFER_SIZE`]: crate::DEFAULT_OUTPUT_STREAM_BUFFER_SIZE
+        #[cfg(feature = "gio")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "gio")))]
+        pub fn for_output_stream(
+            width: f64,
+            height: f64,
+            stream: &impl glib::object::IsA<gio::OutputStream>,
+        ) -> Result<Self, crate::error::Error> {
+            Self::for_output_stream_with_buffer_size(
+                width,
+                height,
+                stream,
+                crate::DEFAULT_OUTPUT_STREAM_BUFFER_SIZE,
+            )
+        }
+
+        /// Like [`Self::for_output_stream`], with chunks of `buffer_size` bytes.
+        #[cfg(feature = "gio")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "gio")))]
+        pub fn for_output_stream_with_buffer_size(
+            width: f64,
+            height: f64,
+            stream: &impl glib::object::IsA<gio::OutputStream>,
+            buffer_size: usize,
+        ) -> Result<Self, crate::error::Error> {
+            Ok(Self(Surface::_for_output_stream(
+                ffi::$constructor_ffi,
+                width,
+                height,
+                stream.as_ref(),
+                buffer_size,
+                glib::Priority::DEFAULT,
+            )?))
+        }
+
         /// Allows writing to a borrowed stream. The lifetime of the borrow is not tracked.
         ///
         /// # Safety
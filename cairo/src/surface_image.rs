@@ -0,0 +1,369 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+// Conversions between `ImageSurface` and the `image` crate.
+//
+// Cairo stores pixels in native-endian 32 bit words with premultiplied alpha, while the
+// `image` crate uses separate channels in memory order with straight alpha.
+
+use std::ops::Deref;
+
+use image::{DynamicImage, ImageBuffer, Luma, Pixel, Rgb, Rgba, RgbaImage};
+
+use crate::{BorrowError, Error, Format, ImageSurface, ImageSurfaceData};
+
+impl ImageSurface {
+    // rustdoc-stripper-ignore-next
+    /// Creates an [`ARgb32`](Format::ARgb32) surface from an RGBA image, premultiplying the
+    /// alpha channel.
+    pub fn from_rgba_image(image: &RgbaImage) -> Result<ImageSurface, Error> {
+        let (width, height) = image.dimensions();
+        Self::from_rgba8_straight(
+            i32::try_from(width).map_err(|_| Error::InvalidSize)?,
+            i32::try_from(height).map_err(|_| Error::InvalidSize)?,
+            image.as_raw(),
+        )
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a surface from an image, in the format that represents it best.
+    ///
+    /// Images without alpha channel become [`Rgb24`](Format::Rgb24) surfaces, 16 bit RGB images
+    /// [`Rgb30`](Format::Rgb30) surfaces and, with cairo 1.18, floating point images
+    /// `Rgb96f` or `Rgba128f` surfaces. Everything else is converted to
+    /// [`ARgb32`](Format::ARgb32).
+    pub fn from_image(image: &DynamicImage) -> Result<ImageSurface, Error> {
+        match image {
+            DynamicImage::ImageRgb16(image) => from_pixels(Format::Rgb30, image, 4, |p, out| {
+                let [r, g, b] = p.0.map(|c| u32::from(c >> 6));
+                out.copy_from_slice(&(r << 20 | g << 10 | b).to_ne_bytes());
+            }),
+            #[cfg(feature = "v1_18")]
+            DynamicImage::ImageRgb32F(image) => from_pixels(Format::Rgb96f, image, 12, |p, out| {
+                for (c, out) in p.0.iter().zip(out.chunks_exact_mut(4)) {
+                    out.copy_from_slice(&c.to_ne_bytes());
+                }
+            }),
+            #[cfg(feature = "v1_18")]
+            DynamicImage::ImageRgba32F(image) => {
+                from_pixels(Format::Rgba128f, image, 16, |&Rgba([r, g, b, a]), out| {
+                    for (c, out) in [r * a, g * a, b * a, a].iter().zip(out.chunks_exact_mut(4)) {
+                        out.copy_from_slice(&c.to_ne_bytes());
+                    }
+                })
+            }
+            image if !image.color().has_alpha() => from_pixels(
+                Format::Rgb24,
+                &image.to_rgb8(),
+                4,
+                |&Rgb([r, g, b]), out| {
+                    let pixel = u32::from(r) << 16 | u32::from(g) << 8 | u32::from(b);
+                    out.copy_from_slice(&pixel.to_ne_bytes());
+                },
+            ),
+            image => Self::from_rgba_image(&image.to_rgba8()),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Converts the surface to an RGBA image with straight alpha.
+    ///
+    /// [`A8`](Format::A8) surfaces become black images with the surface's alpha. Fails with
+    /// [`Error::InvalidFormat`] for [`A1`](Format::A1) surfaces.
+    pub fn to_rgba_image(&self) -> Result<RgbaImage, BorrowError> {
+        match self.format() {
+            Format::ARgb32 | Format::Rgb24 => {
+                let rgba = self.to_rgba8_unpremultiplied()?;
+                Ok(RgbaImage::from_raw(self.width() as u32, self.height() as u32, rgba).unwrap())
+            }
+            Format::A8 => map_pixels(self, 1, |p| Rgba([0, 0, 0, p[0]])),
+            Format::Rgb16_565 => map_pixels(self, 2, |p| {
+                let p = u16::from_ne_bytes(p.try_into().unwrap());
+                let [r, g, b] = [(p >> 11) & 0x1f, (p >> 5) & 0x3f, p & 0x1f];
+                Rgba([
+                    (r << 3 | r >> 2) as u8,
+                    (g << 2 | g >> 4) as u8,
+                    (b << 3 | b >> 2) as u8,
+                    0xff,
+                ])
+            }),
+            Format::Rgb30 => map_pixels(self, 4, |p| {
+                let p = u32::from_ne_bytes(p.try_into().unwrap());
+                let [r, g, b] = [p >> 20, p >> 10, p].map(|c| ((c & 0x3ff) >> 2) as u8);
+                Rgba([r, g, b, 0xff])
+            }),
+            #[cfg(feature = "v1_18")]
+            Format::Rgb96f => map_pixels(self, 12, |p| {
+                let [r, g, b] = read_f32s(p).map(f32_to_u8);
+                Rgba([r, g, b, 0xff])
+            }),
+            #[cfg(feature = "v1_18")]
+            Format::Rgba128f => map_pixels(self, 16, |p| {
+                let Rgba([r, g, b, a]) = rgba_f32_unpremultiplied(p);
+                Rgba([f32_to_u8(r), f32_to_u8(g), f32_to_u8(b), f32_to_u8(a)])
+            }),
+            _ => Err(BorrowError::from(Error::InvalidFormat)),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Converts the surface to the image type that represents it best.
+    ///
+    /// This is the inverse of [`from_image()`](Self::from_image): [`Rgb24`](Format::Rgb24)
+    /// surfaces become RGB images, [`Rgb30`](Format::Rgb30) surfaces 16 bit RGB images and
+    /// floating point surfaces floating point images. Everything else is converted with
+    /// [`to_rgba_image()`](Self::to_rgba_image).
+    pub fn to_dynamic_image(&self) -> Result<DynamicImage, BorrowError> {
+        match self.format() {
+            Format::Rgb24 => map_pixels(self, 4, |p| {
+                let p = u32::from_ne_bytes(p.try_into().unwrap());
+                Rgb([(p >> 16) as u8, (p >> 8) as u8, p as u8])
+            })
+            .map(DynamicImage::ImageRgb8),
+            Format::Rgb30 => map_pixels(self, 4, |p| {
+                let p = u32::from_ne_bytes(p.try_into().unwrap());
+                Rgb([p >> 20, p >> 10, p].map(|c| {
+                    let c = (c & 0x3ff) as u16;
+                    c << 6 | c >> 4
+                }))
+            })
+            .map(DynamicImage::ImageRgb16),
+            #[cfg(feature = "v1_18")]
+            Format::Rgb96f => {
+                map_pixels(self, 12, |p| Rgb(read_f32s(p))).map(DynamicImage::ImageRgb32F)
+            }
+            #[cfg(feature = "v1_18")]
+            Format::Rgba128f => {
+                map_pixels(self, 16, rgba_f32_unpremultiplied).map(DynamicImage::ImageRgba32F)
+            }
+            _ => self.to_rgba_image().map(DynamicImage::ImageRgba8),
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+// rustdoc-stripper-ignore-next
+/// Pixel types whose memory layout matches an [`ImageSurface`] format, so that the surface data
+/// can be viewed as an [`ImageBuffer`] without copying.
+///
+/// Note that cairo uses premultiplied alpha.
+pub trait ImageSurfacePixel: Pixel + sealed::Sealed {
+    const FORMAT: Format;
+}
+
+impl sealed::Sealed for Luma<u8> {}
+impl ImageSurfacePixel for Luma<u8> {
+    const FORMAT: Format = Format::A8;
+}
+
+#[cfg(feature = "v1_18")]
+impl sealed::Sealed for Rgb<f32> {}
+#[cfg(feature = "v1_18")]
+impl ImageSurfacePixel for Rgb<f32> {
+    const FORMAT: Format = Format::Rgb96f;
+}
+
+#[cfg(feature = "v1_18")]
+impl sealed::Sealed for Rgba<f32> {}
+#[cfg(feature = "v1_18")]
+impl ImageSurfacePixel for Rgba<f32> {
+    const FORMAT: Format = Format::Rgba128f;
+}
+
+impl ImageSurfaceData<'_> {
+    // Checks that the surface data can be viewed as an image buffer of `P`
+    fn image_buffer_size<P: ImageSurfacePixel>(&self) -> Option<(u32, u32)> {
+        let surface = &*self.surface;
+        let pixel_size = usize::from(P::CHANNEL_COUNT) * size_of::<P::Subpixel>();
+        (surface.format() == P::FORMAT
+            && surface.stride() as usize == surface.width() as usize * pixel_size)
+            .then_some((surface.width() as u32, surface.height() as u32))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Views the data as an [`ImageBuffer`] without copying.
+    ///
+    /// Returns `None` if the surface format does not match `P` or rows are padded.
+    pub fn as_image_buffer<P: ImageSurfacePixel>(&self) -> Option<ImageBuffer<P, &[P::Subpixel]>> {
+        let (width, height) = self.image_buffer_size::<P>()?;
+        // SAFETY: All bit patterns are valid for the subpixel types of `ImageSurfacePixel`
+        let (prefix, data, suffix) = unsafe { self.deref().align_to::<P::Subpixel>() };
+        if !prefix.is_empty() || !suffix.is_empty() {
+            return None;
+        }
+        ImageBuffer::from_raw(width, height, data)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Views the data as a mutable [`ImageBuffer`] without copying.
+    ///
+    /// Returns `None` if the surface format does not match `P` or rows are padded.
+    pub fn as_image_buffer_mut<P: ImageSurfacePixel>(
+        &mut self,
+    ) -> Option<ImageBuffer<P, &mut [P::Subpixel]>> {
+        let (width, height) = self.image_buffer_size::<P>()?;
+        // SAFETY: All bit patterns are valid for the subpixel types of `ImageSurfacePixel`
+        let (prefix, data, suffix) = unsafe { (**self).align_to_mut::<P::Subpixel>() };
+        if !prefix.is_empty() || !suffix.is_empty() {
+            return None;
+        }
+        ImageBuffer::from_raw(width, height, data)
+    }
+}
+
+fn from_pixels<P: Pixel, C: Deref<Target = [P::Subpixel]>>(
+    format: Format,
+    image: &ImageBuffer<P, C>,
+    bytes_per_pixel: usize,
+    f: impl Fn(&P, &mut [u8]),
+) -> Result<ImageSurface, Error> {
+    let (width, height) = image.dimensions();
+    let width = i32::try_from(width).map_err(|_| Error::InvalidSize)?;
+    let height = i32::try_from(height).map_err(|_| Error::InvalidSize)?;
+    let mut surface = ImageSurface::create(format, width, height)?;
+    if width == 0 || height == 0 {
+        return Ok(surface);
+    }
+
+    let stride = surface.stride() as usize;
+    let mut data = surface.data().map_err(|err| match err {
+        BorrowError::Cairo(err) => err,
+        BorrowError::NonExclusive => unreachable!(),
+    })?;
+    for (row, pixels) in data.chunks_mut(stride).zip(image.rows()) {
+        for (out, pixel) in row.chunks_exact_mut(bytes_per_pixel).zip(pixels) {
+            f(pixel, out);
+        }
+    }
+    drop(data);
+    Ok(surface)
+}
+
+fn map_pixels<P: Pixel>(
+    surface: &ImageSurface,
+    bytes_per_pixel: usize,
+    f: impl Fn(&[u8]) -> P,
+) -> Result<ImageBuffer<P, Vec<P::Subpixel>>, BorrowError> {
+    let (width, height) = (surface.width() as usize, surface.height() as usize);
+    let mut image = ImageBuffer::new(width as u32, height as u32);
+    if width == 0 || height == 0 {
+        return Ok(image);
+    }
+
+    let stride = surface.stride() as usize;
+    surface.with_data(|data| {
+        for (row, pixels) in data.chunks(stride).zip(image.rows_mut()) {
+            for (pixel, out) in row.chunks_exact(bytes_per_pixel).zip(pixels) {
+                *out = f(pixel);
+            }
+        }
+    })?;
+    Ok(image)
+}
+
+#[cfg(feature = "v1_18")]
+fn read_f32s<const N: usize>(p: &[u8]) -> [f32; N] {
+    std::array::from_fn(|i| f32::from_ne_bytes(p[i * 4..i * 4 + 4].try_into().unwrap()))
+}
+
+#[cfg(feature = "v1_18")]
+fn rgba_f32_unpremultiplied(p: &[u8]) -> Rgba<f32> {
+    let [r, g, b, a] = read_f32s(p);
+    if a == 0.0 {
+        Rgba([0.0; 4])
+    } else {
+        Rgba([r / a, g / a, b / a, a])
+    }
+}
+
+#[cfg(feature = "v1_18")]
+fn f32_to_u8(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_round_trip() {
+        let image = RgbaImage::from_fn(3, 2, |x, y| match (x, y) {
+            (0, 0) => Rgba([255, 0, 0, 255]),
+            (1, 0) => Rgba([0, 255, 0, 128]),
+            (2, 0) => Rgba([10, 20, 30, 0]),
+            _ => Rgba([200, 100, 50, 255]),
+        });
+        let surface = ImageSurface::from_rgba_image(&image).unwrap();
+        assert_eq!(surface.format(), Format::ARgb32);
+        assert_eq!((surface.width(), surface.height()), (3, 2));
+
+        surface
+            .with_data(|data| {
+                let pixel =
+                    |i: usize| u32::from_ne_bytes(data[i * 4..i * 4 + 4].try_into().unwrap());
+                assert_eq!(pixel(0), 0xffff0000);
+                assert_eq!(pixel(1), 0x80008000);
+                assert_eq!(pixel(2), 0);
+            })
+            .unwrap();
+
+        let back = surface.to_rgba_image().unwrap();
+        assert_eq!(back.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(back.get_pixel(1, 0), &Rgba([0, 255, 0, 128]));
+        // Fully transparent pixels lose their color
+        assert_eq!(back.get_pixel(2, 0), &Rgba([0, 0, 0, 0]));
+        assert_eq!(back.get_pixel(1, 1), &Rgba([200, 100, 50, 255]));
+    }
+
+    #[test]
+    fn dynamic_image() {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(5, 1, Rgb([1, 2, 3])));
+        let surface = ImageSurface::from_image(&image).unwrap();
+        assert_eq!(surface.format(), Format::Rgb24);
+        assert_eq!(surface.to_dynamic_image().unwrap(), image);
+
+        let image = DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(
+            2,
+            2,
+            Rgb([0xffff, 0x8020, 0]),
+        ));
+        let surface = ImageSurface::from_image(&image).unwrap();
+        assert_eq!(surface.format(), Format::Rgb30);
+        assert_eq!(
+            surface
+                .to_dynamic_image()
+                .unwrap()
+                .as_rgb16()
+                .unwrap()
+                .get_pixel(1, 1),
+            &Rgb([0xffff, 0x8020, 0])
+        );
+    }
+
+    #[test]
+    fn image_buffer_view() {
+        let mut surface = ImageSurface::create(Format::A8, 8, 2).unwrap();
+        {
+            let mut data = surface.data().unwrap();
+            assert!(data.as_image_buffer::<Luma<u8>>().is_some());
+            let mut buffer = data.as_image_buffer_mut::<Luma<u8>>().unwrap();
+            buffer.put_pixel(7, 1, Luma([0xff]));
+        }
+        assert_eq!(
+            surface.to_rgba_image().unwrap().get_pixel(7, 1),
+            &Rgba([0, 0, 0, 0xff])
+        );
+
+        // Rows are padded to multiples of 4 bytes
+        let mut surface = ImageSurface::create(Format::A8, 3, 2).unwrap();
+        assert!(
+            surface
+                .data()
+                .unwrap()
+                .as_image_buffer::<Luma<u8>>()
+                .is_none()
+        );
+    }
+}
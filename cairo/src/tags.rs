@@ -0,0 +1,688 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{cell::RefCell, fmt, fmt::Write, rc::Rc};
+
+use crate::{CAIRO_TAG_DEST, CAIRO_TAG_LINK, Context, Error, Rectangle, UserDataKey};
+
+// rustdoc-stripper-ignore-next
+/// The target of a [`Link`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    // rustdoc-stripper-ignore-next
+    /// An external URI.
+    Uri(String),
+    // rustdoc-stripper-ignore-next
+    /// A destination declared with [`Context::dest()`].
+    Dest(String),
+    // rustdoc-stripper-ignore-next
+    /// A page, starting at 1, and optionally a position on it.
+    Page { page: u32, pos: Option<(f64, f64)> },
+}
+
+// rustdoc-stripper-ignore-next
+/// A hyperlink, for [`Context::link()`] and PDF outlines.
+///
+/// Without any rectangles, the link area is the extents of the drawing operations done while
+/// the link tag is open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    target: LinkTarget,
+    rects: Vec<Rectangle>,
+}
+
+impl Link {
+    pub fn new(target: LinkTarget) -> Self {
+        Self {
+            target,
+            rects: Vec::new(),
+        }
+    }
+
+    pub fn uri(uri: impl Into<String>) -> Self {
+        Self::new(LinkTarget::Uri(uri.into()))
+    }
+
+    pub fn dest(name: impl Into<String>) -> Self {
+        Self::new(LinkTarget::Dest(name.into()))
+    }
+
+    pub fn page(page: u32, pos: Option<(f64, f64)>) -> Self {
+        Self::new(LinkTarget::Page { page, pos })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds a rectangle, in user space, to the link area.
+    pub fn rect(mut self, rect: Rectangle) -> Self {
+        self.rects.push(rect);
+        self
+    }
+
+    pub fn target(&self) -> &LinkTarget {
+        &self.target
+    }
+
+    pub fn rects(&self) -> &[Rectangle] {
+        &self.rects
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the validated attribute string of the link, as expected by
+    /// [`Context::tag_begin()`] and [`PdfSurface::add_outline()`](crate::PdfSurface::add_outline).
+    pub fn attributes(&self) -> Result<String, Error> {
+        let mut attributes = Attributes::default();
+        match &self.target {
+            LinkTarget::Uri(uri) => attributes.string("uri", uri)?,
+            LinkTarget::Dest(name) => attributes.string("dest", name)?,
+            LinkTarget::Page { page, pos } => {
+                if *page == 0 {
+                    return Err(Error::TagError);
+                }
+                attributes.raw("page", page);
+                if let Some((x, y)) = *pos {
+                    attributes.numbers("pos", &[x, y])?;
+                }
+            }
+        }
+        if !self.rects.is_empty() {
+            let numbers = self
+                .rects
+                .iter()
+                .flat_map(|r| [r.x(), r.y(), r.width(), r.height()])
+                .collect::<Vec<_>>();
+            attributes.numbers("rect", &numbers)?;
+        }
+        Ok(attributes.0)
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A named destination that [`Link`]s can point to, for [`Context::dest()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dest {
+    name: String,
+    pos: Option<(f64, f64)>,
+    internal: bool,
+}
+
+impl Dest {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pos: None,
+            internal: false,
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the position of the destination in user space.
+    ///
+    /// By default, it is the top left of the extents of the drawing operations done while the
+    /// destination tag is open.
+    pub fn pos(mut self, x: f64, y: f64) -> Self {
+        self.pos = Some((x, y));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Whether the destination is only used by links within the document, in which case its
+    /// name does not need to be kept in the PDF.
+    pub fn internal(mut self, internal: bool) -> Self {
+        self.internal = internal;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the validated attribute string of the destination.
+    pub fn attributes(&self) -> Result<String, Error> {
+        let mut attributes = Attributes::default();
+        attributes.string("name", &self.name)?;
+        if let Some((x, y)) = self.pos {
+            attributes.number("x", x)?;
+            attributes.number("y", y)?;
+        }
+        if self.internal {
+            attributes.raw("internal", true);
+        }
+        Ok(attributes.0)
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// The standard structure types of tagged PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StructureElement {
+    Document,
+    Part,
+    Art,
+    Sect,
+    Div,
+    BlockQuote,
+    Caption,
+    TOC,
+    TOCI,
+    Index,
+    P,
+    H,
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+    L,
+    LI,
+    Lbl,
+    LBody,
+    Table,
+    TR,
+    TH,
+    TD,
+    THead,
+    TBody,
+    TFoot,
+    Span,
+    Quote,
+    Note,
+    Reference,
+    Code,
+    Figure,
+    Formula,
+}
+
+impl StructureElement {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Document => "Document",
+            Self::Part => "Part",
+            Self::Art => "Art",
+            Self::Sect => "Sect",
+            Self::Div => "Div",
+            Self::BlockQuote => "BlockQuote",
+            Self::Caption => "Caption",
+            Self::TOC => "TOC",
+            Self::TOCI => "TOCI",
+            Self::Index => "Index",
+            Self::P => "P",
+            Self::H => "H",
+            Self::H1 => "H1",
+            Self::H2 => "H2",
+            Self::H3 => "H3",
+            Self::H4 => "H4",
+            Self::H5 => "H5",
+            Self::H6 => "H6",
+            Self::L => "L",
+            Self::LI => "LI",
+            Self::Lbl => "Lbl",
+            Self::LBody => "LBody",
+            Self::Table => "Table",
+            Self::TR => "TR",
+            Self::TH => "TH",
+            Self::TD => "TD",
+            Self::THead => "THead",
+            Self::TBody => "TBody",
+            Self::TFoot => "TFoot",
+            Self::Span => "Span",
+            Self::Quote => "Quote",
+            Self::Note => "Note",
+            Self::Reference => "Reference",
+            Self::Code => "Code",
+            Self::Figure => "Figure",
+            Self::Formula => "Formula",
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds an alternate description, as required for figures and formulas.
+    pub fn alt(self, alt: impl Into<String>) -> Structure {
+        Structure {
+            element: self,
+            alt: Some(alt.into()),
+        }
+    }
+
+    fn requires_alt(self) -> bool {
+        matches!(self, Self::Figure | Self::Formula)
+    }
+
+    // Elements that can only appear inside specific other elements
+    fn allowed_parents(self) -> Option<&'static [StructureElement]> {
+        match self {
+            Self::TOCI => Some(&[Self::TOC]),
+            Self::LI => Some(&[Self::L]),
+            Self::Lbl | Self::LBody => Some(&[Self::LI]),
+            Self::THead | Self::TBody | Self::TFoot => Some(&[Self::Table]),
+            Self::TR => Some(&[Self::Table, Self::THead, Self::TBody, Self::TFoot]),
+            Self::TH | Self::TD => Some(&[Self::TR]),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StructureElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A structure element with its attributes, for [`Context::structure()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Structure {
+    element: StructureElement,
+    alt: Option<String>,
+}
+
+impl Structure {
+    pub fn element(&self) -> StructureElement {
+        self.element
+    }
+
+    pub fn alt(&self) -> Option<&str> {
+        self.alt.as_deref()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the validated attribute string of the structure element.
+    ///
+    /// Figures and formulas without alternate description are rejected with
+    /// [`Error::TagError`].
+    pub fn attributes(&self) -> Result<String, Error> {
+        let mut attributes = Attributes::default();
+        match &self.alt {
+            Some(alt) => attributes.string("alt", alt)?,
+            None if self.element.requires_alt() => return Err(Error::TagError),
+            None => (),
+        }
+        Ok(attributes.0)
+    }
+}
+
+impl From<StructureElement> for Structure {
+    fn from(element: StructureElement) -> Self {
+        Self { element, alt: None }
+    }
+}
+
+#[derive(Default)]
+struct Attributes(String);
+
+impl Attributes {
+    fn separator(&mut self) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+    }
+
+    fn raw(&mut self, name: &str, value: impl fmt::Display) {
+        self.separator();
+        write!(self.0, "{name}={value}").unwrap();
+    }
+
+    fn string(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        if value.is_empty() {
+            return Err(Error::TagError);
+        }
+        if value.contains('\0') {
+            return Err(Error::InvalidString);
+        }
+        self.separator();
+        write!(self.0, "{name}='").unwrap();
+        for c in value.chars() {
+            if matches!(c, '\'' | '\\') {
+                self.0.push('\\');
+            }
+            self.0.push(c);
+        }
+        self.0.push('\'');
+        Ok(())
+    }
+
+    fn number(&mut self, name: &str, value: f64) -> Result<(), Error> {
+        if !value.is_finite() {
+            return Err(Error::TagError);
+        }
+        self.raw(name, value);
+        Ok(())
+    }
+
+    fn numbers(&mut self, name: &str, values: &[f64]) -> Result<(), Error> {
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(Error::TagError);
+        }
+        self.separator();
+        write!(self.0, "{name}=[").unwrap();
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.0.push(' ');
+            }
+            write!(self.0, "{value}").unwrap();
+        }
+        self.0.push(']');
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenTag {
+    Link,
+    Dest,
+    Structure(StructureElement),
+}
+
+impl OpenTag {
+    fn name(self) -> &'static str {
+        match self {
+            OpenTag::Link => CAIRO_TAG_LINK,
+            OpenTag::Dest => CAIRO_TAG_DEST,
+            OpenTag::Structure(element) => element.name(),
+        }
+    }
+
+    fn can_contain_tags(self) -> bool {
+        match self {
+            // Links and destinations only mark content
+            OpenTag::Link | OpenTag::Dest => false,
+            OpenTag::Structure(_) => true,
+        }
+    }
+}
+
+// The tags open on a target surface, innermost last. Each tag gets a unique id, so that guards
+// of tags that were already ended with an outer tag don't end a newer one.
+#[derive(Debug, Default)]
+struct OpenTags {
+    next_id: u64,
+    tags: Vec<(u64, OpenTag)>,
+}
+
+// The tags are tracked per target surface, as that is where cairo builds the structure tree.
+static OPEN_TAGS: UserDataKey<RefCell<OpenTags>> = UserDataKey::new();
+
+// rustdoc-stripper-ignore-next
+/// An open tag, ended when dropped.
+///
+/// Guards have to be dropped in the reverse order of their creation. Ending an outer tag while
+/// inner ones are still open ends those as well, [`end()`](Self::end) reports this with
+/// [`Error::TagError`].
+#[must_use = "the tag is ended when the guard is dropped"]
+#[derive(Debug)]
+pub struct TagGuard<'a> {
+    cr: &'a Context,
+    id: u64,
+}
+
+impl TagGuard<'_> {
+    // rustdoc-stripper-ignore-next
+    /// Ends the tag and returns the status of the context.
+    ///
+    /// Fails with [`Error::TagError`] if tags nested in it were still open, or if it was already
+    /// ended together with an outer tag.
+    pub fn end(self) -> Result<(), Error> {
+        let result = self.close();
+        let cr = self.cr;
+        drop(self);
+        result?;
+        cr.status()
+    }
+
+    // Ends the tag and all tags still open inside it.
+    fn close(&self) -> Result<(), Error> {
+        let open_tags = self.cr.target().user_data(&OPEN_TAGS);
+        let Some(open_tags) = open_tags else {
+            return Err(Error::TagError);
+        };
+        let mut open_tags = open_tags.borrow_mut();
+        let Some(index) = open_tags.tags.iter().position(|(id, _)| *id == self.id) else {
+            return Err(Error::TagError);
+        };
+
+        let nested = open_tags.tags.len() - index - 1;
+        for (_, tag) in open_tags.tags.drain(index..).rev() {
+            self.cr.tag_end(tag.name());
+        }
+        if nested > 0 {
+            return Err(Error::TagError);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TagGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+impl Context {
+    fn begin_tag(&self, tag: OpenTag, attributes: &str) -> Result<TagGuard<'_>, Error> {
+        self.status()?;
+        let target = self.target();
+        let open_tags = match target.user_data(&OPEN_TAGS) {
+            Some(open_tags) => open_tags,
+            None => {
+                let open_tags = Rc::new(RefCell::new(OpenTags::default()));
+                target.set_user_data(&OPEN_TAGS, open_tags.clone())?;
+                open_tags
+            }
+        };
+        let mut open_tags = open_tags.borrow_mut();
+
+        let parent = open_tags.tags.last().map(|(_, tag)| *tag);
+        if parent.is_some_and(|parent| !parent.can_contain_tags()) {
+            return Err(Error::TagError);
+        }
+        if let OpenTag::Structure(element) = tag
+            && let Some(allowed) = element.allowed_parents()
+            && !matches!(parent, Some(OpenTag::Structure(parent)) if allowed.contains(&parent))
+        {
+            return Err(Error::TagError);
+        }
+
+        self.tag_begin(tag.name(), attributes);
+        self.status()?;
+        let id = open_tags.next_id;
+        open_tags.next_id += 1;
+        open_tags.tags.push((id, tag));
+        Ok(TagGuard { cr: self, id })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Begins a hyperlink, ended when the returned guard is dropped.
+    ///
+    /// Links can't contain other tags.
+    #[doc(alias = "CAIRO_TAG_LINK")]
+    pub fn link(&self, link: &Link) -> Result<TagGuard<'_>, Error> {
+        self.begin_tag(OpenTag::Link, &link.attributes()?)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Declares a destination for [`Link`]s, ended when the returned guard is dropped.
+    ///
+    /// Destinations can't contain other tags.
+    #[doc(alias = "CAIRO_TAG_DEST")]
+    pub fn dest(&self, dest: &Dest) -> Result<TagGuard<'_>, Error> {
+        self.begin_tag(OpenTag::Dest, &dest.attributes()?)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Begins a structure element of the document, ended when the returned guard is dropped.
+    ///
+    /// Elements that are only valid inside specific parents, like table rows and cells or list
+    /// items, fail with [`Error::TagError`] anywhere else.
+    pub fn structure(&self, structure: impl Into<Structure>) -> Result<TagGuard<'_>, Error> {
+        let structure = structure.into();
+        self.begin_tag(
+            OpenTag::Structure(structure.element),
+            &structure.attributes()?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Format, ImageSurface};
+
+    #[test]
+    fn attributes() {
+        assert_eq!(
+            Link::uri("https://gtk-rs.org/it's").attributes().unwrap(),
+            r"uri='https://gtk-rs.org/it\'s'"
+        );
+        assert_eq!(
+            Link::dest("intro")
+                .rect(Rectangle::new(1.0, 2.0, 3.5, 4.0))
+                .rect(Rectangle::new(0.0, 10.0, 5.0, 5.0))
+                .attributes()
+                .unwrap(),
+            "dest='intro' rect=[1 2 3.5 4 0 10 5 5]"
+        );
+        assert_eq!(
+            Link::page(2, Some((10.0, 20.5))).attributes().unwrap(),
+            "page=2 pos=[10 20.5]"
+        );
+        assert_eq!(Link::page(0, None).attributes(), Err(Error::TagError));
+        assert_eq!(Link::uri("").attributes(), Err(Error::TagError));
+        assert_eq!(Link::dest("a\0b").attributes(), Err(Error::InvalidString));
+
+        assert_eq!(
+            Dest::new(r"a\b")
+                .pos(1.0, f64::from(2))
+                .internal(true)
+                .attributes()
+                .unwrap(),
+            r"name='a\\b' x=1 y=2 internal=true"
+        );
+        assert_eq!(
+            Dest::new("a").pos(f64::NAN, 0.0).attributes(),
+            Err(Error::TagError)
+        );
+
+        assert_eq!(
+            Structure::from(StructureElement::P).attributes().unwrap(),
+            ""
+        );
+        assert_eq!(
+            StructureElement::Figure
+                .alt("A chart")
+                .attributes()
+                .unwrap(),
+            "alt='A chart'"
+        );
+        assert_eq!(
+            Structure::from(StructureElement::Figure).attributes(),
+            Err(Error::TagError)
+        );
+    }
+
+    #[test]
+    fn nesting() {
+        let surface = ImageSurface::create(Format::ARgb32, 10, 10).unwrap();
+        let cr = Context::new(&surface).unwrap();
+
+        let table = cr.structure(StructureElement::Table).unwrap();
+        assert_eq!(
+            cr.structure(StructureElement::TD).unwrap_err(),
+            Error::TagError
+        );
+        {
+            let _row = cr.structure(StructureElement::TR).unwrap();
+            let _cell = cr.structure(StructureElement::TD).unwrap();
+            let link = cr.link(&Link::dest("intro")).unwrap();
+            assert_eq!(
+                cr.structure(StructureElement::Span).unwrap_err(),
+                Error::TagError
+            );
+            link.end().unwrap();
+        }
+        table.end().unwrap();
+
+        assert_eq!(
+            cr.structure(StructureElement::TR).unwrap_err(),
+            Error::TagError
+        );
+        cr.dest(&Dest::new("intro")).unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn out_of_order() {
+        let surface = ImageSurface::create(Format::ARgb32, 10, 10).unwrap();
+        let cr = Context::new(&surface).unwrap();
+
+        // Ending the outer tag ends the inner one as well
+        let outer = cr.structure(StructureElement::P).unwrap();
+        let inner = cr.structure(StructureElement::Span).unwrap();
+        assert_eq!(outer.end(), Err(Error::TagError));
+        let table = cr.structure(StructureElement::Table).unwrap();
+        assert_eq!(inner.end(), Err(Error::TagError));
+        assert!(cr.structure(StructureElement::TR).is_ok());
+        drop(table);
+
+        // Dropping out of order does not panic
+        let outer = cr.structure(StructureElement::Table).unwrap();
+        let inner = cr.structure(StructureElement::TR).unwrap();
+        drop(outer);
+        assert_eq!(
+            cr.structure(StructureElement::TD).unwrap_err(),
+            Error::TagError
+        );
+        drop(inner);
+        cr.structure(StructureElement::P).unwrap().end().unwrap();
+        assert_eq!(cr.status(), Ok(()));
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn pdf() {
+        use crate::{PDF_OUTLINE_ROOT, PdfOutline, PdfSurface};
+
+        let surface = PdfSurface::for_stream(100., 100., Vec::<u8>::new()).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        {
+            let _document = cr.structure(StructureElement::Document).unwrap();
+            {
+                let _heading = cr.structure(StructureElement::H1).unwrap();
+                let _dest = cr.dest(&Dest::new("intro")).unwrap();
+                cr.move_to(10.0, 20.0);
+                cr.show_text("Introduction").unwrap();
+            }
+            {
+                let _figure = cr
+                    .structure(StructureElement::Figure.alt("A black square"))
+                    .unwrap();
+                cr.rectangle(10.0, 30.0, 20.0, 20.0);
+                cr.fill().unwrap();
+            }
+            let _paragraph = cr.structure(StructureElement::P).unwrap();
+            let _link = cr
+                .link(&Link::uri("https://gtk-rs.org").rect(Rectangle::new(10.0, 60.0, 50.0, 10.0)))
+                .unwrap();
+            cr.move_to(10.0, 70.0);
+            cr.show_text("gtk-rs").unwrap();
+        }
+        surface
+            .add_outline_link(
+                PDF_OUTLINE_ROOT,
+                "Introduction",
+                &Link::dest("intro"),
+                PdfOutline::OPEN,
+            )
+            .unwrap();
+        drop(cr);
+
+        let pdf = *surface
+            .finish_output_stream()
+            .unwrap()
+            .downcast::<Vec<u8>>()
+            .unwrap();
+        let contains = |needle: &[u8]| pdf.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"/StructTreeRoot"));
+        assert!(contains(b"https://gtk-rs.org"));
+        assert!(contains(b"/Outlines"));
+    }
+}
@@ -0,0 +1,610 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+// rustdoc-stripper-ignore-next
+//! Utilities for visual regression tests.
+//!
+//! Rendering results are compared pixel by pixel with a configurable [`Tolerance`], so that
+//! tiny antialiasing differences between cairo versions or platforms don't break tests.
+//!
+//! With the `png` feature, rendering results can be compared against golden files with
+//! [`assert_golden!`](crate::assert_golden). Setting the [`UPDATE_GOLDEN_ENV`] environment
+//! variable to `1` writes the current results as the new golden files instead.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cairo::Error> {
+//! let image = cairo::testing::render(32, 32, |cr| {
+//!     cr.arc(16.0, 16.0, 10.0, 0.0, 2.0 * std::f64::consts::PI);
+//!     cr.fill()
+//! })?;
+//! # #[cfg(feature = "png")]
+//! cairo::assert_golden!(image, "circle", cairo::testing::Tolerance::new(2, 10));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+#[cfg(feature = "png")]
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "png")]
+use crate::IoError;
+use crate::{
+    Context, Error, Format, ImageSurface, Operator, RecordingSurface, Surface, SurfaceType,
+};
+
+// rustdoc-stripper-ignore-next
+/// The environment variable that makes golden file checks update the golden files.
+#[cfg(feature = "png")]
+#[cfg_attr(docsrs, doc(cfg(feature = "png")))]
+pub const UPDATE_GOLDEN_ENV: &str = "CAIRO_UPDATE_GOLDEN";
+
+// rustdoc-stripper-ignore-next
+/// How much two images may differ and still be considered equal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tolerance {
+    // rustdoc-stripper-ignore-next
+    /// The maximum difference of any channel, in premultiplied ARGB, for two pixels to be
+    /// considered equal.
+    pub channel: u8,
+    // rustdoc-stripper-ignore-next
+    /// The maximum number of pixels that may differ by more than `channel`.
+    pub max_differing_pixels: usize,
+}
+
+impl Tolerance {
+    // rustdoc-stripper-ignore-next
+    /// Only identical images are considered equal.
+    pub const EXACT: Tolerance = Tolerance::new(0, 0);
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a tolerance allowing up to `max_differing_pixels` pixels to differ by more than
+    /// `channel` in any channel.
+    pub const fn new(channel: u8, max_differing_pixels: usize) -> Self {
+        Self {
+            channel,
+            max_differing_pixels,
+        }
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// The result of [`compare()`].
+#[derive(Debug)]
+pub struct Comparison {
+    tolerance: Tolerance,
+    sizes: [(i32, i32); 2],
+    differing_pixels: usize,
+    max_channel_difference: u8,
+    diff: ImageSurface,
+}
+
+impl Comparison {
+    // rustdoc-stripper-ignore-next
+    /// Whether the images are equal within the tolerance.
+    pub fn is_match(&self) -> bool {
+        self.differing_pixels <= self.tolerance.max_differing_pixels
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The number of pixels that differ by more than the channel tolerance.
+    ///
+    /// If the sizes of the images differ, pixels that are only covered by one of them count as
+    /// differing.
+    pub fn differing_pixels(&self) -> usize {
+        self.differing_pixels
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The largest difference of any channel of any pixel covered by both images.
+    pub fn max_channel_difference(&self) -> u8 {
+        self.max_channel_difference
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// An image highlighting the differences.
+    ///
+    /// Pixels that differ by more than the tolerance are red, pixels that differ within the
+    /// tolerance yellow, and equal pixels are shown faded.
+    pub fn diff(&self) -> &ImageSurface {
+        &self.diff
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [(w1, h1), (w2, h2)] = self.sizes;
+        if (w1, h1) != (w2, h2) {
+            write!(f, "image sizes differ ({w1}x{h1} vs {w2}x{h2}), ")?;
+        }
+        write!(
+            f,
+            "{} pixels differ by more than {} (at most {} allowed), maximum channel difference {}",
+            self.differing_pixels,
+            self.tolerance.channel,
+            self.tolerance.max_differing_pixels,
+            self.max_channel_difference
+        )
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// Draws with `draw` on a [`RecordingSurface`] and rasterizes the result into an
+/// [`ARgb32`](Format::ARgb32) image of the given size.
+///
+/// The same drawing code can be used for other surface types, recording it makes sure the
+/// result is checked the way cairo replays it.
+pub fn render(
+    width: i32,
+    height: i32,
+    draw: impl FnOnce(&Context) -> Result<(), Error>,
+) -> Result<ImageSurface, Error> {
+    let recording = RecordingSurface::create(
+        crate::Content::ColorAlpha,
+        Some(crate::Rectangle::new(0.0, 0.0, width.into(), height.into())),
+    )?;
+    let cr = Context::new(&recording)?;
+    draw(&cr)?;
+    drop(cr);
+    rasterize(&recording)
+}
+
+// rustdoc-stripper-ignore-next
+/// Rasterizes a surface into an [`ARgb32`](Format::ARgb32) image.
+///
+/// Image surfaces are converted, recording surfaces are replayed covering their extents or, if
+/// they are unbounded, their ink extents. Other surfaces are painted onto a recording surface
+/// first, which is then rasterized the same way. This fails with the error reported by cairo
+/// for surfaces whose content can't be read, like finished surfaces or write-only vector
+/// surfaces.
+pub fn rasterize(surface: impl AsRef<Surface>) -> Result<ImageSurface, Error> {
+    let surface = surface.as_ref();
+    let (x, y, width, height) = match surface.type_() {
+        SurfaceType::Image => {
+            let image =
+                ImageSurface::try_from(surface.clone()).map_err(|_| Error::SurfaceTypeMismatch)?;
+            (0.0, 0.0, image.width().into(), image.height().into())
+        }
+        SurfaceType::Recording => {
+            let recording = RecordingSurface::try_from(surface.clone())
+                .map_err(|_| Error::SurfaceTypeMismatch)?;
+            match recording.extents() {
+                Some(extents) => (extents.x(), extents.y(), extents.width(), extents.height()),
+                None => recording.ink_extents(),
+            }
+        }
+        _ => {
+            let recording = RecordingSurface::create(crate::Content::ColorAlpha, None)?;
+            let cr = Context::new(&recording)?;
+            cr.set_source_surface(surface, 0.0, 0.0)?;
+            cr.paint()?;
+            drop(cr);
+            return rasterize(&recording);
+        }
+    };
+
+    let (x1, y1) = (x.floor(), y.floor());
+    let (x2, y2) = ((x + width).ceil(), (y + height).ceil());
+    let image = ImageSurface::create(Format::ARgb32, (x2 - x1) as i32, (y2 - y1) as i32)?;
+    let cr = Context::new(&image)?;
+    cr.set_operator(Operator::Source);
+    cr.set_source_surface(surface, -x1, -y1)?;
+    cr.paint()?;
+    drop(cr);
+    image.flush();
+    Ok(image)
+}
+
+// rustdoc-stripper-ignore-next
+/// Compares two surfaces pixel by pixel, after rasterizing them with [`rasterize()`].
+pub fn compare(
+    actual: impl AsRef<Surface>,
+    expected: impl AsRef<Surface>,
+    tolerance: Tolerance,
+) -> Result<Comparison, Error> {
+    let actual = pixels(&rasterize(actual)?)?;
+    let expected = pixels(&rasterize(expected)?)?;
+    let width = actual.width.max(expected.width);
+    let height = actual.height.max(expected.height);
+
+    let mut differing_pixels = 0;
+    let mut max_channel_difference = 0;
+    let mut diff = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (Some(a), Some(e)) = (actual.get(x, y), expected.get(x, y)) else {
+                differing_pixels += 1;
+                diff.push(0xffff0000);
+                continue;
+            };
+            let difference = (0..4)
+                .map(|i| ((a >> (i * 8)) as u8).abs_diff((e >> (i * 8)) as u8))
+                .max()
+                .unwrap();
+            max_channel_difference = max_channel_difference.max(difference);
+            diff.push(if difference > tolerance.channel {
+                differing_pixels += 1;
+                0xffff0000
+            } else if difference > 0 {
+                0xffffff00
+            } else {
+                faded(e)
+            });
+        }
+    }
+
+    let stride = Format::ARgb32.stride_for_width(width as u32)?;
+    let data = diff
+        .iter()
+        .flat_map(|pixel| pixel.to_ne_bytes())
+        .collect::<Vec<_>>();
+    debug_assert_eq!(stride as usize, width * 4);
+    let diff =
+        ImageSurface::create_for_data(data, Format::ARgb32, width as i32, height as i32, stride)?;
+
+    Ok(Comparison {
+        tolerance,
+        sizes: [
+            (actual.width as i32, actual.height as i32),
+            (expected.width as i32, expected.height as i32),
+        ],
+        differing_pixels,
+        max_channel_difference,
+        diff,
+    })
+}
+
+struct Pixels {
+    width: usize,
+    height: usize,
+    data: Vec<u32>,
+}
+
+impl Pixels {
+    fn get(&self, x: usize, y: usize) -> Option<u32> {
+        (x < self.width && y < self.height).then(|| self.data[y * self.width + x])
+    }
+}
+
+fn pixels(image: &ImageSurface) -> Result<Pixels, Error> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let stride = image.stride() as usize;
+    let mut data = Vec::with_capacity(width * height);
+    image
+        .with_data(|bytes| {
+            for row in bytes.chunks(stride.max(1)).take(height) {
+                data.extend(
+                    row[..width * 4]
+                        .chunks_exact(4)
+                        .map(|p| u32::from_ne_bytes(p.try_into().unwrap())),
+                );
+            }
+        })
+        .map_err(|err| match err {
+            crate::BorrowError::Cairo(err) => err,
+            crate::BorrowError::NonExclusive => Error::SurfaceFinished,
+        })?;
+    Ok(Pixels {
+        width,
+        height,
+        data,
+    })
+}
+
+// Shows an equal pixel as a light gray, so that differences stand out.
+fn faded(pixel: u32) -> u32 {
+    let [b, g, r, a] = pixel.to_le_bytes().map(u32::from);
+    // Composite over white, then fade towards white
+    let luma = (r * 77 + g * 150 + b * 29) / 256 + (255 - a);
+    0xff000000 | ((0xc0 + luma.min(255) / 4) * 0x010101)
+}
+
+// rustdoc-stripper-ignore-next
+/// An error of a golden file check.
+#[cfg(feature = "png")]
+#[cfg_attr(docsrs, doc(cfg(feature = "png")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GoldenError {
+    // rustdoc-stripper-ignore-next
+    /// The golden file does not exist.
+    Missing(PathBuf),
+    // rustdoc-stripper-ignore-next
+    /// The rendering result does not match the golden file.
+    Mismatch {
+        golden: PathBuf,
+        comparison: Box<Comparison>,
+    },
+    // rustdoc-stripper-ignore-next
+    /// Rendering, reading or writing the images failed.
+    Io(IoError),
+}
+
+#[cfg(feature = "png")]
+impl std::error::Error for GoldenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GoldenError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoldenError::Missing(golden) => write!(
+                f,
+                "Golden file {} does not exist, run with {UPDATE_GOLDEN_ENV}=1 to create it",
+                golden.display()
+            ),
+            GoldenError::Mismatch { golden, comparison } => write!(
+                f,
+                "Rendering does not match golden file {}: {comparison}. The result and the \
+                 differences were written next to it, run with {UPDATE_GOLDEN_ENV}=1 to accept \
+                 the result",
+                golden.display()
+            ),
+            GoldenError::Io(err) => write!(f, "Golden file check failed: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<IoError> for GoldenError {
+    fn from(err: IoError) -> Self {
+        GoldenError::Io(err)
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<Error> for GoldenError {
+    fn from(err: Error) -> Self {
+        GoldenError::Io(IoError::Cairo(err))
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<io::Error> for GoldenError {
+    fn from(err: io::Error) -> Self {
+        GoldenError::Io(IoError::Io(err))
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// Compares a surface against a golden PNG file.
+///
+/// If the [`UPDATE_GOLDEN_ENV`] environment variable is set to `1`, the golden file is written
+/// instead. On a mismatch, the rendering result and the differences are written next to the
+/// golden file, with `.actual.png` and `.diff.png` extensions.
+#[cfg(feature = "png")]
+#[cfg_attr(docsrs, doc(cfg(feature = "png")))]
+pub fn check_golden(
+    actual: impl AsRef<Surface>,
+    golden: impl AsRef<Path>,
+    tolerance: Tolerance,
+) -> Result<(), GoldenError> {
+    let update = std::env::var_os(UPDATE_GOLDEN_ENV).is_some_and(|value| value == "1");
+    check_golden_file(actual.as_ref(), golden.as_ref(), tolerance, update)
+}
+
+#[cfg(feature = "png")]
+fn check_golden_file(
+    actual: &Surface,
+    golden: &Path,
+    tolerance: Tolerance,
+    update: bool,
+) -> Result<(), GoldenError> {
+    let actual = rasterize(actual)?;
+    let write_png = |surface: &ImageSurface, path: &Path| -> Result<(), GoldenError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        surface.write_to_png(&mut file)?;
+        Ok(())
+    };
+
+    if update {
+        return write_png(&actual, golden);
+    }
+
+    let expected = match fs::File::open(golden) {
+        Ok(file) => ImageSurface::create_from_png(&mut io::BufReader::new(file))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(GoldenError::Missing(golden.to_owned()));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let comparison = compare(&actual, &expected, tolerance)?;
+    if comparison.is_match() {
+        return Ok(());
+    }
+
+    write_png(&actual, &golden.with_extension("actual.png"))?;
+    write_png(comparison.diff(), &golden.with_extension("diff.png"))?;
+    Err(GoldenError::Mismatch {
+        golden: golden.to_owned(),
+        comparison: Box::new(comparison),
+    })
+}
+
+// rustdoc-stripper-ignore-next
+/// Returns the path of the golden file `name` for the source file `file` of the crate at
+/// `manifest_dir`, in a `golden` directory next to the source file.
+#[doc(hidden)]
+#[cfg(feature = "png")]
+pub fn golden_path(manifest_dir: &str, file: &str, name: &str) -> PathBuf {
+    // `file!()` is relative to the workspace root, which may be above the crate
+    let file = Path::new(manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join(file))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| Path::new(manifest_dir).join(file));
+    let mut path = file.with_file_name("golden").join(name);
+    if path.extension().is_none() {
+        path.set_extension("png");
+    }
+    path
+}
+
+// rustdoc-stripper-ignore-next
+/// Asserts that a surface matches a golden file.
+///
+/// The golden file `<name>.png` is stored in a `golden` directory next to the source file of
+/// the test. See [`testing::check_golden()`](crate::testing::check_golden) for details, the
+/// tolerance defaults to [`Tolerance::EXACT`](crate::testing::Tolerance::EXACT).
+#[cfg(feature = "png")]
+#[cfg_attr(docsrs, doc(cfg(feature = "png")))]
+#[macro_export]
+macro_rules! assert_golden {
+    ($surface:expr, $name:expr $(,)?) => {
+        $crate::assert_golden!($surface, $name, $crate::testing::Tolerance::EXACT)
+    };
+    ($surface:expr, $name:expr, $tolerance:expr $(,)?) => {
+        if let Err(err) = $crate::testing::check_golden(
+            &$surface,
+            $crate::testing::golden_path(env!("CARGO_MANIFEST_DIR"), file!(), $name),
+            $tolerance,
+        ) {
+            panic!("{}", err);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(offset: f64) -> ImageSurface {
+        render(10, 10, |cr| {
+            cr.set_source_rgb(1.0, 0.0, 0.0);
+            cr.rectangle(2.0 + offset, 2.0, 4.0, 4.0);
+            cr.fill()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn compare_tolerance() {
+        let comparison = compare(square(0.0), square(0.0), Tolerance::EXACT).unwrap();
+        assert!(comparison.is_match());
+        assert_eq!(comparison.max_channel_difference(), 0);
+
+        // A subpixel shift only changes the edges
+        let comparison = compare(square(0.25), square(0.0), Tolerance::EXACT).unwrap();
+        assert!(!comparison.is_match());
+        assert_eq!(comparison.differing_pixels(), 8);
+        assert!(
+            compare(square(0.25), square(0.0), Tolerance::new(0, 8))
+                .unwrap()
+                .is_match()
+        );
+        assert!(
+            compare(square(0.25), square(0.0), Tolerance::new(72, 0))
+                .unwrap()
+                .is_match()
+        );
+
+        let diff = comparison.diff();
+        assert_eq!((diff.width(), diff.height()), (10, 10));
+        diff.with_data(|data| {
+            let pixel = |x: usize, y: usize| {
+                let i = (y * 10 + x) * 4;
+                u32::from_ne_bytes(data[i..i + 4].try_into().unwrap())
+            };
+            assert_eq!(pixel(2, 3), 0xffff0000);
+            assert_eq!(pixel(4, 3), faded(0xffff0000));
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn compare_sizes() {
+        let small = ImageSurface::create(Format::ARgb32, 2, 2).unwrap();
+        let large = ImageSurface::create(Format::Rgb24, 3, 2).unwrap();
+        let comparison = compare(&small, &large, Tolerance::EXACT).unwrap();
+        assert!(!comparison.is_match());
+        // The missing column, and the different alpha
+        assert_eq!(comparison.differing_pixels(), 6);
+    }
+
+    #[test]
+    fn rasterize_recording() {
+        let recording = RecordingSurface::create(crate::Content::ColorAlpha, None).unwrap();
+        let cr = Context::new(&recording).unwrap();
+        cr.rectangle(10.0, 20.0, 5.0, 5.0);
+        cr.fill().unwrap();
+        drop(cr);
+
+        let image = rasterize(&recording).unwrap();
+        assert_eq!((image.width(), image.height()), (5, 5));
+        image
+            .with_data(|data| assert!(data.chunks(4).all(|p| p == [0, 0, 0, 0xff])))
+            .unwrap();
+        assert_eq!(
+            rasterize(ImageSurface::create(Format::A8, 3, 1).unwrap())
+                .unwrap()
+                .format(),
+            Format::ARgb32
+        );
+    }
+
+    #[test]
+    fn rasterize_other() {
+        let image = ImageSurface::create(Format::ARgb32, 10, 10).unwrap();
+        let cr = Context::new(&image).unwrap();
+        cr.rectangle(2.0, 2.0, 4.0, 4.0);
+        cr.fill().unwrap();
+        drop(cr);
+
+        let sub = image
+            .create_for_rectangle(crate::Rectangle::new(2.0, 2.0, 4.0, 4.0))
+            .unwrap();
+        assert_eq!(sub.type_(), SurfaceType::Subsurface);
+        let rasterized = rasterize(&sub).unwrap();
+        assert_eq!((rasterized.width(), rasterized.height()), (4, 4));
+        rasterized
+            .with_data(|data| assert!(data.chunks(4).all(|p| p == [0, 0, 0, 0xff])))
+            .unwrap();
+
+        sub.finish();
+        assert!(rasterize(&sub).is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn golden() {
+        let dir = tempfile::tempdir().unwrap();
+        let golden = dir.path().join("golden").join("square.png");
+
+        assert!(matches!(
+            check_golden_file(&square(0.0), &golden, Tolerance::EXACT, false),
+            Err(GoldenError::Missing(_))
+        ));
+        check_golden_file(&square(0.0), &golden, Tolerance::EXACT, true).unwrap();
+        check_golden_file(&square(0.0), &golden, Tolerance::EXACT, false).unwrap();
+
+        assert!(matches!(
+            check_golden_file(&square(0.25), &golden, Tolerance::EXACT, false),
+            Err(GoldenError::Mismatch { .. })
+        ));
+        assert!(dir.path().join("golden/square.actual.png").exists());
+        assert!(dir.path().join("golden/square.diff.png").exists());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn golden_path_next_to_source() {
+        let path = golden_path(env!("CARGO_MANIFEST_DIR"), file!(), "circle");
+        assert!(
+            path.ends_with("src/golden/circle.png"),
+            "{}",
+            path.display()
+        );
+        assert!(path.is_absolute());
+    }
+}
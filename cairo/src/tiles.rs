@@ -0,0 +1,408 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use crate::{
+    BorrowError, Content, Context, Error, Format, ImageSurface, ImageSurfaceDataOwned, Operator,
+    RecordingSurface, Rectangle,
+};
+
+// rustdoc-stripper-ignore-next
+/// Options for [`RecordingSurface::rasterize_tiled()`] and
+/// [`RecordingSurface::rasterize_tiles()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileOptions {
+    // rustdoc-stripper-ignore-next
+    /// The size of a tile in pixels. Tiles at the right and bottom edges may be smaller.
+    pub tile_width: i32,
+    pub tile_height: i32,
+    // rustdoc-stripper-ignore-next
+    /// The number of pixels rendered around each tile and then cropped, so that drawing
+    /// operations crossing the edge of a tile are antialiased the same way on both sides.
+    pub overlap: i32,
+    // rustdoc-stripper-ignore-next
+    /// The number of worker threads, or 0 to use the available parallelism.
+    pub threads: usize,
+    // rustdoc-stripper-ignore-next
+    /// The number of pixels per user space unit of the recording.
+    pub scale: f64,
+    pub format: Format,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        Self {
+            tile_width: 256,
+            tile_height: 256,
+            overlap: 2,
+            threads: 0,
+            scale: 1.0,
+            format: Format::ARgb32,
+        }
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A tile rasterized by [`RecordingSurface::rasterize_tiles()`].
+#[derive(Debug)]
+pub struct Tile {
+    // rustdoc-stripper-ignore-next
+    /// The position of the tile in the whole image, in pixels.
+    pub x: i32,
+    pub y: i32,
+    pub image: ImageSurface,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TileRect {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl RecordingSurface {
+    // Records `draw` into a new recording surface.
+    fn record(
+        content: Content,
+        extents: Option<Rectangle>,
+        draw: &impl Fn(&Context) -> Result<(), Error>,
+    ) -> Result<Self, Error> {
+        let recording = Self::create(content, extents)?;
+        let cr = Context::new(&recording)?;
+        draw(&cr)?;
+        cr.status()?;
+        drop(cr);
+        Ok(recording)
+    }
+
+    // The area to rasterize in user space: the extents of bounded recording surfaces, or the
+    // ink extents of unbounded ones.
+    fn raster_area(&self) -> Rectangle {
+        self.extents().unwrap_or_else(|| {
+            let (x, y, width, height) = self.ink_extents();
+            Rectangle::new(x, y, width, height)
+        })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Records `draw` and rasterizes the recording into one image, rendering tiles of it in
+    /// parallel.
+    ///
+    /// See [`rasterize_tiles()`](Self::rasterize_tiles) for details.
+    pub fn rasterize_tiled(
+        content: Content,
+        extents: Option<Rectangle>,
+        options: &TileOptions,
+        draw: impl Fn(&Context) -> Result<(), Error> + Sync,
+    ) -> Result<ImageSurface, Error> {
+        let (width, height) = raster_size(raster_area(content, extents, &draw)?, options)?;
+        let image = ImageSurface::create(options.format, width, height)?;
+        let cr = Context::new(&image)?;
+        cr.set_operator(Operator::Source);
+        Self::rasterize_tiles(content, extents, options, draw, |tile| {
+            cr.set_source_surface(&tile.image, tile.x.into(), tile.y.into())?;
+            cr.rectangle(
+                tile.x.into(),
+                tile.y.into(),
+                tile.image.width().into(),
+                tile.image.height().into(),
+            );
+            cr.fill()
+        })?;
+        drop(cr);
+        image.flush();
+        Ok(image)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Records `draw` into a recording surface with the given `content` and `extents`, and
+    /// rasterizes it in tiles on worker threads. Each tile is passed to `f` on the calling
+    /// thread as soon as it is finished.
+    ///
+    /// The rasterized area covers `extents`, or the ink extents of the recording if it is
+    /// unbounded, scaled by `options.scale`. Tiles are passed to `f` in no particular order.
+    ///
+    /// Cairo surfaces can't be shared between threads, so each worker thread calls `draw` to
+    /// record its own copy of the drawing, and replays it into its own image surfaces. `draw`
+    /// is additionally called on the calling thread for unbounded recordings, to find their ink
+    /// extents. It has to draw the same on every call.
+    ///
+    /// If `draw`, `f` or rendering a tile fails, the remaining tiles are skipped and the error
+    /// is returned.
+    pub fn rasterize_tiles(
+        content: Content,
+        extents: Option<Rectangle>,
+        options: &TileOptions,
+        draw: impl Fn(&Context) -> Result<(), Error> + Sync,
+        mut f: impl FnMut(Tile) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let area = raster_area(content, extents, &draw)?;
+        let (width, height) = raster_size(area, options)?;
+        if options.tile_width <= 0 || options.tile_height <= 0 || options.overlap < 0 {
+            return Err(Error::InvalidSize);
+        }
+
+        let tiles = (0..height)
+            .step_by(options.tile_height as usize)
+            .flat_map(|y| {
+                (0..width)
+                    .step_by(options.tile_width as usize)
+                    .map(move |x| TileRect {
+                        x,
+                        y,
+                        width: options.tile_width.min(width - x),
+                        height: options.tile_height.min(height - y),
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let threads = match options.threads {
+            0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            threads => threads,
+        };
+
+        let next_tile = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let (sender, receiver) =
+            mpsc::channel::<Result<(TileRect, ImageSurfaceDataOwned), Error>>();
+
+        thread::scope(|scope| {
+            for _ in 0..threads.min(tiles.len()) {
+                let sender = sender.clone();
+                let (tiles, next_tile, failed, draw) = (&tiles, &next_tile, &failed, &draw);
+                scope.spawn(move || {
+                    let recording = match Self::record(content, extents, draw) {
+                        Ok(recording) => recording,
+                        Err(err) => {
+                            let _ = sender.send(Err(err));
+                            return;
+                        }
+                    };
+                    while !failed.load(Ordering::Relaxed) {
+                        let Some(&tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                        else {
+                            break;
+                        };
+                        let result = render_tile(&recording, area, tile, options);
+                        if sender.send(result.map(|data| (tile, data))).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            let mut result = Ok(());
+            for data in receiver {
+                if result.is_err() {
+                    continue;
+                }
+                result = data.and_then(|(tile, data)| {
+                    f(Tile {
+                        x: tile.x,
+                        y: tile.y,
+                        image: data.into_inner(),
+                    })
+                });
+                if result.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+            }
+            result
+        })
+    }
+}
+
+// The area of the recording of `draw` to rasterize in user space.
+fn raster_area(
+    content: Content,
+    extents: Option<Rectangle>,
+    draw: &impl Fn(&Context) -> Result<(), Error>,
+) -> Result<Rectangle, Error> {
+    match extents {
+        Some(extents) => Ok(extents),
+        None => Ok(RecordingSurface::record(content, None, draw)?.raster_area()),
+    }
+}
+
+fn raster_size(area: Rectangle, options: &TileOptions) -> Result<(i32, i32), Error> {
+    if !(options.scale.is_finite() && options.scale > 0.0) {
+        return Err(Error::InvalidMatrix);
+    }
+    let width = (area.width() * options.scale).ceil();
+    let height = (area.height() * options.scale).ceil();
+    if !(0.0..=f64::from(i32::MAX)).contains(&width)
+        || !(0.0..=f64::from(i32::MAX)).contains(&height)
+    {
+        return Err(Error::InvalidSize);
+    }
+    Ok((width as i32, height as i32))
+}
+
+// Renders a tile with `overlap` pixels around it, then crops it. The image data is taken, so
+// that it can be passed to the calling thread.
+fn render_tile(
+    recording: &RecordingSurface,
+    area: Rectangle,
+    tile: TileRect,
+    options: &TileOptions,
+) -> Result<ImageSurfaceDataOwned, Error> {
+    let overlap = options.overlap;
+    let rendered = ImageSurface::create(
+        options.format,
+        tile.width + 2 * overlap,
+        tile.height + 2 * overlap,
+    )?;
+    let cr = Context::new(&rendered)?;
+    cr.translate(f64::from(overlap - tile.x), f64::from(overlap - tile.y));
+    cr.scale(options.scale, options.scale);
+    cr.set_source_surface(recording, -area.x(), -area.y())?;
+    cr.paint()?;
+    drop(cr);
+
+    let image = ImageSurface::create(options.format, tile.width, tile.height)?;
+    let cr = Context::new(&image)?;
+    cr.set_operator(Operator::Source);
+    cr.set_source_surface(&rendered, (-overlap).into(), (-overlap).into())?;
+    cr.paint()?;
+    drop(cr);
+
+    image.take_data().map_err(|err| match err {
+        BorrowError::Cairo(err) => err,
+        BorrowError::NonExclusive => unreachable!("tile image referenced elsewhere"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Tolerance, compare};
+
+    fn extents() -> Option<Rectangle> {
+        Some(Rectangle::new(-5.0, 10.0, 100.0, 70.0))
+    }
+
+    fn draw(cr: &Context) -> Result<(), Error> {
+        cr.set_line_width(3.3);
+        for i in 0..20 {
+            let i = f64::from(i);
+            cr.set_source_rgba(i / 20.0, 0.5, 1.0 - i / 20.0, 0.7);
+            cr.arc(
+                i * 5.0,
+                20.0 + i * 3.0,
+                7.5,
+                0.0,
+                2.0 * std::f64::consts::PI,
+            );
+            cr.stroke()?;
+        }
+        Ok(())
+    }
+
+    fn rasterize(scale: f64) -> ImageSurface {
+        let recording = RecordingSurface::record(Content::ColorAlpha, extents(), &draw).unwrap();
+        let image = ImageSurface::create(
+            Format::ARgb32,
+            (100.0 * scale).ceil() as i32,
+            (70.0 * scale).ceil() as i32,
+        )
+        .unwrap();
+        let cr = Context::new(&image).unwrap();
+        cr.scale(scale, scale);
+        cr.set_source_surface(&recording, 5.0, -10.0).unwrap();
+        cr.paint().unwrap();
+        drop(cr);
+        image
+    }
+
+    #[test]
+    fn tiled_matches_single() {
+        for (scale, overlap) in [(1.0, 0), (1.0, 2), (2.5, 3)] {
+            let options = TileOptions {
+                tile_width: 16,
+                tile_height: 24,
+                overlap,
+                threads: 3,
+                scale,
+                ..Default::default()
+            };
+            let tiled =
+                RecordingSurface::rasterize_tiled(Content::ColorAlpha, extents(), &options, draw)
+                    .unwrap();
+            let comparison = compare(&tiled, rasterize(scale), Tolerance::EXACT).unwrap();
+            assert!(comparison.is_match(), "{comparison}");
+        }
+    }
+
+    #[test]
+    fn tiles() {
+        let options = TileOptions {
+            tile_width: 64,
+            tile_height: 64,
+            ..Default::default()
+        };
+        let mut tiles = Vec::new();
+        RecordingSurface::rasterize_tiles(Content::ColorAlpha, extents(), &options, draw, |tile| {
+            tiles.push((tile.x, tile.y, tile.image.width(), tile.image.height()));
+            Ok(())
+        })
+        .unwrap();
+        tiles.sort();
+        assert_eq!(
+            tiles,
+            [
+                (0, 0, 64, 64),
+                (0, 64, 64, 6),
+                (64, 0, 36, 64),
+                (64, 64, 36, 6)
+            ]
+        );
+
+        let mut count = 0;
+        let result = RecordingSurface::rasterize_tiles(
+            Content::ColorAlpha,
+            extents(),
+            &options,
+            draw,
+            |_| {
+                count += 1;
+                Err(Error::UserFontError)
+            },
+        );
+        assert_eq!(result, Err(Error::UserFontError));
+        assert_eq!(count, 1);
+
+        let result = RecordingSurface::rasterize_tiles(
+            Content::ColorAlpha,
+            extents(),
+            &options,
+            |_| Err(Error::UserFontError),
+            |_| unreachable!(),
+        );
+        assert_eq!(result, Err(Error::UserFontError));
+    }
+
+    #[test]
+    fn unbounded() {
+        let options = TileOptions {
+            tile_width: 16,
+            tile_height: 16,
+            threads: 2,
+            ..Default::default()
+        };
+        let tiled = RecordingSurface::rasterize_tiled(Content::ColorAlpha, None, &options, |cr| {
+            cr.rectangle(10.0, 20.0, 30.0, 40.0);
+            cr.fill()
+        })
+        .unwrap();
+        assert_eq!((tiled.width(), tiled.height()), (30, 40));
+    }
+}
@@ -306,6 +306,37 @@ pub type cairo_user_scaled_font_unicode_to_glyph_func_t = Option<
     ) -> cairo_status_t,
 >;
 
+pub type cairo_raster_source_acquire_func_t = Option<
+    unsafe extern "C" fn(
+        pattern: *mut cairo_pattern_t,
+        callback_data: *mut c_void,
+        target: *mut cairo_surface_t,
+        extents: *const cairo_rectangle_int_t,
+    ) -> *mut cairo_surface_t,
+>;
+pub type cairo_raster_source_release_func_t = Option<
+    unsafe extern "C" fn(
+        pattern: *mut cairo_pattern_t,
+        callback_data: *mut c_void,
+        surface: *mut cairo_surface_t,
+    ),
+>;
+pub type cairo_raster_source_snapshot_func_t = Option<
+    unsafe extern "C" fn(
+        pattern: *mut cairo_pattern_t,
+        callback_data: *mut c_void,
+    ) -> cairo_status_t,
+>;
+pub type cairo_raster_source_copy_func_t = Option<
+    unsafe extern "C" fn(
+        pattern: *mut cairo_pattern_t,
+        callback_data: *mut c_void,
+        other: *const cairo_pattern_t,
+    ) -> cairo_status_t,
+>;
+pub type cairo_raster_source_finish_func_t =
+    Option<unsafe extern "C" fn(pattern: *mut cairo_pattern_t, callback_data: *mut c_void)>;
+
 unsafe extern "C" {
     pub fn cairo_create(target: *mut cairo_surface_t) -> *mut cairo_t;
     pub fn cairo_reference(cr: *mut cairo_t) -> *mut cairo_t;
@@ -825,29 +856,50 @@ unsafe extern "C" {
     );
 
     // CAIRO RASTER
-    //pub fn cairo_pattern_create_raster_source(user_data: *mut void, content: Content, width: c_int, height: c_int) -> *mut cairo_pattern_t;
-    //pub fn cairo_raster_source_pattern_set_callback_data(pattern: *mut cairo_pattern_t, data: *mut void);
-    //pub fn cairo_raster_source_pattern_get_callback_data(pattern: *mut cairo_pattern_t) -> *mut void;
-    /* FIXME how do we do these _func_t types?
-    pub fn cairo_raster_source_pattern_set_acquire(pattern: *mut cairo_pattern_t, acquire: cairo_raster_source_acquire_func_t, release: cairo_raster_source_release_func_t);
-    pub fn cairo_raster_source_pattern_get_acquire(pattern: *mut cairo_pattern_t, acquire: *mut cairo_raster_source_acquire_func_t, release: *mut cairo_raster_source_release_func_t);
-    pub fn cairo_raster_source_pattern_set_snapshot(pattern: *mut cairo_pattern_t, snapshot: cairo_raster_source_snapshot_func_t);
-    pub fn cairo_raster_source_pattern_get_snapshot(pattern: *mut cairo_pattern_t) -> cairo_raster_source_snapshot_func_t;
-    pub fn cairo_raster_source_pattern_set_copy(pattern: *mut cairo_pattern_t, copy: cairo_raster_source_copy_func_t);
-    pub fn cairo_raster_source_pattern_get_copy(pattern: *mut cairo_pattern_t) -> cairo_raster_source_copy_func_t;
-    pub fn cairo_raster_source_pattern_set_finish(pattern: *mut cairo_pattern_t, finish: cairo_raster_source_finish_func_t);
-    pub fn cairo_raster_source_pattern_get_finish(pattern: *mut cairo_pattern_t) -> cairo_raster_source_finish_func_t;
-    */
-
-    //cairo_surface_t     (*mut cairo_raster_source_acquire_func_t)
-    //                                                        (pattern: *mut cairo_pattern_t, callback_data: *mut void, target: *mut cairo_surface_t, extents: *mut cairo_rectangle_int_t);
-    //void                (*mut cairo_raster_source_release_func_t)
-    //                                                        (pattern: *mut cairo_pattern_t, callback_data: *mut void, surface: *mut cairo_surface_t);
-    //Status      (*mut cairo_raster_source_snapshot_func_t)
-    //                                                        (pattern: *mut cairo_pattern_t, callback_data: *mut void);
-    //Status      (*mut cairo_raster_source_copy_func_t)  (pattern: *mut cairo_pattern_t, callback_data: *mut void, other: *mut cairo_pattern_t);
-    //void                (*mut cairo_raster_source_finish_func_t)
-    //                                                        (pattern: *mut cairo_pattern_t, callback_data: *mut void);
+    pub fn cairo_pattern_create_raster_source(
+        user_data: *mut c_void,
+        content: cairo_content_t,
+        width: c_int,
+        height: c_int,
+    ) -> *mut cairo_pattern_t;
+    pub fn cairo_raster_source_pattern_set_callback_data(
+        pattern: *mut cairo_pattern_t,
+        data: *mut c_void,
+    );
+    pub fn cairo_raster_source_pattern_get_callback_data(
+        pattern: *mut cairo_pattern_t,
+    ) -> *mut c_void;
+    pub fn cairo_raster_source_pattern_set_acquire(
+        pattern: *mut cairo_pattern_t,
+        acquire: cairo_raster_source_acquire_func_t,
+        release: cairo_raster_source_release_func_t,
+    );
+    pub fn cairo_raster_source_pattern_get_acquire(
+        pattern: *mut cairo_pattern_t,
+        acquire: *mut cairo_raster_source_acquire_func_t,
+        release: *mut cairo_raster_source_release_func_t,
+    );
+    pub fn cairo_raster_source_pattern_set_snapshot(
+        pattern: *mut cairo_pattern_t,
+        snapshot: cairo_raster_source_snapshot_func_t,
+    );
+    pub fn cairo_raster_source_pattern_get_snapshot(
+        pattern: *mut cairo_pattern_t,
+    ) -> cairo_raster_source_snapshot_func_t;
+    pub fn cairo_raster_source_pattern_set_copy(
+        pattern: *mut cairo_pattern_t,
+        copy: cairo_raster_source_copy_func_t,
+    );
+    pub fn cairo_raster_source_pattern_get_copy(
+        pattern: *mut cairo_pattern_t,
+    ) -> cairo_raster_source_copy_func_t;
+    pub fn cairo_raster_source_pattern_set_finish(
+        pattern: *mut cairo_pattern_t,
+        finish: cairo_raster_source_finish_func_t,
+    );
+    pub fn cairo_raster_source_pattern_get_finish(
+        pattern: *mut cairo_pattern_t,
+    ) -> cairo_raster_source_finish_func_t;
 
     //CAIRO FONT
     pub fn cairo_font_face_reference(font_face: *mut cairo_font_face_t) -> *mut cairo_font_face_t;
@@ -1810,6 +1862,8 @@ pub const FORMAT_A8: i32 = 2;
 pub const FORMAT_A1: i32 = 3;
 pub const FORMAT_RGB16_565: i32 = 4;
 pub const FORMAT_RGB30: i32 = 5;
+pub const FORMAT_RGB96F: i32 = 6;
+pub const FORMAT_RGBA128F: i32 = 7;
 pub const REGION_OVERLAP_IN: i32 = 0;
 pub const REGION_OVERLAP_OUT: i32 = 1;
 pub const REGION_OVERLAP_PART: i32 = 2;
@@ -2,7 +2,7 @@
 
 use glib::{clone, prelude::*};
 
-use crate::{ActionEntry, ActionMap, SimpleAction, prelude::*};
+use crate::{ActionEntry, ActionMap, Actions, SimpleAction, prelude::*};
 pub trait ActionMapExtManual: IsA<ActionMap> {
     #[doc(alias = "g_action_map_add_action_entries")]
     fn add_action_entries(&self, entries: impl IntoIterator<Item = ActionEntry<Self>>) {
@@ -36,6 +36,15 @@ pub trait ActionMapExtManual: IsA<ActionMap> {
             self.as_ref().add_action(&action);
         }
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds all actions described by `A`, calling `activate` with the typed action on
+    /// activation.
+    ///
+    /// See [`Actions`] for details.
+    fn add_actions<A: Actions>(&self, activate: impl Fn(&Self, A) + 'static) {
+        self.add_action_entries(A::action_entries(activate));
+    }
 }
 
 impl<O: IsA<ActionMap>> ActionMapExtManual for O {}
@@ -0,0 +1,166 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::rc::Rc;
+
+use glib::{Variant, VariantType, prelude::*};
+
+use crate::{Action, ActionEntry, ActionGroup, ActionMap, prelude::*};
+
+// rustdoc-stripper-ignore-next
+/// The name, parameter type and initial state of an action described by an [`Actions`]
+/// implementation.
+#[derive(Debug, Clone)]
+pub struct ActionDescriptor {
+    name: &'static str,
+    parameter_type: Option<VariantType>,
+    state: Option<Variant>,
+}
+
+impl ActionDescriptor {
+    pub fn new(
+        name: &'static str,
+        parameter_type: Option<VariantType>,
+        state: Option<Variant>,
+    ) -> Self {
+        Self {
+            name,
+            parameter_type,
+            state,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn parameter_type(&self) -> Option<&glib::VariantTy> {
+        self.parameter_type.as_deref()
+    }
+
+    pub fn state(&self) -> Option<&Variant> {
+        self.state.as_ref()
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A set of actions described by a Rust type, usually an enum with one variant per action.
+///
+/// This is usually implemented with `#[derive(glib::Actions)]`:
+///
+/// ```
+/// use gio::prelude::*;
+///
+/// #[derive(Debug, PartialEq, glib::Actions)]
+/// enum AppAction {
+///     // An action named "quit" without parameter
+///     Quit,
+///     // An action named "open-uri" with a string parameter
+///     OpenUri(String),
+///     // A stateful action whose state and parameter are `f64`
+///     #[action(name = "zoom", state = 1.0)]
+///     ZoomLevel(f64),
+/// }
+///
+/// let group = gio::SimpleActionGroup::new();
+/// group.add_actions(|_group: &gio::SimpleActionGroup, action: AppAction| match action {
+///     AppAction::Quit => (),
+///     AppAction::OpenUri(uri) => println!("Opening {uri}"),
+///     AppAction::ZoomLevel(level) => println!("Zooming to {level}"),
+/// });
+///
+/// AppAction::OpenUri("https://gtk-rs.org".into()).activate_action(&group);
+/// assert_eq!(
+///     AppAction::OpenUri("https://gtk-rs.org".into()).detailed_name("app"),
+///     "app.open-uri('https://gtk-rs.org')"
+/// );
+/// ```
+///
+/// Unit variants describe actions without parameter, and variants with a single field
+/// describe actions whose parameter is that field's [`StaticVariantType`](glib::StaticVariantType).
+/// Action names default to the kebab-case variant name and can be overridden with
+/// `#[action(name = "...")]`. `#[action(state = ...)]` makes an action stateful, where the
+/// state of an action with parameter must be of the parameter's type. All types are checked at
+/// compile time.
+pub trait Actions: Sized + 'static {
+    // rustdoc-stripper-ignore-next
+    /// Describes all actions.
+    fn action_descriptors() -> Vec<ActionDescriptor>;
+
+    // rustdoc-stripper-ignore-next
+    /// The name of the action described by `self`.
+    fn action_name(&self) -> &'static str;
+
+    // rustdoc-stripper-ignore-next
+    /// The parameter of the action described by `self`, if it has one.
+    fn action_target(&self) -> Option<Variant>;
+
+    // rustdoc-stripper-ignore-next
+    /// Converts the name and parameter of an activated action back into `Self`.
+    ///
+    /// Returns `None` if there is no action with this name or the parameter has the wrong
+    /// type.
+    fn from_action(name: &str, parameter: Option<&Variant>) -> Option<Self>;
+
+    // rustdoc-stripper-ignore-next
+    /// Creates [`ActionEntry`]s for all actions, which call `activate` on activation.
+    fn action_entries<M: IsA<ActionMap>>(
+        activate: impl Fn(&M, Self) + 'static,
+    ) -> Vec<ActionEntry<M>> {
+        let activate = Rc::new(activate);
+
+        Self::action_descriptors()
+            .into_iter()
+            .map(|descriptor| {
+                let activate = activate.clone();
+                let mut builder = ActionEntry::builder(descriptor.name)
+                    .parameter_type(descriptor.parameter_type())
+                    .activate(move |map: &M, action, parameter| {
+                        match Self::from_action(&action.name(), parameter) {
+                            Some(value) => activate(map, value),
+                            None => glib::g_critical!(
+                                "Gio",
+                                "Invalid parameter {:?} for action {}",
+                                parameter,
+                                action.name()
+                            ),
+                        }
+                    });
+                if let Some(state) = descriptor.state {
+                    builder = builder.state(state);
+                }
+                builder.build()
+            })
+            .collect()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Activates the action described by `self` in `group`, with its parameter.
+    fn activate_action(&self, group: &impl IsA<ActionGroup>) {
+        group.activate_action(self.action_name(), self.action_target().as_ref());
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Requests changing the state of the stateful action described by `self` in `group` to
+    /// its parameter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the action has no parameter.
+    fn change_action_state(&self, group: &impl IsA<ActionGroup>) {
+        let value = self
+            .action_target()
+            .expect("only actions with a parameter can change their state");
+        group.change_action_state(self.action_name(), &value);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The detailed action name of `self` with `prefix`, e.g. for use in menus.
+    fn detailed_name(&self, prefix: &str) -> String {
+        let name = Action::print_detailed_name(self.action_name(), self.action_target().as_ref());
+        if prefix.is_empty() {
+            name.into()
+        } else {
+            format!("{prefix}.{name}")
+        }
+    }
+}
@@ -0,0 +1,440 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    io::SeekFrom,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread,
+};
+
+use futures_io::{AsyncRead, AsyncSeek};
+use glib::translate::*;
+
+use crate::{
+    Cancellable, InputStream, PollableInputStream, ffi, prelude::*,
+    read_input_stream::std_error_to_gio_error, subclass::prelude::*,
+};
+
+mod imp {
+    use super::*;
+    use crate::PollableReadiness;
+
+    pub(super) trait AsyncReadSeek: AsyncRead + AsyncSeek + Send {}
+    impl<T: AsyncRead + AsyncSeek + Send> AsyncReadSeek for T {}
+
+    pub(super) enum Reader {
+        Read(Pin<Box<dyn AsyncRead + Send>>),
+        ReadSeek(Pin<Box<dyn AsyncReadSeek>>),
+    }
+
+    impl Reader {
+        fn as_read(&mut self) -> Pin<&mut (dyn AsyncRead + Send)> {
+            match self {
+                Reader::Read(read) => read.as_mut(),
+                Reader::ReadSeek(read) => read.as_mut(),
+            }
+        }
+    }
+
+    // The waker passed to the reader by non-blocking reads.
+    #[derive(Default)]
+    pub(super) struct ReaderWaker {
+        // Cleared when a non-blocking read is pending, and set again once the reader wakes up
+        // the waker or a read succeeds.
+        readable: AtomicBool,
+        sources: PollableReadiness,
+    }
+
+    impl Wake for ReaderWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.readable.store(true, Ordering::SeqCst);
+            self.sources.wake();
+        }
+    }
+
+    // The state is behind a mutex as GIO may call the blocking functions from a worker thread,
+    // e.g. when closing a `GIOStream` asynchronously.
+    pub struct AsyncReadInputStream {
+        pub(super) read: Mutex<Option<Reader>>,
+        pub(super) waker: Arc<ReaderWaker>,
+    }
+
+    impl Default for AsyncReadInputStream {
+        fn default() -> Self {
+            Self {
+                read: Mutex::default(),
+                waker: Arc::new(ReaderWaker {
+                    readable: AtomicBool::new(true),
+                    sources: PollableReadiness::new(),
+                }),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AsyncReadInputStream {
+        const NAME: &'static str = "AsyncReadInputStream";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::AsyncReadInputStream;
+        type ParentType = InputStream;
+        type Interfaces = (PollableInputStream, crate::Seekable);
+
+        fn class_init(klass: &mut Self::Class) {
+            // Closing only drops the reader, so do that right away instead of GIO's default of
+            // running the blocking version on a thread.
+            let klass = unsafe { &mut *(klass as *mut Self::Class as *mut ffi::GInputStreamClass) };
+            klass.close_async = Some(stream_close_async);
+            klass.close_finish = Some(stream_close_finish);
+        }
+    }
+
+    impl ObjectImpl for AsyncReadInputStream {}
+
+    impl AsyncReadInputStream {
+        pub(super) fn poll_read(
+            &self,
+            cx: &mut Context<'_>,
+            buffer: &mut [u8],
+        ) -> Poll<Result<usize, glib::Error>> {
+            let mut read = self.read.lock().unwrap();
+            let Some(read) = read.as_mut() else {
+                return Poll::Ready(Err(glib::Error::new(
+                    crate::IOErrorEnum::Closed,
+                    "Already closed",
+                )));
+            };
+
+            loop {
+                match read.as_read().poll_read(cx, buffer) {
+                    Poll::Ready(res) => match std_error_to_gio_error(res) {
+                        None => continue,
+                        Some(res) => return Poll::Ready(res),
+                    },
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+
+        pub(super) fn poll_seek(
+            &self,
+            cx: &mut Context<'_>,
+            pos: SeekFrom,
+        ) -> Poll<Result<u64, glib::Error>> {
+            let mut read = self.read.lock().unwrap();
+            let read = match *read {
+                Some(Reader::ReadSeek(ref mut read)) => read,
+                _ => {
+                    return Poll::Ready(Err(glib::Error::new(
+                        crate::IOErrorEnum::NotSupported,
+                        "Seeking not supported",
+                    )));
+                }
+            };
+
+            loop {
+                match read.as_mut().poll_seek(cx, pos) {
+                    Poll::Ready(res) => match std_error_to_gio_error(res) {
+                        None => continue,
+                        Some(res) => return Poll::Ready(res),
+                    },
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+
+        pub(super) fn close(&self) {
+            let _ = self.read.lock().unwrap().take();
+        }
+    }
+
+    impl InputStreamImpl for AsyncReadInputStream {
+        fn read(
+            &self,
+            buffer: &mut [u8],
+            cancellable: Option<&Cancellable>,
+        ) -> Result<usize, glib::Error> {
+            let res = block_on_poll(cancellable, |cx| self.poll_read(cx, buffer));
+            self.waker.readable.store(true, Ordering::SeqCst);
+            res
+        }
+
+        fn close(&self, _cancellable: Option<&Cancellable>) -> Result<(), glib::Error> {
+            self.close();
+            Ok(())
+        }
+    }
+
+    impl PollableInputStreamImpl for AsyncReadInputStream {
+        fn can_poll(&self) -> bool {
+            true
+        }
+
+        fn is_readable(&self) -> bool {
+            // `AsyncRead` can't be asked for readiness without reading, so report what the last
+            // non-blocking read found out. False positives are allowed by GIO.
+            self.waker.readable.load(Ordering::SeqCst)
+        }
+
+        fn create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source {
+            self.waker.sources.create_source(&*self.obj(), cancellable)
+        }
+
+        fn read_nonblocking(&self, buffer: &mut [u8]) -> Result<usize, glib::Error> {
+            // Cleared before polling, so that a wake-up during polling isn't lost
+            self.waker.readable.store(false, Ordering::SeqCst);
+            let waker = Waker::from(self.waker.clone());
+            match self.poll_read(&mut Context::from_waker(&waker), buffer) {
+                Poll::Ready(res) => {
+                    self.waker.readable.store(true, Ordering::SeqCst);
+                    res
+                }
+                Poll::Pending => Err(glib::Error::new(
+                    crate::IOErrorEnum::WouldBlock,
+                    "Would Block",
+                )),
+            }
+        }
+    }
+
+    impl SeekableImpl for AsyncReadInputStream {
+        fn tell(&self) -> i64 {
+            if !self.can_seek() {
+                return -1;
+            }
+
+            match block_on_poll(None, |cx| self.poll_seek(cx, SeekFrom::Current(0))) {
+                Ok(pos) => pos as i64,
+                Err(_) => -1,
+            }
+        }
+
+        fn can_seek(&self) -> bool {
+            let read = self.read.lock().unwrap();
+            matches!(*read, Some(Reader::ReadSeek(_)))
+        }
+
+        fn seek(
+            &self,
+            offset: i64,
+            type_: glib::SeekType,
+            cancellable: Option<&Cancellable>,
+        ) -> Result<(), glib::Error> {
+            let pos = match type_ {
+                glib::SeekType::Cur => SeekFrom::Current(offset),
+                glib::SeekType::Set => {
+                    if offset < 0 {
+                        return Err(glib::Error::new(
+                            crate::IOErrorEnum::InvalidArgument,
+                            "Invalid Argument",
+                        ));
+                    } else {
+                        SeekFrom::Start(offset as u64)
+                    }
+                }
+                glib::SeekType::End => SeekFrom::End(offset),
+                _ => unimplemented!(),
+            };
+
+            block_on_poll(cancellable, |cx| self.poll_seek(cx, pos)).map(|_| ())
+        }
+
+        fn can_truncate(&self) -> bool {
+            false
+        }
+
+        fn truncate(
+            &self,
+            _offset: i64,
+            _cancellable: Option<&Cancellable>,
+        ) -> Result<(), glib::Error> {
+            Err(glib::Error::new(
+                crate::IOErrorEnum::NotSupported,
+                "Truncating not supported",
+            ))
+        }
+    }
+
+    unsafe extern "C" fn stream_close_async(
+        stream: *mut ffi::GInputStream,
+        _io_priority: std::ffi::c_int,
+        cancellable: *mut ffi::GCancellable,
+        callback: ffi::GAsyncReadyCallback,
+        user_data: glib::ffi::gpointer,
+    ) {
+        unsafe {
+            let task = ffi::g_task_new(stream as *mut _, cancellable, callback, user_data);
+            let obj: Borrowed<super::AsyncReadInputStream> = from_glib_borrow(stream as *mut _);
+            obj.imp().close();
+            // `GTask` calls `callback` from the thread-default main context once this returned
+            ffi::g_task_return_boolean(task, glib::ffi::GTRUE);
+            glib::gobject_ffi::g_object_unref(task as *mut _);
+        }
+    }
+
+    unsafe extern "C" fn stream_close_finish(
+        _stream: *mut ffi::GInputStream,
+        result: *mut ffi::GAsyncResult,
+        error: *mut *mut glib::ffi::GError,
+    ) -> glib::ffi::gboolean {
+        unsafe { ffi::g_task_propagate_boolean(result as *mut ffi::GTask, error) }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// An [`InputStream`] reading from a Rust [`AsyncRead`].
+    ///
+    /// The stream is pollable, so asynchronous reads, e.g. via
+    /// [`InputStreamExtManual::read_future`], poll the reader on the thread-default
+    /// [`MainContext`](glib::MainContext) without involving any threads. Synchronous reads block
+    /// the calling thread until the reader is ready.
+    ///
+    /// Combined with [`AsyncWriteOutputStream`](crate::AsyncWriteOutputStream) via
+    /// [`SimpleIOStream`](crate::SimpleIOStream), any Rust async transport can be passed to APIs
+    /// like [`DBusConnection::new_future`](crate::DBusConnection::new_future).
+    ///
+    /// [`InputStreamExtManual::read_future`]: crate::prelude::InputStreamExtManual::read_future
+    pub struct AsyncReadInputStream(ObjectSubclass<imp::AsyncReadInputStream>)
+        @extends InputStream,
+        @implements PollableInputStream, crate::Seekable;
+}
+
+impl AsyncReadInputStream {
+    pub fn new<R: AsyncRead + Send + 'static>(read: R) -> AsyncReadInputStream {
+        let obj: Self = glib::Object::new();
+
+        *obj.imp().read.lock().unwrap() = Some(imp::Reader::Read(Box::pin(read)));
+
+        obj
+    }
+
+    pub fn new_seekable<R: AsyncRead + AsyncSeek + Send + 'static>(
+        read: R,
+    ) -> AsyncReadInputStream {
+        let obj: Self = glib::Object::new();
+
+        *obj.imp().read.lock().unwrap() = Some(imp::Reader::ReadSeek(Box::pin(read)));
+
+        obj
+    }
+}
+
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Blocks the current thread until `poll` is ready or `cancellable` is cancelled.
+pub(crate) fn block_on_poll<T>(
+    cancellable: Option<&Cancellable>,
+    mut poll: impl FnMut(&mut Context<'_>) -> Poll<Result<T, glib::Error>>,
+) -> Result<T, glib::Error> {
+    let current = thread::current();
+    let waker = Waker::from(Arc::new(ThreadWaker(current.clone())));
+    let mut cx = Context::from_waker(&waker);
+
+    let handler_id = cancellable.and_then(|c| c.connect_cancelled(move |_| current.unpark()));
+
+    let res = loop {
+        if let Some(cancellable) = cancellable
+            && let Err(err) = cancellable.set_error_if_cancelled()
+        {
+            break Err(err);
+        }
+        if let Poll::Ready(res) = poll(&mut cx) {
+            break res;
+        }
+        thread::park();
+    };
+
+    if let (Some(cancellable), Some(handler_id)) = (cancellable, handler_id) {
+        cancellable.disconnect_cancelled(handler_id);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::io::{AllowStdIo, Cursor};
+
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let cursor = Cursor::new(vec![1, 2, 3, 4, 5]);
+        let stream = AsyncReadInputStream::new(cursor);
+
+        let mut buf = [0u8; 2];
+        assert_eq!(stream.read(&mut buf, None::<&Cancellable>), Ok(2));
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(
+            stream.read_nonblocking(&mut buf, None::<&Cancellable>),
+            Ok(2)
+        );
+        assert_eq!(buf, [3, 4]);
+        assert!(stream.is_readable());
+        assert_eq!(stream.read(&mut buf, None::<&Cancellable>), Ok(1));
+        assert_eq!(buf[0], 5);
+        assert_eq!(stream.read(&mut buf, None::<&Cancellable>), Ok(0));
+    }
+
+    #[test]
+    fn test_read_future() {
+        let data = crate::test_util::run_async_local(|tx, l| {
+            let ctx = glib::MainContext::ref_thread_default();
+            ctx.spawn_local(async move {
+                let stream = AsyncReadInputStream::new(AllowStdIo::new(&b"hello world"[..]));
+                let res = stream
+                    .read_all_future(vec![0; 11], glib::Priority::default())
+                    .await
+                    .unwrap();
+                stream
+                    .close_future(glib::Priority::default())
+                    .await
+                    .unwrap();
+                assert!(stream.is_closed());
+                assert!(
+                    stream
+                        .read_future(vec![0; 1], glib::Priority::default())
+                        .await
+                        .unwrap_err()
+                        .1
+                        .matches(crate::IOErrorEnum::Closed)
+                );
+                tx.send(res.0).unwrap();
+                l.quit();
+            });
+        });
+
+        assert_eq!(data, b"hello world");
+    }
+
+    #[test]
+    fn test_seek() {
+        let cursor = Cursor::new(vec![1, 2, 3, 4, 5]);
+        let stream = AsyncReadInputStream::new_seekable(cursor);
+
+        assert!(stream.can_seek());
+        assert!(stream.is_readable());
+        assert_eq!(stream.tell(), 0);
+        stream
+            .seek(3, glib::SeekType::Set, None::<&Cancellable>)
+            .unwrap();
+        let mut buf = [0u8; 2];
+        assert_eq!(stream.read(&mut buf, None::<&Cancellable>), Ok(2));
+        assert_eq!(buf, [4, 5]);
+        assert_eq!(stream.tell(), 5);
+    }
+}
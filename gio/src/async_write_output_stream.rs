@@ -0,0 +1,452 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    future::poll_fn,
+    io::SeekFrom,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_io::{AsyncSeek, AsyncWrite};
+use glib::translate::*;
+
+use crate::{
+    Cancellable, CancellableFuture, OutputStream, PollableOutputStream,
+    async_read_input_stream::block_on_poll, ffi, read_input_stream::std_error_to_gio_error,
+    subclass::prelude::*,
+};
+
+mod imp {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::PollableReadiness;
+
+    pub(super) trait AsyncWriteSeek: AsyncWrite + AsyncSeek + Send {}
+    impl<T: AsyncWrite + AsyncSeek + Send> AsyncWriteSeek for T {}
+
+    pub(super) enum Writer {
+        Write(Pin<Box<dyn AsyncWrite + Send>>),
+        WriteSeek(Pin<Box<dyn AsyncWriteSeek>>),
+    }
+
+    impl Writer {
+        fn as_write(&mut self) -> Pin<&mut (dyn AsyncWrite + Send)> {
+            match self {
+                Writer::Write(write) => write.as_mut(),
+                Writer::WriteSeek(write) => write.as_mut(),
+            }
+        }
+    }
+
+    // The state is behind a mutex as GIO may call the blocking functions from a worker thread,
+    // e.g. when closing a `GIOStream` asynchronously.
+    #[derive(Default)]
+    pub struct AsyncWriteOutputStream {
+        pub(super) write: Mutex<Option<Writer>>,
+        pub(super) readiness: PollableReadiness,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AsyncWriteOutputStream {
+        const NAME: &'static str = "AsyncWriteOutputStream";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::AsyncWriteOutputStream;
+        type ParentType = OutputStream;
+        type Interfaces = (PollableOutputStream, crate::Seekable);
+
+        fn class_init(klass: &mut Self::Class) {
+            // Flushing and closing can't be expressed via the pollable interface, so implement
+            // them natively instead of GIO's default of running the blocking versions on a
+            // thread.
+            let klass =
+                unsafe { &mut *(klass as *mut Self::Class as *mut ffi::GOutputStreamClass) };
+            klass.flush_async = Some(stream_flush_async);
+            klass.flush_finish = Some(stream_finish);
+            klass.close_async = Some(stream_close_async);
+            klass.close_finish = Some(stream_finish);
+        }
+    }
+
+    impl ObjectImpl for AsyncWriteOutputStream {}
+
+    impl AsyncWriteOutputStream {
+        fn with_writer<T>(
+            &self,
+            f: impl FnOnce(&mut Writer) -> Poll<Result<T, std::io::Error>>,
+        ) -> Poll<Option<Result<T, glib::Error>>> {
+            let mut write = self.write.lock().unwrap();
+            let Some(write) = write.as_mut() else {
+                return Poll::Ready(Some(Err(glib::Error::new(
+                    crate::IOErrorEnum::Closed,
+                    "Already closed",
+                ))));
+            };
+
+            f(write).map(std_error_to_gio_error)
+        }
+
+        fn retry<T>(
+            mut poll: impl FnMut() -> Poll<Option<Result<T, glib::Error>>>,
+        ) -> Poll<Result<T, glib::Error>> {
+            loop {
+                match poll() {
+                    Poll::Ready(None) => continue,
+                    Poll::Ready(Some(res)) => return Poll::Ready(res),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+
+        pub(super) fn poll_write(
+            &self,
+            cx: &mut Context<'_>,
+            buffer: &[u8],
+        ) -> Poll<Result<usize, glib::Error>> {
+            Self::retry(|| self.with_writer(|write| write.as_write().poll_write(cx, buffer)))
+        }
+
+        pub(super) fn poll_flush(&self, cx: &mut Context<'_>) -> Poll<Result<(), glib::Error>> {
+            Self::retry(|| self.with_writer(|write| write.as_write().poll_flush(cx)))
+        }
+
+        pub(super) fn poll_close(&self, cx: &mut Context<'_>) -> Poll<Result<(), glib::Error>> {
+            // Closing twice is caught by `GOutputStream` already
+            if self.write.lock().unwrap().is_none() {
+                return Poll::Ready(Ok(()));
+            }
+
+            let res = std::task::ready!(Self::retry(
+                || self.with_writer(|write| write.as_write().poll_close(cx))
+            ));
+            let _ = self.write.lock().unwrap().take();
+            Poll::Ready(res)
+        }
+
+        fn poll_seek(&self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64, glib::Error>> {
+            Self::retry(|| {
+                self.with_writer(|write| match write {
+                    Writer::WriteSeek(write) => write.as_mut().poll_seek(cx, pos),
+                    Writer::Write(_) => Poll::Ready(Err(std::io::Error::new(
+                        std::io::ErrorKind::Unsupported,
+                        "Seeking not supported",
+                    ))),
+                })
+            })
+        }
+    }
+
+    impl OutputStreamImpl for AsyncWriteOutputStream {
+        fn write(
+            &self,
+            buffer: &[u8],
+            cancellable: Option<&Cancellable>,
+        ) -> Result<usize, glib::Error> {
+            block_on_poll(cancellable, |cx| self.poll_write(cx, buffer))
+        }
+
+        fn close(&self, cancellable: Option<&Cancellable>) -> Result<(), glib::Error> {
+            block_on_poll(cancellable, |cx| self.poll_close(cx))
+        }
+
+        fn flush(&self, cancellable: Option<&Cancellable>) -> Result<(), glib::Error> {
+            block_on_poll(cancellable, |cx| self.poll_flush(cx))
+        }
+    }
+
+    impl PollableOutputStreamImpl for AsyncWriteOutputStream {
+        fn can_poll(&self) -> bool {
+            true
+        }
+
+        fn is_writable(&self) -> bool {
+            // There is no way to check for readiness without writing, so let writes find out.
+            true
+        }
+
+        fn create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source {
+            self.readiness.create_source(&*self.obj(), cancellable)
+        }
+
+        fn write_nonblocking(&self, buffer: &[u8]) -> Result<usize, glib::Error> {
+            let waker = self.readiness.waker();
+            match self.poll_write(&mut Context::from_waker(&waker), buffer) {
+                Poll::Ready(res) => res,
+                Poll::Pending => Err(glib::Error::new(
+                    crate::IOErrorEnum::WouldBlock,
+                    "Would Block",
+                )),
+            }
+        }
+    }
+
+    impl SeekableImpl for AsyncWriteOutputStream {
+        fn tell(&self) -> i64 {
+            if !self.can_seek() {
+                return -1;
+            }
+
+            match block_on_poll(None, |cx| self.poll_seek(cx, SeekFrom::Current(0))) {
+                Ok(pos) => pos as i64,
+                Err(_) => -1,
+            }
+        }
+
+        fn can_seek(&self) -> bool {
+            let write = self.write.lock().unwrap();
+            matches!(*write, Some(Writer::WriteSeek(_)))
+        }
+
+        fn seek(
+            &self,
+            offset: i64,
+            type_: glib::SeekType,
+            cancellable: Option<&Cancellable>,
+        ) -> Result<(), glib::Error> {
+            if !self.can_seek() {
+                return Err(glib::Error::new(
+                    crate::IOErrorEnum::NotSupported,
+                    "Seeking not supported",
+                ));
+            }
+
+            let pos = match type_ {
+                glib::SeekType::Cur => SeekFrom::Current(offset),
+                glib::SeekType::Set => {
+                    if offset < 0 {
+                        return Err(glib::Error::new(
+                            crate::IOErrorEnum::InvalidArgument,
+                            "Invalid Argument",
+                        ));
+                    } else {
+                        SeekFrom::Start(offset as u64)
+                    }
+                }
+                glib::SeekType::End => SeekFrom::End(offset),
+                _ => unimplemented!(),
+            };
+
+            block_on_poll(cancellable, |cx| self.poll_seek(cx, pos)).map(|_| ())
+        }
+
+        fn can_truncate(&self) -> bool {
+            false
+        }
+
+        fn truncate(
+            &self,
+            _offset: i64,
+            _cancellable: Option<&Cancellable>,
+        ) -> Result<(), glib::Error> {
+            Err(glib::Error::new(
+                crate::IOErrorEnum::NotSupported,
+                "Truncating not supported",
+            ))
+        }
+    }
+
+    unsafe extern "C" fn stream_flush_async(
+        stream: *mut ffi::GOutputStream,
+        io_priority: std::ffi::c_int,
+        cancellable: *mut ffi::GCancellable,
+        callback: ffi::GAsyncReadyCallback,
+        user_data: glib::ffi::gpointer,
+    ) {
+        unsafe {
+            spawn_task(
+                stream,
+                io_priority,
+                cancellable,
+                callback,
+                user_data,
+                |imp, cx| imp.poll_flush(cx),
+            );
+        }
+    }
+
+    unsafe extern "C" fn stream_close_async(
+        stream: *mut ffi::GOutputStream,
+        io_priority: std::ffi::c_int,
+        cancellable: *mut ffi::GCancellable,
+        callback: ffi::GAsyncReadyCallback,
+        user_data: glib::ffi::gpointer,
+    ) {
+        unsafe {
+            spawn_task(
+                stream,
+                io_priority,
+                cancellable,
+                callback,
+                user_data,
+                |imp, cx| imp.poll_close(cx),
+            );
+        }
+    }
+
+    unsafe extern "C" fn stream_finish(
+        _stream: *mut ffi::GOutputStream,
+        result: *mut ffi::GAsyncResult,
+        error: *mut *mut glib::ffi::GError,
+    ) -> glib::ffi::gboolean {
+        unsafe { ffi::g_task_propagate_boolean(result as *mut ffi::GTask, error) }
+    }
+
+    // Polls `poll` on the thread-default main context and returns the result via a `GTask`.
+    unsafe fn spawn_task(
+        stream: *mut ffi::GOutputStream,
+        io_priority: std::ffi::c_int,
+        cancellable: *mut ffi::GCancellable,
+        callback: ffi::GAsyncReadyCallback,
+        user_data: glib::ffi::gpointer,
+        mut poll: impl FnMut(&AsyncWriteOutputStream, &mut Context<'_>) -> Poll<Result<(), glib::Error>>
+        + 'static,
+    ) {
+        unsafe {
+            let task = ffi::g_task_new(stream as *mut _, cancellable, callback, user_data);
+            let obj: super::AsyncWriteOutputStream = from_glib_none(stream as *mut _);
+            let cancellable: Option<Cancellable> = from_glib_none(cancellable);
+
+            glib::MainContext::ref_thread_default().spawn_local_with_priority(
+                from_glib(io_priority),
+                async move {
+                    let future = poll_fn(|cx| poll(obj.imp(), cx));
+                    let res = match cancellable {
+                        Some(cancellable) => CancellableFuture::new(future, cancellable.clone())
+                            .await
+                            .unwrap_or_else(|_| {
+                                Err(glib::Error::new(
+                                    crate::IOErrorEnum::Cancelled,
+                                    "Task was cancelled",
+                                ))
+                            }),
+                        None => future.await,
+                    };
+
+                    match res {
+                        Ok(()) => ffi::g_task_return_boolean(task, glib::ffi::GTRUE),
+                        Err(err) => ffi::g_task_return_error(task, err.into_glib_ptr()),
+                    }
+                    glib::gobject_ffi::g_object_unref(task as *mut _);
+                },
+            );
+        }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// An [`OutputStream`] writing to a Rust [`AsyncWrite`].
+    ///
+    /// The stream is pollable and implements flushing and closing natively, so asynchronous
+    /// operations, e.g. via [`OutputStreamExtManual::write_all_future`], poll the writer on the
+    /// thread-default [`MainContext`](glib::MainContext) without involving any threads.
+    /// Synchronous operations block the calling thread until the writer is ready.
+    ///
+    /// [`OutputStreamExtManual::write_all_future`]: crate::prelude::OutputStreamExtManual::write_all_future
+    pub struct AsyncWriteOutputStream(ObjectSubclass<imp::AsyncWriteOutputStream>)
+        @extends OutputStream,
+        @implements PollableOutputStream, crate::Seekable;
+}
+
+impl AsyncWriteOutputStream {
+    pub fn new<W: AsyncWrite + Send + 'static>(write: W) -> AsyncWriteOutputStream {
+        let obj: Self = glib::Object::new();
+
+        *obj.imp().write.lock().unwrap() = Some(imp::Writer::Write(Box::pin(write)));
+
+        obj
+    }
+
+    pub fn new_seekable<W: AsyncWrite + AsyncSeek + Send + 'static>(
+        write: W,
+    ) -> AsyncWriteOutputStream {
+        let obj: Self = glib::Object::new();
+
+        *obj.imp().write.lock().unwrap() = Some(imp::Writer::WriteSeek(Box::pin(write)));
+
+        obj
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{AsyncReadInputStream, SimpleIOStream, prelude::*};
+
+    #[derive(Clone, Default)]
+    struct SharedWriter {
+        data: Arc<Mutex<Vec<u8>>>,
+        closed: Arc<Mutex<bool>>,
+    }
+
+    impl AsyncWrite for SharedWriter {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.data.lock().unwrap().extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            *self.closed.lock().unwrap() = true;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let writer = SharedWriter::default();
+        let stream = AsyncWriteOutputStream::new(writer.clone());
+
+        assert_eq!(stream.write(&[1, 2, 3], None::<&Cancellable>), Ok(3));
+        assert_eq!(
+            stream.write_nonblocking(&[4, 5], None::<&Cancellable>),
+            Ok(2)
+        );
+        stream.close(None::<&Cancellable>).unwrap();
+
+        assert_eq!(*writer.data.lock().unwrap(), [1, 2, 3, 4, 5]);
+        assert!(*writer.closed.lock().unwrap());
+    }
+
+    #[test]
+    fn test_write_future() {
+        let writer = SharedWriter::default();
+        let writer_clone = writer.clone();
+        crate::test_util::run_async_local(move |tx, l| {
+            let ctx = glib::MainContext::ref_thread_default();
+            ctx.spawn_local(async move {
+                let input = AsyncReadInputStream::new(futures_util::io::Cursor::new(vec![]));
+                let output = AsyncWriteOutputStream::new(writer_clone);
+                let stream = SimpleIOStream::new(&input, &output);
+                stream
+                    .output_stream()
+                    .write_all_future(b"hello world", glib::Priority::default())
+                    .await
+                    .unwrap();
+                output
+                    .close_future(glib::Priority::default())
+                    .await
+                    .unwrap();
+                assert!(output.is_closed());
+                stream
+                    .close_future(glib::Priority::default())
+                    .await
+                    .unwrap();
+                tx.send(()).unwrap();
+                l.quit();
+            });
+        });
+
+        assert_eq!(*writer.data.lock().unwrap(), b"hello world");
+        assert!(*writer.closed.lock().unwrap());
+    }
+}
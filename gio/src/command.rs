@@ -0,0 +1,498 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::{FusedStream, Stream};
+use futures_util::FutureExt;
+use glib::prelude::*;
+
+use crate::{
+    DataInputStream, InputStream, MemoryInputStream, OutputStream, OutputStreamSpliceFlags,
+    Subprocess, SubprocessFlags, SubprocessLauncher, prelude::*,
+};
+
+// rustdoc-stripper-ignore-next
+/// Describes what to do with a standard I/O stream of a child process spawned by
+/// [`Command`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stdio {
+    // rustdoc-stripper-ignore-next
+    /// The child inherits the stream from the parent.
+    #[default]
+    Inherit,
+    // rustdoc-stripper-ignore-next
+    /// A pipe to the child is created and made available on the [`Child`].
+    Piped,
+    // rustdoc-stripper-ignore-next
+    /// The stream is connected to `/dev/null`.
+    Null,
+}
+
+#[derive(Debug)]
+enum Stdin {
+    Stdio(Stdio),
+    Stream(InputStream),
+}
+
+// rustdoc-stripper-ignore-next
+/// A builder for spawning child processes, modelled after [`std::process::Command`].
+///
+/// This is a thin layer over [`SubprocessLauncher`] that spawns a [`Child`] whose output can be
+/// consumed asynchronously on the thread-default [`MainContext`](glib::MainContext).
+///
+/// ```no_run
+/// # use futures_util::StreamExt;
+/// # async fn run() -> Result<(), glib::Error> {
+/// let mut child = gio::Command::new("ls")
+///     .arg("-l")
+///     .stdout(gio::Stdio::Piped)
+///     .spawn()?;
+///
+/// let mut lines = child.stdout_lines().unwrap();
+/// while let Some(line) = lines.next().await {
+///     println!("{}", line?);
+/// }
+///
+/// let status = child.wait_check_future().await?;
+/// assert!(status.success());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Command {
+    launcher: SubprocessLauncher,
+    program: OsString,
+    args: Vec<OsString>,
+    stdin: Stdin,
+    stdout: Stdio,
+    stderr: Stdio,
+    kill_on_drop: bool,
+}
+
+impl Command {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new `Command` for launching `program`.
+    ///
+    /// `program` is looked up in `PATH` if it doesn't contain a path separator.
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            launcher: SubprocessLauncher::new(SubprocessFlags::NONE),
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            stdin: Stdin::Stdio(Stdio::Null),
+            stdout: Stdio::Inherit,
+            stderr: Stdio::Inherit,
+            kill_on_drop: true,
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds an argument.
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Adds multiple arguments.
+    pub fn args(&mut self, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> &mut Self {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets an environment variable for the child.
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.launcher.setenv(key, value, true);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets multiple environment variables for the child.
+    pub fn envs(
+        &mut self,
+        vars: impl IntoIterator<Item = (impl AsRef<OsStr>, impl AsRef<OsStr>)>,
+    ) -> &mut Self {
+        for (key, value) in vars {
+            self.launcher.setenv(key, value, true);
+        }
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes an environment variable from the child's environment.
+    pub fn env_remove(&mut self, key: impl AsRef<OsStr>) -> &mut Self {
+        self.launcher.unsetenv(key);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Clears the child's environment, including all variables set so far.
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.launcher.set_environ(&[]);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the working directory of the child.
+    pub fn current_dir(&mut self, dir: impl AsRef<Path>) -> &mut Self {
+        self.launcher.set_cwd(dir);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Configures the child's standard input. Defaults to [`Stdio::Null`].
+    pub fn stdin(&mut self, cfg: Stdio) -> &mut Self {
+        self.stdin = Stdin::Stdio(cfg);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Feeds `bytes` to the child's standard input and closes it afterwards.
+    pub fn stdin_bytes(&mut self, bytes: &glib::Bytes) -> &mut Self {
+        self.stdin = Stdin::Stream(MemoryInputStream::from_bytes(bytes).upcast());
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Feeds the contents of `stream` to the child's standard input and closes both afterwards.
+    pub fn stdin_stream(&mut self, stream: &impl IsA<InputStream>) -> &mut Self {
+        self.stdin = Stdin::Stream(stream.as_ref().clone());
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Configures the child's standard output. Defaults to [`Stdio::Inherit`].
+    pub fn stdout(&mut self, cfg: Stdio) -> &mut Self {
+        self.stdout = cfg;
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Configures the child's standard error. Defaults to [`Stdio::Inherit`].
+    pub fn stderr(&mut self, cfg: Stdio) -> &mut Self {
+        self.stderr = cfg;
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Whether the child is killed when its [`Child`] handle is dropped. Defaults to `true`.
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the underlying launcher for settings not covered by `Command`.
+    pub fn launcher(&self) -> &SubprocessLauncher {
+        &self.launcher
+    }
+
+    fn flags(&self) -> SubprocessFlags {
+        let mut flags = match self.stdin {
+            Stdin::Stdio(Stdio::Inherit) => SubprocessFlags::STDIN_INHERIT,
+            Stdin::Stdio(Stdio::Piped) | Stdin::Stream(_) => SubprocessFlags::STDIN_PIPE,
+            Stdin::Stdio(Stdio::Null) => SubprocessFlags::NONE,
+        };
+        flags |= match self.stdout {
+            Stdio::Inherit => SubprocessFlags::NONE,
+            Stdio::Piped => SubprocessFlags::STDOUT_PIPE,
+            Stdio::Null => SubprocessFlags::STDOUT_SILENCE,
+        };
+        flags |= match self.stderr {
+            Stdio::Inherit => SubprocessFlags::NONE,
+            Stdio::Piped => SubprocessFlags::STDERR_PIPE,
+            Stdio::Null => SubprocessFlags::STDERR_SILENCE,
+        };
+        flags
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Spawns the child process.
+    ///
+    /// If standard input is fed from [`glib::Bytes`] or a stream, this is done asynchronously
+    /// on the thread-default main context, which must be owned by the calling thread.
+    pub fn spawn(&mut self) -> Result<Child, glib::Error> {
+        self.launcher.set_flags(self.flags());
+
+        let argv = std::iter::once(self.program.as_os_str())
+            .chain(self.args.iter().map(|arg| arg.as_os_str()))
+            .collect::<Vec<_>>();
+        let subprocess = self.launcher.spawn(&argv)?;
+
+        let (stdin, stdin_task) = match self.stdin {
+            Stdin::Stream(ref stream) => {
+                let pipe = subprocess.stdin_pipe().expect("no stdin pipe");
+                let fut = pipe.splice_future(
+                    stream,
+                    OutputStreamSpliceFlags::CLOSE_SOURCE | OutputStreamSpliceFlags::CLOSE_TARGET,
+                    glib::Priority::default(),
+                );
+                // Errors are ignored like for a child that exits without reading all its
+                // input, the exit status is what tells about failure.
+                let task = glib::MainContext::ref_thread_default().spawn_local(fut.map(|_| ()));
+                (None, Some(task))
+            }
+            Stdin::Stdio(_) => (subprocess.stdin_pipe(), None),
+        };
+
+        Ok(Child {
+            stdout: subprocess.stdout_pipe(),
+            stderr: subprocess.stderr_pipe(),
+            subprocess,
+            stdin,
+            stdin_task,
+            kill_on_drop: self.kill_on_drop,
+        })
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A child process spawned by [`Command::spawn`].
+///
+/// Unless disabled with [`Command::kill_on_drop`], the process is killed when this is dropped.
+#[derive(Debug)]
+pub struct Child {
+    subprocess: Subprocess,
+    stdin: Option<OutputStream>,
+    stdout: Option<InputStream>,
+    stderr: Option<InputStream>,
+    stdin_task: Option<glib::JoinHandle<()>>,
+    kill_on_drop: bool,
+}
+
+impl Child {
+    // rustdoc-stripper-ignore-next
+    /// Returns the underlying [`Subprocess`].
+    pub fn subprocess(&self) -> &Subprocess {
+        &self.subprocess
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the process identifier, or `None` once the process has exited.
+    pub fn id(&self) -> Option<glib::GString> {
+        self.subprocess.identifier()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Takes the child's standard input if it was configured as [`Stdio::Piped`].
+    ///
+    /// Dropping or closing the stream signals end of input to the child.
+    pub fn take_stdin(&mut self) -> Option<OutputStream> {
+        self.stdin.take()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Takes the child's standard output if it was configured as [`Stdio::Piped`].
+    pub fn take_stdout(&mut self) -> Option<InputStream> {
+        self.stdout.take()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Takes the child's standard error if it was configured as [`Stdio::Piped`].
+    pub fn take_stderr(&mut self) -> Option<InputStream> {
+        self.stderr.take()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Takes the child's standard output as a stream of UTF-8 lines without line terminators.
+    pub fn stdout_lines(&mut self) -> Option<Lines> {
+        self.stdout.take().map(|stream| Lines::new(&stream))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Takes the child's standard error as a stream of UTF-8 lines without line terminators.
+    pub fn stderr_lines(&mut self) -> Option<Lines> {
+        self.stderr.take().map(|stream| Lines::new(&stream))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Forcefully terminates the child. Does nothing if it already exited.
+    #[doc(alias = "g_subprocess_force_exit")]
+    pub fn kill(&self) {
+        self.subprocess.force_exit();
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Waits for the child to exit and returns its exit status.
+    pub fn wait_future(&self) -> impl Future<Output = Result<ExitStatus, glib::Error>> + 'static {
+        let subprocess = self.subprocess.clone();
+        async move {
+            subprocess.wait_future().await?;
+            Ok(ExitStatus::from_subprocess(&subprocess))
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Waits for the child to exit and fails with an error if it didn't exit successfully.
+    pub fn wait_check_future(
+        &self,
+    ) -> impl Future<Output = Result<ExitStatus, glib::Error>> + 'static {
+        let subprocess = self.subprocess.clone();
+        async move {
+            subprocess.wait_check_future().await?;
+            Ok(ExitStatus::from_subprocess(&subprocess))
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if self.kill_on_drop {
+            if let Some(task) = self.stdin_task.take() {
+                task.abort();
+            }
+            self.subprocess.force_exit();
+        }
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// Describes how a child process exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExitStatus {
+    code: Option<i32>,
+    signal: Option<i32>,
+}
+
+impl ExitStatus {
+    fn from_subprocess(subprocess: &Subprocess) -> Self {
+        if subprocess.has_exited() {
+            Self {
+                code: Some(subprocess.exit_status()),
+                signal: None,
+            }
+        } else {
+            Self {
+                code: None,
+                signal: subprocess.has_signaled().then(|| subprocess.term_sig()),
+            }
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Whether the process exited normally with exit code `0`.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The exit code of the process, or `None` if it was terminated by a signal.
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The signal that terminated the process, if any.
+    pub fn signal(&self) -> Option<i32> {
+        self.signal
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code, self.signal) {
+            (Some(code), _) => write!(f, "exit status: {code}"),
+            (None, Some(signal)) => write!(f, "signal: {signal}"),
+            (None, None) => f.write_str("unknown status"),
+        }
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A [`Stream`] of UTF-8 lines read from an [`InputStream`], as returned by
+/// [`Child::stdout_lines`] and [`Child::stderr_lines`].
+pub struct Lines {
+    stream: DataInputStream,
+    future:
+        Option<Pin<Box<dyn Future<Output = Result<Option<glib::GString>, glib::Error>> + 'static>>>,
+}
+
+impl Lines {
+    // rustdoc-stripper-ignore-next
+    /// Reads lines from `stream`.
+    pub fn new(stream: &impl IsA<InputStream>) -> Self {
+        let stream = DataInputStream::new(stream);
+        let future = Some(stream.read_line_utf8_future(glib::Priority::default()));
+        Self { stream, future }
+    }
+}
+
+impl fmt::Debug for Lines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lines")
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl Stream for Lines {
+    type Item = Result<String, glib::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(mut future) = self.future.take() else {
+            return Poll::Ready(None);
+        };
+        match future.poll_unpin(cx) {
+            Poll::Ready(Ok(Some(line))) => {
+                self.future = Some(self.stream.read_line_utf8_future(glib::Priority::default()));
+                Poll::Ready(Some(Ok(line.into())))
+            }
+            Poll::Ready(Ok(None)) => Poll::Ready(None),
+            Poll::Ready(Err(err)) => Poll::Ready(Some(Err(err))),
+            Poll::Pending => {
+                self.future = Some(future);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl FusedStream for Lines {
+    fn is_terminated(&self) -> bool {
+        self.future.is_none()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn lines_and_exit_status() {
+        let (lines, status) = crate::test_util::run_async_local(|tx, l| {
+            glib::MainContext::ref_thread_default().spawn_local(async move {
+                let mut child = Command::new("sh")
+                    .args(["-c", "cat; echo done; exit 3"])
+                    .stdin_bytes(&glib::Bytes::from_static(b"one\ntwo\n"))
+                    .stdout(Stdio::Piped)
+                    .spawn()
+                    .unwrap();
+                let lines = child
+                    .stdout_lines()
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>()
+                    .await;
+                let status = child.wait_future().await.unwrap();
+                assert!(child.wait_check_future().await.is_err());
+                tx.send((lines, status)).unwrap();
+                l.quit();
+            });
+        });
+
+        assert_eq!(lines, ["one", "two", "done"]);
+        assert!(!status.success());
+        assert_eq!(status.code(), Some(3));
+    }
+}
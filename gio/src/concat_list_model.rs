@@ -0,0 +1,126 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use glib::{Object, prelude::*, subclass::prelude::*};
+
+use crate::{ListModel, flatten_list_model::Children, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::OnceCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct ConcatListModel {
+        pub(super) item_type: OnceCell<glib::Type>,
+        pub(super) children: Children,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ConcatListModel {
+        const NAME: &'static str = "ConcatListModel";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::ConcatListModel;
+        type Interfaces = (ListModel,);
+    }
+
+    impl ObjectImpl for ConcatListModel {
+        fn dispose(&self) {
+            self.children.clear();
+        }
+    }
+
+    impl ListModelImpl for ConcatListModel {
+        fn item_type(&self) -> glib::Type {
+            *self.item_type.get().unwrap()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.children.n_items()
+        }
+
+        fn item(&self, position: u32) -> Option<Object> {
+            self.children.item(position)
+        }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// A [`ListModel`] containing the items of a list of other models, one after another.
+    ///
+    /// Unlike [`FlattenListModel`](crate::FlattenListModel) the list of models is managed
+    /// directly instead of being the items of another model.
+    pub struct ConcatListModel(ObjectSubclass<imp::ConcatListModel>) @implements ListModel;
+}
+
+impl ConcatListModel {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model concatenating `models`, whose items are of type `T`.
+    pub fn new<T: IsA<Object>>(models: impl IntoIterator<Item = impl IsA<ListModel>>) -> Self {
+        Self::with_type(T::static_type(), models)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model concatenating `models`, whose items are of type `item_type`.
+    pub fn with_type(
+        item_type: glib::Type,
+        models: impl IntoIterator<Item = impl IsA<ListModel>>,
+    ) -> Self {
+        let obj: Self = Object::new();
+        obj.imp().item_type.set(item_type).unwrap();
+        for model in models {
+            obj.append(&model);
+        }
+        obj
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The number of concatenated models.
+    pub fn n_models(&self) -> u32 {
+        self.imp().children.len()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Appends the items of `model`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the items of `model` are not of this model's item type.
+    pub fn append(&self, model: &impl IsA<ListModel>) {
+        self.insert(self.n_models(), model);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Inserts the items of `model` before the items of the model at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is larger than the number of models or the items of `model` are not
+    /// of this model's item type.
+    pub fn insert(&self, index: u32, model: &impl IsA<ListModel>) {
+        let model = model.as_ref();
+        assert!(index <= self.n_models(), "model index out of bounds");
+        assert!(
+            model.item_type().is_a(self.item_type()),
+            "item type {} is not a {}",
+            model.item_type(),
+            self.item_type()
+        );
+        self.imp()
+            .children
+            .splice(self.upcast_ref(), index, 0, [model.clone()]);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes the model at `index` and its items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&self, index: u32) {
+        assert!(index < self.n_models(), "model index out of bounds");
+        self.imp()
+            .children
+            .splice(self.upcast_ref(), index, 1, std::iter::empty());
+    }
+}
@@ -946,4 +946,94 @@ impl DBusConnection {
             |signal| signal.parameters.try_get(),
         )
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Calls the method `method_name` with typed arguments and return value.
+    ///
+    /// The reply type is checked against `Ret` before the
+    /// reply is converted. Errors returned by the remote side with a D-Bus error name registered
+    /// with [`DBusError::register_error()`](crate::DBusError::register_error) are returned in
+    /// their error domain and without the remote error name in the message, so that they can be
+    /// matched with [`glib::Error::kind()`]. Other remote errors are returned as
+    /// [`IOErrorEnum::DbusError`](crate::IOErrorEnum::DbusError), see
+    /// [`DBusError::remote_error()`](crate::DBusError::remote_error).
+    ///
+    /// `Args` and `Ret` are tuples of values that can be converted to and from variants, see
+    /// [`DBusMethodArgs`](crate::DBusMethodArgs) and [`DBusMethodReply`](crate::DBusMethodReply).
+    /// On Unix, [`FdHandle`](crate::FdHandle)s in them are passed as `h` handles with a
+    /// [`UnixFDList`](crate::UnixFDList).
+    ///
+    /// ```no_run
+    /// # async fn get_id(connection: &gio::DBusConnection) -> Result<String, glib::Error> {
+    /// let (id,) = connection
+    ///     .call_typed_future::<(), (String,)>(
+    ///         Some("org.freedesktop.DBus"),
+    ///         "/org/freedesktop/DBus",
+    ///         "org.freedesktop.DBus",
+    ///         "GetId",
+    ///         (),
+    ///         gio::DBusCallFlags::NONE,
+    ///         -1,
+    ///     )
+    ///     .await?;
+    /// # Ok(id)
+    /// # }
+    /// ```
+    pub fn call_typed_future<Args: crate::DBusMethodArgs, Ret: crate::DBusMethodReply + 'static>(
+        &self,
+        bus_name: Option<&str>,
+        object_path: &str,
+        interface_name: &str,
+        method_name: &str,
+        args: Args,
+        flags: crate::DBusCallFlags,
+        timeout_msec: i32,
+    ) -> std::pin::Pin<Box_<dyn Future<Output = Result<Ret, glib::Error>> + 'static>> {
+        let args = crate::dbus_typed_call::args_to_variant(&args);
+        let reply_type = Ret::static_variant_type();
+        let method_name = method_name.to_owned();
+
+        #[cfg(unix)]
+        let call = args.map(|(parameters, fd_list)| {
+            self.call_with_unix_fd_list_future(
+                bus_name,
+                object_path,
+                interface_name,
+                &method_name,
+                Some(&parameters),
+                Some(&reply_type),
+                flags,
+                timeout_msec,
+                fd_list.as_ref(),
+            )
+        });
+        #[cfg(not(unix))]
+        let call = args.map(|(parameters, _)| {
+            self.call_future(
+                bus_name,
+                object_path,
+                interface_name,
+                &method_name,
+                Some(&parameters),
+                Some(&reply_type),
+                flags,
+                timeout_msec,
+            )
+        });
+
+        Box_::pin(async move {
+            let reply = call?
+                .await
+                .map_err(crate::dbus_typed_call::map_remote_error)?;
+            #[cfg(unix)]
+            let ret = crate::dbus_typed_call::reply_from_variant(
+                &method_name,
+                &reply.0,
+                reply.1.as_ref(),
+            );
+            #[cfg(not(unix))]
+            let ret = crate::dbus_typed_call::reply_from_variant(&method_name, &reply, None);
+            ret
+        })
+    }
 }
@@ -6,6 +6,10 @@ use crate::ffi;
 use crate::{DBusInterfaceInfo, DBusPropertyInfo};
 use glib::translate::*;
 
+// SAFETY:
+// though not explicitly documented, this struct is assumed to be immutable after creation
+// (with the exception of ref_count of course). See usage in gdbusconnection.c
+
 impl DBusInterfaceInfo {
     pub fn name(&self) -> &str {
         unsafe {
@@ -20,6 +24,33 @@ impl DBusInterfaceInfo {
     pub fn properties(&self) -> DBusInterfaceInfoPropertiesIter<'_> {
         DBusInterfaceInfoPropertiesIter::new(self)
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Looks up the value of the annotation `name` on this interface.
+    #[doc(alias = "g_dbus_annotation_info_lookup")]
+    pub fn annotation(&self, name: &str) -> Option<&str> {
+        // SAFETY: See top-level comment.
+        unsafe {
+            let c_obj = self.as_ptr();
+            let value =
+                ffi::g_dbus_annotation_info_lookup((*c_obj).annotations, name.to_glib_none().0);
+            if value.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(value).to_str().unwrap())
+        }
+    }
+
+    #[doc(alias = "g_dbus_interface_info_generate_xml")]
+    pub fn generate_xml(&self, indent: u32, string_builder: &mut glib::GStringBuilder) {
+        unsafe {
+            ffi::g_dbus_interface_info_generate_xml(
+                self.to_glib_none().0,
+                indent,
+                string_builder.to_glib_none_mut().0,
+            );
+        }
+    }
 }
 
 pub struct DBusInterfaceInfoPropertiesIter<'a> {
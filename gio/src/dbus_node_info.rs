@@ -2,7 +2,9 @@
 
 use std::ffi::CStr;
 
-use crate::{DBusInterfaceInfo, DBusNodeInfo};
+use glib::translate::*;
+
+use crate::{DBusInterfaceInfo, DBusNodeInfo, ffi};
 
 impl DBusNodeInfo {
     pub fn path(&self) -> Option<&str> {
@@ -39,4 +41,15 @@ impl DBusNodeInfo {
             glib::collections::PtrSlice::from_glib_borrow(c_ni)
         }
     }
+
+    #[doc(alias = "g_dbus_node_info_generate_xml")]
+    pub fn generate_xml(&self, indent: u32, string_builder: &mut glib::GStringBuilder) {
+        unsafe {
+            ffi::g_dbus_node_info_generate_xml(
+                self.to_glib_none().0,
+                indent,
+                string_builder.to_glib_none_mut().0,
+            );
+        }
+    }
 }
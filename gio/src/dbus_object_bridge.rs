@@ -0,0 +1,604 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{cell::RefCell, fmt::Write};
+
+use glib::{
+    EnumClass, FlagsClass, ParamFlags, Type, VariantTy, VariantType, prelude::*, translate::*,
+};
+
+use crate::{
+    DBusError, DBusExportedInterface, DBusInterfaceHandle, DBusInterfaceInfo, DBusNodeInfo,
+    DBusObjectTree, IOErrorEnum,
+};
+
+// rustdoc-stripper-ignore-next
+/// Object types whose properties are exported with a [`DBusObjectBridge`].
+///
+/// This is implemented by `#[derive(glib::Properties)]` for the wrapper type if any of the
+/// properties is marked with `#[property(dbus)]` or `#[property(dbus = "Name")]`.
+pub trait DBusExportedProperties: IsA<glib::Object> {
+    // rustdoc-stripper-ignore-next
+    /// The exported properties as pairs of GObject property name and D-Bus property name.
+    fn dbus_properties() -> &'static [(&'static str, &'static str)];
+}
+
+// How values of a GObject type are represented on D-Bus.
+#[derive(Debug, Clone)]
+enum ValueMapping {
+    Bool,
+    U8,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    String,
+    Strv,
+    // Variants of indefinite type are boxed into a `v`.
+    Variant(VariantType),
+    // Enums are represented by their nick.
+    Enum(Type),
+    Flags(Type),
+}
+
+impl ValueMapping {
+    fn for_type(type_: Type) -> Option<Self> {
+        Some(match type_ {
+            Type::BOOL => Self::Bool,
+            Type::U8 => Self::U8,
+            Type::I32 => Self::I32,
+            Type::U32 => Self::U32,
+            Type::I64 => Self::I64,
+            Type::U64 => Self::U64,
+            Type::F32 => Self::F32,
+            Type::F64 => Self::F64,
+            Type::STRING => Self::String,
+            Type::VARIANT => Self::Variant(VariantTy::ANY.to_owned()),
+            _ if type_ == Vec::<String>::static_type() => Self::Strv,
+            _ if type_.is_a(Type::ENUM) => Self::Enum(type_),
+            _ if type_.is_a(Type::FLAGS) => Self::Flags(type_),
+            _ => return None,
+        })
+    }
+
+    fn for_param_spec(pspec: &glib::ParamSpec) -> Option<Self> {
+        match pspec.downcast_ref::<glib::ParamSpecVariant>() {
+            Some(pspec) => Some(Self::Variant(
+                pspec.type_().unwrap_or(VariantTy::ANY).to_owned(),
+            )),
+            None => Self::for_type(pspec.value_type()),
+        }
+    }
+
+    fn signature(&self) -> &str {
+        match self {
+            Self::Bool => "b",
+            Self::U8 => "y",
+            Self::I32 => "i",
+            Self::U32 | Self::Flags(_) => "u",
+            Self::I64 => "x",
+            Self::U64 => "t",
+            Self::F32 | Self::F64 => "d",
+            Self::String | Self::Enum(_) => "s",
+            Self::Strv => "as",
+            Self::Variant(type_) if type_.is_definite() => type_.as_str(),
+            Self::Variant(_) => "v",
+        }
+    }
+
+    fn value_to_variant(&self, value: &glib::Value) -> Option<glib::Variant> {
+        Some(match self {
+            Self::Bool => value.get::<bool>().ok()?.to_variant(),
+            Self::U8 => value.get::<u8>().ok()?.to_variant(),
+            Self::I32 => value.get::<i32>().ok()?.to_variant(),
+            Self::U32 => value.get::<u32>().ok()?.to_variant(),
+            Self::I64 => value.get::<i64>().ok()?.to_variant(),
+            Self::U64 => value.get::<u64>().ok()?.to_variant(),
+            Self::F32 => f64::from(value.get::<f32>().ok()?).to_variant(),
+            Self::F64 => value.get::<f64>().ok()?.to_variant(),
+            // D-Bus has no null strings
+            Self::String => value
+                .get::<Option<String>>()
+                .ok()?
+                .unwrap_or_default()
+                .to_variant(),
+            Self::Strv => value.get::<Vec<String>>().ok()?.to_variant(),
+            Self::Variant(type_) => {
+                let variant = value.get::<Option<glib::Variant>>().ok()??;
+                if type_.is_definite() {
+                    variant
+                } else {
+                    glib::Variant::from_variant(&variant)
+                }
+            }
+            Self::Enum(_) => {
+                let (_, enum_value) = glib::EnumValue::from_value(value)?;
+                enum_value.nick().to_variant()
+            }
+            Self::Flags(_) => value
+                .transform::<u32>()
+                .ok()?
+                .get::<u32>()
+                .ok()?
+                .to_variant(),
+        })
+    }
+
+    fn variant_to_value(&self, variant: &glib::Variant) -> Option<glib::Value> {
+        Some(match self {
+            Self::Bool => variant.get::<bool>()?.to_value(),
+            Self::U8 => variant.get::<u8>()?.to_value(),
+            Self::I32 => variant.get::<i32>()?.to_value(),
+            Self::U32 => variant.get::<u32>()?.to_value(),
+            Self::I64 => variant.get::<i64>()?.to_value(),
+            Self::U64 => variant.get::<u64>()?.to_value(),
+            Self::F32 => (variant.get::<f64>()? as f32).to_value(),
+            Self::F64 => variant.get::<f64>()?.to_value(),
+            Self::String => variant.get::<String>()?.to_value(),
+            Self::Strv => variant.get::<Vec<String>>()?.to_value(),
+            Self::Variant(type_) if type_.is_definite() => variant.to_value(),
+            Self::Variant(_) => variant.as_variant()?.to_value(),
+            Self::Enum(type_) => EnumClass::with_type(*type_)?.to_value_by_nick(variant.str()?)?,
+            Self::Flags(type_) => FlagsClass::with_type(*type_)?.to_value(variant.get::<u32>()?)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct BridgedProperty {
+    pspec: glib::ParamSpec,
+    dbus_name: String,
+    mapping: ValueMapping,
+}
+
+impl BridgedProperty {
+    fn is_readable(&self) -> bool {
+        self.pspec.flags().contains(ParamFlags::READABLE)
+    }
+
+    // Construct-only properties can't be changed once the object exists.
+    fn is_writable(&self) -> bool {
+        let flags = self.pspec.flags();
+        flags.contains(ParamFlags::WRITABLE) && !flags.contains(ParamFlags::CONSTRUCT_ONLY)
+    }
+}
+
+#[derive(Debug)]
+struct BridgedSignal {
+    name: String,
+    dbus_name: String,
+    mappings: Vec<ValueMapping>,
+}
+
+// rustdoc-stripper-ignore-next
+/// Exports the properties and signals of a [`glib::Object`] as a D-Bus interface.
+///
+/// Reading a property over D-Bus reads the GObject property, and `Set` calls write through to
+/// the GObject property. `notify` emissions of the object are announced with
+/// `PropertiesChanged`, and emissions of the exported GObject signals are forwarded as D-Bus
+/// signals.
+///
+/// Property and signal argument types are mapped as follows: booleans, integers and strings map
+/// to the corresponding D-Bus types, floating point numbers to `d`, string vectors to `as`,
+/// enums to the nick of their value as `s`, flags to `u`, and variants to their type, or `v` if
+/// the type is indefinite. Names are converted to D-Bus conventions by default, so the
+/// `icon-name` property is exported as `IconName`.
+///
+/// ```no_run
+/// # use gio::prelude::*;
+/// # fn export(tree: &gio::DBusObjectTree, object: &gio::SimpleAction) -> Result<(), glib::Error> {
+/// let bridge = gio::DBusObjectBridge::builder(object, "org.example.Action")
+///     .property("enabled")
+///     .property_as("name", "ActionName")
+///     .signal("activate")
+///     .build()?;
+/// bridge.export(tree, "/org/example/Action")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DBusObjectBridge {
+    object: glib::Object,
+    info: DBusInterfaceInfo,
+    properties: Vec<BridgedProperty>,
+    signals: Vec<BridgedSignal>,
+    handlers: RefCell<Vec<glib::SignalHandlerId>>,
+}
+
+impl DBusObjectBridge {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new builder for exporting `object` as the interface `interface_name`.
+    pub fn builder(
+        object: &impl IsA<glib::Object>,
+        interface_name: &str,
+    ) -> DBusObjectBridgeBuilder {
+        DBusObjectBridgeBuilder {
+            object: object.clone().upcast(),
+            interface_name: interface_name.to_owned(),
+            properties: Vec::new(),
+            signals: Vec::new(),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The exported object.
+    pub fn object(&self) -> &glib::Object {
+        &self.object
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Exports the interface on the object at `path` of `tree`.
+    ///
+    /// The bridge stays connected to the object until the interface is removed from the tree.
+    pub fn export(
+        self,
+        tree: &DBusObjectTree,
+        path: &str,
+    ) -> Result<DBusInterfaceHandle, glib::Error> {
+        let bridge = std::rc::Rc::new(self);
+        let handle = tree.add_interface(path, bridge.clone())?;
+
+        let mut handlers = bridge.handlers.borrow_mut();
+        for property in bridge.properties.iter().filter(|p| p.is_readable()) {
+            let handle = handle.clone();
+            let dbus_name = property.dbus_name.clone();
+            handlers.push(
+                bridge
+                    .object
+                    .connect_notify_local(Some(property.pspec.name()), move |_, _| {
+                        handle.notify_property_changed(&dbus_name)
+                    }),
+            );
+        }
+        for signal in &bridge.signals {
+            let handle = handle.clone();
+            let dbus_name = signal.dbus_name.clone();
+            let mappings = signal.mappings.clone();
+            handlers.push(
+                bridge
+                    .object
+                    .connect_local(&signal.name, false, move |args| {
+                        let parameters = args[1..]
+                            .iter()
+                            .zip(&mappings)
+                            .map(|(value, mapping)| mapping.value_to_variant(value))
+                            .collect::<Option<Vec<_>>>();
+                        if let Some(parameters) = parameters {
+                            let _ = handle.emit_signal(
+                                &dbus_name,
+                                Some(&glib::Variant::tuple_from_iter(parameters)),
+                            );
+                        }
+                        None
+                    }),
+            );
+        }
+
+        Ok(handle)
+    }
+
+    fn lookup_property(&self, dbus_name: &str) -> Result<&BridgedProperty, glib::Error> {
+        self.properties
+            .iter()
+            .find(|p| p.dbus_name == dbus_name)
+            .ok_or_else(|| {
+                glib::Error::new(
+                    DBusError::UnknownProperty,
+                    &format!("Unknown property {dbus_name}"),
+                )
+            })
+    }
+}
+
+impl Drop for DBusObjectBridge {
+    fn drop(&mut self) {
+        for handler in self.handlers.take() {
+            self.object.disconnect(handler);
+        }
+    }
+}
+
+impl DBusExportedInterface for DBusObjectBridge {
+    fn interface_info(&self) -> DBusInterfaceInfo {
+        self.info.clone()
+    }
+
+    fn property(&self, name: &str) -> Result<glib::Variant, glib::Error> {
+        let property = self.lookup_property(name)?;
+        if !property.is_readable() {
+            return Err(glib::Error::new(
+                DBusError::AccessDenied,
+                &format!("Property {name} is not readable"),
+            ));
+        }
+        let value = self.object.property_value(property.pspec.name());
+        property.mapping.value_to_variant(&value).ok_or_else(|| {
+            glib::Error::new(
+                DBusError::Failed,
+                &format!("Property {name} has no value that can be represented on D-Bus"),
+            )
+        })
+    }
+
+    fn set_property(&self, name: &str, value: glib::Variant) -> Result<(), glib::Error> {
+        let property = self.lookup_property(name)?;
+        if !property.is_writable() {
+            return Err(glib::Error::new(
+                DBusError::PropertyReadOnly,
+                &format!("Property {name} is not writable"),
+            ));
+        }
+        let value = property
+            .mapping
+            .variant_to_value(&value)
+            .filter(|value| value_is_valid(&property.pspec, value))
+            .ok_or_else(|| {
+                glib::Error::new(
+                    DBusError::InvalidArgs,
+                    &format!("Invalid value for property {name}"),
+                )
+            })?;
+        self.object
+            .set_property_from_value(property.pspec.name(), &value);
+        Ok(())
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A [builder-pattern] type to construct [`DBusObjectBridge`] objects.
+///
+/// [builder-pattern]: https://doc.rust-lang.org/1.0.0/style/ownership/builders.html
+#[must_use = "The builder must be built to be used"]
+pub struct DBusObjectBridgeBuilder {
+    object: glib::Object,
+    interface_name: String,
+    properties: Vec<(String, Option<String>)>,
+    signals: Vec<(String, Option<String>)>,
+}
+
+impl DBusObjectBridgeBuilder {
+    // rustdoc-stripper-ignore-next
+    /// Exports the GObject property `name` under its D-Bus style name.
+    pub fn property(mut self, name: &str) -> Self {
+        self.properties.push((name.to_owned(), None));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Exports the GObject property `name` as the D-Bus property `dbus_name`.
+    pub fn property_as(mut self, name: &str, dbus_name: &str) -> Self {
+        self.properties
+            .push((name.to_owned(), Some(dbus_name.to_owned())));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Exports the properties marked with `#[property(dbus)]` on `T`.
+    pub fn exported_properties<T: DBusExportedProperties>(mut self) -> Self {
+        self.properties.extend(
+            T::dbus_properties()
+                .iter()
+                .map(|(name, dbus_name)| (String::from(*name), Some(String::from(*dbus_name)))),
+        );
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Forwards the GObject signal `name` as the D-Bus signal with its D-Bus style name.
+    pub fn signal(mut self, name: &str) -> Self {
+        self.signals.push((name.to_owned(), None));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Forwards the GObject signal `name` as the D-Bus signal `dbus_name`.
+    pub fn signal_as(mut self, name: &str, dbus_name: &str) -> Self {
+        self.signals
+            .push((name.to_owned(), Some(dbus_name.to_owned())));
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Builds the bridge.
+    ///
+    /// Fails if a property or signal does not exist, if one of their types can't be
+    /// represented on D-Bus, if a signal has a return value or if a name is invalid.
+    pub fn build(self) -> Result<DBusObjectBridge, glib::Error> {
+        if !crate::dbus_is_interface_name(&self.interface_name) {
+            return Err(invalid_name(&self.interface_name));
+        }
+
+        let mut properties = Vec::new();
+        for (name, dbus_name) in self.properties {
+            let pspec = self.object.find_property(&name).ok_or_else(|| {
+                glib::Error::new(
+                    IOErrorEnum::NotFound,
+                    &format!("{} has no property {name}", self.object.type_()),
+                )
+            })?;
+            let mapping = ValueMapping::for_param_spec(&pspec)
+                .ok_or_else(|| unsupported_type(pspec.value_type(), &format!("property {name}")))?;
+            let dbus_name = dbus_name.unwrap_or_else(|| dbus_member_name(&name));
+            if !crate::dbus_is_member_name(&dbus_name) {
+                return Err(invalid_name(&dbus_name));
+            }
+            let property = BridgedProperty {
+                pspec,
+                dbus_name,
+                mapping,
+            };
+            // Neither readable nor writable from D-Bus, so there is nothing to export.
+            if property.is_readable() || property.is_writable() {
+                properties.push(property);
+            }
+        }
+
+        let mut signals = Vec::new();
+        for (name, dbus_name) in self.signals {
+            let query = glib::subclass::SignalId::lookup(&name, self.object.type_())
+                .ok_or_else(|| {
+                    glib::Error::new(
+                        IOErrorEnum::NotFound,
+                        &format!("{} has no signal {name}", self.object.type_()),
+                    )
+                })?
+                .query();
+            if query.return_type().type_() != Type::UNIT {
+                return Err(glib::Error::new(
+                    IOErrorEnum::NotSupported,
+                    &format!("Signal {name} has a return value"),
+                ));
+            }
+            let mappings = query
+                .param_types()
+                .iter()
+                .map(|type_| {
+                    ValueMapping::for_type(type_.type_()).ok_or_else(|| {
+                        unsupported_type(type_.type_(), &format!("argument of signal {name}"))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let dbus_name = dbus_name.unwrap_or_else(|| dbus_member_name(&name));
+            if !crate::dbus_is_member_name(&dbus_name) {
+                return Err(invalid_name(&dbus_name));
+            }
+            signals.push(BridgedSignal {
+                name,
+                dbus_name,
+                mappings,
+            });
+        }
+
+        let mut xml = format!("<node><interface name=\"{}\">", self.interface_name);
+        for property in &properties {
+            let access = match (property.is_readable(), property.is_writable()) {
+                (true, true) => "readwrite",
+                (true, false) => "read",
+                _ => "write",
+            };
+            let _ = write!(
+                xml,
+                "<property name=\"{}\" type=\"{}\" access=\"{access}\"/>",
+                property.dbus_name,
+                property.mapping.signature(),
+            );
+        }
+        for signal in &signals {
+            let _ = write!(xml, "<signal name=\"{}\">", signal.dbus_name);
+            for mapping in &signal.mappings {
+                let _ = write!(xml, "<arg type=\"{}\"/>", mapping.signature());
+            }
+            xml.push_str("</signal>");
+        }
+        xml.push_str("</interface></node>");
+
+        let info = DBusNodeInfo::for_xml(&xml)?
+            .lookup_interface(&self.interface_name)
+            .unwrap();
+
+        Ok(DBusObjectBridge {
+            object: self.object,
+            info,
+            properties,
+            signals,
+            handlers: Default::default(),
+        })
+    }
+}
+
+// Converts a GObject property or signal name like `icon-name` to `IconName`.
+fn dbus_member_name(name: &str) -> String {
+    name.split(['-', '_'])
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+// Like `ParamSpec::value_is_valid()`, which requires GLib 2.74.
+fn value_is_valid(pspec: &glib::ParamSpec, value: &glib::Value) -> bool {
+    let mut value = value.clone();
+    // `g_param_value_validate()` returns whether it had to modify the value.
+    unsafe {
+        glib::gobject_ffi::g_param_value_validate(
+            pspec.to_glib_none().0,
+            value.to_glib_none_mut().0,
+        ) == glib::ffi::GFALSE
+    }
+}
+
+fn invalid_name(name: &str) -> glib::Error {
+    glib::Error::new(
+        IOErrorEnum::InvalidArgument,
+        &format!("Invalid D-Bus name {name:?}"),
+    )
+}
+
+fn unsupported_type(type_: Type, what: &str) -> glib::Error {
+    glib::Error::new(
+        IOErrorEnum::NotSupported,
+        &format!("Type {type_} of {what} can't be represented on D-Bus"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn member_name() {
+        assert_eq!(dbus_member_name("icon-name"), "IconName");
+        assert_eq!(dbus_member_name("state_hint"), "StateHint");
+        assert_eq!(dbus_member_name("enabled"), "Enabled");
+    }
+
+    #[test]
+    fn value_mapping() {
+        let mapping = ValueMapping::for_type(Type::STRING).unwrap();
+        assert_eq!(mapping.signature(), "s");
+        assert_eq!(
+            mapping.value_to_variant(&None::<String>.to_value()),
+            Some("".to_variant())
+        );
+
+        let mapping = ValueMapping::for_type(Type::F32).unwrap();
+        assert_eq!(mapping.signature(), "d");
+        let value = mapping.variant_to_value(&1.5f64.to_variant()).unwrap();
+        assert_eq!(value.get::<f32>(), Ok(1.5));
+
+        let mapping = ValueMapping::for_type(crate::DBusCallFlags::static_type()).unwrap();
+        assert_eq!(mapping.signature(), "u");
+        let value = crate::DBusCallFlags::NO_AUTO_START.to_value();
+        let variant = mapping.value_to_variant(&value).unwrap();
+        assert_eq!(variant, 1u32.to_variant());
+        assert_eq!(
+            mapping
+                .variant_to_value(&variant)
+                .unwrap()
+                .get::<crate::DBusCallFlags>(),
+            Ok(crate::DBusCallFlags::NO_AUTO_START)
+        );
+
+        let mapping = ValueMapping::for_type(crate::IOErrorEnum::static_type()).unwrap();
+        assert_eq!(mapping.signature(), "s");
+        let variant = mapping
+            .value_to_variant(&crate::IOErrorEnum::NotFound.to_value())
+            .unwrap();
+        assert_eq!(variant, "not-found".to_variant());
+
+        let mapping = ValueMapping::for_type(Type::VARIANT).unwrap();
+        assert_eq!(mapping.signature(), "v");
+        let value = mapping
+            .variant_to_value(&glib::Variant::from_variant(&7i32.to_variant()))
+            .unwrap();
+        assert_eq!(value.get::<glib::Variant>(), Ok(7i32.to_variant()));
+
+        assert!(ValueMapping::for_type(Type::POINTER).is_none());
+    }
+}
@@ -0,0 +1,720 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use glib::{prelude::*, variant::ObjectPath};
+
+use crate::{
+    DBusConnection, DBusError, DBusInterfaceInfo, DBusMethodInvocation, DBusNodeInfo,
+    DBusPropertyInfoFlags, IOErrorEnum, RegistrationId,
+};
+
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const OBJECT_MANAGER_INTERFACE: &str = "org.freedesktop.DBus.ObjectManager";
+const EMITS_CHANGED_SIGNAL: &str = "org.freedesktop.DBus.Property.EmitsChangedSignal";
+
+const OBJECT_MANAGER_XML: &str = r#"
+<node>
+  <interface name="org.freedesktop.DBus.ObjectManager">
+    <method name="GetManagedObjects">
+      <arg type="a{oa{sa{sv}}}" name="object_paths_interfaces_and_properties" direction="out"/>
+    </method>
+    <signal name="InterfacesAdded">
+      <arg type="o" name="object_path"/>
+      <arg type="a{sa{sv}}" name="interfaces_and_properties"/>
+    </signal>
+    <signal name="InterfacesRemoved">
+      <arg type="o" name="object_path"/>
+      <arg type="as" name="interfaces"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+// rustdoc-stripper-ignore-next
+/// A D-Bus interface implemented in Rust that can be exported with a [`DBusObjectTree`].
+///
+/// Only [`interface_info`](Self::interface_info) and [`property`](Self::property) have to be
+/// implemented. Method calls and property writes are rejected by default.
+pub trait DBusExportedInterface: 'static {
+    // rustdoc-stripper-ignore-next
+    /// Returns the description of the interface.
+    ///
+    /// This is called once when the interface is added to a [`DBusObjectTree`].
+    fn interface_info(&self) -> DBusInterfaceInfo;
+
+    // rustdoc-stripper-ignore-next
+    /// Handles a call of the method `method_name`.
+    ///
+    /// The parameters are already checked against the signature in the interface info. The
+    /// reply has to be returned through `invocation`, possibly asynchronously.
+    fn method_call(
+        &self,
+        handle: &DBusInterfaceHandle,
+        sender: Option<&str>,
+        method_name: &str,
+        parameters: glib::Variant,
+        invocation: DBusMethodInvocation,
+    ) {
+        let _ = (handle, sender, parameters);
+        invocation.return_dbus_error(
+            "org.freedesktop.DBus.Error.UnknownMethod",
+            &format!("Method {method_name} is not implemented"),
+        );
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the current value of the readable property `name`.
+    fn property(&self, name: &str) -> Result<glib::Variant, glib::Error>;
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the writable property `name` to `value`.
+    ///
+    /// The value is already checked against the signature in the interface info. On success
+    /// the change is announced with `PropertiesChanged` automatically.
+    fn set_property(&self, name: &str, value: glib::Variant) -> Result<(), glib::Error> {
+        let _ = value;
+        Err(glib::Error::new(
+            DBusError::PropertyReadOnly,
+            &format!("Property {name} is not writable"),
+        ))
+    }
+}
+
+impl<T: DBusExportedInterface + ?Sized> DBusExportedInterface for Rc<T> {
+    fn interface_info(&self) -> DBusInterfaceInfo {
+        (**self).interface_info()
+    }
+
+    fn method_call(
+        &self,
+        handle: &DBusInterfaceHandle,
+        sender: Option<&str>,
+        method_name: &str,
+        parameters: glib::Variant,
+        invocation: DBusMethodInvocation,
+    ) {
+        (**self).method_call(handle, sender, method_name, parameters, invocation)
+    }
+
+    fn property(&self, name: &str) -> Result<glib::Variant, glib::Error> {
+        (**self).property(name)
+    }
+
+    fn set_property(&self, name: &str, value: glib::Variant) -> Result<(), glib::Error> {
+        (**self).set_property(name, value)
+    }
+}
+
+// How changes of a property are announced, from the `EmitsChangedSignal` annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EmitsChangedSignal {
+    True,
+    Invalidates,
+    False,
+}
+
+impl EmitsChangedSignal {
+    fn for_property(info: &DBusInterfaceInfo, name: &str) -> Option<Self> {
+        let property = info.lookup_property(name)?;
+        let value = property
+            .annotation(EMITS_CHANGED_SIGNAL)
+            .or_else(|| info.annotation(EMITS_CHANGED_SIGNAL));
+        Some(match value {
+            Some("invalidates") => Self::Invalidates,
+            Some("false") | Some("const") => Self::False,
+            _ => Self::True,
+        })
+    }
+}
+
+struct Exported {
+    interface: Rc<dyn DBusExportedInterface>,
+    info: DBusInterfaceInfo,
+    registration: Cell<Option<RegistrationId>>,
+}
+
+impl Exported {
+    fn unregister(&self, connection: &DBusConnection) {
+        if let Some(registration) = self.registration.take() {
+            let _ = connection.unregister_object(registration);
+        }
+    }
+
+    // The values of all readable properties, for `GetManagedObjects` and `InterfacesAdded`.
+    fn properties(&self) -> BTreeMap<String, glib::Variant> {
+        self.info
+            .properties()
+            .filter(|p| p.flags().contains(DBusPropertyInfoFlags::READABLE))
+            .filter_map(|p| {
+                let value = self.interface.property(p.name()).ok()?;
+                Some((p.name().to_owned(), value))
+            })
+            .collect()
+    }
+}
+
+struct Inner {
+    connection: DBusConnection,
+    object_manager: Option<(String, RegistrationId)>,
+    objects: RefCell<BTreeMap<String, BTreeMap<String, Rc<Exported>>>>,
+    pending_changes: RefCell<BTreeMap<(String, String), BTreeSet<String>>>,
+    flush_scheduled: Cell<bool>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        let objects = std::mem::take(self.objects.get_mut());
+        for exported in objects.into_values().flat_map(BTreeMap::into_values) {
+            exported.unregister(&self.connection);
+        }
+        if let Some((_, registration)) = self.object_manager.take() {
+            let _ = self.connection.unregister_object(registration);
+        }
+    }
+}
+
+impl Inner {
+    fn new(connection: &DBusConnection, object_manager: Option<(String, RegistrationId)>) -> Self {
+        Self {
+            connection: connection.clone(),
+            object_manager,
+            objects: Default::default(),
+            pending_changes: Default::default(),
+            flush_scheduled: Cell::new(false),
+        }
+    }
+
+    fn lookup(&self, path: &str, interface_name: &str) -> Option<Rc<Exported>> {
+        self.objects
+            .borrow()
+            .get(path)?
+            .get(interface_name)
+            .cloned()
+    }
+
+    // Whether `path` is announced through the object manager, if any.
+    fn is_managed(&self, path: &str) -> bool {
+        let Some((ref root, _)) = self.object_manager else {
+            return false;
+        };
+        path != root
+            && (root == "/"
+                || path
+                    .strip_prefix(root.as_str())
+                    .is_some_and(|rest| rest.starts_with('/')))
+    }
+
+    fn notify_property_changed(self: &Rc<Self>, path: &str, interface_name: &str, name: &str) {
+        let Some(exported) = self.lookup(path, interface_name) else {
+            return;
+        };
+        match EmitsChangedSignal::for_property(&exported.info, name) {
+            None | Some(EmitsChangedSignal::False) => return,
+            Some(_) => (),
+        }
+
+        self.pending_changes
+            .borrow_mut()
+            .entry((path.to_owned(), interface_name.to_owned()))
+            .or_default()
+            .insert(name.to_owned());
+
+        if !self.flush_scheduled.replace(true) {
+            let inner = Rc::downgrade(self);
+            glib::MainContext::ref_thread_default().spawn_local_with_priority(
+                glib::Priority::DEFAULT_IDLE,
+                async move {
+                    if let Some(inner) = inner.upgrade() {
+                        inner.flush_property_changes();
+                    }
+                },
+            );
+        }
+    }
+
+    fn flush_property_changes(&self) {
+        self.flush_scheduled.set(false);
+        let pending = std::mem::take(&mut *self.pending_changes.borrow_mut());
+
+        for ((path, interface_name), names) in pending {
+            let Some(exported) = self.lookup(&path, &interface_name) else {
+                continue;
+            };
+
+            let mut changed = BTreeMap::new();
+            let mut invalidated = Vec::new();
+            for name in names {
+                match EmitsChangedSignal::for_property(&exported.info, &name) {
+                    Some(EmitsChangedSignal::True) => match exported.interface.property(&name) {
+                        Ok(value) => {
+                            changed.insert(name, value);
+                        }
+                        Err(_) => invalidated.push(name),
+                    },
+                    Some(EmitsChangedSignal::Invalidates) => invalidated.push(name),
+                    Some(EmitsChangedSignal::False) | None => (),
+                }
+            }
+            if changed.is_empty() && invalidated.is_empty() {
+                continue;
+            }
+
+            let _ = self.connection.emit_signal(
+                None,
+                &path,
+                PROPERTIES_INTERFACE,
+                "PropertiesChanged",
+                Some(&(interface_name, changed, invalidated).to_variant()),
+            );
+        }
+    }
+
+    fn managed_objects(&self) -> glib::Variant {
+        let objects = self
+            .objects
+            .borrow()
+            .iter()
+            .filter(|(path, _)| self.is_managed(path))
+            .map(|(path, interfaces)| (path.clone(), interfaces.clone()))
+            .collect::<Vec<_>>();
+
+        let objects = objects
+            .into_iter()
+            .map(|(path, interfaces)| {
+                let interfaces = interfaces
+                    .into_iter()
+                    .map(|(name, exported)| (name, exported.properties()))
+                    .collect::<BTreeMap<_, _>>();
+                (ObjectPath::try_from(path).unwrap(), interfaces)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        (objects,).to_variant()
+    }
+
+    fn emit_object_manager_signal(&self, path: &str, signal_name: &str, body: glib::Variant) {
+        if let Some((ref root, _)) = self.object_manager {
+            let _ = self.connection.emit_signal(
+                None,
+                root,
+                OBJECT_MANAGER_INTERFACE,
+                signal_name,
+                Some(&glib::Variant::tuple_from_iter([
+                    ObjectPath::try_from(path).unwrap().to_variant(),
+                    body,
+                ])),
+            );
+        }
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A tree of objects exported on a [`DBusConnection`], whose interfaces are implemented by
+/// [`DBusExportedInterface`]s.
+///
+/// Compared to [`DBusConnection::register_object()`], this takes care of the standard
+/// interfaces:
+///
+/// * `org.freedesktop.DBus.Introspectable`: GDBus answers `Introspect` from the
+///   [`DBusInterfaceInfo`]s of the added interfaces, together with the child nodes of each path,
+///   including intermediate nodes and objects registered outside the tree. See also
+///   [`introspect()`](Self::introspect).
+/// * `org.freedesktop.DBus.Properties`: `Get`, `GetAll` and `Set` are dispatched to the
+///   interfaces, and changes announced with
+///   [`notify_property_changed()`](DBusInterfaceHandle::notify_property_changed) are coalesced
+///   into one `PropertiesChanged` signal per interface, emitted from an idle callback. The
+///   `org.freedesktop.DBus.Property.EmitsChangedSignal` annotation is honored.
+/// * `org.freedesktop.DBus.ObjectManager`, optionally: see
+///   [`with_object_manager()`](Self::with_object_manager).
+///
+/// All callbacks are invoked on the [thread-default main context](glib::MainContext::ref_thread_default)
+/// of the thread that created the tree. The objects are unregistered when the last clone of the
+/// tree is dropped.
+#[derive(Clone)]
+pub struct DBusObjectTree {
+    inner: Rc<Inner>,
+}
+
+impl fmt::Debug for DBusObjectTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DBusObjectTree")
+            .field("connection", &self.inner.connection)
+            .field(
+                "object_manager",
+                &self.inner.object_manager.as_ref().map(|(path, _)| path),
+            )
+            .field("objects", &self.object_paths())
+            .finish()
+    }
+}
+
+impl DBusObjectTree {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new, empty object tree on `connection`.
+    pub fn new(connection: &DBusConnection) -> Self {
+        Self {
+            inner: Rc::new(Inner::new(connection, None)),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new, empty object tree on `connection` with an
+    /// `org.freedesktop.DBus.ObjectManager` at `path`.
+    ///
+    /// All objects below `path` are reported by `GetManagedObjects` and announced with the
+    /// `InterfacesAdded` and `InterfacesRemoved` signals.
+    pub fn with_object_manager(
+        connection: &DBusConnection,
+        path: &str,
+    ) -> Result<Self, glib::Error> {
+        check_object_path(path)?;
+        let info = DBusNodeInfo::for_xml(OBJECT_MANAGER_XML)?
+            .lookup_interface(OBJECT_MANAGER_INTERFACE)
+            .unwrap();
+
+        let mut result = Ok(());
+        let inner = Rc::new_cyclic(|weak: &Weak<Inner>| {
+            let weak = weak.clone();
+            let registration = connection
+                .register_object(path, &info)
+                .method_call(move |_, _, _, _, method_name, _, invocation| {
+                    match (weak.upgrade(), method_name) {
+                        (Some(inner), "GetManagedObjects") => {
+                            invocation.return_value(Some(&inner.managed_objects()))
+                        }
+                        _ => invocation.return_dbus_error(
+                            "org.freedesktop.DBus.Error.UnknownMethod",
+                            &format!("Unknown method {method_name}"),
+                        ),
+                    }
+                })
+                .build();
+            let object_manager = match registration {
+                Ok(registration) => Some((path.to_owned(), registration)),
+                Err(err) => {
+                    result = Err(err);
+                    None
+                }
+            };
+            Inner::new(connection, object_manager)
+        });
+        result?;
+
+        Ok(Self { inner })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The connection the objects are exported on.
+    pub fn connection(&self) -> &DBusConnection {
+        &self.inner.connection
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The path of the object manager, if any.
+    pub fn object_manager_path(&self) -> Option<&str> {
+        self.inner
+            .object_manager
+            .as_ref()
+            .map(|(path, _)| path.as_str())
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Exports `interface` on the object at `path`.
+    ///
+    /// Fails if the path is invalid or an interface with the same name is already exported at
+    /// `path`. The returned handle can be used to notify about property changes and to emit
+    /// signals; the same handle is passed to [`DBusExportedInterface::method_call()`].
+    pub fn add_interface(
+        &self,
+        path: &str,
+        interface: impl DBusExportedInterface,
+    ) -> Result<DBusInterfaceHandle, glib::Error> {
+        check_object_path(path)?;
+        let interface: Rc<dyn DBusExportedInterface> = Rc::new(interface);
+        let info = interface.interface_info();
+        let interface_name = info.name().to_owned();
+        let handle = DBusInterfaceHandle {
+            tree: Rc::downgrade(&self.inner),
+            path: path.to_owned(),
+            interface_name: interface_name.clone(),
+        };
+
+        let registration = self
+            .inner
+            .connection
+            .register_object(path, &info)
+            .method_call({
+                let interface = interface.clone();
+                let handle = handle.clone();
+                move |_, sender, _, _, method_name, parameters, invocation| {
+                    interface.method_call(&handle, sender, method_name, parameters, invocation)
+                }
+            })
+            .property({
+                let interface = interface.clone();
+                move |_, _, _, _, name| interface.property(name)
+            })
+            .set_property({
+                let interface = interface.clone();
+                let handle = handle.clone();
+                move |_, _, _, _, name, value| {
+                    interface.set_property(name, value)?;
+                    handle.notify_property_changed(name);
+                    Ok(())
+                }
+            })
+            .build()?;
+
+        let exported = Rc::new(Exported {
+            interface,
+            info,
+            registration: Cell::new(Some(registration)),
+        });
+        self.inner
+            .objects
+            .borrow_mut()
+            .entry(path.to_owned())
+            .or_default()
+            .insert(interface_name.clone(), exported.clone());
+
+        if self.inner.is_managed(path) {
+            let properties = BTreeMap::from([(interface_name, exported.properties())]);
+            self.inner
+                .emit_object_manager_signal(path, "InterfacesAdded", properties.to_variant());
+        }
+
+        Ok(handle)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes the interface `interface_name` from the object at `path`.
+    ///
+    /// Returns `false` if no such interface was exported.
+    pub fn remove_interface(&self, path: &str, interface_name: &str) -> bool {
+        let exported = {
+            let mut objects = self.inner.objects.borrow_mut();
+            let Some(interfaces) = objects.get_mut(path) else {
+                return false;
+            };
+            let Some(exported) = interfaces.remove(interface_name) else {
+                return false;
+            };
+            if interfaces.is_empty() {
+                objects.remove(path);
+            }
+            exported
+        };
+
+        self.unexport(path, [(interface_name.to_owned(), exported)]);
+        true
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes all interfaces of the object at `path`.
+    ///
+    /// Returns `false` if no object was exported at `path`.
+    pub fn remove_object(&self, path: &str) -> bool {
+        let interfaces = self.inner.objects.borrow_mut().remove(path);
+        match interfaces {
+            Some(interfaces) => {
+                self.unexport(path, interfaces);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn unexport(&self, path: &str, interfaces: impl IntoIterator<Item = (String, Rc<Exported>)>) {
+        let mut names = Vec::new();
+        for (name, exported) in interfaces {
+            self.inner
+                .pending_changes
+                .borrow_mut()
+                .remove(&(path.to_owned(), name.clone()));
+            exported.unregister(&self.inner.connection);
+            names.push(name);
+        }
+
+        if self.inner.is_managed(path) {
+            self.inner
+                .emit_object_manager_signal(path, "InterfacesRemoved", names.to_variant());
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The paths of all objects with at least one exported interface.
+    pub fn object_paths(&self) -> Vec<String> {
+        self.inner.objects.borrow().keys().cloned().collect()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The names of the interfaces exported on the object at `path`.
+    pub fn interfaces(&self, path: &str) -> Vec<String> {
+        self.inner
+            .objects
+            .borrow()
+            .get(path)
+            .map(|interfaces| interfaces.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The names of the direct child nodes of `path`, as listed in its introspection data.
+    ///
+    /// A node is listed if it or any of its descendants has an exported interface.
+    pub fn children(&self, path: &str) -> Vec<String> {
+        let prefix = if path == "/" {
+            String::from("/")
+        } else {
+            format!("{path}/")
+        };
+
+        let objects = self.inner.objects.borrow();
+        let root = self.object_manager_path();
+        objects
+            .keys()
+            .map(String::as_str)
+            .chain(root)
+            .filter_map(|p| p.strip_prefix(prefix.as_str()))
+            .filter(|rest| !rest.is_empty())
+            .map(|rest| rest.split('/').next().unwrap().to_owned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the introspection data of the object at `path`.
+    ///
+    /// This contains the interfaces exported at `path` and its [child nodes](Self::children),
+    /// but neither the standard interfaces nor objects registered outside the tree.
+    pub fn introspect(&self, path: &str) -> DBusNodeInfo {
+        let mut xml = glib::GStringBuilder::new("<node>\n");
+        if let Some(interfaces) = self.inner.objects.borrow().get(path) {
+            for exported in interfaces.values() {
+                exported.info.generate_xml(2, &mut xml);
+            }
+        }
+        if self.object_manager_path() == Some(path) {
+            DBusNodeInfo::for_xml(OBJECT_MANAGER_XML)
+                .unwrap()
+                .interfaces()[0]
+                .generate_xml(2, &mut xml);
+        }
+        for child in self.children(path) {
+            xml.append(&format!("  <node name=\"{child}\"/>\n"));
+        }
+        xml.append("</node>\n");
+
+        DBusNodeInfo::for_xml(&xml).expect("generated invalid introspection data")
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Announces that the property `name` of the interface `interface_name` on the object at
+    /// `path` has changed.
+    ///
+    /// See [`DBusInterfaceHandle::notify_property_changed()`].
+    pub fn notify_property_changed(&self, path: &str, interface_name: &str, name: &str) {
+        self.inner
+            .notify_property_changed(path, interface_name, name);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Emits all pending `PropertiesChanged` signals right away instead of waiting for the idle
+    /// callback.
+    pub fn flush_property_changes(&self) {
+        self.inner.flush_property_changes();
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A handle to an interface exported with [`DBusObjectTree::add_interface()`].
+///
+/// The handle does not keep the tree alive.
+#[derive(Debug, Clone)]
+pub struct DBusInterfaceHandle {
+    tree: Weak<Inner>,
+    path: String,
+    interface_name: String,
+}
+
+impl DBusInterfaceHandle {
+    // rustdoc-stripper-ignore-next
+    /// The path of the object the interface is exported on.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The name of the interface.
+    pub fn interface_name(&self) -> &str {
+        &self.interface_name
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The tree the interface is exported with, if it still exists.
+    pub fn tree(&self) -> Option<DBusObjectTree> {
+        self.tree.upgrade().map(|inner| DBusObjectTree { inner })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns `true` if the interface is still exported.
+    pub fn is_exported(&self) -> bool {
+        self.tree
+            .upgrade()
+            .is_some_and(|inner| inner.lookup(&self.path, &self.interface_name).is_some())
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Announces that the property `name` has changed.
+    ///
+    /// All changes announced until the thread-default main context becomes idle are sent as a
+    /// single `PropertiesChanged` signal, with the property values at that time. Depending on the
+    /// `org.freedesktop.DBus.Property.EmitsChangedSignal` annotation of the property or
+    /// interface, the property is listed as changed (`true`, the default), as invalidated
+    /// (`invalidates`) or not at all (`false` and `const`).
+    pub fn notify_property_changed(&self, name: &str) {
+        if let Some(inner) = self.tree.upgrade() {
+            inner.notify_property_changed(&self.path, &self.interface_name, name);
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Emits the signal `signal_name` of the interface as a broadcast.
+    pub fn emit_signal(
+        &self,
+        signal_name: &str,
+        parameters: Option<&glib::Variant>,
+    ) -> Result<(), glib::Error> {
+        let Some(inner) = self.tree.upgrade() else {
+            return Err(glib::Error::new(
+                IOErrorEnum::Closed,
+                "The object tree was dropped",
+            ));
+        };
+        inner.connection.emit_signal(
+            None,
+            &self.path,
+            &self.interface_name,
+            signal_name,
+            parameters,
+        )
+    }
+}
+
+fn check_object_path(path: &str) -> Result<(), glib::Error> {
+    if glib::Variant::is_object_path(path) {
+        Ok(())
+    } else {
+        Err(glib::Error::new(
+            IOErrorEnum::InvalidArgument,
+            &format!("Invalid object path {path:?}"),
+        ))
+    }
+}
@@ -38,4 +38,22 @@ impl DBusPropertyInfo {
             from_glib(flags)
         }
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Looks up the value of the annotation `name` on this property.
+    #[doc(alias = "g_dbus_annotation_info_lookup")]
+    pub fn annotation(&self, name: &str) -> Option<&str> {
+        // SAFETY: See top-level comment.
+        unsafe {
+            let c_obj = self.as_ptr();
+            let value = crate::ffi::g_dbus_annotation_info_lookup(
+                (*c_obj).annotations,
+                name.to_glib_none().0,
+            );
+            if value.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(value).to_str().unwrap())
+        }
+    }
 }
@@ -4,7 +4,7 @@ use std::{boxed::Box as Box_, mem::transmute};
 
 use glib::{SignalHandlerId, prelude::*, signal::connect_raw, translate::*};
 
-use crate::{DBusProxy, ffi};
+use crate::{DBusProxy, ffi, prelude::*};
 
 pub trait DBusProxyExtManual: IsA<DBusProxy> + 'static {
     #[doc(alias = "g-properties-changed")]
@@ -135,6 +135,52 @@ pub trait DBusProxyExtManual: IsA<DBusProxy> + 'static {
             )
         }
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Calls the method `method_name` with typed arguments and return value.
+    ///
+    /// See [`DBusConnection::call_typed_future()`](crate::DBusConnection::call_typed_future).
+    fn call_typed_future<Args: crate::DBusMethodArgs, Ret: crate::DBusMethodReply + 'static>(
+        &self,
+        method_name: &str,
+        args: Args,
+        flags: crate::DBusCallFlags,
+        timeout_msec: i32,
+    ) -> std::pin::Pin<Box_<dyn std::future::Future<Output = Result<Ret, glib::Error>> + 'static>>
+    {
+        let args = crate::dbus_typed_call::args_to_variant(&args);
+        let method_name = method_name.to_owned();
+
+        #[cfg(unix)]
+        let call = args.map(|(parameters, fd_list)| {
+            self.call_with_unix_fd_list_future(
+                &method_name,
+                Some(&parameters),
+                flags,
+                timeout_msec,
+                fd_list.as_ref(),
+            )
+        });
+        #[cfg(not(unix))]
+        let call = args.map(|(parameters, _)| {
+            self.call_future(&method_name, Some(&parameters), flags, timeout_msec)
+        });
+
+        Box_::pin(async move {
+            let reply = call?
+                .await
+                .map_err(crate::dbus_typed_call::map_remote_error)?;
+            #[cfg(unix)]
+            let ret = crate::dbus_typed_call::reply_from_variant(
+                &method_name,
+                &reply.0,
+                reply.1.as_ref(),
+            );
+            #[cfg(not(unix))]
+            let ret = crate::dbus_typed_call::reply_from_variant(&method_name, &reply, None);
+            ret
+        })
+    }
 }
 
 impl<O: IsA<DBusProxy>> DBusProxyExtManual for O {}
@@ -0,0 +1,335 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+// Conversions shared by the typed method calls of `DBusConnection` and `DBusProxy`.
+
+use std::borrow::Cow;
+#[cfg(unix)]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+
+use glib::{VariantTy, prelude::*};
+
+#[cfg(unix)]
+use crate::prelude::*;
+use crate::{DBusError, IOErrorEnum, UnixFDList};
+
+// rustdoc-stripper-ignore-next
+/// Conversion of a value to a [`glib::Variant`] that can put file descriptors into a
+/// [`UnixFDList`].
+///
+/// This is implemented for all [`ToVariant`] types and, on Unix, for [`FdHandle`]. It is used for
+/// the elements of the arguments of typed D-Bus method calls, see [`DBusMethodArgs`].
+pub trait ToVariantWithFdList {
+    // rustdoc-stripper-ignore-next
+    /// Converts the value, appending file descriptors to `fd_list`.
+    ///
+    /// `fd_list` is `None` on platforms that can't pass file descriptors.
+    fn to_variant_with_fd_list(
+        &self,
+        fd_list: Option<&UnixFDList>,
+    ) -> Result<glib::Variant, glib::Error>;
+}
+
+impl<T: ToVariant + ?Sized> ToVariantWithFdList for T {
+    fn to_variant_with_fd_list(
+        &self,
+        _fd_list: Option<&UnixFDList>,
+    ) -> Result<glib::Variant, glib::Error> {
+        Ok(self.to_variant())
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// Conversion of a [`glib::Variant`] to a value that can refer to file descriptors in a
+/// [`UnixFDList`].
+///
+/// This is implemented for all [`FromVariant`] types and, on Unix, for [`FdHandle`]. It is used
+/// for the elements of the replies of typed D-Bus method calls, see [`DBusMethodReply`].
+pub trait FromVariantWithFdList: StaticVariantType + Sized {
+    // rustdoc-stripper-ignore-next
+    /// Converts the variant, looking up file descriptors in `fd_list`.
+    ///
+    /// Returns `None` if the variant has a different type or refers to file descriptors that
+    /// are not in `fd_list`.
+    fn from_variant_with_fd_list(
+        variant: &glib::Variant,
+        fd_list: Option<&UnixFDList>,
+    ) -> Option<Self>;
+}
+
+impl<T: FromVariant> FromVariantWithFdList for T {
+    fn from_variant_with_fd_list(
+        variant: &glib::Variant,
+        _fd_list: Option<&UnixFDList>,
+    ) -> Option<Self> {
+        variant.get()
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// The arguments of a typed D-Bus method call.
+///
+/// This is implemented for tuples of [`ToVariantWithFdList`] values, and for [`glib::Variant`]s
+/// whose type is checked when calling.
+pub trait DBusMethodArgs {
+    // rustdoc-stripper-ignore-next
+    /// Converts the arguments, appending file descriptors to `fd_list`.
+    fn to_method_args(&self, fd_list: Option<&UnixFDList>) -> Result<glib::Variant, glib::Error>;
+}
+
+// rustdoc-stripper-ignore-next
+/// The reply of a typed D-Bus method call.
+///
+/// This is implemented for tuples of [`FromVariantWithFdList`] values.
+pub trait DBusMethodReply: StaticVariantType + Sized {
+    // rustdoc-stripper-ignore-next
+    /// Converts the reply, looking up file descriptors in `fd_list`.
+    fn from_method_reply(variant: &glib::Variant, fd_list: Option<&UnixFDList>) -> Option<Self>;
+}
+
+impl DBusMethodArgs for glib::Variant {
+    fn to_method_args(&self, _fd_list: Option<&UnixFDList>) -> Result<glib::Variant, glib::Error> {
+        if self.type_().is_tuple() {
+            Ok(self.clone())
+        } else {
+            Err(glib::Error::new(
+                IOErrorEnum::InvalidArgument,
+                &format!(
+                    "Method call arguments must be a tuple, not '{}'",
+                    self.type_()
+                ),
+            ))
+        }
+    }
+}
+
+impl DBusMethodArgs for () {
+    fn to_method_args(&self, _fd_list: Option<&UnixFDList>) -> Result<glib::Variant, glib::Error> {
+        Ok(().to_variant())
+    }
+}
+
+impl DBusMethodReply for () {
+    fn from_method_reply(variant: &glib::Variant, _fd_list: Option<&UnixFDList>) -> Option<Self> {
+        variant.get()
+    }
+}
+
+macro_rules! tuple_impls {
+    ($($len:expr => ($($n:tt $name:ident)+))+) => {
+        $(
+            impl<$($name: ToVariantWithFdList),+> DBusMethodArgs for ($($name,)+) {
+                fn to_method_args(
+                    &self,
+                    fd_list: Option<&UnixFDList>,
+                ) -> Result<glib::Variant, glib::Error> {
+                    Ok(glib::Variant::tuple_from_iter([
+                        $(self.$n.to_variant_with_fd_list(fd_list)?,)+
+                    ]))
+                }
+            }
+
+            impl<$($name: FromVariantWithFdList),+> DBusMethodReply for ($($name,)+) {
+                fn from_method_reply(
+                    variant: &glib::Variant,
+                    fd_list: Option<&UnixFDList>,
+                ) -> Option<Self> {
+                    if !variant.is::<Self>() {
+                        return None;
+                    }
+                    Some(($(
+                        $name::from_variant_with_fd_list(&variant.try_child_value($n)?, fd_list)?,
+                    )+))
+                }
+            }
+        )+
+    }
+}
+
+tuple_impls! {
+    1 => (0 T0)
+    2 => (0 T0 1 T1)
+    3 => (0 T0 1 T1 2 T2)
+    4 => (0 T0 1 T1 2 T2 3 T3)
+    5 => (0 T0 1 T1 2 T2 3 T3 4 T4)
+    6 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5)
+    7 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6)
+    8 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7)
+    9 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8)
+    10 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9)
+    11 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10)
+    12 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11)
+    13 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12)
+    14 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12 13 T13)
+    15 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12 13 T13 14 T14)
+    16 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12 13 T13 14 T14 15 T15)
+}
+
+// rustdoc-stripper-ignore-next
+/// A file descriptor in the arguments or reply of a typed D-Bus method call.
+///
+/// It is passed as an `h` handle, the index of the file descriptor in the [`UnixFDList`] sent
+/// along with the message.
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+#[derive(Debug)]
+pub struct FdHandle(pub OwnedFd);
+
+#[cfg(unix)]
+impl From<OwnedFd> for FdHandle {
+    fn from(fd: OwnedFd) -> Self {
+        Self(fd)
+    }
+}
+
+#[cfg(unix)]
+impl From<std::fs::File> for FdHandle {
+    fn from(file: std::fs::File) -> Self {
+        Self(file.into())
+    }
+}
+
+#[cfg(unix)]
+impl From<FdHandle> for OwnedFd {
+    fn from(fd: FdHandle) -> Self {
+        fd.0
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for FdHandle {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for FdHandle {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+#[cfg(unix)]
+impl StaticVariantType for FdHandle {
+    fn static_variant_type() -> Cow<'static, VariantTy> {
+        Cow::Borrowed(VariantTy::HANDLE)
+    }
+}
+
+#[cfg(unix)]
+impl ToVariantWithFdList for FdHandle {
+    // rustdoc-stripper-ignore-next
+    /// Appends a duplicate of the file descriptor to `fd_list` and returns its index as handle.
+    fn to_variant_with_fd_list(
+        &self,
+        fd_list: Option<&UnixFDList>,
+    ) -> Result<glib::Variant, glib::Error> {
+        let fd_list = fd_list.ok_or_else(|| {
+            glib::Error::new(
+                IOErrorEnum::NotSupported,
+                "File descriptors can't be passed here",
+            )
+        })?;
+        let index = fd_list.append(&self.0)?;
+        Ok(glib::variant::Handle(index).to_variant())
+    }
+}
+
+#[cfg(unix)]
+impl FromVariantWithFdList for FdHandle {
+    // rustdoc-stripper-ignore-next
+    /// Returns a duplicate of the file descriptor in `fd_list` the handle refers to.
+    fn from_variant_with_fd_list(
+        variant: &glib::Variant,
+        fd_list: Option<&UnixFDList>,
+    ) -> Option<Self> {
+        let glib::variant::Handle(index) = variant.get()?;
+        fd_list?.get(index).ok().map(Self)
+    }
+}
+
+// Converts the arguments of a method call. On Unix, file descriptors in the arguments are
+// collected into the returned list.
+pub(crate) fn args_to_variant<Args: DBusMethodArgs>(
+    args: &Args,
+) -> Result<(glib::Variant, Option<UnixFDList>), glib::Error> {
+    #[cfg(unix)]
+    {
+        let fd_list = UnixFDList::new();
+        let parameters = args.to_method_args(Some(&fd_list))?;
+        Ok((parameters, (fd_list.length() > 0).then_some(fd_list)))
+    }
+    #[cfg(not(unix))]
+    {
+        Ok((args.to_method_args(None)?, None))
+    }
+}
+
+// Converts the reply of a method call. On Unix, handles in the reply are resolved against the
+// file descriptors received with it.
+pub(crate) fn reply_from_variant<Ret: DBusMethodReply>(
+    method_name: &str,
+    reply: &glib::Variant,
+    fd_list: Option<&UnixFDList>,
+) -> Result<Ret, glib::Error> {
+    Ret::from_method_reply(reply, fd_list)
+        .ok_or_else(|| reply_type_error::<Ret>(method_name, reply))
+}
+
+// Same error as GDBus returns for a mismatching `reply_type`.
+fn reply_type_error<Ret: StaticVariantType>(
+    method_name: &str,
+    reply: &glib::Variant,
+) -> glib::Error {
+    glib::Error::new(
+        IOErrorEnum::InvalidArgument,
+        &format!(
+            "Method '{method_name}' returned type '{}', but expected '{}'",
+            reply.type_(),
+            Ret::static_variant_type()
+        ),
+    )
+}
+
+// Errors with a registered D-Bus error name are already mapped to their error domain by GDBus,
+// only the remote error name in the message has to be removed.
+pub(crate) fn map_remote_error(mut error: glib::Error) -> glib::Error {
+    if DBusError::is_remote_error(&error) && !error.matches(IOErrorEnum::DbusError) {
+        DBusError::strip_remote_error(&mut error);
+    }
+    error
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fd_handles() {
+        let fd_list = UnixFDList::new();
+        let file = FdHandle::from(std::fs::File::open("/dev/null").unwrap());
+        let args = ("null", file).to_method_args(Some(&fd_list)).unwrap();
+        assert_eq!(args.type_(), "(sh)");
+        assert_eq!(fd_list.length(), 1);
+
+        // Converting does not consume the file descriptors
+        for _ in 0..2 {
+            let (name, fd) =
+                <(String, FdHandle)>::from_method_reply(&args, Some(&fd_list)).unwrap();
+            assert_eq!(name, "null");
+            assert!(fd.as_raw_fd() >= 0);
+        }
+        assert!(<(String, FdHandle)>::from_method_reply(&args, None).is_none());
+        assert!(<(String, String)>::from_method_reply(&args, Some(&fd_list)).is_none());
+
+        assert!(
+            (
+                "null",
+                FdHandle::from(std::fs::File::open("/dev/null").unwrap())
+            )
+                .to_method_args(None)
+                .is_err()
+        );
+        assert!("not a tuple".to_variant().to_method_args(None).is_err());
+    }
+}
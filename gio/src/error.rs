@@ -2,11 +2,13 @@
 
 use std::io;
 
-use crate::IOErrorEnum;
+use glib::error::ErrorDomain;
+
 #[cfg(feature = "v2_74")]
 use crate::glib::FileError;
 #[cfg(feature = "v2_74")]
 use crate::glib::translate::*;
+use crate::{DBusError, IOErrorEnum};
 
 impl From<IOErrorEnum> for io::ErrorKind {
     fn from(kind: IOErrorEnum) -> Self {
@@ -43,3 +45,45 @@ impl From<FileError> for IOErrorEnum {
         unsafe { from_glib(crate::ffi::g_io_error_from_file_error(e.into_glib())) }
     }
 }
+
+// rustdoc-stripper-ignore-next
+/// D-Bus specific methods of [`glib::Error`].
+pub trait DBusErrorExt {
+    // rustdoc-stripper-ignore-next
+    /// Returns the D-Bus error name of an error received from a remote peer.
+    ///
+    /// See [`DBusError::remote_error()`].
+    #[doc(alias = "g_dbus_error_get_remote_error")]
+    fn dbus_remote_error(&self) -> Option<glib::GString>;
+
+    // rustdoc-stripper-ignore-next
+    /// Removes the D-Bus error name prefix from the message of an error received from a remote
+    /// peer.
+    ///
+    /// Returns `true` if the message was changed.
+    #[doc(alias = "g_dbus_error_strip_remote_error")]
+    fn strip_remote_error(&mut self) -> bool;
+
+    // rustdoc-stripper-ignore-next
+    /// Tries to convert to a specific error enum, including errors received from a remote peer.
+    ///
+    /// In addition to [`glib::Error::kind()`], this maps errors carrying the D-Bus error name of
+    /// a variant of `T`, see [`ErrorDomain::from_dbus_error_name()`]. This is needed if the error
+    /// arrived before the error domain registered its D-Bus error names.
+    fn dbus_kind<T: ErrorDomain>(&self) -> Option<T>;
+}
+
+impl DBusErrorExt for glib::Error {
+    fn dbus_remote_error(&self) -> Option<glib::GString> {
+        DBusError::remote_error(self)
+    }
+
+    fn strip_remote_error(&mut self) -> bool {
+        DBusError::strip_remote_error(self)
+    }
+
+    fn dbus_kind<T: ErrorDomain>(&self) -> Option<T> {
+        self.kind::<T>()
+            .or_else(|| T::from_dbus_error_name(&self.dbus_remote_error()?))
+    }
+}
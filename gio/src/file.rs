@@ -1081,6 +1081,125 @@ pub trait FileExtManual: IsA<File> + Sized {
         (fut, Box::pin(receiver))
     }
 
+    // rustdoc-stripper-ignore-next
+    /// Like [`replace_contents_future`](Self::replace_contents_future), but writes the contents
+    /// in chunks and reports `(written, total)` bytes on the returned stream.
+    ///
+    /// The file is replaced atomically: if writing fails or the future is dropped, the previous
+    /// contents are kept.
+    fn replace_contents_with_progress_future<B: AsRef<[u8]> + Send + 'static>(
+        &self,
+        contents: B,
+        etag: Option<&str>,
+        make_backup: bool,
+        flags: FileCreateFlags,
+        io_priority: glib::Priority,
+    ) -> (
+        Pin<
+            Box<
+                dyn std::future::Future<
+                        Output = Result<(B, Option<glib::GString>), (B, glib::Error)>,
+                    > + 'static,
+            >,
+        >,
+        Pin<Box<dyn futures_core::stream::Stream<Item = (i64, i64)> + 'static>>,
+    ) {
+        let (sender, receiver) = futures_channel::mpsc::unbounded();
+
+        let fut = Box::pin(crate::file_recursive::replace_contents_with_progress(
+            self.as_ref().clone(),
+            contents,
+            etag.map(glib::GString::from),
+            make_backup,
+            flags,
+            io_priority,
+            sender,
+        ));
+
+        (fut, Box::pin(receiver))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Recursively copies `self` to `destination`.
+    ///
+    /// Directories are created as needed and merged into existing ones. If a file or a
+    /// non-directory already exists at the destination, `conflict_callback` is called with the
+    /// source and destination to decide what to do. Without a callback, `flags` decides whether
+    /// existing files are overwritten and the copy fails otherwise.
+    ///
+    /// With [`FileCopyFlags::ALL_METADATA`](crate::FileCopyFlags::ALL_METADATA) the metadata of
+    /// directories is preserved too. Dropping the future cancels the operation.
+    ///
+    /// Symbolic links are followed unless `flags` contains
+    /// [`FileCopyFlags::NOFOLLOW_SYMLINKS`](crate::FileCopyFlags::NOFOLLOW_SYMLINKS). A link
+    /// back to a directory that is being copied fails with
+    /// [`IOErrorEnum::WouldRecurse`](crate::IOErrorEnum::WouldRecurse).
+    fn copy_recursive_future(
+        &self,
+        destination: &(impl IsA<File> + Clone + 'static),
+        flags: crate::FileCopyFlags,
+        io_priority: glib::Priority,
+        conflict_callback: Option<
+            Box<dyn FnMut(&File, &File) -> crate::FileConflictResolution + 'static>,
+        >,
+    ) -> (
+        Pin<Box<dyn std::future::Future<Output = Result<(), glib::Error>> + 'static>>,
+        Pin<Box<dyn futures_core::stream::Stream<Item = crate::FileProgress> + 'static>>,
+    ) {
+        let (operation, receiver) =
+            crate::file_recursive::RecursiveOperation::new(flags, io_priority, conflict_callback);
+        let fut = Box::pin(operation.copy(self.as_ref().clone(), destination.as_ref().clone()));
+
+        (fut, Box::pin(receiver))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Recursively moves `self` to `destination`.
+    ///
+    /// A rename is tried first. If that's not possible, for example because the destination is
+    /// on another filesystem, this falls back to copying like
+    /// [`copy_recursive_future`](Self::copy_recursive_future) and deleting each source after it
+    /// was copied. Skipped sources are left in place.
+    fn move_recursive_future(
+        &self,
+        destination: &(impl IsA<File> + Clone + 'static),
+        flags: crate::FileCopyFlags,
+        io_priority: glib::Priority,
+        conflict_callback: Option<
+            Box<dyn FnMut(&File, &File) -> crate::FileConflictResolution + 'static>,
+        >,
+    ) -> (
+        Pin<Box<dyn std::future::Future<Output = Result<(), glib::Error>> + 'static>>,
+        Pin<Box<dyn futures_core::stream::Stream<Item = crate::FileProgress> + 'static>>,
+    ) {
+        let (operation, receiver) =
+            crate::file_recursive::RecursiveOperation::new(flags, io_priority, conflict_callback);
+        let fut = Box::pin(operation.move_(self.as_ref().clone(), destination.as_ref().clone()));
+
+        (fut, Box::pin(receiver))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Recursively deletes `self` and, if it is a directory, everything below it.
+    ///
+    /// Symbolic links are deleted, not followed. Dropping the future cancels the operation.
+    fn delete_recursive_future(
+        &self,
+        io_priority: glib::Priority,
+    ) -> (
+        Pin<Box<dyn std::future::Future<Output = Result<(), glib::Error>> + 'static>>,
+        Pin<Box<dyn futures_core::stream::Stream<Item = crate::FileProgress> + 'static>>,
+    ) {
+        let (operation, receiver) = crate::file_recursive::RecursiveOperation::new(
+            crate::FileCopyFlags::NONE,
+            io_priority,
+            None,
+        );
+        let fut = Box::pin(operation.delete(self.as_ref().clone()));
+
+        (fut, Box::pin(receiver))
+    }
+
     #[doc(alias = "g_file_set_attribute")]
     fn set_attribute<'a>(
         &self,
@@ -0,0 +1,666 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{future::Future, ops::Range};
+
+use futures_channel::mpsc;
+use futures_util::{FutureExt, StreamExt, future::LocalBoxFuture};
+
+use crate::{
+    Cancellable, File, FileCopyFlags, FileCreateFlags, FileMeasureFlags, FileOutputStream,
+    FileQueryInfoFlags, FileType, IOErrorEnum, prelude::*,
+};
+
+// rustdoc-stripper-ignore-next
+/// Progress of a recursive file operation.
+///
+/// Reported by [`FileExtManual::copy_recursive_future`],
+/// [`FileExtManual::move_recursive_future`] and
+/// [`FileExtManual::delete_recursive_future`]. The totals are measured before the operation
+/// starts and stay `0` if the backend can't measure the tree.
+///
+/// [`FileExtManual::copy_recursive_future`]: crate::prelude::FileExtManual::copy_recursive_future
+/// [`FileExtManual::move_recursive_future`]: crate::prelude::FileExtManual::move_recursive_future
+/// [`FileExtManual::delete_recursive_future`]: crate::prelude::FileExtManual::delete_recursive_future
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileProgress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+}
+
+// rustdoc-stripper-ignore-next
+/// How a recursive copy or move handles a destination that already exists.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileConflictResolution {
+    // rustdoc-stripper-ignore-next
+    /// Leave the destination untouched and skip the source.
+    Skip,
+    // rustdoc-stripper-ignore-next
+    /// Replace the destination.
+    Overwrite,
+    // rustdoc-stripper-ignore-next
+    /// Use the given basename in the same destination directory instead.
+    Rename(String),
+    // rustdoc-stripper-ignore-next
+    /// Stop the whole operation with [`IOErrorEnum::Exists`].
+    Abort,
+}
+
+pub(crate) type ConflictCallback = Box<dyn FnMut(&File, &File) -> FileConflictResolution>;
+
+// Attributes that are restored on directories when `FileCopyFlags::ALL_METADATA` is set.
+// Regular files get their metadata copied by `g_file_copy()` itself.
+const DIRECTORY_METADATA: &str =
+    "time::modified,time::modified-usec,time::access,time::access-usec,unix::mode";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Copy,
+    Move,
+}
+
+pub(crate) struct RecursiveOperation {
+    flags: FileCopyFlags,
+    io_priority: glib::Priority,
+    conflict_callback: Option<ConflictCallback>,
+    progress: FileProgress,
+    sender: mpsc::UnboundedSender<FileProgress>,
+    // Device and inode of the directories being transferred, to detect cycles through
+    // followed symbolic links.
+    ancestors: Vec<DirectoryId>,
+}
+
+type DirectoryId = (u32, u64);
+
+// Returns the device and inode of a directory, if the backend provides them.
+fn directory_id(info: &crate::FileInfo) -> Option<DirectoryId> {
+    (info.has_attribute(crate::FILE_ATTRIBUTE_UNIX_DEVICE)
+        && info.has_attribute(crate::FILE_ATTRIBUTE_UNIX_INODE))
+    .then(|| {
+        (
+            info.attribute_uint32(crate::FILE_ATTRIBUTE_UNIX_DEVICE),
+            info.attribute_uint64(crate::FILE_ATTRIBUTE_UNIX_INODE),
+        )
+    })
+}
+
+impl RecursiveOperation {
+    pub(crate) fn new(
+        flags: FileCopyFlags,
+        io_priority: glib::Priority,
+        conflict_callback: Option<ConflictCallback>,
+    ) -> (Self, mpsc::UnboundedReceiver<FileProgress>) {
+        let (sender, receiver) = mpsc::unbounded();
+        (
+            Self {
+                flags,
+                io_priority,
+                conflict_callback,
+                progress: FileProgress::default(),
+                sender,
+                ancestors: Vec::new(),
+            },
+            receiver,
+        )
+    }
+
+    pub(crate) async fn copy(mut self, source: File, destination: File) -> Result<(), glib::Error> {
+        self.measure(&source).await?;
+        self.transfer(source, destination, Mode::Copy)
+            .await
+            .map(|_| ())
+    }
+
+    pub(crate) async fn move_(
+        mut self,
+        source: File,
+        destination: File,
+    ) -> Result<(), glib::Error> {
+        self.measure(&source).await?;
+
+        // Try a plain rename first, this is atomic and cheap if source and
+        // destination are on the same filesystem.
+        match self.rename(&source, &destination).await {
+            Ok(()) => {
+                self.progress.bytes_done = self.progress.bytes_total;
+                self.progress.files_done = self.progress.files_total;
+                self.report();
+                return Ok(());
+            }
+            Err(err)
+                if err.matches(IOErrorEnum::WouldRecurse)
+                    || err.matches(IOErrorEnum::NotSupported)
+                    || err.matches(IOErrorEnum::WouldMerge)
+                    || err.matches(IOErrorEnum::Exists) => {}
+            Err(err) => return Err(err),
+        }
+
+        self.transfer(source, destination, Mode::Move)
+            .await
+            .map(|_| ())
+    }
+
+    // Renames `source` to `destination` without falling back to copying.
+    #[cfg(feature = "v2_72")]
+    async fn rename(&self, source: &File, destination: &File) -> Result<(), glib::Error> {
+        let flags = self.flags | FileCopyFlags::NO_FALLBACK_FOR_MOVE;
+        let io_priority = self.io_priority;
+        let destination = destination.clone();
+        crate::GioFuture::new(source, move |obj, cancellable, send| {
+            obj.move_async(
+                &destination,
+                flags,
+                io_priority,
+                Some(cancellable),
+                None,
+                move |res| send.resolve(res),
+            );
+        })
+        .await
+    }
+
+    // Renames `source` to `destination` without falling back to copying.
+    //
+    // `g_file_move_async()` only exists since GLib 2.72. Without the fallback `g_file_move()`
+    // doesn't copy anything, so calling it synchronously only costs a single rename.
+    #[cfg(not(feature = "v2_72"))]
+    async fn rename(&self, source: &File, destination: &File) -> Result<(), glib::Error> {
+        source.move_(
+            destination,
+            self.flags | FileCopyFlags::NO_FALLBACK_FOR_MOVE,
+            Cancellable::NONE,
+            None,
+        )
+    }
+
+    pub(crate) async fn delete(mut self, file: File) -> Result<(), glib::Error> {
+        self.measure(&file).await?;
+        self.remove(file).await
+    }
+
+    async fn measure(&mut self, file: &File) -> Result<(), glib::Error> {
+        let (fut, _) = file.measure_disk_usage_future(
+            FileMeasureFlags::APPARENT_SIZE | FileMeasureFlags::NO_XDEV,
+            self.io_priority,
+        );
+        match fut.await {
+            Ok((disk_usage, num_dirs, num_files)) => {
+                self.progress.bytes_total = disk_usage;
+                self.progress.files_total = num_dirs + num_files;
+            }
+            Err(err) if err.matches(IOErrorEnum::NotSupported) => (),
+            Err(err) => return Err(err),
+        }
+        self.report();
+        Ok(())
+    }
+
+    fn report(&self) {
+        let _ = self.sender.unbounded_send(self.progress);
+    }
+
+    fn finish_entry(&mut self, size: u64) {
+        self.progress.bytes_done += size;
+        self.progress.files_done += 1;
+        self.report();
+    }
+
+    fn query_flags(&self) -> FileQueryInfoFlags {
+        if self.flags.contains(FileCopyFlags::NOFOLLOW_SYMLINKS) {
+            FileQueryInfoFlags::NOFOLLOW_SYMLINKS
+        } else {
+            FileQueryInfoFlags::NONE
+        }
+    }
+
+    fn resolve_conflict(
+        &mut self,
+        source: &File,
+        destination: &File,
+        error: glib::Error,
+    ) -> Result<FileConflictResolution, glib::Error> {
+        match self.conflict_callback {
+            Some(ref mut callback) => match callback(source, destination) {
+                FileConflictResolution::Abort => Err(error),
+                resolution => Ok(resolution),
+            },
+            None => Err(error),
+        }
+    }
+
+    // Copies or moves `source` to `destination`. Resolves to `false` if something
+    // was skipped, in which case the source of a move can't be removed completely.
+    fn transfer(
+        &mut self,
+        source: File,
+        destination: File,
+        mode: Mode,
+    ) -> LocalBoxFuture<'_, Result<bool, glib::Error>> {
+        async move {
+            let info = source
+                .query_info_future(
+                    "standard::type,standard::size,unix::device,unix::inode",
+                    self.query_flags(),
+                    self.io_priority,
+                )
+                .await?;
+
+            if info.file_type() == FileType::Directory {
+                let id = directory_id(&info);
+                self.transfer_directory(source, destination, info.size() as u64, id, mode)
+                    .await
+            } else {
+                self.transfer_file(source, destination, info.size() as u64, mode)
+                    .await
+            }
+        }
+        .boxed_local()
+    }
+
+    async fn transfer_directory(
+        &mut self,
+        source: File,
+        destination: File,
+        size: u64,
+        id: Option<DirectoryId>,
+        mode: Mode,
+    ) -> Result<bool, glib::Error> {
+        // Following a symbolic link back to a directory that is being transferred would
+        // recurse forever.
+        if let Some(id) = id
+            && self.ancestors.contains(&id)
+        {
+            return Err(glib::Error::new(
+                IOErrorEnum::WouldRecurse,
+                &format!("Symbolic link cycle at {}", source.parse_name().as_str()),
+            ));
+        }
+
+        match destination.make_directory_future(self.io_priority).await {
+            Ok(()) => (),
+            Err(err) if err.matches(IOErrorEnum::Exists) => {
+                let existing = destination
+                    .query_info_future(
+                        "standard::type",
+                        FileQueryInfoFlags::NOFOLLOW_SYMLINKS,
+                        self.io_priority,
+                    )
+                    .await?;
+                // Existing directories are merged, anything else is a conflict.
+                if existing.file_type() != FileType::Directory {
+                    match self.resolve_conflict(&source, &destination, err)? {
+                        FileConflictResolution::Skip => {
+                            self.skip_tree(&source).await?;
+                            return Ok(false);
+                        }
+                        FileConflictResolution::Overwrite => {
+                            destination.delete_future(self.io_priority).await?;
+                            destination.make_directory_future(self.io_priority).await?;
+                        }
+                        FileConflictResolution::Rename(name) => {
+                            let destination = renamed(&destination, &name);
+                            return self.transfer(source, destination, mode).await;
+                        }
+                        FileConflictResolution::Abort => unreachable!(),
+                    }
+                }
+            }
+            Err(err) => return Err(err),
+        }
+        self.finish_entry(size);
+
+        if let Some(id) = id {
+            self.ancestors.push(id);
+        }
+        let complete = self.transfer_children(&source, &destination, mode).await;
+        if id.is_some() {
+            self.ancestors.pop();
+        }
+        let complete = complete?;
+
+        if self.flags.contains(FileCopyFlags::ALL_METADATA) {
+            let info = source
+                .query_info_future(DIRECTORY_METADATA, self.query_flags(), self.io_priority)
+                .await?;
+            destination
+                .set_attributes_future(
+                    &info,
+                    FileQueryInfoFlags::NOFOLLOW_SYMLINKS,
+                    self.io_priority,
+                )
+                .await?;
+        }
+
+        if mode == Mode::Move && complete {
+            source.delete_future(self.io_priority).await?;
+        }
+
+        Ok(complete)
+    }
+
+    async fn transfer_children(
+        &mut self,
+        source: &File,
+        destination: &File,
+        mode: Mode,
+    ) -> Result<bool, glib::Error> {
+        let mut complete = true;
+        let mut children = source
+            .enumerate_children_future(
+                crate::FILE_ATTRIBUTE_STANDARD_NAME,
+                FileQueryInfoFlags::NOFOLLOW_SYMLINKS,
+                self.io_priority,
+            )
+            .await?
+            .into_stream(64, self.io_priority);
+        while let Some(infos) = children.next().await {
+            for info in infos? {
+                let name = info.name();
+                complete &= self
+                    .transfer(source.child(&name), destination.child(&name), mode)
+                    .await?;
+            }
+        }
+        Ok(complete)
+    }
+
+    async fn transfer_file(
+        &mut self,
+        source: File,
+        destination: File,
+        size: u64,
+        mode: Mode,
+    ) -> Result<bool, glib::Error> {
+        let mut flags = self.flags;
+        // Let the callback decide about existing destinations.
+        if self.conflict_callback.is_some() {
+            flags.remove(FileCopyFlags::OVERWRITE);
+        }
+
+        match self.copy_file(&source, &destination, flags).await {
+            Ok(()) => (),
+            Err(err) if err.matches(IOErrorEnum::Exists) => {
+                match self.resolve_conflict(&source, &destination, err)? {
+                    FileConflictResolution::Skip => {
+                        self.finish_entry(size);
+                        return Ok(false);
+                    }
+                    FileConflictResolution::Overwrite => {
+                        self.copy_file(&source, &destination, flags | FileCopyFlags::OVERWRITE)
+                            .await?
+                    }
+                    FileConflictResolution::Rename(name) => {
+                        let destination = renamed(&destination, &name);
+                        return self.transfer(source, destination, mode).await;
+                    }
+                    FileConflictResolution::Abort => unreachable!(),
+                }
+            }
+            Err(err) => return Err(err),
+        }
+
+        if mode == Mode::Move {
+            source.delete_future(self.io_priority).await?;
+        }
+        self.finish_entry(size);
+
+        Ok(true)
+    }
+
+    fn copy_file(
+        &self,
+        source: &File,
+        destination: &File,
+        flags: FileCopyFlags,
+    ) -> impl Future<Output = Result<(), glib::Error>> + 'static {
+        let destination = destination.clone();
+        let io_priority = self.io_priority;
+        let progress = self.progress;
+        let sender = self.sender.clone();
+
+        crate::GioFuture::new(source, move |obj, cancellable, send| {
+            obj.copy_async(
+                &destination,
+                flags,
+                io_priority,
+                Some(cancellable),
+                Some(Box::new(move |current_num_bytes, _total_num_bytes| {
+                    let mut progress = progress;
+                    progress.bytes_done += current_num_bytes.max(0) as u64;
+                    let _ = sender.unbounded_send(progress);
+                })),
+                move |res| send.resolve(res),
+            );
+        })
+    }
+
+    // Accounts for everything below a skipped directory.
+    async fn skip_tree(&mut self, file: &File) -> Result<(), glib::Error> {
+        let (fut, _) = file.measure_disk_usage_future(
+            FileMeasureFlags::APPARENT_SIZE | FileMeasureFlags::NO_XDEV,
+            self.io_priority,
+        );
+        let (disk_usage, num_dirs, num_files) = fut.await.unwrap_or_default();
+        self.progress.bytes_done += disk_usage;
+        self.progress.files_done += num_dirs + num_files;
+        self.report();
+        Ok(())
+    }
+
+    fn remove(&mut self, file: File) -> LocalBoxFuture<'_, Result<(), glib::Error>> {
+        async move {
+            let info = file
+                .query_info_future(
+                    "standard::type,standard::size",
+                    FileQueryInfoFlags::NOFOLLOW_SYMLINKS,
+                    self.io_priority,
+                )
+                .await?;
+
+            if info.file_type() == FileType::Directory {
+                let mut children = file
+                    .enumerate_children_future(
+                        crate::FILE_ATTRIBUTE_STANDARD_NAME,
+                        FileQueryInfoFlags::NOFOLLOW_SYMLINKS,
+                        self.io_priority,
+                    )
+                    .await?
+                    .into_stream(64, self.io_priority);
+                while let Some(infos) = children.next().await {
+                    for info in infos? {
+                        self.remove(file.child(info.name())).await?;
+                    }
+                }
+            }
+            file.delete_future(self.io_priority).await?;
+            self.finish_entry(info.size() as u64);
+
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
+
+fn renamed(destination: &File, name: &str) -> File {
+    match destination.parent() {
+        Some(parent) => parent.child(name),
+        None => File::for_path(name),
+    }
+}
+
+// A window into the contents passed to `replace_contents_with_progress_future()`, so
+// that the buffer can be handed to `write_all_future()` chunk by chunk without copying.
+struct Chunk<B> {
+    contents: B,
+    range: Range<usize>,
+}
+
+impl<B: AsRef<[u8]>> AsRef<[u8]> for Chunk<B> {
+    fn as_ref(&self) -> &[u8] {
+        &self.contents.as_ref()[self.range.clone()]
+    }
+}
+
+// Output streams returned by `g_file_replace()` only replace the target on a
+// successful close. Closing with a cancelled cancellable discards the temporary
+// file instead, so an error or a dropped future leaves the target untouched.
+struct DiscardOnDrop(Option<FileOutputStream>);
+
+impl Drop for DiscardOnDrop {
+    fn drop(&mut self) {
+        if let Some(stream) = self.0.take() {
+            let cancellable = Cancellable::new();
+            cancellable.cancel();
+            let _ = stream.close(Some(&cancellable));
+        }
+    }
+}
+
+const REPLACE_CHUNK_SIZE: usize = 64 * 1024;
+
+pub(crate) async fn replace_contents_with_progress<B: AsRef<[u8]> + Send + 'static>(
+    file: File,
+    contents: B,
+    etag: Option<glib::GString>,
+    make_backup: bool,
+    flags: FileCreateFlags,
+    io_priority: glib::Priority,
+    sender: mpsc::UnboundedSender<(i64, i64)>,
+) -> Result<(B, Option<glib::GString>), (B, glib::Error)> {
+    let stream = match file
+        .replace_future(etag.as_deref(), make_backup, flags, io_priority)
+        .await
+    {
+        Ok(stream) => stream,
+        Err(err) => return Err((contents, err)),
+    };
+    let mut guard = DiscardOnDrop(Some(stream.clone()));
+
+    let total = contents.as_ref().len();
+    let _ = sender.unbounded_send((0, total as i64));
+
+    let mut contents = contents;
+    let mut written = 0;
+    while written < total {
+        let end = usize::min(written + REPLACE_CHUNK_SIZE, total);
+        let chunk = Chunk {
+            contents,
+            range: written..end,
+        };
+        match stream.write_all_future(chunk, io_priority).await {
+            Ok((chunk, _, None)) => contents = chunk.contents,
+            Ok((chunk, _, Some(err))) | Err((chunk, err)) => return Err((chunk.contents, err)),
+        }
+        written = end;
+        let _ = sender.unbounded_send((written as i64, total as i64));
+    }
+
+    guard.0 = None;
+    match stream.close_future(io_priority).await {
+        Ok(()) => Ok((contents, stream.etag())),
+        Err(err) => Err((contents, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, fs, rc::Rc};
+
+    use futures_util::StreamExt;
+
+    use crate::{FileCopyFlags, prelude::*};
+
+    #[test]
+    fn copy_and_delete_recursive() {
+        let root = std::env::temp_dir().join(format!("gio-rs-recursive-{}", std::process::id()));
+        let source = root.join("source");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("a"), b"hello").unwrap();
+        fs::write(source.join("sub").join("b"), b"world!").unwrap();
+        fs::create_dir_all(root.join("destination")).unwrap();
+        fs::write(root.join("destination").join("a"), b"old").unwrap();
+
+        let ctx = glib::MainContext::new();
+        let lp = glib::MainLoop::new(Some(&ctx), false);
+        let res = Rc::new(Cell::new(None));
+
+        let lp_clone = lp.clone();
+        let res_clone = res.clone();
+        let root_clone = root.clone();
+        ctx.spawn_local(async move {
+            res_clone.replace(Some(
+                async {
+                    let source = crate::File::for_path(root_clone.join("source"));
+                    let destination = crate::File::for_path(root_clone.join("destination"));
+
+                    let (fut, progress) = source.copy_recursive_future(
+                        &destination,
+                        FileCopyFlags::NONE,
+                        glib::Priority::default(),
+                        Some(Box::new(|_, _| crate::FileConflictResolution::Skip)),
+                    );
+                    fut.await?;
+                    let last = progress.collect::<Vec<_>>().await.pop().unwrap();
+                    assert_eq!(last.bytes_done, last.bytes_total);
+                    assert_eq!(last.files_done, 4);
+                    assert_eq!(last.files_total, 4);
+
+                    let root = crate::File::for_path(&root_clone);
+                    let (fut, _) = root.delete_recursive_future(glib::Priority::default());
+                    fut.await
+                }
+                .await,
+            ));
+            lp_clone.quit();
+        });
+        lp.run();
+
+        let res = Rc::try_unwrap(res)
+            .unwrap_or_else(|_| panic!("future not finished"))
+            .into_inner()
+            .unwrap();
+        res.unwrap();
+        assert!(!root.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_symlink_cycle() {
+        let root = std::env::temp_dir().join(format!("gio-rs-cycle-{}", std::process::id()));
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        std::os::unix::fs::symlink(&source, source.join("loop")).unwrap();
+
+        let ctx = glib::MainContext::new();
+        let res = ctx.block_on(async {
+            let (fut, _) = crate::File::for_path(&source).copy_recursive_future(
+                &crate::File::for_path(root.join("destination")),
+                FileCopyFlags::NONE,
+                glib::Priority::default(),
+                None,
+            );
+            fut.await
+        });
+        assert!(res.unwrap_err().matches(crate::IOErrorEnum::WouldRecurse));
+
+        // Without following symbolic links the link itself is copied
+        let res = ctx.block_on(async {
+            let (fut, _) = crate::File::for_path(&source).copy_recursive_future(
+                &crate::File::for_path(root.join("copy")),
+                FileCopyFlags::NOFOLLOW_SYMLINKS,
+                glib::Priority::default(),
+                None,
+            );
+            fut.await
+        });
+        res.unwrap();
+        assert!(
+            fs::symlink_metadata(root.join("copy").join("loop"))
+                .unwrap()
+                .is_symlink()
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use glib::{Object, clone, prelude::*, subclass::prelude::*};
+
+use crate::{ListModel, list_model::changed_ranges, prelude::*, subclass::prelude::*};
+
+// rustdoc-stripper-ignore-next
+/// Describes how the filter function of a [`FilterListModel`] changed, to limit which items
+/// have to be checked again by [`FilterListModel::refilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterChange {
+    // rustdoc-stripper-ignore-next
+    /// The filter may match any item differently.
+    Different,
+    // rustdoc-stripper-ignore-next
+    /// The filter matches at least all items it matched before.
+    LessStrict,
+    // rustdoc-stripper-ignore-next
+    /// The filter matches at most the items it matched before.
+    MoreStrict,
+}
+
+mod imp {
+    use std::cell::{OnceCell, RefCell};
+
+    use super::*;
+
+    pub(super) type FilterFunc = Box<dyn Fn(&Object) -> bool>;
+
+    #[derive(Default)]
+    pub struct FilterListModel {
+        pub(super) model: OnceCell<ListModel>,
+        pub(super) filter_func: RefCell<Option<FilterFunc>>,
+        // Sorted positions of the matching items in `model`.
+        pub(super) matches: RefCell<Vec<u32>>,
+        pub(super) handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FilterListModel {
+        const NAME: &'static str = "FilterListModel";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::FilterListModel;
+        type Interfaces = (ListModel,);
+    }
+
+    impl ObjectImpl for FilterListModel {
+        fn dispose(&self) {
+            if let (Some(model), Some(handler)) = (self.model.get(), self.handler.take()) {
+                model.disconnect(handler);
+            }
+        }
+    }
+
+    impl ListModelImpl for FilterListModel {
+        fn item_type(&self) -> glib::Type {
+            self.model.get().unwrap().item_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.matches.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<Object> {
+            let source_position = *self.matches.borrow().get(position as usize)?;
+            self.model.get().unwrap().item(source_position)
+        }
+    }
+
+    impl FilterListModel {
+        fn matches(&self, source_position: u32) -> bool {
+            let Some(item) = self.model.get().unwrap().item(source_position) else {
+                return false;
+            };
+            match *self.filter_func.borrow() {
+                Some(ref filter_func) => filter_func(&item),
+                None => true,
+            }
+        }
+
+        pub(super) fn source_items_changed(&self, position: u32, removed: u32, added: u32) {
+            let new_matches = (position..position + added)
+                .filter(|&source_position| self.matches(source_position))
+                .collect::<Vec<_>>();
+
+            let (start, n_removed) = {
+                let mut matches = self.matches.borrow_mut();
+                let start = matches.partition_point(|&p| p < position);
+                let end = matches.partition_point(|&p| p < position + removed);
+                for p in &mut matches[end..] {
+                    *p = *p - removed + added;
+                }
+                matches.splice(start..end, new_matches.iter().copied());
+                (start as u32, (end - start) as u32)
+            };
+
+            if n_removed > 0 || !new_matches.is_empty() {
+                self.obj()
+                    .items_changed(start, n_removed, new_matches.len() as u32);
+            }
+        }
+
+        pub(super) fn refilter(&self, change: FilterChange) {
+            let old = self.matches.borrow().clone();
+            let n_items = self.model.get().unwrap().n_items();
+
+            let new = match change {
+                FilterChange::Different => (0..n_items).filter(|&p| self.matches(p)).collect(),
+                // Only items that didn't match before have to be checked
+                FilterChange::LessStrict => {
+                    let mut new = Vec::with_capacity(old.len());
+                    let mut old_iter = old.iter().copied().peekable();
+                    for p in 0..n_items {
+                        if old_iter.next_if_eq(&p).is_some() || self.matches(p) {
+                            new.push(p);
+                        }
+                    }
+                    new
+                }
+                // Only items that matched before have to be checked
+                FilterChange::MoreStrict => {
+                    old.iter().copied().filter(|&p| self.matches(p)).collect()
+                }
+            };
+
+            // Both are sorted, so the items matching before and after can be found by merging
+            let mut old_kept = vec![false; old.len()];
+            let mut new_kept = vec![false; new.len()];
+            let (mut i, mut j) = (0, 0);
+            while i < old.len() && j < new.len() {
+                match old[i].cmp(&new[j]) {
+                    std::cmp::Ordering::Less => i += 1,
+                    std::cmp::Ordering::Greater => j += 1,
+                    std::cmp::Ordering::Equal => {
+                        old_kept[i] = true;
+                        new_kept[j] = true;
+                        i += 1;
+                        j += 1;
+                    }
+                }
+            }
+
+            for (position, removed, added) in changed_ranges(&old_kept, &new_kept) {
+                let (position, removed, added) =
+                    (position as usize, removed as usize, added as usize);
+                self.matches.borrow_mut().splice(
+                    position..position + removed,
+                    new[position..position + added].iter().copied(),
+                );
+                self.obj()
+                    .items_changed(position as u32, removed as u32, added as u32);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// A [`ListModel`] containing only the items of another model that match a filter
+    /// function.
+    ///
+    /// Changes of the underlying model are applied incrementally by only checking the added
+    /// items. If the filter function's behaviour changes, call [`refilter`](Self::refilter).
+    pub struct FilterListModel(ObjectSubclass<imp::FilterListModel>) @implements ListModel;
+}
+
+impl FilterListModel {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model containing the items of `model` for which `filter_func` returns
+    /// `true`.
+    pub fn new<F: Fn(&Object) -> bool + 'static>(
+        model: &impl IsA<ListModel>,
+        filter_func: F,
+    ) -> Self {
+        let obj: Self = Object::new();
+        let imp = obj.imp();
+        let model = model.as_ref();
+
+        imp.model.set(model.clone()).unwrap();
+        obj.set_filter_func(filter_func);
+
+        let handler = model.connect_items_changed(clone!(
+            #[weak]
+            obj,
+            move |_, position, removed, added| {
+                obj.imp().source_items_changed(position, removed, added);
+            }
+        ));
+        *imp.handler.borrow_mut() = Some(handler);
+
+        obj
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The model whose items are filtered.
+    pub fn model(&self) -> &ListModel {
+        self.imp().model.get().unwrap()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Replaces the filter function and filters all items again.
+    pub fn set_filter_func<F: Fn(&Object) -> bool + 'static>(&self, filter_func: F) {
+        *self.imp().filter_func.borrow_mut() = Some(Box::new(filter_func));
+        self.refilter(FilterChange::Different);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Checks the items again after the behaviour of the filter function changed.
+    ///
+    /// One `items-changed` signal is emitted for each run of consecutive items that stopped or
+    /// started matching.
+    pub fn refilter(&self, change: FilterChange) {
+        self.imp().refilter(change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use glib::BoxedAnyObject;
+
+    use super::*;
+    use crate::ListStore;
+
+    fn values(model: &impl IsA<ListModel>) -> Vec<i32> {
+        model
+            .snapshot()
+            .iter()
+            .map(|item| {
+                *item
+                    .downcast_ref::<BoxedAnyObject>()
+                    .unwrap()
+                    .borrow::<i32>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn filter() {
+        let store = (0..10).map(BoxedAnyObject::new).collect::<ListStore>();
+        let threshold = Rc::new(RefCell::new(5));
+        let threshold_clone = threshold.clone();
+        let filter = store.filtered(move |item| {
+            *item
+                .downcast_ref::<BoxedAnyObject>()
+                .unwrap()
+                .borrow::<i32>()
+                < *threshold_clone.borrow()
+        });
+        assert_eq!(values(&filter), [0, 1, 2, 3, 4]);
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        filter.connect_items_changed(move |_, position, removed, added| {
+            changes_clone.borrow_mut().push((position, removed, added));
+        });
+
+        store.splice(2, 6, &[BoxedAnyObject::new(20), BoxedAnyObject::new(3)]);
+        assert_eq!(values(&filter), [0, 1, 3]);
+
+        *threshold.borrow_mut() = 21;
+        filter.refilter(FilterChange::LessStrict);
+        assert_eq!(values(&filter), [0, 1, 20, 3, 8, 9]);
+
+        *threshold.borrow_mut() = 2;
+        filter.refilter(FilterChange::MoreStrict);
+        assert_eq!(values(&filter), [0, 1]);
+
+        assert_eq!(
+            *changes.borrow(),
+            [(2, 3, 1), (2, 0, 1), (4, 0, 2), (2, 4, 0)]
+        );
+    }
+
+    #[test]
+    fn refilter_runs() {
+        let store = (0..10).map(BoxedAnyObject::new).collect::<ListStore>();
+        let modulo = Rc::new(RefCell::new(2));
+        let modulo_clone = modulo.clone();
+        let filter = store.filtered(move |item| {
+            *item
+                .downcast_ref::<BoxedAnyObject>()
+                .unwrap()
+                .borrow::<i32>()
+                % *modulo_clone.borrow()
+                == 0
+        });
+        assert_eq!(values(&filter), [0, 2, 4, 6, 8]);
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        filter.connect_items_changed(move |filter, position, removed, added| {
+            changes_clone
+                .borrow_mut()
+                .push((position, removed, added, values(filter)));
+        });
+
+        *modulo.borrow_mut() = 3;
+        filter.refilter(FilterChange::Different);
+        assert_eq!(values(&filter), [0, 3, 6, 9]);
+        assert_eq!(
+            *changes.borrow(),
+            [(1, 2, 1, vec![0, 3, 6, 8]), (3, 1, 1, vec![0, 3, 6, 9])]
+        );
+    }
+}
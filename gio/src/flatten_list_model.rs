@@ -0,0 +1,312 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use glib::{Object, clone, prelude::*, subclass::prelude::*};
+
+use crate::{ListModel, prelude::*, subclass::prelude::*};
+
+// A model whose items are part of a flattened model.
+struct Child {
+    model: ListModel,
+    n_items: Cell<u32>,
+    handler: RefCell<Option<glib::SignalHandlerId>>,
+}
+
+// The list of child models shared by `FlattenListModel` and `ConcatListModel`.
+#[derive(Default)]
+pub(crate) struct Children {
+    children: Rc<RefCell<Vec<Rc<Child>>>>,
+}
+
+impl Children {
+    pub(crate) fn len(&self) -> u32 {
+        self.children.borrow().len() as u32
+    }
+
+    pub(crate) fn n_items(&self) -> u32 {
+        self.children
+            .borrow()
+            .iter()
+            .map(|child| child.n_items.get())
+            .sum()
+    }
+
+    // Returns the child model containing `position`, and the position inside of it.
+    pub(crate) fn model_for_item(&self, mut position: u32) -> Option<(ListModel, u32)> {
+        let children = self.children.borrow();
+        let child = children.iter().find(|child| {
+            if position < child.n_items.get() {
+                true
+            } else {
+                position -= child.n_items.get();
+                false
+            }
+        })?;
+        Some((child.model.clone(), position))
+    }
+
+    pub(crate) fn item(&self, position: u32) -> Option<Object> {
+        let (model, position) = self.model_for_item(position)?;
+        model.item(position)
+    }
+
+    // Replaces `removed` children at `index` with `additions`, and emits `items-changed` on
+    // `obj` for the affected items.
+    pub(crate) fn splice(
+        &self,
+        obj: &ListModel,
+        index: u32,
+        removed: u32,
+        additions: impl IntoIterator<Item = ListModel>,
+    ) {
+        let additions = additions
+            .into_iter()
+            .map(|model| {
+                let child = Rc::new(Child {
+                    n_items: Cell::new(model.n_items()),
+                    model,
+                    handler: RefCell::default(),
+                });
+                let weak_child = Rc::downgrade(&child);
+                let weak_children = Rc::downgrade(&self.children);
+                let handler = child.model.connect_items_changed(clone!(
+                    #[weak]
+                    obj,
+                    move |_, position, removed, added| {
+                        let (Some(child), Some(children)) =
+                            (weak_child.upgrade(), weak_children.upgrade())
+                        else {
+                            return;
+                        };
+                        child.n_items.set(child.n_items.get() - removed + added);
+                        let offset = children
+                            .borrow()
+                            .iter()
+                            .take_while(|other| !Rc::ptr_eq(other, &child))
+                            .map(|other| other.n_items.get())
+                            .sum::<u32>();
+                        obj.items_changed(offset + position, removed, added);
+                    }
+                ));
+                *child.handler.borrow_mut() = Some(handler);
+                child
+            })
+            .collect::<Vec<_>>();
+        let n_added = additions.iter().map(|child| child.n_items.get()).sum();
+
+        let (offset, n_removed) = {
+            let mut children = self.children.borrow_mut();
+            let index = index as usize;
+            let offset = children[..index]
+                .iter()
+                .map(|child| child.n_items.get())
+                .sum();
+            let removed_children = children
+                .splice(index..index + removed as usize, additions)
+                .collect::<Vec<_>>();
+            let mut n_removed = 0;
+            for child in removed_children {
+                n_removed += child.n_items.get();
+                child.disconnect();
+            }
+            (offset, n_removed)
+        };
+
+        if n_removed > 0 || n_added > 0 {
+            obj.items_changed(offset, n_removed, n_added);
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        for child in self.children.take() {
+            child.disconnect();
+        }
+    }
+}
+
+impl Child {
+    fn disconnect(&self) {
+        if let Some(handler) = self.handler.take() {
+            self.model.disconnect(handler);
+        }
+    }
+}
+
+mod imp {
+    use std::cell::OnceCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct FlattenListModel {
+        pub(super) model: OnceCell<ListModel>,
+        pub(super) item_type: OnceCell<glib::Type>,
+        pub(super) children: Children,
+        pub(super) handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FlattenListModel {
+        const NAME: &'static str = "FlattenListModel";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::FlattenListModel;
+        type Interfaces = (ListModel,);
+    }
+
+    impl ObjectImpl for FlattenListModel {
+        fn dispose(&self) {
+            if let (Some(model), Some(handler)) = (self.model.get(), self.handler.take()) {
+                model.disconnect(handler);
+            }
+            self.children.clear();
+        }
+    }
+
+    impl ListModelImpl for FlattenListModel {
+        fn item_type(&self) -> glib::Type {
+            *self.item_type.get().unwrap()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.children.n_items()
+        }
+
+        fn item(&self, position: u32) -> Option<Object> {
+            self.children.item(position)
+        }
+    }
+
+    impl FlattenListModel {
+        pub(super) fn source_items_changed(&self, position: u32, removed: u32, added: u32) {
+            let model = self.model.get().unwrap();
+            let additions = (position..position + added).map(|p| {
+                model
+                    .item(p)
+                    .and_downcast::<ListModel>()
+                    .expect("items of a flattened model must be list models")
+            });
+            self.children
+                .splice(self.obj().upcast_ref(), position, removed, additions);
+        }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// A [`ListModel`] containing the items of all [`ListModel`]s contained in another model,
+    /// in order.
+    pub struct FlattenListModel(ObjectSubclass<imp::FlattenListModel>) @implements ListModel;
+}
+
+impl FlattenListModel {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model flattening the models contained in `model`, whose items are of
+    /// type `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an item of `model` is not a [`ListModel`].
+    pub fn new<T: IsA<Object>>(model: &impl IsA<ListModel>) -> Self {
+        Self::with_type(T::static_type(), model)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model flattening the models contained in `model`, whose items are of
+    /// type `item_type`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an item of `model` is not a [`ListModel`].
+    pub fn with_type(item_type: glib::Type, model: &impl IsA<ListModel>) -> Self {
+        let obj: Self = Object::new();
+        let imp = obj.imp();
+        let model = model.as_ref();
+
+        imp.model.set(model.clone()).unwrap();
+        imp.item_type.set(item_type).unwrap();
+        imp.source_items_changed(0, 0, model.n_items());
+
+        let handler = model.connect_items_changed(clone!(
+            #[weak]
+            obj,
+            move |_, position, removed, added| {
+                obj.imp().source_items_changed(position, removed, added);
+            }
+        ));
+        *imp.handler.borrow_mut() = Some(handler);
+
+        obj
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The model containing the flattened models.
+    pub fn model(&self) -> &ListModel {
+        self.imp().model.get().unwrap()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The flattened model containing the item at `position`, and the position of the item
+    /// inside of it.
+    pub fn model_for_item(&self, position: u32) -> Option<(ListModel, u32)> {
+        self.imp().children.model_for_item(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConcatListModel, ListStore, Menu};
+
+    #[test]
+    fn flatten() {
+        let items = (0..4).map(|_| Menu::new()).collect::<Vec<_>>();
+        let a = ListStore::from_iter([items[0].clone(), items[1].clone()]);
+        let b = ListStore::new::<Menu>();
+        let models = ListStore::from_iter([a.clone(), b.clone()]);
+
+        let flatten = models.flattened::<Menu>();
+        assert_eq!(flatten.n_items(), 2);
+
+        b.append(&items[2]);
+        assert_eq!(flatten.item(2).as_ref(), Some(items[2].upcast_ref()));
+        assert_eq!(
+            flatten.model_for_item(2),
+            Some((b.clone().upcast::<ListModel>(), 0))
+        );
+
+        a.remove(0);
+        models.insert(1, &ListStore::from_iter([items[3].clone()]));
+        assert_eq!(
+            flatten.snapshot(),
+            [items[1].clone(), items[3].clone(), items[2].clone()]
+        );
+
+        models.remove(0);
+        assert_eq!(flatten.snapshot(), [items[3].clone(), items[2].clone()]);
+    }
+
+    #[test]
+    fn concat() {
+        let items = (0..3).map(|_| Menu::new()).collect::<Vec<_>>();
+        let a = ListStore::from_iter([items[0].clone()]);
+        let b = ListStore::from_iter([items[1].clone()]);
+        let concat = a.concat(&b);
+        assert_eq!(concat.snapshot(), [items[0].clone(), items[1].clone()]);
+
+        a.append(&items[2]);
+        assert_eq!(
+            concat.snapshot(),
+            [items[0].clone(), items[2].clone(), items[1].clone()]
+        );
+
+        concat.remove(0);
+        assert_eq!(concat.snapshot(), [items[1].clone()]);
+
+        let empty = ConcatListModel::new::<Menu>(std::iter::empty::<ListStore>());
+        assert_eq!(empty.n_items(), 0);
+    }
+}
@@ -10,8 +10,13 @@
 pub use gio_sys as ffi;
 pub use glib;
 
+// for macros
+extern crate self as gio;
+
 mod action_entry;
 mod action_map;
+mod actions;
+pub use crate::actions::{ActionDescriptor, Actions};
 #[cfg(feature = "v2_60")]
 mod app_info;
 mod application;
@@ -23,6 +28,10 @@ mod cancellable;
 pub use cancellable::CancelledHandlerId;
 mod cancellable_future;
 pub use crate::cancellable_future::{CancellableFuture, Cancelled};
+mod command;
+pub use crate::command::{Child, Command, ExitStatus, Lines, Stdio};
+mod concat_list_model;
+pub use crate::concat_list_model::ConcatListModel;
 mod content_type;
 mod converter;
 mod credentials;
@@ -41,6 +50,18 @@ mod dbus_interface_info;
 mod dbus_message;
 mod dbus_method_invocation;
 mod dbus_node_info;
+mod dbus_object_bridge;
+pub use self::dbus_object_bridge::{
+    DBusExportedProperties, DBusObjectBridge, DBusObjectBridgeBuilder,
+};
+mod dbus_object_tree;
+pub use self::dbus_object_tree::{DBusExportedInterface, DBusInterfaceHandle, DBusObjectTree};
+mod dbus_typed_call;
+#[cfg(unix)]
+pub use self::dbus_typed_call::FdHandle;
+pub use self::dbus_typed_call::{
+    DBusMethodArgs, DBusMethodReply, FromVariantWithFdList, ToVariantWithFdList,
+};
 #[cfg(feature = "v2_72")]
 #[cfg_attr(docsrs, doc(cfg(feature = "v2_72")))]
 mod debug_controller_dbus;
@@ -56,7 +77,13 @@ pub use file_attribute_value::FileAttributeValue;
 mod file_enumerator;
 pub use crate::file_enumerator::FileEnumeratorStream;
 mod file_info;
+mod file_recursive;
+pub use crate::file_recursive::{FileConflictResolution, FileProgress};
+mod filter_list_model;
+pub use crate::filter_list_model::{FilterChange, FilterListModel};
 mod flags;
+mod flatten_list_model;
+pub use crate::flatten_list_model::FlattenListModel;
 mod inet_address;
 pub use crate::inet_address::InetAddressBytes;
 mod inet_socket_address;
@@ -67,21 +94,29 @@ mod input_stream;
 pub use crate::input_stream::{InputStreamAsyncBufRead, InputStreamRead};
 mod list_model;
 mod list_store;
+mod map_list_model;
+pub use crate::map_list_model::MapListModel;
 #[cfg(test)]
 mod memory_input_stream;
 #[cfg(test)]
 mod memory_output_stream;
+mod menu_tree;
+pub use crate::menu_tree::{MenuTree, MenuTreeChange, MenuTreeItem};
 mod output_stream;
 pub use crate::output_stream::OutputStreamWrite;
 mod pollable_input_stream;
 pub use crate::pollable_input_stream::InputStreamAsyncRead;
 mod pollable_output_stream;
 pub use crate::pollable_output_stream::OutputStreamAsyncWrite;
+mod pollable_readiness;
+pub use crate::pollable_readiness::PollableReadiness;
 mod resource;
 pub use crate::resource::resources_register_include_impl;
 mod settings;
 pub use crate::settings::BindingBuilder;
 mod simple_proxy_resolver;
+mod slice_list_model;
+pub use crate::slice_list_model::SliceListModel;
 mod socket;
 pub use socket::{InputMessage, InputVector, OutputMessage, OutputVector, SocketControlMessages};
 mod dbus_object_manager_client;
@@ -89,16 +124,25 @@ mod socket_control_message;
 mod socket_listener;
 mod socket_msg_flags;
 pub use socket_msg_flags::*;
+mod socket_server;
+pub use crate::socket_server::{ServerConnection, SocketServer, SocketServerBuilder};
+mod sort_list_model;
+pub use crate::sort_list_model::SortListModel;
 mod subprocess;
 mod subprocess_launcher;
 mod threaded_socket_service;
+mod typed_list_model;
+pub use crate::typed_list_model::{ItemsChangedStream, TypedListModel};
+mod typed_list_store;
+pub use crate::typed_list_store::TypedListStore;
 #[cfg(unix)]
 mod unix_fd_list;
 #[cfg(unix)]
 mod unix_socket_address;
 
-#[cfg(test)]
-mod test_util;
+#[cfg(any(test, feature = "test-util"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+pub mod test_util;
 
 pub mod builders {
     pub use super::async_initable::AsyncInitableBuilder;
@@ -141,6 +185,10 @@ pub use crate::task::*;
 
 #[macro_use]
 pub mod subclass;
+mod async_read_input_stream;
+pub use crate::async_read_input_stream::AsyncReadInputStream;
+mod async_write_output_stream;
+pub use crate::async_write_output_stream::AsyncWriteOutputStream;
 mod read_input_stream;
 pub use crate::read_input_stream::ReadInputStream;
 mod write_output_stream;
@@ -1,10 +1,13 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
-use std::{cell::Cell, fmt, iter::FusedIterator, marker::PhantomData, rc::Rc};
+use std::{cell::Cell, cmp::Ordering, fmt, iter::FusedIterator, marker::PhantomData, rc::Rc};
 
 use glib::SignalHandlerId;
 
-use crate::{ListModel, prelude::*};
+use crate::{
+    ConcatListModel, FilterListModel, FlattenListModel, ListModel, MapListModel, SliceListModel,
+    SortListModel, prelude::*,
+};
 
 pub trait ListModelExtManual: IsA<ListModel> + Sized {
     // rustdoc-stripper-ignore-next
@@ -48,6 +51,54 @@ pub trait ListModelExtManual: IsA<ListModel> + Sized {
             signal_id,
         }
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`MapListModel`] mapping the items of this model with `map_func`.
+    fn mapped<T: IsA<glib::Object>, F: Fn(&glib::Object) -> T + 'static>(
+        &self,
+        map_func: F,
+    ) -> MapListModel {
+        MapListModel::new(self, map_func)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`FilterListModel`] containing the items of this model for which
+    /// `filter_func` returns `true`.
+    fn filtered<F: Fn(&glib::Object) -> bool + 'static>(&self, filter_func: F) -> FilterListModel {
+        FilterListModel::new(self, filter_func)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`SortListModel`] containing the items of this model sorted by
+    /// `compare_func`.
+    fn sorted<F: Fn(&glib::Object, &glib::Object) -> Ordering + 'static>(
+        &self,
+        compare_func: F,
+    ) -> SortListModel {
+        SortListModel::new(self, compare_func)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`FlattenListModel`] containing the items of all models contained in this
+    /// model, which are of type `T`.
+    fn flattened<T: IsA<glib::Object>>(&self) -> FlattenListModel {
+        FlattenListModel::new::<T>(self)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`SliceListModel`] containing at most `size` items of this model, starting at
+    /// `offset`.
+    fn sliced(&self, offset: u32, size: u32) -> SliceListModel {
+        SliceListModel::new(self, offset, size)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`ConcatListModel`] containing the items of this model followed by the items
+    /// of `other`.
+    fn concat(&self, other: &impl IsA<ListModel>) -> ConcatListModel {
+        let models: [ListModel; 2] = [self.clone().upcast(), other.clone().upcast()];
+        ConcatListModel::with_type(self.item_type(), models)
+    }
 }
 
 impl<T: IsA<ListModel>> ListModelExtManual for T {}
@@ -193,6 +244,33 @@ impl<'a> std::iter::IntoIterator for &'a ListModel {
     }
 }
 
+// Returns the `items-changed` ranges that turn a list into another, in the order they have to
+// be applied. `old_kept` and `new_kept` mark the items that are in both lists, which have to be
+// in the same order in both.
+//
+// Each range's position is relative to the list with all previous ranges applied, so the
+// added items of a range starting at `position` are `new[position..position + added]`.
+pub(crate) fn changed_ranges(old_kept: &[bool], new_kept: &[bool]) -> Vec<(u32, u32, u32)> {
+    let mut ranges = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    loop {
+        let removed = old_kept[i..].iter().take_while(|&&kept| !kept).count();
+        let added = new_kept[j..].iter().take_while(|&&kept| !kept).count();
+        if removed > 0 || added > 0 {
+            ranges.push((j as u32, removed as u32, added as u32));
+        }
+        i += removed;
+        j += added;
+
+        if i == old_kept.len() || j == new_kept.len() {
+            debug_assert!(i == old_kept.len() && j == new_kept.len());
+            return ranges;
+        }
+        i += 1;
+        j += 1;
+    }
+}
+
 #[test]
 fn list_model_iter_ok() {
     let list = crate::ListStore::new::<crate::Menu>();
@@ -200,6 +200,229 @@ impl ListStore {
 
         result
     }
+
+    // rustdoc-stripper-ignore-next
+    /// Updates the store to contain `items`, with as few changes as possible.
+    ///
+    /// Items of the store and of `items` with the same key returned by `key_fn` are considered
+    /// to be the same item. A minimal edit script is computed from the keys with Myers' diff
+    /// algorithm and applied with one [`splice`](Self::splice) call per contiguous changed
+    /// range, so that items that didn't change are kept in the store and unaffected ranges
+    /// don't emit `items-changed`. If the keys differ too much to find the edit script
+    /// quickly, parts of it may be replaced as a whole instead.
+    ///
+    /// Kept items are replaced by their counterpart in `items` if `eq_fn` returns `false` for
+    /// them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the item type of the store is not `T` or a subtype of `T`.
+    pub fn update_from<T: IsA<glib::Object>, K: PartialEq>(
+        &self,
+        items: &[T],
+        key_fn: impl Fn(&T) -> K,
+        eq_fn: impl Fn(&T, &T) -> bool,
+    ) {
+        assert!(self.item_type().is_a(T::static_type()));
+
+        let old = self
+            .snapshot()
+            .into_iter()
+            .map(|item| item.downcast::<T>().unwrap())
+            .collect::<Vec<_>>();
+        let old_keys = old.iter().map(&key_fn).collect::<Vec<_>>();
+        let new_keys = items.iter().map(&key_fn).collect::<Vec<_>>();
+
+        let prefix = old_keys
+            .iter()
+            .zip(&new_keys)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old_keys[prefix..]
+            .iter()
+            .rev()
+            .zip(new_keys[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old_end = old_keys.len() - suffix;
+        let new_end = new_keys.len() - suffix;
+
+        let matches = (0..prefix)
+            .map(|i| (i, i))
+            .chain(
+                myers_matches(&old_keys[prefix..old_end], &new_keys[prefix..new_end])
+                    .into_iter()
+                    .map(|(i, j)| (prefix + i, prefix + j)),
+            )
+            .chain((0..suffix).map(|i| (old_end + i, new_end + i)))
+            // The end of both lists, to apply the last changed range
+            .chain(std::iter::once((old.len(), items.len())));
+
+        let mut delta = 0i64;
+        let mut changed_start = None;
+        let (mut next_old, mut next_new) = (0, 0);
+        for (i, j) in matches {
+            if (i > next_old || j > next_new) && changed_start.is_none() {
+                changed_start = Some((next_old, next_new));
+            }
+            next_old = i + 1;
+            next_new = j + 1;
+
+            if i < old.len() && !eq_fn(&old[i], &items[j]) {
+                changed_start.get_or_insert((i, j));
+                continue;
+            }
+
+            if let Some((old_start, new_start)) = changed_start.take() {
+                let removed = i - old_start;
+                let added = &items[new_start..j];
+                self.splice((old_start as i64 + delta) as u32, removed as u32, added);
+                delta += added.len() as i64 - removed as i64;
+            }
+        }
+    }
+}
+
+// Maximum number of edits searched from each end when splitting the lists. Lists that differ by
+// more are considered to have nothing in common, which bounds the time spent on them.
+const MAX_MIDDLE_SNAKE_EDITS: usize = 1024;
+
+// Returns the pairs of positions of the items shared by `a` and `b` according to a shortest
+// edit script, see "An O(ND) Difference Algorithm and Its Variations" by Eugene W. Myers.
+//
+// This is the linear space variant, which recursively splits both lists at the middle snake of
+// the edit script.
+pub(crate) fn myers_matches<K: PartialEq>(a: &[K], b: &[K]) -> Vec<(usize, usize)> {
+    let limit = ((a.len() + b.len()).div_ceil(2) + 1).min(MAX_MIDDLE_SNAKE_EDITS) as isize;
+    let mut forward = Diagonals::new(limit);
+    let mut backward = Diagonals::new(limit);
+    let mut matches = Vec::new();
+    myers_split(a, b, (0, 0), &mut forward, &mut backward, &mut matches);
+    matches
+}
+
+// The furthest reaching `x` per diagonal `k = x - y`, for `k` in `-limit..=limit`.
+struct Diagonals {
+    v: Vec<isize>,
+    limit: isize,
+}
+
+impl Diagonals {
+    fn new(limit: isize) -> Self {
+        Self {
+            v: vec![0; 2 * limit as usize + 1],
+            limit,
+        }
+    }
+}
+
+impl std::ops::Index<isize> for Diagonals {
+    type Output = isize;
+
+    fn index(&self, k: isize) -> &isize {
+        &self.v[(k + self.limit) as usize]
+    }
+}
+
+impl std::ops::IndexMut<isize> for Diagonals {
+    fn index_mut(&mut self, k: isize) -> &mut isize {
+        &mut self.v[(k + self.limit) as usize]
+    }
+}
+
+fn myers_split<K: PartialEq>(
+    a: &[K],
+    b: &[K],
+    start: (usize, usize),
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    matches.extend((0..prefix).map(|i| (start.0 + i, start.1 + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let start = (start.0 + prefix, start.1 + prefix);
+
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    if !a.is_empty()
+        && !b.is_empty()
+        && let Some((x, y)) = middle_snake(a, b, forward, backward)
+    {
+        myers_split(&a[..x], &b[..y], start, forward, backward, matches);
+        myers_split(
+            &a[x..],
+            &b[y..],
+            (start.0 + x, start.1 + y),
+            forward,
+            backward,
+            matches,
+        );
+    }
+
+    matches.extend((0..suffix).map(|i| (start.0 + a.len() + i, start.1 + b.len() + i)));
+}
+
+// Searches a shortest edit script from both ends at once, and returns the start of the snake
+// where both searches meet. Returns `None` if that takes more than the diagonals' limit.
+fn middle_snake<K: PartialEq>(
+    a: &[K],
+    b: &[K],
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta & 1 == 1;
+    let max = ((n + m + 1) / 2 + 1).min(forward.limit);
+
+    forward[1] = 0;
+    backward[1] = 0;
+    for d in 0..max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[k - 1] < forward[k + 1]) {
+                forward[k + 1]
+            } else {
+                forward[k - 1] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            let mut y = y0;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[k] = x;
+            if odd && (k - delta).abs() < d && forward[k] + backward[-(k - delta)] >= n {
+                return Some((x0 as usize, y0 as usize));
+            }
+        }
+
+        // Here `x` and `y` count from the ends of `a` and `b`
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[k - 1] < backward[k + 1]) {
+                backward[k + 1]
+            } else {
+                backward[k - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[k] = x;
+            if !odd && (k - delta).abs() <= d && backward[k] + forward[-(k - delta)] >= n {
+                return Some(((n - x) as usize, (m - y) as usize));
+            }
+        }
+    }
+
+    None
 }
 
 impl<P: IsA<glib::Object>> std::iter::FromIterator<P> for ListStore {
@@ -342,4 +565,102 @@ mod tests {
 
         assert_eq!(signal_count.get(), 4);
     }
+
+    #[test]
+    fn update_from() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use glib::BoxedAnyObject;
+
+        let item = |key: i32, value: i32| BoxedAnyObject::new((key, value));
+        let key = |item: &BoxedAnyObject| item.borrow::<(i32, i32)>().0;
+        let eq = |a: &BoxedAnyObject, b: &BoxedAnyObject| {
+            *a.borrow::<(i32, i32)>() == *b.borrow::<(i32, i32)>()
+        };
+        let values = |list: &ListStore| {
+            list.iter::<BoxedAnyObject>()
+                .map(|item| *item.unwrap().borrow::<(i32, i32)>())
+                .collect::<Vec<_>>()
+        };
+
+        let list = ListStore::from_iter((0..6).map(|i| item(i, 0)));
+        let kept = list.item(1).unwrap();
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        list.connect_items_changed(move |_, position, removed, added| {
+            changes_clone.borrow_mut().push((position, removed, added));
+        });
+
+        list.update_from(
+            &[
+                item(0, 0),
+                item(1, 0),
+                item(7, 0),
+                item(3, 1),
+                item(4, 0),
+                item(6, 0),
+            ],
+            key,
+            eq,
+        );
+        assert_eq!(
+            values(&list),
+            [(0, 0), (1, 0), (7, 0), (3, 1), (4, 0), (6, 0)]
+        );
+        assert_eq!(list.item(1), Some(kept));
+        assert_eq!(*changes.borrow(), [(2, 2, 2), (5, 1, 1)]);
+
+        changes.borrow_mut().clear();
+        list.update_from(&[item(6, 0), item(0, 0)], key, eq);
+        assert_eq!(values(&list), [(6, 0), (0, 0)]);
+
+        changes.borrow_mut().clear();
+        list.update_from(&[item(6, 0), item(0, 0)], key, eq);
+        assert!(changes.borrow().is_empty());
+    }
+
+    #[test]
+    fn update_from_large() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use glib::BoxedAnyObject;
+
+        let key = |item: &BoxedAnyObject| *item.borrow::<i32>();
+        let eq = |a: &BoxedAnyObject, b: &BoxedAnyObject| key(a) == key(b);
+
+        // Disjoint lists are replaced with a single splice without searching all edit scripts
+        let list = ListStore::from_iter((0..10_000).map(BoxedAnyObject::new));
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        list.connect_items_changed(move |_, position, removed, added| {
+            changes_clone.borrow_mut().push((position, removed, added));
+        });
+        let items = (10_000..25_000)
+            .map(BoxedAnyObject::new)
+            .collect::<Vec<_>>();
+        list.update_from(&items, key, eq);
+        assert_eq!(list.n_items(), 15_000);
+        assert_eq!(*changes.borrow(), [(0, 10_000, 15_000)]);
+
+        let a = (0..200_000).collect::<Vec<_>>();
+        let b = (200_000..300_000).collect::<Vec<_>>();
+        assert!(super::myers_matches(&a, &b).is_empty());
+
+        // Every third item removed and a new one added after every fifth
+        let a = (0..3_000).collect::<Vec<_>>();
+        let b = a
+            .iter()
+            .filter(|&&i| i % 3 != 0)
+            .flat_map(|&i| if i % 5 == 0 { vec![i, -i] } else { vec![i] })
+            .collect::<Vec<_>>();
+        let matches = super::myers_matches(&a, &b);
+        assert_eq!(matches.len(), 2_000);
+        assert!(matches.iter().all(|&(i, j)| a[i] == b[j]));
+        assert!(
+            matches
+                .windows(2)
+                .all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1)
+        );
+    }
 }
@@ -0,0 +1,149 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use glib::{Object, clone, prelude::*, subclass::prelude::*};
+
+use crate::{ListModel, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::{OnceCell, RefCell};
+
+    use super::*;
+
+    pub(super) type MapFunc = Box<dyn Fn(&Object) -> Object>;
+
+    #[derive(Default)]
+    pub struct MapListModel {
+        pub(super) model: OnceCell<ListModel>,
+        pub(super) item_type: OnceCell<glib::Type>,
+        pub(super) map_func: OnceCell<MapFunc>,
+        // Mapped items, created lazily on first access.
+        pub(super) items: RefCell<Vec<Option<Object>>>,
+        pub(super) handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MapListModel {
+        const NAME: &'static str = "MapListModel";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::MapListModel;
+        type Interfaces = (ListModel,);
+    }
+
+    impl ObjectImpl for MapListModel {
+        fn dispose(&self) {
+            if let (Some(model), Some(handler)) = (self.model.get(), self.handler.take()) {
+                model.disconnect(handler);
+            }
+        }
+    }
+
+    impl ListModelImpl for MapListModel {
+        fn item_type(&self) -> glib::Type {
+            *self.item_type.get().unwrap()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.items.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<Object> {
+            if let Some(item) = self.items.borrow().get(position as usize)?.clone() {
+                return Some(item);
+            }
+
+            let source = self.model.get().unwrap().item(position)?;
+            let item = (self.map_func.get().unwrap())(&source);
+            assert!(
+                item.type_().is_a(self.item_type()),
+                "mapped item of type {} is not a {}",
+                item.type_(),
+                self.item_type()
+            );
+            self.items.borrow_mut()[position as usize] = Some(item.clone());
+            Some(item)
+        }
+    }
+
+    impl MapListModel {
+        pub(super) fn source_items_changed(&self, position: u32, removed: u32, added: u32) {
+            {
+                let mut items = self.items.borrow_mut();
+                let start = position as usize;
+                items.splice(
+                    start..start + removed as usize,
+                    std::iter::repeat_n(None, added as usize),
+                );
+            }
+            self.obj().items_changed(position, removed, added);
+        }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// A [`ListModel`] that maps each item of another model with a function.
+    ///
+    /// Mapped items are created on first access and kept until the corresponding item is
+    /// removed from the underlying model.
+    pub struct MapListModel(ObjectSubclass<imp::MapListModel>) @implements ListModel;
+}
+
+impl MapListModel {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model mapping the items of `model` with `map_func`.
+    pub fn new<T: IsA<Object>, F: Fn(&Object) -> T + 'static>(
+        model: &impl IsA<ListModel>,
+        map_func: F,
+    ) -> Self {
+        let obj: Self = Object::new();
+        let imp = obj.imp();
+        let model = model.as_ref();
+
+        imp.model.set(model.clone()).unwrap();
+        imp.item_type.set(T::static_type()).unwrap();
+        let _ = imp
+            .map_func
+            .set(Box::new(move |item| map_func(item).upcast()));
+        *imp.items.borrow_mut() = vec![None; model.n_items() as usize];
+
+        let handler = model.connect_items_changed(clone!(
+            #[weak]
+            obj,
+            move |_, position, removed, added| {
+                obj.imp().source_items_changed(position, removed, added);
+            }
+        ));
+        *imp.handler.borrow_mut() = Some(handler);
+
+        obj
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The model whose items are mapped.
+    pub fn model(&self) -> &ListModel {
+        self.imp().model.get().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ListStore, Menu, MenuItem};
+
+    #[test]
+    fn map() {
+        let store = ListStore::new::<Menu>();
+        let map =
+            store.mapped(|item| MenuItem::new_section(None, item.downcast_ref::<Menu>().unwrap()));
+        assert_eq!(map.item_type(), MenuItem::static_type());
+
+        store.extend_from_slice(&[Menu::new(), Menu::new()]);
+        assert_eq!(map.n_items(), 2);
+        let first = map.item(0).unwrap();
+        assert_eq!(map.item(0), Some(first.clone()));
+
+        store.remove(0);
+        assert_eq!(map.n_items(), 1);
+        assert_ne!(map.item(0), Some(first));
+    }
+}
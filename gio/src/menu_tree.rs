@@ -0,0 +1,1082 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    collections::{BTreeMap, btree_map},
+    ffi::{CStr, c_char},
+    fmt::Write,
+    ptr,
+};
+
+use glib::{MarkupError, Variant, VariantTy, VariantType, prelude::*, translate::*};
+
+use crate::{
+    Action, MENU_ATTRIBUTE_ACTION, MENU_ATTRIBUTE_LABEL, MENU_ATTRIBUTE_TARGET, MENU_LINK_SECTION,
+    MENU_LINK_SUBMENU, Menu, MenuItem, MenuModel, prelude::*,
+};
+
+// rustdoc-stripper-ignore-next
+/// A menu as a plain Rust tree of items, their attributes and links.
+///
+/// A `MenuTree` can be built with [`menu!`](crate::menu!) or the builder-style methods,
+/// created from any [`MenuModel`] with [`from_model`](Self::from_model) and turned into a
+/// [`Menu`] with [`to_menu`](Self::to_menu), e.g. to export it with
+/// [`DBusConnection::export_menu_model`](crate::DBusConnection::export_menu_model). It can also
+/// be converted to and from the GtkBuilder XML menu format.
+///
+/// ```
+/// let tree = gio::menu! {
+///     item("_New Window", "app.new-window"),
+///     section {
+///         item("Zoom _In", "win.zoom", target = 1.25),
+///         item("Zoom _Out", "win.zoom", target = 0.8),
+///     },
+///     submenu("_Help") {
+///         item("_About", "app.about", "hidden-when" = "action-disabled"),
+///     },
+/// };
+///
+/// let menu = tree.to_menu();
+/// assert_eq!(gio::MenuTree::from_model(&menu), tree);
+///
+/// let xml = tree.to_xml("app-menu");
+/// assert_eq!(
+///     gio::MenuTree::from_xml(&xml).unwrap(),
+///     [("app-menu".to_string(), tree)]
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MenuTree {
+    items: Vec<MenuTreeItem>,
+}
+
+// rustdoc-stripper-ignore-next
+/// A change of one of the menus of a [`MenuTree`], as returned by [`MenuTree::diff`].
+///
+/// Like the `items-changed` signal of [`MenuModel`], `removed` items at `position` are replaced
+/// with the `added` items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuTreeChange {
+    // rustdoc-stripper-ignore-next
+    /// The menu that changed, as the links to follow from the root menu. Each link is given by
+    /// the position of the item and the name of the link.
+    pub path: Vec<(u32, String)>,
+    pub position: u32,
+    pub removed: u32,
+    pub added: Vec<MenuTreeItem>,
+}
+
+// rustdoc-stripper-ignore-next
+/// An item of a [`MenuTree`], with its attributes and links to other menus.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MenuTreeItem {
+    attributes: BTreeMap<String, Variant>,
+    links: BTreeMap<String, MenuTree>,
+}
+
+impl MenuTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn items(&self) -> &[MenuTreeItem] {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut Vec<MenuTreeItem> {
+        &mut self.items
+    }
+
+    pub fn push(&mut self, item: MenuTreeItem) {
+        self.items.push(item);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Appends `item`.
+    pub fn item(mut self, item: MenuTreeItem) -> Self {
+        self.push(item);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Appends an item linking to `section`.
+    pub fn section(self, label: Option<&str>, section: MenuTree) -> Self {
+        self.item(MenuTreeItem::new_section(label, section))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Appends an item linking to `submenu`.
+    pub fn submenu(self, label: Option<&str>, submenu: MenuTree) -> Self {
+        self.item(MenuTreeItem::new_submenu(label, submenu))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a tree from the current contents of `model` and all menus it links to.
+    ///
+    /// Remote models such as [`DBusMenuModel`](crate::DBusMenuModel) only contain items once
+    /// they have been loaded.
+    pub fn from_model(model: &impl IsA<MenuModel>) -> Self {
+        let model = model.as_ref();
+        let items = (0..model.n_items())
+            .map(|index| {
+                let mut item = MenuTreeItem::default();
+                let attributes = model.iterate_item_attributes(index);
+                while let Some((name, value)) = attributes.next() {
+                    item.attributes.insert(name.into(), value);
+                }
+                let links = model.iterate_item_links(index);
+                while let Some((name, link)) = links.next() {
+                    item.links.insert(name.into(), Self::from_model(&link));
+                }
+                item
+            })
+            .collect();
+        Self { items }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new [`Menu`] with the contents of this tree.
+    pub fn to_menu(&self) -> Menu {
+        let menu = Menu::new();
+        for item in &self.items {
+            menu.append_item(&item.to_menu_item());
+        }
+        menu
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Computes the changes that turn this tree into `new`.
+    ///
+    /// Items with the same attributes and link names are kept, and the menus they link to are
+    /// compared recursively. All other items are removed or added, with as few changes as
+    /// possible. The changes have to be applied in order, as each change's `path` and
+    /// `position` refer to the tree with all previous changes applied.
+    ///
+    /// ```
+    /// let old = gio::menu! {
+    ///     item("_Open", "app.open"),
+    ///     section { item("_Quit", "app.quit") },
+    /// };
+    /// let new = gio::menu! {
+    ///     item("_Open", "app.open"),
+    ///     section { item("_Close", "win.close"), item("_Quit", "app.quit") },
+    /// };
+    ///
+    /// let changes = old.diff(&new);
+    /// assert_eq!(changes.len(), 1);
+    /// assert_eq!(changes[0].path, [(1, "section".to_string())]);
+    /// assert_eq!((changes[0].position, changes[0].removed), (0, 0));
+    ///
+    /// let menu = old.to_menu();
+    /// gio::MenuTree::patch_menu(&menu, &changes);
+    /// assert_eq!(gio::MenuTree::from_model(&menu), new);
+    /// ```
+    pub fn diff(&self, new: &MenuTree) -> Vec<MenuTreeChange> {
+        let mut changes = Vec::new();
+        self.diff_into(new, &mut Vec::new(), &mut changes);
+        changes
+    }
+
+    fn diff_into(
+        &self,
+        new: &MenuTree,
+        path: &mut Vec<(u32, String)>,
+        changes: &mut Vec<MenuTreeChange>,
+    ) {
+        let key = |item: &MenuTreeItem| {
+            (
+                item.attributes.clone(),
+                item.links.keys().cloned().collect::<Vec<_>>(),
+            )
+        };
+        let old_keys = self.items.iter().map(key).collect::<Vec<_>>();
+        let new_keys = new.items.iter().map(key).collect::<Vec<_>>();
+
+        let matches = crate::list_store::myers_matches(&old_keys, &new_keys)
+            .into_iter()
+            // The end of both menus, to add the last changed range
+            .chain(std::iter::once((self.items.len(), new.items.len())));
+
+        let (mut next_old, mut next_new) = (0, 0);
+        for (i, j) in matches {
+            // Everything before `next_new` was already changed to match `new`
+            if i > next_old || j > next_new {
+                changes.push(MenuTreeChange {
+                    path: path.clone(),
+                    position: next_new as u32,
+                    removed: (i - next_old) as u32,
+                    added: new.items[next_new..j].to_vec(),
+                });
+            }
+            if let (Some(old_item), Some(new_item)) = (self.items.get(i), new.items.get(j)) {
+                for ((name, old_link), new_link) in
+                    old_item.links.iter().zip(new_item.links.values())
+                {
+                    path.push((j as u32, name.clone()));
+                    old_link.diff_into(new_link, path, changes);
+                    path.pop();
+                }
+            }
+            next_old = i + 1;
+            next_new = j + 1;
+        }
+    }
+
+    fn menu_at_mut(&mut self, path: &[(u32, String)]) -> Option<&mut MenuTree> {
+        path.iter().try_fold(self, |tree, (position, name)| {
+            tree.items.get_mut(*position as usize)?.links.get_mut(name)
+        })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Applies `changes` as returned by [`diff`](Self::diff) to this tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a change refers to a menu or items that don't exist.
+    pub fn patch(&mut self, changes: &[MenuTreeChange]) {
+        for change in changes {
+            let tree = self
+                .menu_at_mut(&change.path)
+                .expect("menu of change doesn't exist");
+            let position = change.position as usize;
+            tree.items.splice(
+                position..position + change.removed as usize,
+                change.added.iter().cloned(),
+            );
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Applies `changes` as returned by [`diff`](Self::diff) to `menu`, e.g. to update a menu
+    /// created with [`to_menu`](Self::to_menu) from the old tree.
+    ///
+    /// [`Menu`] emits one `items-changed` signal per removed or added item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a change refers to a menu that doesn't exist or is not a [`Menu`], or to items
+    /// that don't exist.
+    pub fn patch_menu(menu: &Menu, changes: &[MenuTreeChange]) {
+        for change in changes {
+            let menu = change
+                .path
+                .iter()
+                .fold(menu.clone(), |menu, (position, name)| {
+                    menu.item_link(*position as i32, name)
+                        .and_downcast::<Menu>()
+                        .expect("menu of change doesn't exist or is not a gio::Menu")
+                });
+            assert!(change.position + change.removed <= menu.n_items() as u32);
+            for _ in 0..change.removed {
+                menu.remove(change.position as i32);
+            }
+            for (offset, item) in change.added.iter().enumerate() {
+                menu.insert_item(change.position as i32 + offset as i32, &item.to_menu_item());
+            }
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Parses all `<menu>` elements of a GtkBuilder UI definition, together with their ids.
+    ///
+    /// Other elements of the UI definition are ignored. The `translatable`, `context` and
+    /// `comments` attributes are accepted but labels are not translated.
+    pub fn from_xml(xml: &str) -> Result<Vec<(String, MenuTree)>, glib::Error> {
+        static PARSER: glib::ffi::GMarkupParser = glib::ffi::GMarkupParser {
+            start_element: Some(start_element_trampoline),
+            end_element: Some(end_element_trampoline),
+            text: Some(text_trampoline),
+            passthrough: None,
+            error: None,
+        };
+
+        let mut parser = MenuParser::default();
+        unsafe {
+            let context = glib::ffi::g_markup_parse_context_new(
+                &PARSER,
+                glib::ffi::G_MARKUP_TREAT_CDATA_AS_TEXT,
+                &mut parser as *mut MenuParser as glib::ffi::gpointer,
+                None,
+            );
+            let mut error = ptr::null_mut();
+            let ok = glib::ffi::g_markup_parse_context_parse(
+                context,
+                xml.as_ptr() as *const c_char,
+                xml.len() as isize,
+                &mut error,
+            ) != glib::ffi::GFALSE
+                && glib::ffi::g_markup_parse_context_end_parse(context, &mut error)
+                    != glib::ffi::GFALSE;
+            glib::ffi::g_markup_parse_context_free(context);
+
+            if ok {
+                Ok(parser.menus)
+            } else {
+                Err(from_glib_full(error))
+            }
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Serializes this tree as a GtkBuilder UI definition containing a single menu with `id`.
+    ///
+    /// Attributes that are not strings are written with their type and in the
+    /// [`Variant`] text format.
+    pub fn to_xml(&self, id: &str) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<interface>\n");
+        writeln!(xml, "  <menu id=\"{}\">", glib::markup_escape_text(id)).unwrap();
+        self.write_items(&mut xml, 1);
+        xml.push_str("  </menu>\n</interface>\n");
+        xml
+    }
+
+    fn write_items(&self, xml: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for item in &self.items {
+            // Items with a single section or submenu link use the shorter <section> and
+            // <submenu> elements, with the item's attributes inside.
+            let shorthand = match item.links.iter().next() {
+                Some((name, link)) if item.links.len() == 1 => {
+                    [MENU_LINK_SECTION, MENU_LINK_SUBMENU]
+                        .into_iter()
+                        .find(|n| n.as_str() == name)
+                        .map(|name| (name, link))
+                }
+                _ => None,
+            };
+
+            if let Some((name, link)) = shorthand {
+                writeln!(xml, "{indent}  <{name}>").unwrap();
+                item.write_attributes(xml, depth + 2);
+                link.write_items(xml, depth + 1);
+                writeln!(xml, "{indent}  </{name}>").unwrap();
+            } else {
+                writeln!(xml, "{indent}  <item>").unwrap();
+                item.write_attributes(xml, depth + 2);
+                for (name, link) in &item.links {
+                    writeln!(
+                        xml,
+                        "{indent}    <link name=\"{}\">",
+                        glib::markup_escape_text(name)
+                    )
+                    .unwrap();
+                    link.write_items(xml, depth + 2);
+                    writeln!(xml, "{indent}    </link>").unwrap();
+                }
+                writeln!(xml, "{indent}  </item>").unwrap();
+            }
+        }
+    }
+}
+
+impl FromIterator<MenuTreeItem> for MenuTree {
+    fn from_iter<I: IntoIterator<Item = MenuTreeItem>>(iter: I) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<MenuTreeItem> for MenuTree {
+    fn extend<I: IntoIterator<Item = MenuTreeItem>>(&mut self, iter: I) {
+        self.items.extend(iter);
+    }
+}
+
+impl IntoIterator for MenuTree {
+    type Item = MenuTreeItem;
+    type IntoIter = std::vec::IntoIter<MenuTreeItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl From<&MenuTree> for Menu {
+    fn from(tree: &MenuTree) -> Self {
+        tree.to_menu()
+    }
+}
+
+impl MenuTreeItem {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new item like [`MenuItem::new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `detailed_action` is not a valid detailed action name.
+    pub fn new(label: Option<&str>, detailed_action: Option<&str>) -> Self {
+        let mut item = Self::default();
+        if let Some(label) = label {
+            item.set_attribute(MENU_ATTRIBUTE_LABEL, label);
+        }
+        if let Some(detailed_action) = detailed_action {
+            item.set_detailed_action(detailed_action);
+        }
+        item
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new item linking to `section`, like [`MenuItem::new_section`].
+    pub fn new_section(label: Option<&str>, section: MenuTree) -> Self {
+        Self::new(label, None).with_link(MENU_LINK_SECTION, section)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new item linking to `submenu`, like [`MenuItem::new_submenu`].
+    pub fn new_submenu(label: Option<&str>, submenu: MenuTree) -> Self {
+        Self::new(label, None).with_link(MENU_LINK_SUBMENU, submenu)
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.attribute(MENU_ATTRIBUTE_LABEL)?.str()
+    }
+
+    pub fn action(&self) -> Option<&str> {
+        self.attribute(MENU_ATTRIBUTE_ACTION)?.str()
+    }
+
+    pub fn target(&self) -> Option<&Variant> {
+        self.attribute(MENU_ATTRIBUTE_TARGET)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the action and target from a detailed action name such as `app.open::file` or
+    /// `win.zoom(1.5)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `detailed_action` is not a valid detailed action name.
+    pub fn set_detailed_action(&mut self, detailed_action: &str) {
+        let (action, target) = Action::parse_detailed_name(detailed_action)
+            .unwrap_or_else(|err| panic!("invalid detailed action {detailed_action:?}: {err}"));
+        self.set_attribute(MENU_ATTRIBUTE_ACTION, action.as_str());
+        match target {
+            Some(target) => self.set_attribute(MENU_ATTRIBUTE_TARGET, target),
+            None => {
+                self.remove_attribute(MENU_ATTRIBUTE_TARGET);
+            }
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&Variant> {
+        self.attributes.get(name)
+    }
+
+    pub fn attributes(&self) -> btree_map::Iter<'_, String, Variant> {
+        self.attributes.iter()
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: impl ToVariant) {
+        self.attributes.insert(name.to_owned(), value.to_variant());
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) -> Option<Variant> {
+        self.attributes.remove(name)
+    }
+
+    pub fn link(&self, name: &str) -> Option<&MenuTree> {
+        self.links.get(name)
+    }
+
+    pub fn links(&self) -> btree_map::Iter<'_, String, MenuTree> {
+        self.links.iter()
+    }
+
+    pub fn set_link(&mut self, name: &str, link: MenuTree) {
+        self.links.insert(name.to_owned(), link);
+    }
+
+    pub fn remove_link(&mut self, name: &str) -> Option<MenuTree> {
+        self.links.remove(name)
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sets the target the action is activated with.
+    pub fn with_target(self, target: impl ToVariant) -> Self {
+        self.with_attribute(MENU_ATTRIBUTE_TARGET, target)
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: impl ToVariant) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    pub fn with_link(mut self, name: &str, link: MenuTree) -> Self {
+        self.set_link(name, link);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new [`MenuItem`] with the contents of this item.
+    pub fn to_menu_item(&self) -> MenuItem {
+        let item = MenuItem::new(None, None);
+        for (name, value) in &self.attributes {
+            item.set_attribute_value(name, Some(value));
+        }
+        for (name, link) in &self.links {
+            item.set_link(name, Some(&link.to_menu()));
+        }
+        item
+    }
+
+    fn write_attributes(&self, xml: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for (name, value) in &self.attributes {
+            let name = glib::markup_escape_text(name);
+            match value.str().filter(|_| value.type_() == VariantTy::STRING) {
+                Some(s) => writeln!(
+                    xml,
+                    "{indent}<attribute name=\"{name}\">{}</attribute>",
+                    glib::markup_escape_text(s)
+                ),
+                None => writeln!(
+                    xml,
+                    "{indent}<attribute name=\"{name}\" type=\"{}\">{}</attribute>",
+                    glib::markup_escape_text(value.type_().as_str()),
+                    glib::markup_escape_text(&value.print(false))
+                ),
+            }
+            .unwrap();
+        }
+    }
+}
+
+// Element currently being parsed by `MenuParser`.
+enum Frame {
+    // An element that is not part of a menu, with all its children.
+    Ignored,
+    Interface,
+    Menu {
+        id: String,
+        tree: MenuTree,
+    },
+    Item(MenuTreeItem),
+    Link {
+        name: String,
+        tree: MenuTree,
+    },
+    // A <section> or <submenu>
+    Shorthand {
+        link: &'static str,
+        item: MenuTreeItem,
+        tree: MenuTree,
+    },
+    Attribute {
+        name: String,
+        type_: Option<VariantType>,
+        text: String,
+    },
+}
+
+impl Frame {
+    fn has_tree(&self) -> bool {
+        matches!(
+            self,
+            Self::Menu { .. } | Self::Link { .. } | Self::Shorthand { .. }
+        )
+    }
+
+    fn has_item(&self) -> bool {
+        matches!(self, Self::Item(_) | Self::Shorthand { .. })
+    }
+
+    fn tree(&mut self) -> Option<&mut MenuTree> {
+        match self {
+            Self::Menu { tree, .. } | Self::Link { tree, .. } | Self::Shorthand { tree, .. } => {
+                Some(tree)
+            }
+            _ => None,
+        }
+    }
+
+    fn item(&mut self) -> Option<&mut MenuTreeItem> {
+        match self {
+            Self::Item(item) | Self::Shorthand { item, .. } => Some(item),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MenuParser {
+    stack: Vec<Frame>,
+    menus: Vec<(String, MenuTree)>,
+}
+
+fn required_attribute<'a>(
+    element: &str,
+    attributes: &[(&str, &'a str)],
+    name: &str,
+) -> Result<&'a str, glib::Error> {
+    attributes
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| {
+            glib::Error::new(
+                MarkupError::MissingAttribute,
+                &format!("Element <{element}> requires the attribute '{name}'"),
+            )
+        })
+}
+
+impl MenuParser {
+    fn start_element(
+        &mut self,
+        element: &str,
+        attributes: &[(&str, &str)],
+    ) -> Result<(), glib::Error> {
+        let frame = match (self.stack.last_mut(), element) {
+            (None, "interface") => Frame::Interface,
+            (None | Some(Frame::Interface), "menu") => Frame::Menu {
+                id: required_attribute(element, attributes, "id")?.to_owned(),
+                tree: MenuTree::new(),
+            },
+            (Some(Frame::Interface | Frame::Ignored), _) => Frame::Ignored,
+            (Some(parent), "item") if parent.has_tree() => Frame::Item(MenuTreeItem::default()),
+            (Some(parent), "section" | "submenu") if parent.has_tree() => Frame::Shorthand {
+                link: if element == "section" {
+                    MENU_LINK_SECTION.as_str()
+                } else {
+                    MENU_LINK_SUBMENU.as_str()
+                },
+                item: MenuTreeItem::default(),
+                tree: MenuTree::new(),
+            },
+            (Some(parent), "attribute") if parent.has_item() => {
+                let type_ = attributes
+                    .iter()
+                    .find(|(n, _)| *n == "type")
+                    .map(|(_, type_)| {
+                        VariantType::new(type_).map_err(|_| {
+                            glib::Error::new(
+                                MarkupError::InvalidContent,
+                                &format!("Invalid GVariant type string '{type_}'"),
+                            )
+                        })
+                    })
+                    .transpose()?;
+                Frame::Attribute {
+                    name: required_attribute(element, attributes, "name")?.to_owned(),
+                    type_,
+                    text: String::new(),
+                }
+            }
+            (Some(Frame::Item(_)), "link") => Frame::Link {
+                name: required_attribute(element, attributes, "name")?.to_owned(),
+                tree: MenuTree::new(),
+            },
+            (_, _) => {
+                return Err(glib::Error::new(
+                    MarkupError::UnknownElement,
+                    &format!("Element <{element}> is not allowed here"),
+                ));
+            }
+        };
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    fn end_element(&mut self) -> Result<(), glib::Error> {
+        let frame = self.stack.pop().unwrap();
+        let parent = self.stack.last_mut();
+        match frame {
+            Frame::Ignored | Frame::Interface => (),
+            Frame::Menu { id, tree } => self.menus.push((id, tree)),
+            Frame::Item(item) => parent.and_then(Frame::tree).unwrap().push(item),
+            Frame::Link { name, tree } => {
+                parent.and_then(Frame::item).unwrap().set_link(&name, tree)
+            }
+            Frame::Shorthand { link, item, tree } => parent
+                .and_then(Frame::tree)
+                .unwrap()
+                .push(item.with_link(link, tree)),
+            Frame::Attribute { name, type_, text } => {
+                let value = match type_ {
+                    Some(type_) => Variant::parse(Some(&type_), &text).map_err(|err| {
+                        glib::Error::new(
+                            MarkupError::InvalidContent,
+                            &format!("Invalid value for attribute '{name}': {}", err.message()),
+                        )
+                    })?,
+                    None => text.to_variant(),
+                };
+                parent
+                    .and_then(Frame::item)
+                    .unwrap()
+                    .attributes
+                    .insert(name, value);
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(Frame::Attribute { text: value, .. }) = self.stack.last_mut() {
+            value.push_str(text);
+        }
+    }
+}
+
+unsafe fn set_markup_error(
+    context: *mut glib::ffi::GMarkupParseContext,
+    error: *mut *mut glib::ffi::GError,
+    err: glib::Error,
+) {
+    unsafe {
+        let mut line = 0;
+        let mut char = 0;
+        glib::ffi::g_markup_parse_context_get_position(context, &mut line, &mut char);
+        let kind = err
+            .kind::<MarkupError>()
+            .unwrap_or(MarkupError::InvalidContent);
+        let err = glib::Error::new(
+            kind,
+            &format!("Line {line}, character {char}: {}", err.message()),
+        );
+        *error = err.into_glib_ptr();
+    }
+}
+
+unsafe extern "C" fn start_element_trampoline(
+    context: *mut glib::ffi::GMarkupParseContext,
+    element_name: *const c_char,
+    attribute_names: *mut *const c_char,
+    attribute_values: *mut *const c_char,
+    user_data: glib::ffi::gpointer,
+    error: *mut *mut glib::ffi::GError,
+) {
+    unsafe {
+        let parser = &mut *(user_data as *mut MenuParser);
+        // GMarkup only passes valid UTF-8
+        let element = CStr::from_ptr(element_name).to_str().unwrap();
+        let mut attributes = Vec::new();
+        let mut i = 0;
+        while !(*attribute_names.add(i)).is_null() {
+            attributes.push((
+                CStr::from_ptr(*attribute_names.add(i)).to_str().unwrap(),
+                CStr::from_ptr(*attribute_values.add(i)).to_str().unwrap(),
+            ));
+            i += 1;
+        }
+
+        if let Err(err) = parser.start_element(element, &attributes) {
+            set_markup_error(context, error, err);
+        }
+    }
+}
+
+unsafe extern "C" fn end_element_trampoline(
+    context: *mut glib::ffi::GMarkupParseContext,
+    _element_name: *const c_char,
+    user_data: glib::ffi::gpointer,
+    error: *mut *mut glib::ffi::GError,
+) {
+    unsafe {
+        let parser = &mut *(user_data as *mut MenuParser);
+        if let Err(err) = parser.end_element() {
+            set_markup_error(context, error, err);
+        }
+    }
+}
+
+unsafe extern "C" fn text_trampoline(
+    _context: *mut glib::ffi::GMarkupParseContext,
+    text: *const c_char,
+    text_len: usize,
+    user_data: glib::ffi::gpointer,
+    _error: *mut *mut glib::ffi::GError,
+) {
+    unsafe {
+        let parser = &mut *(user_data as *mut MenuParser);
+        let text = std::slice::from_raw_parts(text as *const u8, text_len);
+        parser.text(std::str::from_utf8(text).unwrap());
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// Builds a [`MenuTree`] declaratively.
+///
+/// The menu is a comma-separated list of:
+///
+/// - `item(label)` and `item(label, detailed_action, key = value, ...)` for items, where each
+///   `key = value` sets an additional attribute. Keys are identifiers or string literals, and
+///   values anything implementing [`ToVariant`](glib::ToVariant). The `target` key sets the
+///   target the action is activated with.
+/// - `section { ... }` and `section(label, key = value, ...) { ... }` for sections.
+/// - `submenu(label, key = value, ...) { ... }` for submenus.
+///
+/// ```
+/// let menu = gio::menu! {
+///     item("_Open", "app.open"),
+///     section("Recent") {
+///         item("notes.txt", "app.open-recent", target = "/home/user/notes.txt"),
+///     },
+///     submenu("_Zoom", icon = "zoom-in-symbolic") {
+///         item("_Reset", "win.zoom(1.0)"),
+///         item("_Double", "win.zoom", target = 2.0),
+///     },
+/// }
+/// .to_menu();
+/// ```
+#[macro_export]
+macro_rules! menu {
+    ($($body:tt)*) => {
+        $crate::__menu_items!($crate::MenuTree::new(); $($body)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __menu_items {
+    ($tree:expr;) => {
+        $tree
+    };
+    ($tree:expr; item($label:expr $(,)?) $(, $($rest:tt)*)?) => {
+        $crate::__menu_items!(
+            $tree.item($crate::MenuTreeItem::new(::std::option::Option::Some($label), ::std::option::Option::None));
+            $($($rest)*)?
+        )
+    };
+    ($tree:expr; item($label:expr, $action:expr $(, $key:tt = $value:expr)* $(,)?) $(, $($rest:tt)*)?) => {
+        $crate::__menu_items!(
+            $tree.item(
+                $crate::MenuTreeItem::new(
+                    ::std::option::Option::Some($label),
+                    ::std::option::Option::Some($action),
+                )
+                $(.with_attribute($crate::__menu_attribute_name!($key), $value))*
+            );
+            $($($rest)*)?
+        )
+    };
+    ($tree:expr; section { $($body:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__menu_items!(
+            $tree.section(::std::option::Option::None, $crate::menu!($($body)*));
+            $($($rest)*)?
+        )
+    };
+    ($tree:expr; section($label:expr $(, $key:tt = $value:expr)* $(,)?) { $($body:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__menu_items!(
+            $tree.item(
+                $crate::MenuTreeItem::new_section(::std::option::Option::Some($label), $crate::menu!($($body)*))
+                $(.with_attribute($crate::__menu_attribute_name!($key), $value))*
+            );
+            $($($rest)*)?
+        )
+    };
+    ($tree:expr; submenu($label:expr $(, $key:tt = $value:expr)* $(,)?) { $($body:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__menu_items!(
+            $tree.item(
+                $crate::MenuTreeItem::new_submenu(::std::option::Option::Some($label), $crate::menu!($($body)*))
+                $(.with_attribute($crate::__menu_attribute_name!($key), $value))*
+            );
+            $($($rest)*)?
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __menu_attribute_name {
+    ($key:ident) => {
+        ::std::stringify!($key)
+    };
+    ($key:literal) => {
+        $key
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> MenuTree {
+        crate::menu! {
+            item("_New Window", "app.new-window"),
+            section {
+                item("Zoom _In", "win.zoom(1.25)"),
+                item("Zoom _Out", "win.zoom", target = 0.8, "hidden-when" = "action-missing"),
+            },
+            submenu("_Help", icon = "help-symbolic") {
+                item("_About <Example>"),
+            },
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let tree = tree();
+        assert_eq!(tree.items().len(), 3);
+
+        let zoom = tree.items()[1].link(MENU_LINK_SECTION).unwrap().items();
+        assert_eq!(zoom[0].action(), Some("win.zoom"));
+        assert_eq!(zoom[0].target(), Some(&1.25.to_variant()));
+        assert_eq!(zoom[1].target(), Some(&0.8.to_variant()));
+        assert_eq!(
+            zoom[1].attribute("hidden-when"),
+            Some(&"action-missing".to_variant())
+        );
+
+        let help = &tree.items()[2];
+        assert_eq!(help.label(), Some("_Help"));
+        assert_eq!(help.attribute("icon"), Some(&"help-symbolic".to_variant()));
+        assert_eq!(
+            help.link(MENU_LINK_SUBMENU).unwrap().items()[0].action(),
+            None
+        );
+    }
+
+    #[test]
+    fn model_round_trip() {
+        let tree = tree();
+        let menu = tree.to_menu();
+        assert_eq!(menu.n_items(), 3);
+        assert_eq!(menu.item_link(1, MENU_LINK_SECTION).unwrap().n_items(), 2);
+        assert_eq!(MenuTree::from_model(&menu), tree);
+    }
+
+    #[test]
+    fn xml_round_trip() {
+        let tree = tree();
+        let xml = tree.to_xml("app-menu");
+        assert!(xml.contains("<attribute name=\"target\" type=\"d\">"));
+        assert!(xml.contains("_About &lt;Example&gt;"));
+        assert_eq!(
+            MenuTree::from_xml(&xml).unwrap(),
+            [("app-menu".to_string(), tree)]
+        );
+    }
+
+    #[test]
+    fn from_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<interface>
+  <object class="GtkWindow" id="window">
+    <child><object class="GtkLabel"/></child>
+  </object>
+  <menu id="menu">
+    <item>
+      <attribute name="label" translatable="yes">_Quit</attribute>
+      <attribute name="action">app.quit</attribute>
+      <attribute name="accel">&lt;Control&gt;q</attribute>
+    </item>
+    <item>
+      <link name="section">
+        <item>
+          <attribute name="action">win.level</attribute>
+          <attribute name="target" type="u">3</attribute>
+        </item>
+      </link>
+    </item>
+  </menu>
+</interface>"#;
+
+        let menus = MenuTree::from_xml(xml).unwrap();
+        assert_eq!(menus.len(), 1);
+        let (id, tree) = &menus[0];
+        assert_eq!(id, "menu");
+        assert_eq!(
+            tree.items()[0].attribute("accel"),
+            Some(&"<Control>q".to_variant())
+        );
+        let section = tree.items()[1].link(MENU_LINK_SECTION).unwrap();
+        assert_eq!(section.items()[0].target(), Some(&3u32.to_variant()));
+
+        let err =
+            MenuTree::from_xml("<menu id=\"m\"><attribute name=\"label\"/></menu>").unwrap_err();
+        assert!(err.matches(MarkupError::UnknownElement));
+        let err = MenuTree::from_xml(
+            "<menu id=\"m\"><item><attribute name=\"a\" type=\"u\">x</attribute></item></menu>",
+        )
+        .unwrap_err();
+        assert!(err.matches(MarkupError::InvalidContent));
+    }
+
+    #[test]
+    fn diff() {
+        let old = tree();
+        assert!(old.diff(&old).is_empty());
+
+        let new = crate::menu! {
+            item("_New Window", "app.new-window"),
+            item("_Preferences", "app.preferences"),
+            section {
+                item("Zoom _In", "win.zoom(1.25)"),
+                item("_Reset Zoom", "win.zoom(1.0)"),
+            },
+            submenu("_Help", icon = "help-symbolic") {
+                item("_About <Example>"),
+                item("_Documentation", "app.docs"),
+            },
+        };
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes,
+            [
+                MenuTreeChange {
+                    path: vec![],
+                    position: 1,
+                    removed: 0,
+                    added: vec![new.items()[1].clone()],
+                },
+                MenuTreeChange {
+                    path: vec![(2, MENU_LINK_SECTION.to_string())],
+                    position: 1,
+                    removed: 1,
+                    added: vec![new.items()[2].link(MENU_LINK_SECTION).unwrap().items()[1].clone()],
+                },
+                MenuTreeChange {
+                    path: vec![(3, MENU_LINK_SUBMENU.to_string())],
+                    position: 1,
+                    removed: 0,
+                    added: vec![new.items()[3].link(MENU_LINK_SUBMENU).unwrap().items()[1].clone()],
+                },
+            ]
+        );
+
+        let mut patched = old.clone();
+        patched.patch(&changes);
+        assert_eq!(patched, new);
+
+        let menu = old.to_menu();
+        let section = menu
+            .item_link(1, MENU_LINK_SECTION)
+            .and_downcast::<Menu>()
+            .unwrap();
+        let signals = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let signals_clone = signals.clone();
+        section.connect_items_changed(move |_, position, removed, added| {
+            signals_clone.borrow_mut().push((position, removed, added));
+        });
+        MenuTree::patch_menu(&menu, &changes);
+        assert_eq!(MenuTree::from_model(&menu), new);
+        assert_eq!(*signals.borrow(), [(1, 1, 0), (1, 0, 1)]);
+
+        // Items with changed attributes or links are replaced, including their links
+        let new = crate::menu! {
+            item("_New Tab", "app.new-window"),
+            item("Zoom _In", "win.zoom(1.25)"),
+            submenu("_Help", icon = "help-symbolic") {
+                item("_About <Example>"),
+            },
+        };
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (
+                    change.path.len(),
+                    change.position,
+                    change.removed,
+                    change.added.len()
+                ))
+                .collect::<Vec<_>>(),
+            [(0, 0, 2, 2)]
+        );
+        let mut patched = old;
+        patched.patch(&changes);
+        assert_eq!(patched, new);
+    }
+}
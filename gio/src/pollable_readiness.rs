@@ -0,0 +1,261 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    mem, ptr,
+    sync::{Arc, Mutex},
+    task::{Wake, Waker},
+};
+
+use glib::{prelude::*, translate::*};
+
+use crate::{Cancellable, ffi};
+
+// rustdoc-stripper-ignore-next
+/// Readiness notifications for implementing pollable streams.
+///
+/// This creates the [`glib::Source`]s returned from
+/// [`PollableInputStreamImpl::create_source`] and [`PollableOutputStreamImpl::create_source`],
+/// and dispatches them whenever [`wake`](Self::wake) is called, for example once the underlying
+/// resource became readable or writable. It can be turned into a [`Waker`], so that readiness
+/// reported by Rust futures or `AsyncRead`/`AsyncWrite` implementations directly wakes the
+/// sources.
+///
+/// Like for any pollable stream, spurious wake-ups are possible and callers are expected to
+/// retry the non-blocking operation until it fails with
+/// [`IOErrorEnum::WouldBlock`](crate::IOErrorEnum::WouldBlock).
+///
+/// [`PollableInputStreamImpl::create_source`]: crate::subclass::prelude::PollableInputStreamImpl::create_source
+/// [`PollableOutputStreamImpl::create_source`]: crate::subclass::prelude::PollableOutputStreamImpl::create_source
+#[derive(Debug, Clone, Default)]
+pub struct PollableReadiness {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // Incremented on every wake-up. Waker sources are ready while this differs from the
+    // generation they were last dispatched for.
+    generation: u64,
+    // Set if woken while no source existed, so that the next source is dispatched
+    // immediately and the wake-up isn't lost.
+    pending: bool,
+    // The number of waker sources that were not finalized yet.
+    sources: usize,
+    // The main contexts of waker sources waiting for the next wake-up.
+    contexts: Vec<glib::MainContext>,
+}
+
+impl PollableReadiness {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new `PollableReadiness` without any sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a pollable source for `stream` that is dispatched on the next call to
+    /// [`wake`](Self::wake), or when `cancellable` is cancelled.
+    ///
+    /// `stream` must be the [`PollableInputStream`](crate::PollableInputStream) or
+    /// [`PollableOutputStream`](crate::PollableOutputStream) the source is created for.
+    #[doc(alias = "g_pollable_source_new_full")]
+    pub fn create_source(
+        &self,
+        stream: &impl IsA<glib::Object>,
+        cancellable: Option<&Cancellable>,
+    ) -> glib::Source {
+        unsafe {
+            let waker_source: glib::Source = from_glib_full(new_waker_source(self.inner.clone()));
+            from_glib_full(ffi::g_pollable_source_new_full(
+                stream.as_ref().to_glib_none().0,
+                waker_source.to_glib_none().0,
+                cancellable.to_glib_none().0,
+            ))
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Dispatches all sources created by [`create_source`](Self::create_source) that weren't
+    /// destroyed yet.
+    ///
+    /// This can be called from any thread.
+    pub fn wake(&self) {
+        self.inner.wake_by_ref();
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a [`Waker`] that calls [`wake`](Self::wake).
+    pub fn waker(&self) -> Waker {
+        Waker::from(self.inner.clone())
+    }
+}
+
+impl Wake for Inner {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let contexts = {
+            let mut state = self.state.lock().unwrap();
+            state.generation = state.generation.wrapping_add(1);
+            if state.sources == 0 {
+                state.pending = true;
+            }
+            mem::take(&mut state.contexts)
+        };
+        // Waiting sources check the new generation when their main context iterates again
+        for context in contexts {
+            context.wakeup();
+        }
+    }
+}
+
+// A child source of the pollable source, which is ready whenever `PollableReadiness` was woken
+// since it was last dispatched.
+#[repr(C)]
+struct WakerSource {
+    source: glib::ffi::GSource,
+    // A strong reference, released on finalization.
+    inner: *const Inner,
+    dispatched: u64,
+}
+
+// Returns whether the source is ready, and otherwise registers its main context to be woken up
+// by the next wake-up.
+unsafe fn waker_source_ready(source: *mut glib::ffi::GSource, register: bool) -> bool {
+    unsafe {
+        let waker_source = &*(source as *const WakerSource);
+        let mut state = (*waker_source.inner).state.lock().unwrap();
+        if state.generation != waker_source.dispatched {
+            return true;
+        }
+        if register {
+            let context = glib::ffi::g_source_get_context(source);
+            if !context.is_null() {
+                let context: glib::MainContext = from_glib_none(context);
+                if !state.contexts.contains(&context) {
+                    state.contexts.push(context);
+                }
+            }
+        }
+        false
+    }
+}
+
+unsafe fn new_waker_source(inner: Arc<Inner>) -> *mut glib::ffi::GSource {
+    unsafe extern "C" fn prepare(
+        source: *mut glib::ffi::GSource,
+        timeout: *mut std::ffi::c_int,
+    ) -> glib::ffi::gboolean {
+        unsafe {
+            *timeout = -1;
+            waker_source_ready(source, true).into_glib()
+        }
+    }
+
+    unsafe extern "C" fn check(source: *mut glib::ffi::GSource) -> glib::ffi::gboolean {
+        unsafe { waker_source_ready(source, false).into_glib() }
+    }
+
+    unsafe extern "C" fn dispatch(
+        source: *mut glib::ffi::GSource,
+        _callback: glib::ffi::GSourceFunc,
+        _user_data: glib::ffi::gpointer,
+    ) -> glib::ffi::gboolean {
+        unsafe {
+            // Only dispatch again after being woken up another time.
+            let waker_source = &mut *(source as *mut WakerSource);
+            waker_source.dispatched = (*waker_source.inner).state.lock().unwrap().generation;
+            glib::ffi::G_SOURCE_CONTINUE
+        }
+    }
+
+    unsafe extern "C" fn finalize(source: *mut glib::ffi::GSource) {
+        unsafe {
+            let waker_source = &mut *(source as *mut WakerSource);
+            let inner = Arc::from_raw(waker_source.inner);
+            let contexts = {
+                let mut state = inner.state.lock().unwrap();
+                state.sources -= 1;
+                if state.sources == 0 {
+                    mem::take(&mut state.contexts)
+                } else {
+                    Vec::new()
+                }
+            };
+            drop(contexts);
+        }
+    }
+
+    static WAKER_SOURCE_FUNCS: glib::ffi::GSourceFuncs = glib::ffi::GSourceFuncs {
+        prepare: Some(prepare),
+        check: Some(check),
+        dispatch: Some(dispatch),
+        finalize: Some(finalize),
+        closure_callback: None,
+        closure_marshal: None,
+    };
+
+    unsafe {
+        let source = glib::ffi::g_source_new(
+            mut_override(&WAKER_SOURCE_FUNCS),
+            mem::size_of::<WakerSource>() as u32,
+        );
+        let waker_source = &mut *(source as *mut WakerSource);
+        {
+            let mut state = inner.state.lock().unwrap();
+            state.sources += 1;
+            waker_source.dispatched = if mem::take(&mut state.pending) {
+                state.generation.wrapping_sub(1)
+            } else {
+                state.generation
+            };
+        }
+        ptr::write(&mut waker_source.inner, Arc::into_raw(inner));
+        source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_lifetime() {
+        let readiness = PollableReadiness::new();
+        let stream = crate::MemoryInputStream::new();
+        let sources = || readiness.inner.state.lock().unwrap().sources;
+
+        // Sources that are dropped without being attached are finalized right away
+        let source = readiness.create_source(&stream, None);
+        assert_eq!(sources(), 1);
+        drop(source);
+        assert_eq!(sources(), 0);
+
+        // Wake-ups without sources are kept for the next source
+        readiness.wake();
+        assert!(readiness.inner.state.lock().unwrap().pending);
+        let context = glib::MainContext::new();
+        let source = readiness.create_source(&stream, None);
+        assert!(!readiness.inner.state.lock().unwrap().pending);
+        source.attach(Some(&context));
+        assert!(context.pending());
+        source.destroy();
+        drop(source);
+        assert_eq!(sources(), 0);
+
+        // Attached sources become ready on the next wake-up
+        let source = readiness.create_source(&stream, None);
+        source.attach(Some(&context));
+        assert!(!context.pending());
+        readiness.wake();
+        assert!(context.pending());
+        source.destroy();
+    }
+}
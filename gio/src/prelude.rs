@@ -12,15 +12,16 @@ pub use crate::app_info::AppInfoExtManual;
 #[cfg_attr(docsrs, doc(cfg(feature = "v2_72")))]
 pub use crate::debug_controller_dbus::DebugControllerDBusExtManual;
 pub use crate::{
-    action_map::ActionMapExtManual, application::ApplicationExtManual,
+    action_map::ActionMapExtManual, actions::Actions, application::ApplicationExtManual,
     application_command_line::ApplicationCommandLineExtManual, auto::traits::*,
     cancellable::CancellableExtManual, converter::ConverterExtManual,
     data_input_stream::DataInputStreamExtManual, datagram_based::DatagramBasedExtManual,
     dbus_connection::DBusMethodCall, dbus_object_manager_client::DBusObjectManagerClientExtManual,
-    dbus_proxy::DBusProxyExtManual, file::FileExtManual, file_enumerator::FileEnumeratorExtManual,
-    inet_address::InetAddressExtManual, input_stream::InputStreamExtManual,
-    io_stream::IOStreamExtManual, list_model::ListModelExtManual,
-    output_stream::OutputStreamExtManual, pollable_input_stream::PollableInputStreamExtManual,
+    dbus_proxy::DBusProxyExtManual, error::DBusErrorExt, file::FileExtManual,
+    file_enumerator::FileEnumeratorExtManual, inet_address::InetAddressExtManual,
+    input_stream::InputStreamExtManual, io_stream::IOStreamExtManual,
+    list_model::ListModelExtManual, output_stream::OutputStreamExtManual,
+    pollable_input_stream::PollableInputStreamExtManual,
     pollable_output_stream::PollableOutputStreamExtManual, settings::SettingsExtManual,
     simple_proxy_resolver::SimpleProxyResolverExtManual, socket::SocketExtManual,
     socket_control_message::SocketControlMessageExtManual,
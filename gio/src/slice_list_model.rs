@@ -0,0 +1,208 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use glib::{Object, clone, prelude::*, subclass::prelude::*};
+
+use crate::{ListModel, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct SliceListModel {
+        pub(super) model: OnceCell<ListModel>,
+        pub(super) offset: Cell<u32>,
+        pub(super) size: Cell<u32>,
+        // Number of items of `model`, as of the last `items-changed` emission.
+        pub(super) source_n_items: Cell<u32>,
+        pub(super) handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SliceListModel {
+        const NAME: &'static str = "SliceListModel";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::SliceListModel;
+        type Interfaces = (ListModel,);
+    }
+
+    impl ObjectImpl for SliceListModel {
+        fn dispose(&self) {
+            if let (Some(model), Some(handler)) = (self.model.get(), self.handler.take()) {
+                model.disconnect(handler);
+            }
+        }
+    }
+
+    impl ListModelImpl for SliceListModel {
+        fn item_type(&self) -> glib::Type {
+            self.model.get().unwrap().item_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.slice_len(self.source_n_items.get())
+        }
+
+        fn item(&self, position: u32) -> Option<Object> {
+            if position >= self.n_items() {
+                return None;
+            }
+            self.model.get().unwrap().item(self.offset.get() + position)
+        }
+    }
+
+    impl SliceListModel {
+        pub(super) fn slice_len(&self, source_n_items: u32) -> u32 {
+            source_n_items
+                .saturating_sub(self.offset.get())
+                .min(self.size.get())
+        }
+
+        pub(super) fn source_items_changed(&self, position: u32, removed: u32, added: u32) {
+            let offset = self.offset.get();
+            let end = offset.saturating_add(self.size.get());
+            let old_len = self.n_items();
+            self.source_n_items
+                .set(self.source_n_items.get() - removed + added);
+            let new_len = self.n_items();
+
+            if position >= end {
+                return;
+            }
+
+            let start = position.saturating_sub(offset);
+            if removed == added {
+                // Only the replaced items inside the slice changed
+                let changed_end = (position + added).min(end);
+                if changed_end > offset.max(position) {
+                    let changed = changed_end - offset - start;
+                    self.obj().items_changed(start, changed, changed);
+                }
+            } else {
+                // All items after the change moved
+                let (removed, added) = (old_len - start.min(old_len), new_len - start.min(new_len));
+                if removed > 0 || added > 0 {
+                    self.obj().items_changed(start, removed, added);
+                }
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// A [`ListModel`] containing a contiguous range of items of another model.
+    pub struct SliceListModel(ObjectSubclass<imp::SliceListModel>) @implements ListModel;
+}
+
+impl SliceListModel {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model containing at most `size` items of `model`, starting at `offset`.
+    pub fn new(model: &impl IsA<ListModel>, offset: u32, size: u32) -> Self {
+        let obj: Self = Object::new();
+        let imp = obj.imp();
+        let model = model.as_ref();
+
+        imp.model.set(model.clone()).unwrap();
+        imp.offset.set(offset);
+        imp.size.set(size);
+        imp.source_n_items.set(model.n_items());
+
+        let handler = model.connect_items_changed(clone!(
+            #[weak]
+            obj,
+            move |_, position, removed, added| {
+                obj.imp().source_items_changed(position, removed, added);
+            }
+        ));
+        *imp.handler.borrow_mut() = Some(handler);
+
+        obj
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The model the items are taken from.
+    pub fn model(&self) -> &ListModel {
+        self.imp().model.get().unwrap()
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.imp().offset.get()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Moves the start of the slice to `offset`.
+    pub fn set_offset(&self, offset: u32) {
+        let imp = self.imp();
+        if imp.offset.get() == offset {
+            return;
+        }
+
+        let old_len = self.n_items();
+        imp.offset.set(offset);
+        let new_len = self.n_items();
+        if old_len > 0 || new_len > 0 {
+            self.items_changed(0, old_len, new_len);
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.imp().size.get()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Changes the maximum number of items of the slice.
+    pub fn set_size(&self, size: u32) {
+        let imp = self.imp();
+        let old_len = self.n_items();
+        imp.size.set(size);
+        let new_len = self.n_items();
+
+        if new_len > old_len {
+            self.items_changed(old_len, 0, new_len - old_len);
+        } else if new_len < old_len {
+            self.items_changed(new_len, old_len - new_len, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{ListStore, Menu};
+
+    #[test]
+    fn slice() {
+        let items = (0..6).map(|_| Menu::new()).collect::<Vec<_>>();
+        let store = ListStore::from_iter(items.clone());
+        let slice = store.sliced(1, 3);
+        assert_eq!(slice.snapshot(), items[1..4]);
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        slice.connect_items_changed(move |_, position, removed, added| {
+            changes_clone.borrow_mut().push((position, removed, added));
+        });
+
+        // Outside of the slice
+        store.remove(5);
+        // Replacing an item inside of the slice
+        store.splice(2, 1, &[items[5].clone()]);
+        // Shifting all items
+        store.remove(0);
+        assert_eq!(
+            slice.snapshot(),
+            [items[5].clone(), items[3].clone(), items[4].clone()]
+        );
+
+        slice.set_size(5);
+        assert_eq!(slice.n_items(), 3);
+        slice.set_offset(3);
+        assert_eq!(slice.snapshot(), [items[4].clone()]);
+
+        assert_eq!(*changes.borrow(), [(1, 1, 1), (0, 3, 3), (0, 3, 1)]);
+    }
+}
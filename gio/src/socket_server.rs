@@ -0,0 +1,412 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    rc::Rc,
+};
+
+use futures_channel::oneshot;
+use futures_util::future::{Either, select};
+use glib::{Object, prelude::*};
+
+use crate::{
+    IOStream, SocketConnection, SocketListener, TlsCertificate, TlsServerConnection, prelude::*,
+};
+
+// rustdoc-stripper-ignore-next
+/// A connection accepted by a [`SocketServer`] and passed to its handler.
+#[derive(Debug, Clone)]
+pub struct ServerConnection {
+    connection: SocketConnection,
+    tls_connection: Option<TlsServerConnection>,
+    source_object: Option<Object>,
+}
+
+impl ServerConnection {
+    // rustdoc-stripper-ignore-next
+    /// The accepted socket connection.
+    pub fn connection(&self) -> &SocketConnection {
+        &self.connection
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The TLS connection wrapping [`connection`](Self::connection) if the server was
+    /// configured with a certificate. The handshake has already completed.
+    pub fn tls_connection(&self) -> Option<&TlsServerConnection> {
+        self.tls_connection.as_ref()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The source object that was passed when adding the listening address or socket.
+    pub fn source_object(&self) -> Option<&Object> {
+        self.source_object.as_ref()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The stream to communicate over, i.e. the TLS connection if there is one and the plain
+    /// socket connection otherwise.
+    pub fn stream(&self) -> IOStream {
+        match self.tls_connection {
+            Some(ref tls_connection) => tls_connection.clone().upcast(),
+            None => self.connection.clone().upcast(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    listener: SocketListener,
+    max_connections: Option<usize>,
+    tls_certificate: Option<TlsCertificate>,
+    io_priority: glib::Priority,
+    active_connections: Cell<usize>,
+    // The connections whose handlers are running, in the order they were accepted.
+    connections: RefCell<Vec<ServerConnection>>,
+    shutting_down: Cell<bool>,
+    shutdown_senders: RefCell<Vec<oneshot::Sender<()>>>,
+    // Woken whenever a connection finishes, to wait for a free slot or for draining.
+    finished_senders: RefCell<Vec<oneshot::Sender<()>>>,
+}
+
+impl Inner {
+    fn finished(&self) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        let mut senders = self.finished_senders.borrow_mut();
+        senders.retain(|sender| !sender.is_canceled());
+        senders.push(sender);
+        receiver
+    }
+
+    fn shutdown(&self) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        let mut senders = self.shutdown_senders.borrow_mut();
+        senders.retain(|sender| !sender.is_canceled());
+        senders.push(sender);
+        receiver
+    }
+
+    fn notify_finished(&self) {
+        for sender in self.finished_senders.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A server that accepts connections from a [`SocketListener`] and runs an async handler for
+/// each of them on the thread-default [`MainContext`](glib::MainContext).
+///
+/// Unlike [`SocketService`](crate::SocketService) this doesn't require connecting to signals
+/// or tracking connections manually, and supports shutting down gracefully with
+/// [`shutdown_future`](Self::shutdown_future).
+///
+/// ```no_run
+/// # use gio::prelude::*;
+/// # async fn run() -> Result<(), glib::Error> {
+/// let listener = gio::SocketListener::new();
+/// listener.add_inet_port(8080, None::<&glib::Object>)?;
+///
+/// let server = gio::SocketServer::builder(&listener)
+///     .max_connections(64)
+///     .build();
+///
+/// server
+///     .serve(|conn| async move {
+///         let output = conn.stream().output_stream();
+///         let _ = output.write_all_future(b"hello\n", glib::Priority::default()).await;
+///     })
+///     .await
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SocketServer {
+    inner: Rc<Inner>,
+}
+
+impl SocketServer {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new server for `listener` without a connection limit and without TLS.
+    pub fn new(listener: &impl IsA<SocketListener>) -> Self {
+        Self::builder(listener).build()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a new builder for a server accepting connections from `listener`.
+    pub fn builder(listener: &impl IsA<SocketListener>) -> SocketServerBuilder {
+        SocketServerBuilder {
+            listener: listener.as_ref().clone(),
+            max_connections: None,
+            tls_certificate: None,
+            io_priority: glib::Priority::default(),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The listener connections are accepted from.
+    pub fn listener(&self) -> &SocketListener {
+        &self.inner.listener
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The number of accepted connections that are still being handled, including connections
+    /// whose TLS handshake is in progress.
+    pub fn active_connections(&self) -> usize {
+        self.inner.active_connections.get()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The connections whose handlers are currently running, in the order they were accepted.
+    ///
+    /// This can be used to close the remaining connections when shutting down, for example
+    /// after [`shutdown_future`](Self::shutdown_future) didn't resolve within a timeout.
+    pub fn connections(&self) -> Vec<ServerConnection> {
+        self.inner.connections.borrow().clone()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Whether the future returned by [`shutdown_future`](Self::shutdown_future) was polled.
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.shutting_down.get()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Accepts connections and spawns `handler` for each of them on the thread-default main
+    /// context.
+    ///
+    /// If the server has a connection limit, no new connections are accepted while that many
+    /// handlers are running. If it has a TLS certificate, each connection is wrapped in a
+    /// [`TlsServerConnection`] and handed to `handler` after a successful handshake.
+    /// Connections failing the handshake are dropped.
+    ///
+    /// The returned future resolves once the server was shut down, or with an error if
+    /// accepting a connection failed.
+    pub fn serve<F, Fut>(
+        &self,
+        handler: F,
+    ) -> impl Future<Output = Result<(), glib::Error>> + 'static
+    where
+        F: Fn(ServerConnection) -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let inner = self.inner.clone();
+        let handler = Rc::new(handler);
+
+        async move {
+            let main_context = glib::MainContext::ref_thread_default();
+
+            loop {
+                if inner.shutting_down.get() {
+                    break;
+                }
+
+                if let Some(max_connections) = inner.max_connections
+                    && inner.active_connections.get() >= max_connections
+                {
+                    let _ = select(inner.finished(), inner.shutdown()).await;
+                    continue;
+                }
+
+                let (connection, source_object) =
+                    match select(inner.listener.accept_future(), inner.shutdown()).await {
+                        Either::Left((Err(_), _)) if inner.shutting_down.get() => break,
+                        Either::Left((res, _)) => res?,
+                        // Dropping the accept future cancels it
+                        Either::Right(_) => break,
+                    };
+
+                inner
+                    .active_connections
+                    .set(inner.active_connections.get() + 1);
+
+                let inner = inner.clone();
+                let handler = handler.clone();
+                main_context.spawn_local(async move {
+                    if let Ok(connection) =
+                        accept_connection(&inner, connection, source_object).await
+                    {
+                        inner.connections.borrow_mut().push(connection.clone());
+                        let socket = connection.connection.clone();
+                        handler(connection).await;
+                        inner
+                            .connections
+                            .borrow_mut()
+                            .retain(|connection| connection.connection != socket);
+                    }
+
+                    inner
+                        .active_connections
+                        .set(inner.active_connections.get() - 1);
+                    inner.notify_finished();
+                });
+            }
+
+            Ok(())
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Stops accepting new connections and closes the listener once the returned future is
+    /// first polled.
+    ///
+    /// The future resolves once all running handlers have finished. Running connections are not
+    /// closed, see [`connections`](Self::connections) for that.
+    pub fn shutdown_future(&self) -> impl Future<Output = ()> + 'static {
+        let inner = self.inner.clone();
+
+        async move {
+            if !inner.shutting_down.replace(true) {
+                for sender in inner.shutdown_senders.take() {
+                    let _ = sender.send(());
+                }
+                inner.listener.close();
+            }
+
+            while inner.active_connections.get() > 0 {
+                let _ = inner.finished().await;
+            }
+        }
+    }
+}
+
+async fn accept_connection(
+    inner: &Inner,
+    connection: SocketConnection,
+    source_object: Option<Object>,
+) -> Result<ServerConnection, glib::Error> {
+    let tls_connection = match inner.tls_certificate {
+        Some(ref certificate) => {
+            let tls_connection = TlsServerConnection::new(&connection, Some(certificate))?;
+            tls_connection.handshake_future(inner.io_priority).await?;
+            Some(tls_connection)
+        }
+        None => None,
+    };
+
+    Ok(ServerConnection {
+        connection,
+        tls_connection,
+        source_object,
+    })
+}
+
+// rustdoc-stripper-ignore-next
+/// A [builder-pattern] type to construct [`SocketServer`] objects.
+///
+/// [builder-pattern]: https://doc.rust-lang.org/1.0.0/style/ownership/builders.html
+#[must_use = "The builder must be built to be used"]
+#[derive(Debug)]
+pub struct SocketServerBuilder {
+    listener: SocketListener,
+    max_connections: Option<usize>,
+    tls_certificate: Option<TlsCertificate>,
+    io_priority: glib::Priority,
+}
+
+impl SocketServerBuilder {
+    // rustdoc-stripper-ignore-next
+    /// Limits the number of connections handled concurrently.
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        assert!(
+            max_connections > 0,
+            "at least one connection must be allowed"
+        );
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Wraps all connections in a [`TlsServerConnection`] using `certificate`.
+    pub fn tls_certificate(mut self, certificate: &impl IsA<TlsCertificate>) -> Self {
+        self.tls_certificate = Some(certificate.as_ref().clone());
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The I/O priority used for TLS handshakes.
+    pub fn io_priority(mut self, io_priority: glib::Priority) -> Self {
+        self.io_priority = io_priority;
+        self
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Build the [`SocketServer`].
+    #[must_use = "Building the server without using it makes no sense"]
+    pub fn build(self) -> SocketServer {
+        SocketServer {
+            inner: Rc::new(Inner {
+                listener: self.listener,
+                max_connections: self.max_connections,
+                tls_certificate: self.tls_certificate,
+                io_priority: self.io_priority,
+                active_connections: Cell::new(0),
+                connections: RefCell::default(),
+                shutting_down: Cell::new(false),
+                shutdown_senders: RefCell::default(),
+                finished_senders: RefCell::default(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InetAddress, InetSocketAddress, SocketClient, SocketFamily};
+
+    #[test]
+    fn serve_and_shutdown() {
+        let res = crate::test_util::run_async_local(|tx, l| {
+            let ctx = glib::MainContext::ref_thread_default();
+            ctx.clone().spawn_local(async move {
+                let listener = SocketListener::new();
+                let port = listener.add_any_inet_port(None::<&Object>).unwrap();
+
+                let server = SocketServer::builder(&listener).max_connections(1).build();
+                let served = Rc::new(Cell::new(0));
+                let served_clone = served.clone();
+                let server_clone = server.clone();
+                let serve = ctx.spawn_local(server.serve(move |conn| {
+                    let served = served_clone.clone();
+                    let server = server_clone.clone();
+                    async move {
+                        let connections = server.connections();
+                        assert_eq!(connections.len(), 1);
+                        assert_eq!(connections[0].connection(), conn.connection());
+                        let output = conn.stream().output_stream();
+                        output
+                            .write_all_future(b"hi", glib::Priority::default())
+                            .await
+                            .unwrap();
+                        served.set(served.get() + 1);
+                    }
+                }));
+
+                let address =
+                    InetSocketAddress::new(&InetAddress::new_loopback(SocketFamily::Ipv4), port);
+                for _ in 0..2 {
+                    let conn = SocketClient::new().connect_future(&address).await.unwrap();
+                    let (buf, _, _) = conn
+                        .input_stream()
+                        .read_all_future(vec![0; 2], glib::Priority::default())
+                        .await
+                        .unwrap();
+                    assert_eq!(buf, b"hi");
+                }
+
+                // Nothing happens until the future is polled
+                let shutdown = server.shutdown_future();
+                assert!(!server.is_shutting_down());
+                shutdown.await;
+                assert!(server.is_shutting_down());
+                assert!(server.connections().is_empty());
+                serve.await.unwrap().unwrap();
+                assert_eq!(server.active_connections(), 0);
+                tx.send(served.get()).unwrap();
+                l.quit();
+            });
+        });
+
+        assert_eq!(res, 2);
+    }
+}
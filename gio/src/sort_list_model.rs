@@ -0,0 +1,384 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::cmp::Ordering;
+
+use glib::{Object, clone, prelude::*, subclass::prelude::*};
+
+use crate::{ListModel, list_model::changed_ranges, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::{OnceCell, RefCell};
+
+    use super::*;
+
+    pub(super) type CompareFunc = Box<dyn Fn(&Object, &Object) -> Ordering>;
+
+    #[derive(Default)]
+    pub struct SortListModel {
+        pub(super) model: OnceCell<ListModel>,
+        pub(super) compare_func: RefCell<Option<CompareFunc>>,
+        // The items of `model` in their original order.
+        pub(super) items: RefCell<Vec<Object>>,
+        // Positions in `items`, in sorted order.
+        pub(super) order: RefCell<Vec<u32>>,
+        pub(super) handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SortListModel {
+        const NAME: &'static str = "SortListModel";
+        const ALLOW_NAME_CONFLICT: bool = true;
+        type Type = super::SortListModel;
+        type Interfaces = (ListModel,);
+    }
+
+    impl ObjectImpl for SortListModel {
+        fn dispose(&self) {
+            if let (Some(model), Some(handler)) = (self.model.get(), self.handler.take()) {
+                model.disconnect(handler);
+            }
+        }
+    }
+
+    impl ListModelImpl for SortListModel {
+        fn item_type(&self) -> glib::Type {
+            self.model.get().unwrap().item_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.order.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<Object> {
+            let index = *self.order.borrow().get(position as usize)?;
+            Some(self.items.borrow()[index as usize].clone())
+        }
+    }
+
+    impl SortListModel {
+        // Compares two positions in `items`. Equal items keep their original order.
+        fn compare(&self, items: &[Object], a: u32, b: u32) -> Ordering {
+            let ordering = match *self.compare_func.borrow() {
+                Some(ref compare_func) => compare_func(&items[a as usize], &items[b as usize]),
+                None => Ordering::Equal,
+            };
+            ordering.then(a.cmp(&b))
+        }
+
+        // Sorts `additions` into the items at `position` in place of `removed` items, and
+        // returns the new order.
+        fn merge(&self, items: &[Object], position: u32, removed: u32, added: u32) -> Vec<u32> {
+            // Keep the relative order of the remaining items and merge the sorted additions
+            // into them.
+            let mut retained = self.order.borrow().clone();
+            retained.retain(|&index| index < position || index >= position + removed);
+            for index in &mut retained {
+                if *index >= position + removed {
+                    *index = *index - removed + added;
+                }
+            }
+
+            let mut added_order = (position..position + added).collect::<Vec<_>>();
+            added_order.sort_by(|&a, &b| self.compare(items, a, b));
+
+            let mut order = Vec::with_capacity(retained.len() + added_order.len());
+            let mut retained = retained.into_iter().peekable();
+            let mut added_order = added_order.into_iter().peekable();
+            loop {
+                let next = match (retained.peek(), added_order.peek()) {
+                    (Some(&a), Some(&b)) => {
+                        if self.compare(items, a, b) == Ordering::Greater {
+                            added_order.next()
+                        } else {
+                            retained.next()
+                        }
+                    }
+                    (Some(_), None) => retained.next(),
+                    (None, Some(_)) => added_order.next(),
+                    (None, None) => break,
+                };
+                order.extend(next);
+            }
+            order
+        }
+
+        // Changes `order` to `new` and emits `items-changed` for each range of consecutive
+        // items that is not marked as kept.
+        fn apply(&self, old_kept: &[bool], new: &[u32], new_kept: &[bool]) {
+            for (position, removed, added) in changed_ranges(old_kept, new_kept) {
+                let (position, removed, added) =
+                    (position as usize, removed as usize, added as usize);
+                self.order.borrow_mut().splice(
+                    position..position + removed,
+                    new[position..position + added].iter().copied(),
+                );
+                self.obj()
+                    .items_changed(position as u32, removed as u32, added as u32);
+            }
+        }
+
+        pub(super) fn source_items_changed(&self, position: u32, removed: u32, added: u32) {
+            let model = self.model.get().unwrap();
+            let additions = (position..position + added)
+                .map(|p| model.item(p).unwrap())
+                .collect::<Vec<_>>();
+
+            let old_items = self.items.borrow().clone();
+            let n_old = old_items.len() as u32;
+            let mut items = old_items.clone();
+            items.splice(
+                position as usize..(position + removed) as usize,
+                additions.iter().cloned(),
+            );
+            let order = self.merge(&items, position, removed, added);
+
+            // While emitting the changes, the removed items have to stay available. So refer
+            // to the old items followed by the added ones until all changes are applied.
+            let combined_order = order
+                .iter()
+                .map(|&index| {
+                    if index < position {
+                        index
+                    } else if index < position + added {
+                        n_old + index - position
+                    } else {
+                        index - added + removed
+                    }
+                })
+                .collect::<Vec<_>>();
+            let old_kept = self
+                .order
+                .borrow()
+                .iter()
+                .map(|&index| index < position || index >= position + removed)
+                .collect::<Vec<_>>();
+            let new_kept = combined_order
+                .iter()
+                .map(|&index| index < n_old)
+                .collect::<Vec<_>>();
+
+            {
+                let mut combined_items = old_items;
+                combined_items.extend(additions);
+                *self.items.borrow_mut() = combined_items;
+            }
+            self.apply(&old_kept, &combined_order, &new_kept);
+
+            *self.items.borrow_mut() = items;
+            *self.order.borrow_mut() = order;
+        }
+
+        pub(super) fn resort(&self) {
+            let old = self.order.borrow().clone();
+            let new = {
+                let items = self.items.borrow();
+                let mut new = (0..items.len() as u32).collect::<Vec<_>>();
+                new.sort_by(|&a, &b| self.compare(&items, a, b));
+                new
+            };
+
+            // Keep the longest run of items that are still in the same relative order, and
+            // move all others.
+            let mut old_positions = vec![0; old.len()];
+            for (position, &index) in old.iter().enumerate() {
+                old_positions[index as usize] = position;
+            }
+            let sequence = new
+                .iter()
+                .map(|&index| old_positions[index as usize])
+                .collect::<Vec<_>>();
+            let mut new_kept = vec![false; new.len()];
+            let mut old_kept = vec![false; old.len()];
+            for j in longest_increasing_subsequence(&sequence) {
+                new_kept[j] = true;
+                old_kept[sequence[j]] = true;
+            }
+
+            self.apply(&old_kept, &new, &new_kept);
+        }
+    }
+
+    // Returns the indices of a longest strictly increasing subsequence of `sequence`.
+    fn longest_increasing_subsequence(sequence: &[usize]) -> Vec<usize> {
+        // `tails[k]` is the index of the smallest last element of an increasing subsequence
+        // of length `k + 1`, `previous[i]` the element before `i` in its subsequence.
+        let mut tails = Vec::<usize>::new();
+        let mut previous = vec![usize::MAX; sequence.len()];
+        for (i, &value) in sequence.iter().enumerate() {
+            let k = tails.partition_point(|&t| sequence[t] < value);
+            if k > 0 {
+                previous[i] = tails[k - 1];
+            }
+            if k == tails.len() {
+                tails.push(i);
+            } else {
+                tails[k] = i;
+            }
+        }
+
+        let mut indices = Vec::with_capacity(tails.len());
+        let mut next = tails.last().copied();
+        while let Some(i) = next {
+            indices.push(i);
+            next = Some(previous[i]).filter(|&p| p != usize::MAX);
+        }
+        indices.reverse();
+        indices
+    }
+}
+
+glib::wrapper! {
+    // rustdoc-stripper-ignore-next
+    /// A [`ListModel`] containing the items of another model sorted by a comparison function.
+    ///
+    /// The sort is stable, i.e. equal items keep the order of the underlying model. Changes of
+    /// the underlying model are applied incrementally by merging the added items into the
+    /// existing order. If the comparison function's behaviour changes, call
+    /// [`resort`](Self::resort).
+    pub struct SortListModel(ObjectSubclass<imp::SortListModel>) @implements ListModel;
+}
+
+impl SortListModel {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new model containing the items of `model` sorted by `compare_func`.
+    pub fn new<F: Fn(&Object, &Object) -> Ordering + 'static>(
+        model: &impl IsA<ListModel>,
+        compare_func: F,
+    ) -> Self {
+        let obj: Self = Object::new();
+        let imp = obj.imp();
+        let model = model.as_ref();
+
+        imp.model.set(model.clone()).unwrap();
+        *imp.items.borrow_mut() = model.snapshot();
+        *imp.order.borrow_mut() = (0..model.n_items()).collect();
+        obj.set_compare_func(compare_func);
+
+        let handler = model.connect_items_changed(clone!(
+            #[weak]
+            obj,
+            move |_, position, removed, added| {
+                obj.imp().source_items_changed(position, removed, added);
+            }
+        ));
+        *imp.handler.borrow_mut() = Some(handler);
+
+        obj
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The model whose items are sorted.
+    pub fn model(&self) -> &ListModel {
+        self.imp().model.get().unwrap()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Replaces the comparison function and sorts all items again.
+    pub fn set_compare_func<F: Fn(&Object, &Object) -> Ordering + 'static>(&self, compare_func: F) {
+        *self.imp().compare_func.borrow_mut() = Some(Box::new(compare_func));
+        self.resort();
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sorts all items again after the behaviour of the comparison function changed.
+    ///
+    /// Items that keep their relative order stay in place, and one `items-changed` signal is
+    /// emitted for each run of consecutive items that moved.
+    pub fn resort(&self) {
+        self.imp().resort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use glib::BoxedAnyObject;
+
+    use super::*;
+    use crate::ListStore;
+
+    fn value(item: &Object) -> (i32, i32) {
+        *item
+            .downcast_ref::<BoxedAnyObject>()
+            .unwrap()
+            .borrow::<(i32, i32)>()
+    }
+
+    fn values(model: &impl IsA<ListModel>) -> Vec<(i32, i32)> {
+        model.snapshot().iter().map(value).collect()
+    }
+
+    #[test]
+    fn sort_stable() {
+        let store = [(3, 0), (1, 0), (3, 1), (2, 0)]
+            .into_iter()
+            .map(BoxedAnyObject::new)
+            .collect::<ListStore>();
+        let sort = store.sorted(|a, b| value(a).0.cmp(&value(b).0));
+        assert_eq!(values(&sort), [(1, 0), (2, 0), (3, 0), (3, 1)]);
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        sort.connect_items_changed(move |_, position, removed, added| {
+            changes_clone.borrow_mut().push((position, removed, added));
+        });
+
+        store.insert(0, &BoxedAnyObject::new((3, 2)));
+        store.append(&BoxedAnyObject::new((2, 1)));
+        store.remove(2);
+        assert_eq!(values(&sort), [(2, 0), (2, 1), (3, 2), (3, 0), (3, 1)]);
+        assert_eq!(*changes.borrow(), [(2, 0, 1), (2, 0, 1), (0, 1, 0)]);
+
+        sort.set_compare_func(|a, b| value(b).0.cmp(&value(a).0));
+        assert_eq!(values(&sort), [(3, 2), (3, 0), (3, 1), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn sort_moved_ranges() {
+        let store = [1, 2, 3, 4, 5]
+            .into_iter()
+            .map(|key| BoxedAnyObject::new((key, 0)))
+            .collect::<ListStore>();
+        let sort = store.sorted(|a, b| value(a).0.cmp(&value(b).0));
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        sort.connect_items_changed(move |sort, position, removed, added| {
+            let keys = values(sort).into_iter().map(|(key, _)| key).collect();
+            changes_clone
+                .borrow_mut()
+                .push((position, removed, added, keys));
+        });
+
+        store.splice(
+            1,
+            1,
+            &[BoxedAnyObject::new((6, 0)), BoxedAnyObject::new((0, 0))],
+        );
+        assert_eq!(
+            changes.take(),
+            [
+                (0, 0, 1, vec![0, 1, 2, 3, 4, 5]),
+                (2, 1, 0, vec![0, 1, 3, 4, 5]),
+                (5, 0, 1, vec![0, 1, 3, 4, 5, 6]),
+            ]
+        );
+
+        // Only 3 moves to the front
+        sort.set_compare_func(|a, b| {
+            let key = |item| match value(item).0 {
+                3 => -1,
+                key => key,
+            };
+            key(a).cmp(&key(b))
+        });
+        assert_eq!(
+            changes.take(),
+            [
+                (0, 0, 1, vec![3, 0, 1, 3, 4, 5, 6]),
+                (3, 1, 0, vec![3, 0, 1, 4, 5, 6]),
+            ]
+        );
+    }
+}
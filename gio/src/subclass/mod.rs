@@ -14,6 +14,8 @@ mod input_stream;
 mod io_stream;
 mod list_model;
 mod output_stream;
+mod pollable_input_stream;
+mod pollable_output_stream;
 mod seekable;
 mod socket_control_message;
 mod vfs;
@@ -39,6 +41,8 @@ pub mod prelude {
         io_stream::{IOStreamImpl, IOStreamImplExt},
         list_model::{ListModelImpl, ListModelImplExt},
         output_stream::{OutputStreamImpl, OutputStreamImplExt},
+        pollable_input_stream::{PollableInputStreamImpl, PollableInputStreamImplExt},
+        pollable_output_stream::{PollableOutputStreamImpl, PollableOutputStreamImplExt},
         seekable::{SeekableImpl, SeekableImplExt},
         socket_control_message::{SocketControlMessageImpl, SocketControlMessageImplExt},
         vfs::{VfsImpl, VfsImplExt},
@@ -0,0 +1,306 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::ptr;
+
+use glib::{Error, prelude::*, subclass::prelude::*, translate::*};
+
+use crate::{Cancellable, InputStream, PollableInputStream, ffi};
+
+pub trait PollableInputStreamImpl:
+    Send + ObjectImpl + ObjectSubclass<Type: IsA<InputStream> + IsA<PollableInputStream>>
+{
+    fn can_poll(&self) -> bool {
+        self.parent_can_poll()
+    }
+
+    fn is_readable(&self) -> bool;
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a source that is dispatched once the stream is readable, or `cancellable` is
+    /// cancelled. [`PollableReadiness`](crate::PollableReadiness) helps implementing this.
+    fn create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source;
+
+    // rustdoc-stripper-ignore-next
+    /// Reads without blocking. Fails with [`IOErrorEnum::WouldBlock`](crate::IOErrorEnum::WouldBlock)
+    /// if no data is available.
+    fn read_nonblocking(&self, buffer: &mut [u8]) -> Result<usize, Error>;
+}
+
+pub trait PollableInputStreamImplExt: PollableInputStreamImpl {
+    fn parent_can_poll(&self) -> bool {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data.as_ref().parent_interface::<PollableInputStream>()
+                as *const ffi::GPollableInputStreamInterface;
+
+            // Streams without a `can_poll` implementation are always pollable
+            match (*parent_iface).can_poll {
+                Some(func) => from_glib(func(
+                    self.obj()
+                        .unsafe_cast_ref::<PollableInputStream>()
+                        .to_glib_none()
+                        .0,
+                )),
+                None => true,
+            }
+        }
+    }
+
+    fn parent_is_readable(&self) -> bool {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data.as_ref().parent_interface::<PollableInputStream>()
+                as *const ffi::GPollableInputStreamInterface;
+
+            let func = (*parent_iface)
+                .is_readable
+                .expect("no parent \"is_readable\" implementation");
+            from_glib(func(
+                self.obj()
+                    .unsafe_cast_ref::<PollableInputStream>()
+                    .to_glib_none()
+                    .0,
+            ))
+        }
+    }
+
+    fn parent_create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data.as_ref().parent_interface::<PollableInputStream>()
+                as *const ffi::GPollableInputStreamInterface;
+
+            let func = (*parent_iface)
+                .create_source
+                .expect("no parent \"create_source\" implementation");
+            from_glib_full(func(
+                self.obj()
+                    .unsafe_cast_ref::<PollableInputStream>()
+                    .to_glib_none()
+                    .0,
+                cancellable.to_glib_none().0,
+            ))
+        }
+    }
+
+    fn parent_read_nonblocking(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data.as_ref().parent_interface::<PollableInputStream>()
+                as *const ffi::GPollableInputStreamInterface;
+
+            let func = (*parent_iface)
+                .read_nonblocking
+                .expect("no parent \"read_nonblocking\" implementation");
+
+            let mut err = ptr::null_mut();
+            let res = func(
+                self.obj()
+                    .unsafe_cast_ref::<PollableInputStream>()
+                    .to_glib_none()
+                    .0,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut err,
+            );
+            if res == -1 {
+                Err(from_glib_full(err))
+            } else {
+                debug_assert!(res >= 0);
+                let res = res as usize;
+                debug_assert!(res <= buffer.len());
+                Ok(res)
+            }
+        }
+    }
+}
+
+impl<T: PollableInputStreamImpl> PollableInputStreamImplExt for T {}
+
+unsafe impl<T: PollableInputStreamImpl> IsImplementable<T> for PollableInputStream {
+    fn interface_init(iface: &mut glib::Interface<Self>) {
+        let iface = iface.as_mut();
+
+        iface.can_poll = Some(pollable_input_stream_can_poll::<T>);
+        iface.is_readable = Some(pollable_input_stream_is_readable::<T>);
+        iface.create_source = Some(pollable_input_stream_create_source::<T>);
+        iface.read_nonblocking = Some(pollable_input_stream_read_nonblocking::<T>);
+    }
+}
+
+unsafe extern "C" fn pollable_input_stream_can_poll<T: PollableInputStreamImpl>(
+    stream: *mut ffi::GPollableInputStream,
+) -> glib::ffi::gboolean {
+    unsafe {
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        imp.can_poll().into_glib()
+    }
+}
+
+unsafe extern "C" fn pollable_input_stream_is_readable<T: PollableInputStreamImpl>(
+    stream: *mut ffi::GPollableInputStream,
+) -> glib::ffi::gboolean {
+    unsafe {
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        imp.is_readable().into_glib()
+    }
+}
+
+unsafe extern "C" fn pollable_input_stream_create_source<T: PollableInputStreamImpl>(
+    stream: *mut ffi::GPollableInputStream,
+    cancellable: *mut ffi::GCancellable,
+) -> *mut glib::ffi::GSource {
+    unsafe {
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        imp.create_source(
+            Option::<Cancellable>::from_glib_borrow(cancellable)
+                .as_ref()
+                .as_ref(),
+        )
+        .into_glib_ptr()
+    }
+}
+
+unsafe extern "C" fn pollable_input_stream_read_nonblocking<T: PollableInputStreamImpl>(
+    stream: *mut ffi::GPollableInputStream,
+    buffer: *mut u8,
+    count: usize,
+    err: *mut *mut glib::ffi::GError,
+) -> isize {
+    unsafe {
+        debug_assert!(count <= isize::MAX as usize);
+
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        let buffer = if count == 0 {
+            &mut []
+        } else {
+            std::slice::from_raw_parts_mut(buffer, count)
+        };
+
+        match imp.read_nonblocking(buffer) {
+            Ok(res) => {
+                assert!(res <= isize::MAX as usize);
+                assert!(res <= count);
+                res as isize
+            }
+            Err(e) => {
+                if !err.is_null() {
+                    *err = e.into_glib_ptr();
+                }
+                -1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures_util::io::AsyncReadExt;
+
+    use super::*;
+    use crate::{IOErrorEnum, PollableReadiness, prelude::*, subclass::prelude::*};
+
+    mod imp {
+        use super::*;
+
+        #[derive(Default)]
+        pub struct PipeInputStream {
+            pub data: Mutex<(Vec<u8>, bool)>,
+            pub readiness: PollableReadiness,
+        }
+
+        #[glib::object_subclass]
+        impl ObjectSubclass for PipeInputStream {
+            const NAME: &'static str = "PipeInputStream";
+            type Type = super::PipeInputStream;
+            type ParentType = InputStream;
+            type Interfaces = (PollableInputStream,);
+        }
+
+        impl ObjectImpl for PipeInputStream {}
+
+        impl InputStreamImpl for PipeInputStream {
+            fn read(
+                &self,
+                buffer: &mut [u8],
+                _cancellable: Option<&Cancellable>,
+            ) -> Result<usize, Error> {
+                self.read_nonblocking(buffer)
+            }
+        }
+
+        impl PollableInputStreamImpl for PipeInputStream {
+            fn is_readable(&self) -> bool {
+                let data = self.data.lock().unwrap();
+                !data.0.is_empty() || data.1
+            }
+
+            fn create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source {
+                self.readiness.create_source(&*self.obj(), cancellable)
+            }
+
+            fn read_nonblocking(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+                let mut data = self.data.lock().unwrap();
+                if data.0.is_empty() && !data.1 {
+                    return Err(Error::new(IOErrorEnum::WouldBlock, "No data"));
+                }
+                let len = buffer.len().min(data.0.len());
+                buffer[..len].copy_from_slice(&data.0[..len]);
+                data.0.drain(..len);
+                Ok(len)
+            }
+        }
+
+        impl PipeInputStream {
+            pub fn push(&self, bytes: &[u8], close: bool) {
+                let mut data = self.data.lock().unwrap();
+                data.0.extend_from_slice(bytes);
+                data.1 |= close;
+                self.readiness.wake();
+            }
+        }
+    }
+
+    glib::wrapper! {
+        pub struct PipeInputStream(ObjectSubclass<imp::PipeInputStream>)
+            @extends InputStream,
+            @implements PollableInputStream;
+    }
+
+    #[test]
+    fn test_pollable_read() {
+        let res = crate::test_util::run_async_local(|tx, l| {
+            let stream = glib::Object::new::<PipeInputStream>();
+            assert!(stream.can_poll());
+            assert!(!stream.is_readable());
+
+            let ctx = glib::MainContext::ref_thread_default();
+
+            let reader = stream.clone();
+            ctx.spawn_local(async move {
+                let mut read = reader.into_async_read().unwrap();
+                let mut buf = Vec::new();
+                read.read_to_end(&mut buf).await.unwrap();
+                tx.send(buf).unwrap();
+                l.quit();
+            });
+
+            ctx.spawn_local(async move {
+                stream.imp().push(b"hello ", false);
+                stream.imp().push(b"world", true);
+            });
+        });
+
+        assert_eq!(res, b"hello world");
+    }
+}
@@ -0,0 +1,317 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::ptr;
+
+use glib::{Error, prelude::*, subclass::prelude::*, translate::*};
+
+use crate::{Cancellable, OutputStream, PollableOutputStream, ffi};
+
+pub trait PollableOutputStreamImpl:
+    Send + ObjectImpl + ObjectSubclass<Type: IsA<OutputStream> + IsA<PollableOutputStream>>
+{
+    fn can_poll(&self) -> bool {
+        self.parent_can_poll()
+    }
+
+    fn is_writable(&self) -> bool;
+
+    // rustdoc-stripper-ignore-next
+    /// Creates a source that is dispatched once the stream is writable, or `cancellable` is
+    /// cancelled. [`PollableReadiness`](crate::PollableReadiness) helps implementing this.
+    fn create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source;
+
+    // rustdoc-stripper-ignore-next
+    /// Writes without blocking. Fails with [`IOErrorEnum::WouldBlock`](crate::IOErrorEnum::WouldBlock)
+    /// if nothing can be written currently.
+    fn write_nonblocking(&self, buffer: &[u8]) -> Result<usize, Error>;
+}
+
+pub trait PollableOutputStreamImplExt: PollableOutputStreamImpl {
+    fn parent_can_poll(&self) -> bool {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data
+                .as_ref()
+                .parent_interface::<PollableOutputStream>()
+                as *const ffi::GPollableOutputStreamInterface;
+
+            // Streams without a `can_poll` implementation are always pollable
+            match (*parent_iface).can_poll {
+                Some(func) => from_glib(func(
+                    self.obj()
+                        .unsafe_cast_ref::<PollableOutputStream>()
+                        .to_glib_none()
+                        .0,
+                )),
+                None => true,
+            }
+        }
+    }
+
+    fn parent_is_writable(&self) -> bool {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data
+                .as_ref()
+                .parent_interface::<PollableOutputStream>()
+                as *const ffi::GPollableOutputStreamInterface;
+
+            let func = (*parent_iface)
+                .is_writable
+                .expect("no parent \"is_writable\" implementation");
+            from_glib(func(
+                self.obj()
+                    .unsafe_cast_ref::<PollableOutputStream>()
+                    .to_glib_none()
+                    .0,
+            ))
+        }
+    }
+
+    fn parent_create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data
+                .as_ref()
+                .parent_interface::<PollableOutputStream>()
+                as *const ffi::GPollableOutputStreamInterface;
+
+            let func = (*parent_iface)
+                .create_source
+                .expect("no parent \"create_source\" implementation");
+            from_glib_full(func(
+                self.obj()
+                    .unsafe_cast_ref::<PollableOutputStream>()
+                    .to_glib_none()
+                    .0,
+                cancellable.to_glib_none().0,
+            ))
+        }
+    }
+
+    fn parent_write_nonblocking(&self, buffer: &[u8]) -> Result<usize, Error> {
+        unsafe {
+            let type_data = Self::type_data();
+            let parent_iface = type_data
+                .as_ref()
+                .parent_interface::<PollableOutputStream>()
+                as *const ffi::GPollableOutputStreamInterface;
+
+            let func = (*parent_iface)
+                .write_nonblocking
+                .expect("no parent \"write_nonblocking\" implementation");
+
+            let mut err = ptr::null_mut();
+            let res = func(
+                self.obj()
+                    .unsafe_cast_ref::<PollableOutputStream>()
+                    .to_glib_none()
+                    .0,
+                mut_override(buffer.as_ptr()),
+                buffer.len(),
+                &mut err,
+            );
+            if res == -1 {
+                Err(from_glib_full(err))
+            } else {
+                debug_assert!(res >= 0);
+                let res = res as usize;
+                debug_assert!(res <= buffer.len());
+                Ok(res)
+            }
+        }
+    }
+}
+
+impl<T: PollableOutputStreamImpl> PollableOutputStreamImplExt for T {}
+
+unsafe impl<T: PollableOutputStreamImpl> IsImplementable<T> for PollableOutputStream {
+    fn interface_init(iface: &mut glib::Interface<Self>) {
+        let iface = iface.as_mut();
+
+        iface.can_poll = Some(pollable_output_stream_can_poll::<T>);
+        iface.is_writable = Some(pollable_output_stream_is_writable::<T>);
+        iface.create_source = Some(pollable_output_stream_create_source::<T>);
+        iface.write_nonblocking = Some(pollable_output_stream_write_nonblocking::<T>);
+    }
+}
+
+unsafe extern "C" fn pollable_output_stream_can_poll<T: PollableOutputStreamImpl>(
+    stream: *mut ffi::GPollableOutputStream,
+) -> glib::ffi::gboolean {
+    unsafe {
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        imp.can_poll().into_glib()
+    }
+}
+
+unsafe extern "C" fn pollable_output_stream_is_writable<T: PollableOutputStreamImpl>(
+    stream: *mut ffi::GPollableOutputStream,
+) -> glib::ffi::gboolean {
+    unsafe {
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        imp.is_writable().into_glib()
+    }
+}
+
+unsafe extern "C" fn pollable_output_stream_create_source<T: PollableOutputStreamImpl>(
+    stream: *mut ffi::GPollableOutputStream,
+    cancellable: *mut ffi::GCancellable,
+) -> *mut glib::ffi::GSource {
+    unsafe {
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        imp.create_source(
+            Option::<Cancellable>::from_glib_borrow(cancellable)
+                .as_ref()
+                .as_ref(),
+        )
+        .into_glib_ptr()
+    }
+}
+
+unsafe extern "C" fn pollable_output_stream_write_nonblocking<T: PollableOutputStreamImpl>(
+    stream: *mut ffi::GPollableOutputStream,
+    buffer: *mut u8,
+    count: usize,
+    err: *mut *mut glib::ffi::GError,
+) -> isize {
+    unsafe {
+        debug_assert!(count <= isize::MAX as usize);
+
+        let instance = &*(stream as *mut T::Instance);
+        let imp = instance.imp();
+
+        let buffer = if count == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(buffer as *const u8, count)
+        };
+
+        match imp.write_nonblocking(buffer) {
+            Ok(res) => {
+                assert!(res <= isize::MAX as usize);
+                assert!(res <= count);
+                res as isize
+            }
+            Err(e) => {
+                if !err.is_null() {
+                    *err = e.into_glib_ptr();
+                }
+                -1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use futures_util::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::{IOErrorEnum, PollableReadiness, prelude::*, subclass::prelude::*};
+
+    const CAPACITY: usize = 4;
+
+    mod imp {
+        use super::*;
+
+        #[derive(Default)]
+        pub struct PipeOutputStream {
+            pub data: Mutex<Vec<u8>>,
+            pub readiness: PollableReadiness,
+        }
+
+        #[glib::object_subclass]
+        impl ObjectSubclass for PipeOutputStream {
+            const NAME: &'static str = "PipeOutputStream";
+            type Type = super::PipeOutputStream;
+            type ParentType = OutputStream;
+            type Interfaces = (PollableOutputStream,);
+        }
+
+        impl ObjectImpl for PipeOutputStream {}
+
+        impl OutputStreamImpl for PipeOutputStream {
+            fn write(
+                &self,
+                buffer: &[u8],
+                _cancellable: Option<&Cancellable>,
+            ) -> Result<usize, Error> {
+                self.write_nonblocking(buffer)
+            }
+        }
+
+        impl PollableOutputStreamImpl for PipeOutputStream {
+            fn is_writable(&self) -> bool {
+                self.data.lock().unwrap().len() < CAPACITY
+            }
+
+            fn create_source(&self, cancellable: Option<&Cancellable>) -> glib::Source {
+                self.readiness.create_source(&*self.obj(), cancellable)
+            }
+
+            fn write_nonblocking(&self, buffer: &[u8]) -> Result<usize, Error> {
+                let mut data = self.data.lock().unwrap();
+                if data.len() >= CAPACITY {
+                    return Err(Error::new(IOErrorEnum::WouldBlock, "Pipe full"));
+                }
+                let len = buffer.len().min(CAPACITY - data.len());
+                data.extend_from_slice(&buffer[..len]);
+                Ok(len)
+            }
+        }
+
+        impl PipeOutputStream {
+            pub fn take(&self) -> Vec<u8> {
+                let data = std::mem::take(&mut *self.data.lock().unwrap());
+                self.readiness.wake();
+                data
+            }
+        }
+    }
+
+    glib::wrapper! {
+        pub struct PipeOutputStream(ObjectSubclass<imp::PipeOutputStream>)
+            @extends OutputStream,
+            @implements PollableOutputStream;
+    }
+
+    #[test]
+    fn test_pollable_write() {
+        let res = crate::test_util::run_async_local(|tx, l| {
+            let stream = glib::Object::new::<PipeOutputStream>();
+            assert!(stream.can_poll());
+            assert!(stream.is_writable());
+
+            let ctx = glib::MainContext::ref_thread_default();
+
+            let writer = stream.clone();
+            ctx.spawn_local(async move {
+                let mut write = writer.into_async_write().unwrap();
+                write.write_all(b"hello world").await.unwrap();
+            });
+
+            ctx.spawn_local(async move {
+                let mut received = Vec::new();
+                while received.len() < 11 {
+                    glib::timeout_future(Duration::from_millis(1)).await;
+                    let chunk = stream.imp().take();
+                    assert!(chunk.len() <= CAPACITY);
+                    received.extend(chunk);
+                }
+                tx.send(received).unwrap();
+                l.quit();
+            });
+        });
+
+        assert_eq!(res, b"hello world");
+    }
+}
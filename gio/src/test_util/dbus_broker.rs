@@ -0,0 +1,738 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use glib::{Variant, prelude::*};
+
+use super::match_rule::MatchRule;
+use crate::{
+    Cancellable, DBusConnection, DBusConnectionFlags, DBusMessage, DBusMessageFlags,
+    DBusMessageType, DBusSendMessageFlags, DBusServer, DBusServerFlags, IOErrorEnum,
+};
+
+const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+const BUS_INTERFACE: &str = "org.freedesktop.DBus";
+
+const BUS_INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.freedesktop.DBus">
+    <method name="Hello"><arg direction="out" type="s"/></method>
+    <method name="RequestName"><arg direction="in" type="s"/><arg direction="in" type="u"/><arg direction="out" type="u"/></method>
+    <method name="ReleaseName"><arg direction="in" type="s"/><arg direction="out" type="u"/></method>
+    <method name="GetNameOwner"><arg direction="in" type="s"/><arg direction="out" type="s"/></method>
+    <method name="NameHasOwner"><arg direction="in" type="s"/><arg direction="out" type="b"/></method>
+    <method name="ListNames"><arg direction="out" type="as"/></method>
+    <method name="ListActivatableNames"><arg direction="out" type="as"/></method>
+    <method name="ListQueuedOwners"><arg direction="in" type="s"/><arg direction="out" type="as"/></method>
+    <method name="StartServiceByName"><arg direction="in" type="s"/><arg direction="in" type="u"/><arg direction="out" type="u"/></method>
+    <method name="AddMatch"><arg direction="in" type="s"/></method>
+    <method name="RemoveMatch"><arg direction="in" type="s"/></method>
+    <method name="GetId"><arg direction="out" type="s"/></method>
+    <signal name="NameOwnerChanged"><arg type="s"/><arg type="s"/><arg type="s"/></signal>
+    <signal name="NameLost"><arg type="s"/></signal>
+    <signal name="NameAcquired"><arg type="s"/></signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Peer">
+    <method name="Ping"/>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg direction="out" type="s"/></method>
+  </interface>
+</node>
+"#;
+
+// `RequestName` flags and return values
+const NAME_FLAG_ALLOW_REPLACEMENT: u32 = 0x1;
+const NAME_FLAG_REPLACE_EXISTING: u32 = 0x2;
+const NAME_FLAG_DO_NOT_QUEUE: u32 = 0x4;
+const REQUEST_NAME_REPLY_PRIMARY_OWNER: u32 = 1;
+const REQUEST_NAME_REPLY_IN_QUEUE: u32 = 2;
+const REQUEST_NAME_REPLY_EXISTS: u32 = 3;
+const REQUEST_NAME_REPLY_ALREADY_OWNER: u32 = 4;
+
+// `ReleaseName` return values
+const RELEASE_NAME_REPLY_RELEASED: u32 = 1;
+const RELEASE_NAME_REPLY_NON_EXISTENT: u32 = 2;
+const RELEASE_NAME_REPLY_NOT_OWNER: u32 = 3;
+
+// The D-Bus errors returned by the bus.
+#[derive(Debug, Clone, Copy)]
+enum BusErrorName {
+    AccessDenied,
+    InvalidArgs,
+    MatchRuleInvalid,
+    MatchRuleNotFound,
+    NameHasNoOwner,
+    ServiceUnknown,
+    UnknownMethod,
+}
+
+impl BusErrorName {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::AccessDenied => "org.freedesktop.DBus.Error.AccessDenied",
+            Self::InvalidArgs => "org.freedesktop.DBus.Error.InvalidArgs",
+            Self::MatchRuleInvalid => "org.freedesktop.DBus.Error.MatchRuleInvalid",
+            Self::MatchRuleNotFound => "org.freedesktop.DBus.Error.MatchRuleNotFound",
+            Self::NameHasNoOwner => "org.freedesktop.DBus.Error.NameHasNoOwner",
+            Self::ServiceUnknown => "org.freedesktop.DBus.Error.ServiceUnknown",
+            Self::UnknownMethod => "org.freedesktop.DBus.Error.UnknownMethod",
+        }
+    }
+}
+
+// A D-Bus error name and message, returned to the caller of a bus method.
+type BusError = (BusErrorName, String);
+
+fn bus_error(name: BusErrorName, message: impl Into<String>) -> BusError {
+    (name, message.into())
+}
+
+struct Peer {
+    connection: DBusConnection,
+    // Assigned by `Hello`
+    unique_name: Option<String>,
+    match_rules: Vec<MatchRule>,
+}
+
+#[derive(Clone)]
+struct NameOwner {
+    unique_name: String,
+    flags: u32,
+}
+
+struct State {
+    guid: String,
+    next_id: u64,
+    peers: Vec<Peer>,
+    // The queue of owners of each well-known name, the primary owner first.
+    names: HashMap<String, Vec<NameOwner>>,
+}
+
+impl State {
+    fn peer(&self, unique_name: &str) -> Option<&Peer> {
+        self.peers
+            .iter()
+            .find(|peer| peer.unique_name.as_deref() == Some(unique_name))
+    }
+
+    fn peer_mut(&mut self, unique_name: &str) -> Option<&mut Peer> {
+        self.peers
+            .iter_mut()
+            .find(|peer| peer.unique_name.as_deref() == Some(unique_name))
+    }
+
+    fn owner(&self, name: &str) -> Option<&str> {
+        if name == BUS_NAME {
+            Some(BUS_NAME)
+        } else if name.starts_with(':') {
+            self.peer(name).and_then(|peer| peer.unique_name.as_deref())
+        } else {
+            self.names
+                .get(name)
+                .and_then(|queue| queue.first())
+                .map(|owner| owner.unique_name.as_str())
+        }
+    }
+
+    fn deliver(&self, peer: &Peer, message: &DBusMessage) {
+        let Ok(message) = message.copy() else {
+            return;
+        };
+        // Messages created by the bus itself get a serial from the receiving connection.
+        let flags = if message.serial() == 0 {
+            DBusSendMessageFlags::NONE
+        } else {
+            DBusSendMessageFlags::PRESERVE_SERIAL
+        };
+        let _ = peer.connection.send_message(&message, flags);
+    }
+
+    fn rule_matches(&self, rule: &MatchRule, message: &DBusMessage) -> bool {
+        rule.matches(message, |name| self.owner(name))
+    }
+
+    // Sends `message` to all peers with a matching match rule.
+    fn broadcast(&self, message: &DBusMessage) {
+        for peer in &self.peers {
+            if peer.unique_name.is_some()
+                && peer
+                    .match_rules
+                    .iter()
+                    .any(|rule| self.rule_matches(rule, message))
+            {
+                self.deliver(peer, message);
+            }
+        }
+    }
+
+    // Sends `message` to `destination`, and to all other peers eavesdropping on it.
+    fn unicast(&self, destination: &Peer, message: &DBusMessage) {
+        self.deliver(destination, message);
+        for peer in &self.peers {
+            if !std::ptr::eq(peer, destination)
+                && peer.unique_name.is_some()
+                && peer
+                    .match_rules
+                    .iter()
+                    .any(|rule| rule.eavesdrop() && self.rule_matches(rule, message))
+            {
+                self.deliver(peer, message);
+            }
+        }
+    }
+
+    fn emit_bus_signal(&self, destination: Option<&str>, member: &str, args: Variant) {
+        let message = DBusMessage::new_signal(BUS_PATH, BUS_INTERFACE, member);
+        message.set_sender(Some(BUS_NAME));
+        message.set_destination(destination);
+        message.set_body(&args);
+        match destination {
+            Some(destination) => {
+                if let Some(peer) = self.peer(destination) {
+                    self.unicast(peer, &message);
+                }
+            }
+            None => self.broadcast(&message),
+        }
+    }
+
+    fn name_owner_changed(&self, name: &str, old_owner: &str, new_owner: &str) {
+        self.emit_bus_signal(
+            None,
+            "NameOwnerChanged",
+            (name, old_owner, new_owner).to_variant(),
+        );
+        if !old_owner.is_empty() && !name.starts_with(':') {
+            self.emit_bus_signal(Some(old_owner), "NameLost", (name,).to_variant());
+        }
+        if !new_owner.is_empty() {
+            self.emit_bus_signal(Some(new_owner), "NameAcquired", (name,).to_variant());
+        }
+    }
+
+    fn reply(&self, peer: &Peer, call: &DBusMessage, result: Result<Option<Variant>, BusError>) {
+        if call.flags().contains(DBusMessageFlags::NO_REPLY_EXPECTED) {
+            return;
+        }
+        let reply = match result {
+            Ok(body) => {
+                let reply = call.new_method_reply();
+                if let Some(body) = body {
+                    reply.set_body(&body);
+                }
+                reply
+            }
+            Err((name, message)) => call.new_method_error_literal(name.as_str(), &message),
+        };
+        reply.set_sender(Some(BUS_NAME));
+        self.deliver(peer, &reply);
+    }
+
+    fn add_connection(&mut self, connection: &DBusConnection) {
+        self.peers.push(Peer {
+            connection: connection.clone(),
+            unique_name: None,
+            match_rules: Vec::new(),
+        });
+    }
+
+    fn remove_connection(&mut self, connection: &DBusConnection) {
+        let Some(index) = self.peers.iter().position(|p| p.connection == *connection) else {
+            return;
+        };
+        let peer = self.peers.remove(index);
+        let Some(unique_name) = peer.unique_name else {
+            return;
+        };
+
+        let mut owned = self
+            .names
+            .iter()
+            .filter(|(_, queue)| queue.iter().any(|o| o.unique_name == unique_name))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        owned.sort();
+        for name in owned {
+            self.release_name(&unique_name, &name);
+        }
+        self.name_owner_changed(&unique_name, &unique_name, "");
+    }
+
+    // Handles a message received from `connection`.
+    fn dispatch(&mut self, connection: &DBusConnection, message: &DBusMessage) {
+        let Some(index) = self.peers.iter().position(|p| p.connection == *connection) else {
+            return;
+        };
+        let Ok(message) = message.copy() else {
+            return;
+        };
+
+        let Some(sender) = self.peers[index].unique_name.clone() else {
+            // Clients have to say `Hello` before anything else.
+            let is_hello = message.message_type() == DBusMessageType::MethodCall
+                && message.destination().as_deref() == Some(BUS_NAME)
+                && message.member().as_deref() == Some("Hello");
+            if is_hello {
+                let unique_name = format!(":1.{}", self.next_id);
+                self.next_id += 1;
+                self.peers[index].unique_name = Some(unique_name.clone());
+                message.set_sender(Some(&unique_name));
+                self.reply(
+                    &self.peers[index],
+                    &message,
+                    Ok(Some((unique_name.as_str(),).to_variant())),
+                );
+                self.name_owner_changed(&unique_name, "", &unique_name);
+            } else {
+                self.reply(
+                    &self.peers[index],
+                    &message,
+                    Err(bus_error(
+                        BusErrorName::AccessDenied,
+                        "Client tried to send a message other than Hello without being registered",
+                    )),
+                );
+            }
+            return;
+        };
+        message.set_sender(Some(&sender));
+
+        match message.destination() {
+            Some(destination) if destination == BUS_NAME => {
+                if message.message_type() == DBusMessageType::MethodCall {
+                    let result = self.handle_bus_call(&sender, &message);
+                    if let Some(peer) = self.peer(&sender) {
+                        self.reply(peer, &message, result);
+                    }
+                }
+            }
+            Some(destination) => match self.owner(&destination).and_then(|o| self.peer(o)) {
+                Some(peer) => self.unicast(peer, &message),
+                None if message.message_type() == DBusMessageType::MethodCall => {
+                    self.reply(
+                        &self.peers[index],
+                        &message,
+                        Err(bus_error(
+                            BusErrorName::ServiceUnknown,
+                            format!(
+                                "The name {destination} was not provided by any .service files"
+                            ),
+                        )),
+                    );
+                }
+                None => (),
+            },
+            None => self.broadcast(&message),
+        }
+    }
+
+    fn handle_bus_call(
+        &mut self,
+        sender: &str,
+        message: &DBusMessage,
+    ) -> Result<Option<Variant>, BusError> {
+        fn args<T: FromVariant>(message: &DBusMessage) -> Result<T, BusError> {
+            let body = message.body().unwrap_or_else(|| ().to_variant());
+            T::from_variant(&body).ok_or_else(|| {
+                bus_error(
+                    BusErrorName::InvalidArgs,
+                    format!("Invalid arguments of type {}", body.type_()),
+                )
+            })
+        }
+
+        let member = message.member().unwrap_or_default();
+        match (message.interface().as_deref(), member.as_str()) {
+            (Some(BUS_INTERFACE) | None, "Hello") => Err(bus_error(
+                BusErrorName::AccessDenied,
+                "Already handled an Hello message",
+            )),
+            (Some(BUS_INTERFACE) | None, "RequestName") => {
+                let (name, flags) = args::<(String, u32)>(message)?;
+                Ok(Some(
+                    (self.request_name(sender, &name, flags)?,).to_variant(),
+                ))
+            }
+            (Some(BUS_INTERFACE) | None, "ReleaseName") => {
+                let (name,) = args::<(String,)>(message)?;
+                if !crate::dbus_is_name(&name) || crate::dbus_is_unique_name(&name) {
+                    return Err(bus_error(
+                        BusErrorName::InvalidArgs,
+                        format!("Invalid bus name {name}"),
+                    ));
+                }
+                Ok(Some((self.release_name(sender, &name),).to_variant()))
+            }
+            (Some(BUS_INTERFACE) | None, "GetNameOwner") => {
+                let (name,) = args::<(String,)>(message)?;
+                match self.owner(&name) {
+                    Some(owner) => Ok(Some((owner,).to_variant())),
+                    None => Err(bus_error(
+                        BusErrorName::NameHasNoOwner,
+                        format!("Could not get owner of name '{name}': no such name"),
+                    )),
+                }
+            }
+            (Some(BUS_INTERFACE) | None, "NameHasOwner") => {
+                let (name,) = args::<(String,)>(message)?;
+                Ok(Some((self.owner(&name).is_some(),).to_variant()))
+            }
+            (Some(BUS_INTERFACE) | None, "ListNames") => {
+                let mut names = vec![BUS_NAME.to_string()];
+                names.extend(self.peers.iter().filter_map(|p| p.unique_name.clone()));
+                names.extend(self.names.keys().cloned());
+                Ok(Some((names,).to_variant()))
+            }
+            (Some(BUS_INTERFACE) | None, "ListActivatableNames") => {
+                Ok(Some((vec![BUS_NAME],).to_variant()))
+            }
+            (Some(BUS_INTERFACE) | None, "ListQueuedOwners") => {
+                let (name,) = args::<(String,)>(message)?;
+                let owners = match self.names.get(&name) {
+                    Some(queue) => queue.iter().map(|o| o.unique_name.clone()).collect(),
+                    None => match self.owner(&name) {
+                        Some(owner) => vec![owner.to_string()],
+                        None => {
+                            return Err(bus_error(
+                                BusErrorName::NameHasNoOwner,
+                                format!("Could not get owners of name '{name}': no such name"),
+                            ));
+                        }
+                    },
+                };
+                Ok(Some((owners,).to_variant()))
+            }
+            (Some(BUS_INTERFACE) | None, "StartServiceByName") => {
+                let (name, _flags) = args::<(String, u32)>(message)?;
+                if self.owner(&name).is_some() {
+                    // DBUS_START_REPLY_ALREADY_RUNNING
+                    Ok(Some((2u32,).to_variant()))
+                } else {
+                    Err(bus_error(
+                        BusErrorName::ServiceUnknown,
+                        format!("The name {name} was not provided by any .service files"),
+                    ))
+                }
+            }
+            (Some(BUS_INTERFACE) | None, "AddMatch") => {
+                let (rule,) = args::<(String,)>(message)?;
+                let match_rule = MatchRule::parse(&rule).ok_or_else(|| {
+                    bus_error(
+                        BusErrorName::MatchRuleInvalid,
+                        format!("Invalid match rule '{rule}'"),
+                    )
+                })?;
+                self.peer_mut(sender).unwrap().match_rules.push(match_rule);
+                Ok(None)
+            }
+            (Some(BUS_INTERFACE) | None, "RemoveMatch") => {
+                let (rule,) = args::<(String,)>(message)?;
+                let match_rule = MatchRule::parse(&rule).ok_or_else(|| {
+                    bus_error(
+                        BusErrorName::MatchRuleInvalid,
+                        format!("Invalid match rule '{rule}'"),
+                    )
+                })?;
+                let rules = &mut self.peer_mut(sender).unwrap().match_rules;
+                match rules.iter().position(|r| *r == match_rule) {
+                    Some(index) => {
+                        rules.remove(index);
+                        Ok(None)
+                    }
+                    None => Err(bus_error(
+                        BusErrorName::MatchRuleNotFound,
+                        format!("The given match rule wasn't found and can't be removed: {rule}"),
+                    )),
+                }
+            }
+            (Some(BUS_INTERFACE) | None, "GetId") => Ok(Some((self.guid.as_str(),).to_variant())),
+            (Some("org.freedesktop.DBus.Peer"), "Ping") => Ok(None),
+            (Some("org.freedesktop.DBus.Introspectable"), "Introspect") => {
+                Ok(Some((BUS_INTROSPECTION,).to_variant()))
+            }
+            (interface, member) => Err(bus_error(
+                BusErrorName::UnknownMethod,
+                format!(
+                    "Method {member} on interface {} is not supported",
+                    interface.unwrap_or_default()
+                ),
+            )),
+        }
+    }
+
+    fn request_name(&mut self, sender: &str, name: &str, flags: u32) -> Result<u32, BusError> {
+        if !crate::dbus_is_name(name) || crate::dbus_is_unique_name(name) {
+            return Err(bus_error(
+                BusErrorName::InvalidArgs,
+                format!("Invalid bus name {name}"),
+            ));
+        }
+        if name == BUS_NAME {
+            return Err(bus_error(
+                BusErrorName::InvalidArgs,
+                format!("Connection is not allowed to own the name {BUS_NAME}"),
+            ));
+        }
+
+        let new_owner = NameOwner {
+            unique_name: sender.to_string(),
+            flags,
+        };
+        let queue = self.names.entry(name.to_string()).or_default();
+        let Some(current) = queue.first().cloned() else {
+            queue.push(new_owner);
+            self.name_owner_changed(name, "", sender);
+            return Ok(REQUEST_NAME_REPLY_PRIMARY_OWNER);
+        };
+
+        if current.unique_name == sender {
+            queue[0].flags = flags;
+            Ok(REQUEST_NAME_REPLY_ALREADY_OWNER)
+        } else if flags & NAME_FLAG_REPLACE_EXISTING != 0
+            && current.flags & NAME_FLAG_ALLOW_REPLACEMENT != 0
+        {
+            queue.retain(|o| o.unique_name != sender);
+            queue.remove(0);
+            queue.insert(0, new_owner);
+            if current.flags & NAME_FLAG_DO_NOT_QUEUE == 0 {
+                queue.insert(1, current.clone());
+            }
+            self.name_owner_changed(name, &current.unique_name, sender);
+            Ok(REQUEST_NAME_REPLY_PRIMARY_OWNER)
+        } else if flags & NAME_FLAG_DO_NOT_QUEUE != 0 {
+            queue.retain(|o| o.unique_name != sender);
+            Ok(REQUEST_NAME_REPLY_EXISTS)
+        } else {
+            match queue.iter_mut().find(|o| o.unique_name == sender) {
+                Some(owner) => owner.flags = flags,
+                None => queue.push(new_owner),
+            }
+            Ok(REQUEST_NAME_REPLY_IN_QUEUE)
+        }
+    }
+
+    fn release_name(&mut self, sender: &str, name: &str) -> u32 {
+        let Some(queue) = self.names.get_mut(name) else {
+            return RELEASE_NAME_REPLY_NON_EXISTENT;
+        };
+        let Some(position) = queue.iter().position(|o| o.unique_name == sender) else {
+            return RELEASE_NAME_REPLY_NOT_OWNER;
+        };
+
+        queue.remove(position);
+        let new_owner = queue.first().map(|o| o.unique_name.clone());
+        if queue.is_empty() {
+            self.names.remove(name);
+        }
+        if position == 0 {
+            self.name_owner_changed(name, sender, new_owner.as_deref().unwrap_or_default());
+        }
+        RELEASE_NAME_REPLY_RELEASED
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A minimal D-Bus message bus running in the current process, for tests.
+///
+/// The bus listens on a Unix socket in a new temporary directory and serves connections from
+/// a thread of its own. It implements the parts of `org.freedesktop.DBus` needed by most
+/// applications: `Hello`, name ownership with queueing (`RequestName`, `ReleaseName`,
+/// `NameOwnerChanged`, `NameAcquired`, `NameLost`), name queries, and match rules for
+/// routing signals. It has no service activation and no security policy.
+///
+/// The bus shuts down, disconnecting all clients, when it is dropped.
+///
+/// ```no_run
+/// use gio::prelude::*;
+///
+/// let bus = gio::test_util::DBusBroker::new().unwrap();
+/// let connection = bus.connect().unwrap();
+/// assert!(connection.unique_name().is_some());
+///
+/// let _owner_id = gio::bus_own_name_on_connection(
+///     &connection,
+///     "org.gtk_rs.Test",
+///     gio::BusNameOwnerFlags::NONE,
+///     |_connection, name| println!("Acquired {name}"),
+///     |_connection, name| println!("Lost {name}"),
+/// );
+/// ```
+pub struct DBusBroker {
+    address: String,
+    dir: PathBuf,
+    main_loop: glib::MainLoop,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl DBusBroker {
+    // rustdoc-stripper-ignore-next
+    /// Starts a new bus.
+    pub fn new() -> Result<Self, glib::Error> {
+        let guid = crate::dbus_generate_guid().to_string();
+        let dir = glib::tmp_dir().join(format!("gio-test-bus-{guid}"));
+        std::fs::create_dir(&dir).map_err(|err| {
+            glib::Error::new(
+                IOErrorEnum::Failed,
+                &format!("Failed to create {}: {err}", dir.display()),
+            )
+        })?;
+        let path = dir.join("bus");
+        let address = format!(
+            "unix:path={}",
+            crate::dbus_address_escape_value(&path.to_string_lossy())
+        );
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let server_address = address.clone();
+        let thread = thread::Builder::new()
+            .name("gio-test-bus".into())
+            .spawn(move || {
+                let context = glib::MainContext::new();
+                context
+                    .with_thread_default(|| run_bus(&context, &server_address, guid, sender))
+                    .unwrap();
+            })
+            .unwrap();
+
+        let main_loop = match receiver.recv().unwrap() {
+            Ok(main_loop) => main_loop,
+            Err(err) => {
+                let _ = thread.join();
+                let _ = std::fs::remove_dir(&dir);
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            address,
+            dir,
+            main_loop,
+            thread: Some(thread),
+        })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The D-Bus address of the bus.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Opens a new message bus connection to the bus.
+    pub fn connect(&self) -> Result<DBusConnection, glib::Error> {
+        DBusConnection::for_address_sync(
+            &self.address,
+            DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            Cancellable::NONE,
+        )
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Opens a new message bus connection to the bus asynchronously.
+    pub async fn connect_future(&self) -> Result<DBusConnection, glib::Error> {
+        DBusConnection::for_address_future(
+            &self.address,
+            DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+        )
+        .await
+    }
+}
+
+impl Drop for DBusBroker {
+    fn drop(&mut self) {
+        self.main_loop.quit();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(self.dir.join("bus"));
+        let _ = std::fs::remove_dir(&self.dir);
+    }
+}
+
+impl std::fmt::Debug for DBusBroker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DBusBroker")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+// Runs the bus on the thread-default main context `context` until its main loop is quit.
+fn run_bus(
+    context: &glib::MainContext,
+    address: &str,
+    guid: String,
+    sender: std::sync::mpsc::Sender<Result<glib::MainLoop, glib::Error>>,
+) {
+    let server = match DBusServer::new_sync(
+        address,
+        DBusServerFlags::NONE,
+        &guid,
+        None,
+        Cancellable::NONE,
+    ) {
+        Ok(server) => server,
+        Err(err) => {
+            let _ = sender.send(Err(err));
+            return;
+        }
+    };
+
+    let state = Arc::new(Mutex::new(State {
+        guid,
+        next_id: 1,
+        peers: Vec::new(),
+        names: HashMap::new(),
+    }));
+
+    server.connect_new_connection({
+        let state = state.clone();
+        move |_server, connection| {
+            state.lock().unwrap().add_connection(connection);
+
+            // Filters run on the GDBus worker thread. All incoming messages are handled here
+            // and then dropped so that the connection doesn't process them itself.
+            let filter_state = state.clone();
+            connection.add_filter(move |connection, message, incoming| {
+                if incoming {
+                    filter_state.lock().unwrap().dispatch(connection, message);
+                    None
+                } else {
+                    Some(message.clone())
+                }
+            });
+
+            let closed_state = state.clone();
+            connection.connect_closed(move |connection, _, _| {
+                closed_state.lock().unwrap().remove_connection(connection);
+            });
+
+            true
+        }
+    });
+    server.start();
+
+    let main_loop = glib::MainLoop::new(Some(context), false);
+    if sender.send(Ok(main_loop.clone())).is_err() {
+        return;
+    }
+    main_loop.run();
+
+    server.stop();
+    let peers = std::mem::take(&mut state.lock().unwrap().peers);
+    for peer in peers {
+        let _ = peer.connection.close_sync(Cancellable::NONE);
+    }
+}
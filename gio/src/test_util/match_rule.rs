@@ -0,0 +1,288 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::collections::BTreeMap;
+
+use glib::VariantTy;
+
+use crate::{DBusMessage, DBusMessageType};
+
+// A parsed D-Bus match rule, as passed to `org.freedesktop.DBus.AddMatch`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct MatchRule {
+    message_type: Option<DBusMessageType>,
+    sender: Option<String>,
+    interface: Option<String>,
+    member: Option<String>,
+    path: Option<String>,
+    path_namespace: Option<String>,
+    destination: Option<String>,
+    args: BTreeMap<u32, String>,
+    arg_paths: BTreeMap<u32, String>,
+    arg0_namespace: Option<String>,
+    eavesdrop: bool,
+}
+
+// The highest argument index allowed in `argN` keys.
+const MAX_ARG: u32 = 63;
+
+impl MatchRule {
+    pub(super) fn parse(rule: &str) -> Option<Self> {
+        let mut result = Self::default();
+        let mut chars = rule.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let mut key = String::new();
+            loop {
+                match chars.next()? {
+                    '=' => break,
+                    c => key.push(c),
+                }
+            }
+
+            // Values are quoted with single quotes. Outside of quotes, `\'` is a quote.
+            let mut value = String::new();
+            let mut quoted = false;
+            while let Some(c) = chars.next() {
+                match c {
+                    '\'' => quoted = !quoted,
+                    '\\' if !quoted && chars.peek() == Some(&'\'') => {
+                        chars.next();
+                        value.push('\'');
+                    }
+                    ',' if !quoted => break,
+                    c => value.push(c),
+                }
+            }
+            if quoted {
+                return None;
+            }
+
+            result.set(key.trim(), value)?;
+        }
+
+        Some(result)
+    }
+
+    fn set(&mut self, key: &str, value: String) -> Option<()> {
+        match key {
+            "type" => {
+                self.message_type = Some(match value.as_str() {
+                    "signal" => DBusMessageType::Signal,
+                    "method_call" => DBusMessageType::MethodCall,
+                    "method_return" => DBusMessageType::MethodReturn,
+                    "error" => DBusMessageType::Error,
+                    _ => return None,
+                })
+            }
+            "sender" => self.sender = Some(value),
+            "interface" => self.interface = Some(value),
+            "member" => self.member = Some(value),
+            "path" => self.path = Some(value),
+            "path_namespace" => self.path_namespace = Some(value),
+            "destination" => self.destination = Some(value),
+            "arg0namespace" => self.arg0_namespace = Some(value),
+            "eavesdrop" => self.eavesdrop = value == "true",
+            _ => {
+                let arg = key.strip_prefix("arg")?;
+                let (index, is_path) = match arg.strip_suffix("path") {
+                    Some(index) => (index, true),
+                    None => (arg, false),
+                };
+                let index = index.parse::<u32>().ok().filter(|i| *i <= MAX_ARG)?;
+                if is_path {
+                    self.arg_paths.insert(index, value);
+                } else {
+                    self.args.insert(index, value);
+                }
+            }
+        }
+        Some(())
+    }
+
+    pub(super) fn eavesdrop(&self) -> bool {
+        self.eavesdrop
+    }
+
+    // Checks whether `message` matches this rule. `owner` resolves a well-known name to the
+    // unique name of its owner, to match `sender` rules using well-known names.
+    pub(super) fn matches<'a>(
+        &self,
+        message: &DBusMessage,
+        owner: impl Fn(&str) -> Option<&'a str>,
+    ) -> bool {
+        if self
+            .message_type
+            .is_some_and(|type_| type_ != message.message_type())
+        {
+            return false;
+        }
+
+        if let Some(ref sender) = self.sender {
+            let message_sender = message.sender();
+            let message_sender = message_sender.as_deref();
+            if message_sender != Some(sender.as_str()) && message_sender != owner(sender) {
+                return false;
+            }
+        }
+
+        let header_matches = |rule: &Option<String>, value: Option<glib::GString>| match rule {
+            Some(rule) => value.as_deref() == Some(rule.as_str()),
+            None => true,
+        };
+        if !header_matches(&self.interface, message.interface())
+            || !header_matches(&self.member, message.member())
+            || !header_matches(&self.path, message.path())
+            || !header_matches(&self.destination, message.destination())
+        {
+            return false;
+        }
+
+        if let Some(ref namespace) = self.path_namespace {
+            let Some(path) = message.path() else {
+                return false;
+            };
+            if namespace != "/"
+                && path != namespace.as_str()
+                && !path
+                    .strip_prefix(namespace.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            {
+                return false;
+            }
+        }
+
+        if self.args.is_empty() && self.arg_paths.is_empty() && self.arg0_namespace.is_none() {
+            return true;
+        }
+
+        let body = message.body();
+        let arg = |index: u32, path: bool| {
+            let body = body.as_ref()?;
+            if index as usize >= body.n_children() {
+                return None;
+            }
+            let value = body.child_value(index as usize);
+            let type_ = value.type_();
+            if type_ == VariantTy::STRING || (path && type_ == VariantTy::OBJECT_PATH) {
+                value.str().map(str::to_owned)
+            } else {
+                None
+            }
+        };
+
+        for (index, expected) in &self.args {
+            if arg(*index, false).as_deref() != Some(expected.as_str()) {
+                return false;
+            }
+        }
+
+        for (index, expected) in &self.arg_paths {
+            let Some(value) = arg(*index, true) else {
+                return false;
+            };
+            // Either both are equal, or one of them ends with '/' and is a prefix of the other.
+            let matches = value == *expected
+                || (expected.ends_with('/') && value.starts_with(expected.as_str()))
+                || (value.ends_with('/') && expected.starts_with(value.as_str()));
+            if !matches {
+                return false;
+            }
+        }
+
+        if let Some(ref namespace) = self.arg0_namespace {
+            let Some(value) = arg(0, false) else {
+                return false;
+            };
+            if value != *namespace
+                && !value
+                    .strip_prefix(namespace.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glib::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let rule = MatchRule::parse(
+            "type='signal', sender='org.freedesktop.DBus',member='NameOwnerChanged',arg0='org.example.Name'",
+        )
+        .unwrap();
+        assert_eq!(rule.message_type, Some(DBusMessageType::Signal));
+        assert_eq!(rule.sender.as_deref(), Some("org.freedesktop.DBus"));
+        assert_eq!(rule.member.as_deref(), Some("NameOwnerChanged"));
+        assert_eq!(rule.args[&0], "org.example.Name");
+
+        let rule = MatchRule::parse(r"arg1='it'\''s',arg2path='/a/'").unwrap();
+        assert_eq!(rule.args[&1], "it's");
+        assert_eq!(rule.arg_paths[&2], "/a/");
+
+        assert_eq!(MatchRule::parse(""), Some(MatchRule::default()));
+        assert_eq!(MatchRule::parse("type='unknown'"), None);
+        assert_eq!(MatchRule::parse("member='unterminated"), None);
+        assert_eq!(MatchRule::parse("arg64='x'"), None);
+        assert_eq!(MatchRule::parse("foo='bar'"), None);
+    }
+
+    #[test]
+    fn matches() {
+        let message =
+            DBusMessage::new_signal("/org/example/Object", "org.example.Iface", "Changed");
+        message.set_sender(Some(":1.1"));
+        message.set_body(&("org.example.Name.Sub", "/org/example/").to_variant());
+        let no_owner = |_: &str| None;
+
+        for rule in [
+            "",
+            "type='signal',interface='org.example.Iface',member='Changed'",
+            "sender=':1.1'",
+            "path_namespace='/org/example'",
+            "path_namespace='/'",
+            "arg0='org.example.Name.Sub'",
+            "arg0namespace='org.example.Name'",
+            "arg1path='/org/example/Object'",
+        ] {
+            assert!(
+                MatchRule::parse(rule).unwrap().matches(&message, no_owner),
+                "{rule}"
+            );
+        }
+
+        for rule in [
+            "type='method_call'",
+            "sender=':1.2'",
+            "member='Other'",
+            "path='/org/example'",
+            "path_namespace='/org/ex'",
+            "arg0='org.example.Name'",
+            "arg0namespace='org.example.Na'",
+            "arg1='/org/'",
+            "arg2='x'",
+        ] {
+            assert!(
+                !MatchRule::parse(rule).unwrap().matches(&message, no_owner),
+                "{rule}"
+            );
+        }
+
+        let rule = MatchRule::parse("sender='org.example.Name'").unwrap();
+        assert!(rule.matches(&message, |name: &str| {
+            (name == "org.example.Name").then_some(":1.1")
+        }));
+    }
+}
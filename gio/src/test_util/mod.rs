@@ -1,5 +1,17 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
+// rustdoc-stripper-ignore-next
+//! Utilities for testing code that uses GIO.
+
+#[cfg(unix)]
+mod dbus_broker;
+#[cfg(unix)]
+mod match_rule;
+
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+pub use self::dbus_broker::DBusBroker;
+
 #[cfg(test)]
 use std::sync::mpsc::{Sender, channel};
 
@@ -7,7 +19,7 @@ use std::sync::mpsc::{Sender, channel};
 use glib::{MainContext, MainLoop};
 
 #[cfg(test)]
-pub fn run_async<T: Send + 'static, Q: FnOnce(Sender<T>, MainLoop) + Send + 'static>(
+pub(crate) fn run_async<T: Send + 'static, Q: FnOnce(Sender<T>, MainLoop) + Send + 'static>(
     start: Q,
 ) -> T {
     let c = MainContext::new();
@@ -26,7 +38,9 @@ pub fn run_async<T: Send + 'static, Q: FnOnce(Sender<T>, MainLoop) + Send + 'sta
 }
 
 #[cfg(test)]
-pub fn run_async_local<T: 'static, Q: FnOnce(Sender<T>, MainLoop) + Send + 'static>(start: Q) -> T {
+pub(crate) fn run_async_local<T: 'static, Q: FnOnce(Sender<T>, MainLoop) + Send + 'static>(
+    start: Q,
+) -> T {
     let c = MainContext::new();
     let l = MainLoop::new(Some(&c), false);
     let l_clone = l.clone();
@@ -0,0 +1,215 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_channel::mpsc;
+use futures_core::{FusedStream, Stream};
+use glib::{Object, SignalHandlerId, prelude::*};
+
+use crate::{ListModel, list_model::ListModelIter, prelude::*};
+
+// rustdoc-stripper-ignore-next
+/// A [`ListModel`] whose items are known to be of type `T`.
+///
+/// All accessors return `T` directly instead of [`glib::Object`]s that have to be downcast.
+#[repr(transparent)]
+pub struct TypedListModel<T: IsA<Object>> {
+    model: ListModel,
+    phantom: PhantomData<T>,
+}
+
+impl<T: IsA<Object>> TypedListModel<T> {
+    // rustdoc-stripper-ignore-next
+    /// Wraps `model` if its item type is `T` or a subtype of `T`, and returns the model back
+    /// otherwise.
+    pub fn new(model: impl IsA<ListModel>) -> Result<Self, ListModel> {
+        let model = model.upcast();
+        if model.item_type().is_a(T::static_type()) {
+            Ok(Self {
+                model,
+                phantom: PhantomData,
+            })
+        } else {
+            Err(model)
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The underlying untyped model.
+    pub fn model(&self) -> &ListModel {
+        &self.model
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The number of items in the model.
+    pub fn len(&self) -> u32 {
+        self.model.n_items()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The item at `position`, or `None` if `position` is out of bounds.
+    pub fn get(&self, position: u32) -> Option<T> {
+        self.model
+            .item(position)
+            .map(|item| item.downcast::<T>().unwrap())
+    }
+
+    pub fn first(&self) -> Option<T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<T> {
+        self.len().checked_sub(1).and_then(|last| self.get(last))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns an iterator over the items of the model.
+    ///
+    /// See [`ListModelExtManual::iter`] for how modifications during iteration are handled.
+    pub fn iter(&self) -> ListModelIter<'_, T> {
+        self.model.iter()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the first item matching `predicate`.
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        (0..self.len())
+            .filter_map(|position| self.get(position))
+            .find(|item| predicate(item))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the position of the first item matching `predicate`.
+    pub fn position(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<u32> {
+        (0..self.len()).find(|&position| self.get(position).is_some_and(|item| predicate(&item)))
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Get an immutable snapshot of the items of the model.
+    pub fn snapshot(&self) -> Vec<T> {
+        (0..self.len())
+            .filter_map(|position| self.get(position))
+            .collect()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Returns a stream of `(position, removed, added)` tuples for every emission of
+    /// [`items-changed`](crate::prelude::ListModelExt::connect_items_changed).
+    ///
+    /// The stream ends once the model is finalized.
+    pub fn items_changed_stream(&self) -> ItemsChangedStream {
+        ItemsChangedStream::new(&self.model)
+    }
+}
+
+impl<T: IsA<Object>> Clone for TypedListModel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: IsA<Object>> fmt::Debug for TypedListModel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedListModel")
+            .field("model", &self.model)
+            .field("item_type", &T::static_type())
+            .finish()
+    }
+}
+
+impl<T: IsA<Object>> PartialEq for TypedListModel<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.model == other.model
+    }
+}
+
+impl<T: IsA<Object>> Eq for TypedListModel<T> {}
+
+impl<T: IsA<Object>> AsRef<ListModel> for TypedListModel<T> {
+    fn as_ref(&self) -> &ListModel {
+        &self.model
+    }
+}
+
+impl<T: IsA<Object>> From<TypedListModel<T>> for ListModel {
+    fn from(model: TypedListModel<T>) -> Self {
+        model.model
+    }
+}
+
+impl<'a, T: IsA<Object>> IntoIterator for &'a TypedListModel<T> {
+    type Item = <ListModelIter<'a, T> as Iterator>::Item;
+    type IntoIter = ListModelIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// rustdoc-stripper-ignore-next
+/// A stream of `(position, removed, added)` tuples emitted by a [`ListModel`]'s
+/// `items-changed` signal.
+///
+/// Dropping the stream disconnects from the signal.
+pub struct ItemsChangedStream {
+    receiver: mpsc::UnboundedReceiver<(u32, u32, u32)>,
+    model: glib::WeakRef<ListModel>,
+    signal_id: Option<SignalHandlerId>,
+}
+
+impl ItemsChangedStream {
+    pub(crate) fn new(model: &ListModel) -> Self {
+        let (sender, receiver) = mpsc::unbounded();
+        let signal_id = model.connect_items_changed(move |_, position, removed, added| {
+            let _ = sender.unbounded_send((position, removed, added));
+        });
+
+        Self {
+            receiver,
+            model: model.downgrade(),
+            signal_id: Some(signal_id),
+        }
+    }
+}
+
+impl fmt::Debug for ItemsChangedStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ItemsChangedStream")
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+impl Stream for ItemsChangedStream {
+    type Item = (u32, u32, u32);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl FusedStream for ItemsChangedStream {
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+impl Drop for ItemsChangedStream {
+    fn drop(&mut self) {
+        if let Some(model) = self.model.upgrade() {
+            model.disconnect(self.signal_id.take().unwrap());
+        }
+    }
+}
@@ -0,0 +1,322 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Bound, Deref, RangeBounds},
+};
+
+use glib::{Object, prelude::*};
+
+use crate::{ListModel, ListStore, TypedListModel};
+
+// rustdoc-stripper-ignore-next
+/// A [`ListStore`] whose items are of type `T`.
+///
+/// It dereferences to a [`TypedListModel<T>`] for read access and provides `Vec`-like
+/// mutation methods on top. Each mutation emits a single `items-changed` signal unless
+/// documented otherwise.
+///
+/// ```
+/// # use gio::prelude::*;
+/// let store = gio::TypedListStore::<gio::Menu>::new();
+/// store.extend_from_slice(&[gio::Menu::new(), gio::Menu::new()]);
+/// let first: gio::Menu = store.get(0).unwrap();
+/// assert_eq!(store.remove(0), first);
+/// assert_eq!(store.len(), 1);
+/// ```
+pub struct TypedListStore<T: IsA<Object>> {
+    model: TypedListModel<T>,
+}
+
+impl<T: IsA<Object>> TypedListStore<T> {
+    // rustdoc-stripper-ignore-next
+    /// Creates a new, empty store with item type `T`.
+    pub fn new() -> Self {
+        Self::from_store(ListStore::new::<T>()).unwrap()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Wraps `store` if its item type is `T` or a subtype of `T`, and returns the store back
+    /// otherwise.
+    pub fn from_store(store: ListStore) -> Result<Self, ListStore> {
+        match TypedListModel::new(store) {
+            Ok(model) => Ok(Self { model }),
+            Err(model) => Err(model.downcast().unwrap()),
+        }
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// The underlying untyped store.
+    pub fn store(&self) -> &ListStore {
+        // SAFETY: The model was created from a `ListStore`
+        unsafe { self.model.model().unsafe_cast_ref() }
+    }
+
+    pub fn append(&self, item: &T) {
+        self.store().append(item);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Inserts `item` at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position > len`.
+    pub fn insert(&self, position: u32, item: &T) {
+        assert!(
+            position <= self.len(),
+            "insertion position {position} out of bounds"
+        );
+        self.store().insert(position, item);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Inserts `item` at the position given by the sorted order of `compare`, and returns the
+    /// position.
+    pub fn insert_sorted_by(&self, item: &T, mut compare: impl FnMut(&T, &T) -> Ordering) -> u32 {
+        self.store().insert_sorted(item, |a, b| {
+            compare(a.downcast_ref().unwrap(), b.downcast_ref().unwrap())
+        })
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes and returns the item at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn remove(&self, position: u32) -> T {
+        let item = self
+            .get(position)
+            .unwrap_or_else(|| panic!("removal position {position} out of bounds"));
+        self.store().remove(position);
+        item
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Swaps the items at positions `a` and `b`.
+    ///
+    /// This emits `items-changed` twice if `a != b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn swap(&self, a: u32, b: u32) {
+        let len = self.len();
+        assert!(a < len && b < len, "swap positions out of bounds");
+        if a == b {
+            return;
+        }
+
+        let (low, high) = (a.min(b), a.max(b));
+        let low_item = self.get(low).unwrap();
+        let high_item = self.get(high).unwrap();
+        self.store().splice(high, 1, &[low_item]);
+        self.store().splice(low, 1, &[high_item]);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes the items in `range` and returns them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is after its end.
+    pub fn drain(&self, range: impl RangeBounds<u32>) -> std::vec::IntoIter<T> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).expect("range start overflow"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1).expect("range end overflow"),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end, "drain range start {start} after end {end}");
+        assert!(end <= self.len(), "drain range end {end} out of bounds");
+
+        let items = (start..end)
+            .map(|position| self.get(position).unwrap())
+            .collect::<Vec<_>>();
+        self.splice(start, end - start, &[]);
+        items.into_iter()
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes `n_removals` items at `position` and inserts `additions` in their place.
+    pub fn splice(&self, position: u32, n_removals: u32, additions: &[T]) {
+        self.store().splice(position, n_removals, additions);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Appends all items in `additions`.
+    pub fn extend_from_slice(&self, additions: &[T]) {
+        self.store().extend_from_slice(additions);
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Removes all items.
+    pub fn clear(&self) {
+        self.store().remove_all();
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Retains only the items for which `f` returns `true`.
+    ///
+    /// See [`ListStore::retain`].
+    pub fn retain(&self, mut f: impl FnMut(&T) -> bool) {
+        self.store().retain(|item| f(item.downcast_ref().unwrap()));
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Sorts the items with `compare`.
+    pub fn sort_by(&self, mut compare: impl FnMut(&T, &T) -> Ordering) {
+        self.store()
+            .sort(|a, b| compare(a.downcast_ref().unwrap(), b.downcast_ref().unwrap()));
+    }
+}
+
+impl<T: IsA<Object>> Default for TypedListStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: IsA<Object>> Clone for TypedListStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+        }
+    }
+}
+
+impl<T: IsA<Object>> fmt::Debug for TypedListStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedListStore")
+            .field("store", self.store())
+            .field("item_type", &T::static_type())
+            .finish()
+    }
+}
+
+impl<T: IsA<Object>> PartialEq for TypedListStore<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.model == other.model
+    }
+}
+
+impl<T: IsA<Object>> Eq for TypedListStore<T> {}
+
+impl<T: IsA<Object>> Deref for TypedListStore<T> {
+    type Target = TypedListModel<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.model
+    }
+}
+
+impl<T: IsA<Object>> AsRef<ListModel> for TypedListStore<T> {
+    fn as_ref(&self) -> &ListModel {
+        self.model.model()
+    }
+}
+
+impl<T: IsA<Object>> AsRef<ListStore> for TypedListStore<T> {
+    fn as_ref(&self) -> &ListStore {
+        self.store()
+    }
+}
+
+impl<T: IsA<Object>> From<TypedListStore<T>> for ListStore {
+    fn from(store: TypedListStore<T>) -> Self {
+        store.store().clone()
+    }
+}
+
+impl<T: IsA<Object>> From<TypedListStore<T>> for TypedListModel<T> {
+    fn from(store: TypedListStore<T>) -> Self {
+        store.model
+    }
+}
+
+impl<T: IsA<Object>> FromIterator<T> for TypedListStore<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut store = Self::new();
+        store.extend(iter);
+        store
+    }
+}
+
+impl<T: IsA<Object>> Extend<T> for TypedListStore<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let additions = iter.into_iter().collect::<Vec<_>>();
+        self.extend_from_slice(&additions);
+    }
+}
+
+impl<'a, T: IsA<Object>> Extend<&'a T> for TypedListStore<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().cloned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::Menu;
+
+    #[test]
+    fn mutations() {
+        let items = (0..5).map(|_| Menu::new()).collect::<Vec<_>>();
+        let store = items.iter().cloned().collect::<TypedListStore<Menu>>();
+        assert_eq!(store.len(), 5);
+        assert_eq!(store.get(2).as_ref(), Some(&items[2]));
+        assert_eq!(store.last().as_ref(), Some(&items[4]));
+
+        store.swap(0, 4);
+        assert_eq!(store.snapshot(), [4, 1, 2, 3, 0].map(|i| items[i].clone()));
+
+        assert_eq!(store.remove(1), items[1]);
+        store.insert(1, &items[1]);
+        assert_eq!(store.position(|item| item == &items[1]), Some(1));
+        assert_eq!(
+            store.find(|item| item == &items[3]).as_ref(),
+            Some(&items[3])
+        );
+
+        let drained = store.drain(1..3).collect::<Vec<_>>();
+        assert_eq!(drained, [items[1].clone(), items[2].clone()]);
+        assert_eq!(
+            store.snapshot(),
+            [items[4].clone(), items[3].clone(), items[0].clone()]
+        );
+
+        let iterated = store.iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(iterated, store.snapshot());
+
+        store.clear();
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn items_changed_stream() {
+        let store = TypedListStore::<Menu>::new();
+        let mut changes = store.items_changed_stream();
+
+        store.append(&Menu::new());
+        store.extend_from_slice(&[Menu::new(), Menu::new()]);
+        let _ = store.drain(..2);
+
+        let changes = futures::executor::block_on(changes.by_ref().take(3).collect::<Vec<_>>());
+        assert_eq!(changes, [(0, 0, 1), (1, 0, 2), (0, 2, 0)]);
+    }
+
+    #[test]
+    fn wrong_item_type() {
+        let store = ListStore::new::<ListStore>();
+        assert!(TypedListStore::<Menu>::from_store(store).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::{cell::RefCell, rc::Rc};
+
+use gio::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, glib::Actions)]
+enum TestAction {
+    Quit,
+    OpenUri(String),
+    #[action(name = "zoom", state = 1.0)]
+    ZoomLevel(f64),
+    #[action(state = false)]
+    Fullscreen,
+}
+
+#[test]
+fn descriptors() {
+    let descriptors = TestAction::action_descriptors();
+    let names = descriptors.iter().map(|d| d.name()).collect::<Vec<_>>();
+    assert_eq!(names, ["quit", "open-uri", "zoom", "fullscreen"]);
+
+    assert_eq!(descriptors[0].parameter_type(), None);
+    assert_eq!(
+        descriptors[1].parameter_type(),
+        Some(glib::VariantTy::STRING)
+    );
+    assert_eq!(descriptors[2].state(), Some(&1.0.to_variant()));
+    assert_eq!(descriptors[3].parameter_type(), None);
+    assert_eq!(descriptors[3].state(), Some(&false.to_variant()));
+}
+
+#[test]
+fn from_action() {
+    assert_eq!(
+        TestAction::from_action("quit", None),
+        Some(TestAction::Quit)
+    );
+    assert_eq!(
+        TestAction::from_action("open-uri", Some(&"foo".to_variant())),
+        Some(TestAction::OpenUri("foo".into()))
+    );
+    assert_eq!(TestAction::from_action("open-uri", None), None);
+    assert_eq!(
+        TestAction::from_action("open-uri", Some(&1.to_variant())),
+        None
+    );
+    assert_eq!(TestAction::from_action("unknown", None), None);
+}
+
+#[test]
+fn activate() {
+    let group = gio::SimpleActionGroup::new();
+    let activated = Rc::new(RefCell::new(Vec::new()));
+    let activated_clone = activated.clone();
+    group.add_actions(move |group: &gio::SimpleActionGroup, action: TestAction| {
+        if let TestAction::ZoomLevel(level) = action {
+            group
+                .lookup_action("zoom")
+                .unwrap()
+                .change_state(&level.to_variant());
+        }
+        activated_clone.borrow_mut().push(action);
+    });
+
+    assert_eq!(
+        group.action_parameter_type("open-uri").as_deref(),
+        Some(glib::VariantTy::STRING)
+    );
+    assert_eq!(group.action_state("zoom"), Some(1.0.to_variant()));
+
+    TestAction::Quit.activate_action(&group);
+    TestAction::OpenUri("https://gtk-rs.org".into()).activate_action(&group);
+    TestAction::ZoomLevel(2.0).activate_action(&group);
+    assert_eq!(
+        *activated.borrow(),
+        [
+            TestAction::Quit,
+            TestAction::OpenUri("https://gtk-rs.org".into()),
+            TestAction::ZoomLevel(2.0),
+        ]
+    );
+    assert_eq!(group.action_state("zoom"), Some(2.0.to_variant()));
+
+    TestAction::ZoomLevel(0.5).change_action_state(&group);
+    assert_eq!(group.action_state("zoom"), Some(0.5.to_variant()));
+}
+
+#[test]
+fn detailed_name() {
+    assert_eq!(TestAction::Quit.detailed_name("app"), "app.quit");
+    assert_eq!(
+        TestAction::OpenUri("https://gtk-rs.org".into()).detailed_name("win"),
+        "win.open-uri('https://gtk-rs.org')"
+    );
+    assert_eq!(TestAction::ZoomLevel(2.0).detailed_name(""), "zoom(2.0)");
+}
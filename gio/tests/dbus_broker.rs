@@ -0,0 +1,255 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+#[cfg(unix)]
+mod broker {
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use gio::{
+        BusNameOwnerFlags, BusNameWatcherFlags, Cancellable, DBusCallFlags, DBusConnection,
+        DBusSignalFlags, glib, prelude::*, test_util::DBusBroker,
+    };
+
+    fn wait_for(context: &glib::MainContext, mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            if !context.iteration(false) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    fn call_bus<T: FromVariant>(
+        connection: &DBusConnection,
+        method: &str,
+        args: impl ToVariant,
+    ) -> Result<T, glib::Error> {
+        let reply = connection.call_sync(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            method,
+            Some(&args.to_variant()),
+            None,
+            DBusCallFlags::NONE,
+            -1,
+            Cancellable::NONE,
+        )?;
+        Ok(T::from_variant(&reply).unwrap())
+    }
+
+    #[test]
+    fn own_and_watch_name() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let owner = bus.connect().unwrap();
+                let watcher = bus.connect().unwrap();
+                let owner_name = owner.unique_name().unwrap();
+                let events = Rc::new(RefCell::new(Vec::new()));
+
+                let watcher_id = gio::bus_watch_name_on_connection(
+                    &watcher,
+                    "org.gtk_rs.Test",
+                    BusNameWatcherFlags::NONE,
+                    glib::clone!(
+                        #[strong]
+                        events,
+                        move |_, name, name_owner| {
+                            events
+                                .borrow_mut()
+                                .push(format!("appeared {name} {name_owner}"));
+                        }
+                    ),
+                    glib::clone!(
+                        #[strong]
+                        events,
+                        move |_, name| events.borrow_mut().push(format!("vanished {name}"))
+                    ),
+                );
+                wait_for(&context, || events.borrow().len() == 1);
+
+                let owner_id = gio::bus_own_name_on_connection(
+                    &owner,
+                    "org.gtk_rs.Test",
+                    BusNameOwnerFlags::NONE,
+                    glib::clone!(
+                        #[strong]
+                        events,
+                        move |_, name| events.borrow_mut().push(format!("acquired {name}"))
+                    ),
+                    glib::clone!(
+                        #[strong]
+                        events,
+                        move |_, name| events.borrow_mut().push(format!("lost {name}"))
+                    ),
+                );
+                wait_for(&context, || events.borrow().len() == 3);
+                assert!(
+                    events
+                        .borrow()
+                        .contains(&"acquired org.gtk_rs.Test".to_string())
+                );
+                assert!(
+                    events
+                        .borrow()
+                        .contains(&format!("appeared org.gtk_rs.Test {owner_name}"))
+                );
+
+                gio::bus_unown_name(owner_id);
+                wait_for(&context, || events.borrow().len() == 4);
+                assert_eq!(events.borrow()[3], "vanished org.gtk_rs.Test");
+                gio::bus_unwatch_name(watcher_id);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn name_queue() {
+        let bus = DBusBroker::new().unwrap();
+        let first = bus.connect().unwrap();
+        let second = bus.connect().unwrap();
+        let first_name = first.unique_name().unwrap();
+        let second_name = second.unique_name().unwrap();
+        let name = "org.gtk_rs.Queue";
+
+        // DBUS_NAME_FLAG_ALLOW_REPLACEMENT => PRIMARY_OWNER
+        assert_eq!(
+            call_bus::<(u32,)>(&first, "RequestName", (name, 1u32)).unwrap(),
+            (1,)
+        );
+        // No flags => IN_QUEUE
+        assert_eq!(
+            call_bus::<(u32,)>(&second, "RequestName", (name, 0u32)).unwrap(),
+            (2,)
+        );
+        assert_eq!(
+            call_bus::<(Vec<String>,)>(&first, "ListQueuedOwners", (name,)).unwrap(),
+            (vec![first_name.to_string(), second_name.to_string()],)
+        );
+
+        // DBUS_NAME_FLAG_REPLACE_EXISTING => PRIMARY_OWNER
+        assert_eq!(
+            call_bus::<(u32,)>(&second, "RequestName", (name, 2u32)).unwrap(),
+            (1,)
+        );
+        assert_eq!(
+            call_bus::<(String,)>(&first, "GetNameOwner", (name,)).unwrap(),
+            (second_name.to_string(),)
+        );
+
+        // RELEASED, and the queued first connection becomes the owner again
+        assert_eq!(
+            call_bus::<(u32,)>(&second, "ReleaseName", (name,)).unwrap(),
+            (1,)
+        );
+        assert_eq!(
+            call_bus::<(String,)>(&second, "GetNameOwner", (name,)).unwrap(),
+            (first_name.to_string(),)
+        );
+
+        // Closing the connection releases its names
+        first.close_sync(Cancellable::NONE).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while call_bus::<(bool,)>(&second, "NameHasOwner", (name,))
+            .unwrap()
+            .0
+        {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let err = call_bus::<(String,)>(&second, "GetNameOwner", (name,)).unwrap_err();
+        assert_eq!(
+            gio::DBusError::remote_error(&err).as_deref(),
+            Some("org.freedesktop.DBus.Error.NameHasNoOwner")
+        );
+    }
+
+    #[test]
+    fn routing() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let emitter = bus.connect().unwrap();
+                let receiver = bus.connect().unwrap();
+                let emitter_name = emitter.unique_name().unwrap();
+
+                // Method calls and their replies are forwarded between peers
+                receiver
+                    .call_sync(
+                        Some(&emitter_name),
+                        "/",
+                        "org.freedesktop.DBus.Peer",
+                        "Ping",
+                        None,
+                        None,
+                        DBusCallFlags::NONE,
+                        -1,
+                        Cancellable::NONE,
+                    )
+                    .unwrap();
+
+                // Broadcast signals reach peers with matching match rules
+                let received = Rc::new(RefCell::new(Vec::new()));
+                let _subscription = receiver.subscribe_to_signal(
+                    Some(&emitter_name),
+                    Some("org.gtk_rs.Test"),
+                    Some("Changed"),
+                    None,
+                    None,
+                    DBusSignalFlags::NONE,
+                    glib::clone!(
+                        #[strong]
+                        received,
+                        move |signal| {
+                            received
+                                .borrow_mut()
+                                .push(signal.parameters.child_get::<i32>(0));
+                        }
+                    ),
+                );
+                // Make sure the match rule was added before emitting
+                call_bus::<(String,)>(&receiver, "GetId", ()).unwrap();
+
+                for value in [1, 2] {
+                    emitter
+                        .emit_signal(
+                            None,
+                            "/org/gtk_rs/Test",
+                            "org.gtk_rs.Test",
+                            "Changed",
+                            Some(&(value,).to_variant()),
+                        )
+                        .unwrap();
+                }
+                wait_for(&context, || received.borrow().len() == 2);
+                assert_eq!(*received.borrow(), [1, 2]);
+
+                // Calls to unknown names fail
+                let err = receiver
+                    .call_sync(
+                        Some("org.gtk_rs.Unknown"),
+                        "/",
+                        "org.freedesktop.DBus.Peer",
+                        "Ping",
+                        None,
+                        None,
+                        DBusCallFlags::NONE,
+                        -1,
+                        Cancellable::NONE,
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    gio::DBusError::remote_error(&err).as_deref(),
+                    Some("org.freedesktop.DBus.Error.ServiceUnknown")
+                );
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+#[cfg(unix)]
+mod error_domain {
+    use gio::{
+        DBusCallFlags, DBusConnection, DBusError, DBusNodeInfo, glib, glib::error::ErrorDomain,
+        prelude::*, test_util::DBusBroker,
+    };
+
+    const XML: &str = r#"
+    <node>
+      <interface name="org.gtk_rs.Store">
+        <method name="Lookup"/>
+        <method name="Remove"/>
+      </interface>
+    </node>
+    "#;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, glib::ErrorDomain)]
+    #[error_domain(name = "gio-test-store-error", dbus = "org.gtk_rs.Store.Error")]
+    enum StoreError {
+        NotFound,
+        #[error_domain(dbus_name = "org.gtk_rs.Store.Denied")]
+        PermissionDenied,
+    }
+
+    // Never used before an error of it is received
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, glib::ErrorDomain)]
+    #[error_domain(name = "gio-test-late-error", dbus = "org.gtk_rs.Late.Error")]
+    enum LateError {
+        Gone,
+    }
+
+    fn serve(connection: &DBusConnection) -> gio::RegistrationId {
+        let info = DBusNodeInfo::for_xml(XML)
+            .unwrap()
+            .lookup_interface("org.gtk_rs.Store")
+            .unwrap();
+        connection
+            .register_object("/org/gtk_rs/Store", &info)
+            .method_call(|_, _, _, _, method_name, _, invocation| match method_name {
+                "Lookup" => invocation.return_error(StoreError::NotFound, "no such key"),
+                _ => invocation.return_dbus_error("org.gtk_rs.Late.Error.Gone", "gone"),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            StoreError::NotFound.dbus_error_name(),
+            Some("org.gtk_rs.Store.Error.NotFound")
+        );
+        assert_eq!(
+            StoreError::from_dbus_error_name("org.gtk_rs.Store.Denied"),
+            Some(StoreError::PermissionDenied)
+        );
+        assert_eq!(
+            StoreError::from_dbus_error_name("org.gtk_rs.Store.Error.PermissionDenied"),
+            None
+        );
+
+        // Creating an error registers the names of the domain
+        let err = glib::Error::new(StoreError::PermissionDenied, "denied");
+        assert_eq!(
+            DBusError::encode_gerror(&err).as_str(),
+            "org.gtk_rs.Store.Denied"
+        );
+        let err = DBusError::new_for_dbus_error("org.gtk_rs.Store.Error.NotFound", "missing");
+        assert_eq!(err.kind::<StoreError>(), Some(StoreError::NotFound));
+    }
+
+    #[test]
+    fn round_trip() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let server = bus.connect().unwrap();
+                let client = bus.connect().unwrap();
+                let _registration = serve(&server);
+                let call = |method_name| {
+                    context.block_on(client.call_future(
+                        server.unique_name().as_deref(),
+                        "/org/gtk_rs/Store",
+                        "org.gtk_rs.Store",
+                        method_name,
+                        None,
+                        None,
+                        DBusCallFlags::NONE,
+                        -1,
+                    ))
+                };
+
+                let mut err = call("Lookup").unwrap_err();
+                assert_eq!(err.kind::<StoreError>(), Some(StoreError::NotFound));
+                assert_eq!(err.dbus_kind::<StoreError>(), Some(StoreError::NotFound));
+                assert_eq!(
+                    err.dbus_remote_error().as_deref(),
+                    Some("org.gtk_rs.Store.Error.NotFound")
+                );
+                assert!(err.strip_remote_error());
+                assert_eq!(err.message(), "no such key");
+                assert!(!err.strip_remote_error());
+
+                // Errors received before their domain was registered are still recognized by
+                // their name
+                let mut err = call("Remove").unwrap_err();
+                assert!(err.matches(gio::IOErrorEnum::DbusError));
+                assert_eq!(err.kind::<LateError>(), None);
+                assert_eq!(err.dbus_kind::<LateError>(), Some(LateError::Gone));
+                assert_eq!(err.dbus_kind::<StoreError>(), None);
+                assert!(err.strip_remote_error());
+                assert_eq!(err.message(), "gone");
+            })
+            .unwrap();
+    }
+}
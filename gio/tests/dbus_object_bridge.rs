@@ -0,0 +1,262 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+#[cfg(unix)]
+mod bridge {
+    use std::{
+        cell::RefCell,
+        collections::HashMap,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use gio::{
+        DBusCallFlags, DBusConnection, DBusObjectBridge, DBusObjectTree, DBusSignalFlags, glib,
+        prelude::*, test_util::DBusBroker,
+    };
+
+    mod imp {
+        use std::{
+            cell::{Cell, RefCell},
+            sync::OnceLock,
+        };
+
+        use glib::{prelude::*, subclass::Signal, subclass::prelude::*};
+
+        #[derive(Default, glib::Properties)]
+        #[properties(wrapper_type = super::Thermostat)]
+        pub struct Thermostat {
+            #[property(get, set, dbus)]
+            target_temperature: Cell<f64>,
+            #[property(get, set, dbus = "Label", nullable)]
+            display_name: RefCell<Option<String>>,
+            #[property(get, set)]
+            secret: Cell<u32>,
+            #[property(get, set, construct_only)]
+            serial: Cell<u32>,
+            #[property(get)]
+            model: RefCell<String>,
+            #[property(set)]
+            pin: Cell<u32>,
+        }
+
+        #[glib::object_subclass]
+        impl ObjectSubclass for Thermostat {
+            const NAME: &'static str = "GioTestThermostat";
+            type Type = super::Thermostat;
+        }
+
+        #[glib::derived_properties]
+        impl ObjectImpl for Thermostat {
+            fn signals() -> &'static [Signal] {
+                static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+                SIGNALS.get_or_init(|| {
+                    vec![
+                        Signal::builder("overheated")
+                            .param_types([f64::static_type(), String::static_type()])
+                            .build(),
+                    ]
+                })
+            }
+        }
+    }
+
+    glib::wrapper! {
+        pub struct Thermostat(ObjectSubclass<imp::Thermostat>);
+    }
+
+    fn call(
+        context: &glib::MainContext,
+        connection: &DBusConnection,
+        method_name: &str,
+        parameters: impl ToVariant,
+    ) -> Result<glib::Variant, glib::Error> {
+        context.block_on(connection.call_future(
+            connection.unique_name().as_deref(),
+            "/org/gtk_rs/Thermostat",
+            "org.freedesktop.DBus.Properties",
+            method_name,
+            Some(&parameters.to_variant()),
+            None,
+            DBusCallFlags::NONE,
+            -1,
+        ))
+    }
+
+    fn wait_for(context: &glib::MainContext, mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            if !context.iteration(false) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    #[test]
+    fn build() {
+        let thermostat = glib::Object::new::<Thermostat>();
+        let bridge = DBusObjectBridge::builder(&thermostat, "org.gtk_rs.Thermostat")
+            .exported_properties::<Thermostat>()
+            .property("secret")
+            .signal("overheated")
+            .build()
+            .unwrap();
+
+        let info = gio::DBusExportedInterface::interface_info(&bridge);
+        assert_eq!(info.name(), "org.gtk_rs.Thermostat");
+        let properties = info
+            .properties()
+            .map(|p| (p.name().to_owned(), p.signature().to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            properties,
+            [
+                ("TargetTemperature".to_owned(), "d".to_owned()),
+                ("Label".to_owned(), "s".to_owned()),
+                ("Secret".to_owned(), "u".to_owned()),
+            ]
+        );
+        assert!(info.lookup_signal("Overheated").is_some());
+
+        for builder in [
+            DBusObjectBridge::builder(&thermostat, "org.gtk_rs.Thermostat").property("missing"),
+            DBusObjectBridge::builder(&thermostat, "org.gtk_rs.Thermostat").signal("missing"),
+            DBusObjectBridge::builder(&thermostat, "not an interface"),
+        ] {
+            assert!(builder.build().is_err());
+        }
+    }
+
+    #[test]
+    fn access() {
+        use gio::{DBusError, DBusExportedInterface};
+
+        let thermostat = glib::Object::builder::<Thermostat>()
+            .property("serial", 7u32)
+            .build();
+        let bridge = DBusObjectBridge::builder(&thermostat, "org.gtk_rs.Thermostat")
+            .property("serial")
+            .property("model")
+            .property("pin")
+            .build()
+            .unwrap();
+
+        let access = bridge
+            .interface_info()
+            .properties()
+            .map(|p| (p.name().to_owned(), p.flags()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            access,
+            [
+                ("Serial".to_owned(), gio::DBusPropertyInfoFlags::READABLE),
+                ("Model".to_owned(), gio::DBusPropertyInfoFlags::READABLE),
+                ("Pin".to_owned(), gio::DBusPropertyInfoFlags::WRITABLE),
+            ]
+        );
+
+        assert_eq!(bridge.property("Serial").unwrap().get::<u32>(), Some(7));
+        for name in ["Serial", "Model"] {
+            let err = bridge.set_property(name, 8u32.to_variant()).unwrap_err();
+            assert!(err.matches(DBusError::PropertyReadOnly), "{err}");
+        }
+        assert_eq!(thermostat.serial(), 7);
+
+        let err = bridge.property("Pin").unwrap_err();
+        assert!(err.matches(DBusError::AccessDenied), "{err}");
+        bridge.set_property("Pin", 1234u32.to_variant()).unwrap();
+    }
+
+    #[test]
+    fn export() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let connection = bus.connect().unwrap();
+                let tree = DBusObjectTree::new(&connection);
+                let thermostat = glib::Object::builder::<Thermostat>()
+                    .property("display-name", "Hall")
+                    .build();
+                DBusObjectBridge::builder(&thermostat, "org.gtk_rs.Thermostat")
+                    .exported_properties::<Thermostat>()
+                    .signal("overheated")
+                    .build()
+                    .unwrap()
+                    .export(&tree, "/org/gtk_rs/Thermostat")
+                    .unwrap();
+
+                let received = Rc::new(RefCell::new(Vec::new()));
+                let _subscription = connection.subscribe_to_signal(
+                    None,
+                    None,
+                    None,
+                    Some("/org/gtk_rs/Thermostat"),
+                    None,
+                    DBusSignalFlags::NONE,
+                    glib::clone!(
+                        #[strong]
+                        received,
+                        move |signal| {
+                            received
+                                .borrow_mut()
+                                .push((signal.signal_name.to_owned(), signal.parameters.clone()))
+                        }
+                    ),
+                );
+
+                let (all,) = call(&context, &connection, "GetAll", ("org.gtk_rs.Thermostat",))
+                    .unwrap()
+                    .get::<(HashMap<String, glib::Variant>,)>()
+                    .unwrap();
+                assert_eq!(all["Label"].get::<String>().as_deref(), Some("Hall"));
+                assert_eq!(all["TargetTemperature"].get::<f64>(), Some(0.0));
+
+                // D-Bus writes go through to the object
+                call(
+                    &context,
+                    &connection,
+                    "Set",
+                    (
+                        "org.gtk_rs.Thermostat",
+                        "TargetTemperature",
+                        21.5.to_variant(),
+                    ),
+                )
+                .unwrap();
+                assert_eq!(thermostat.target_temperature(), 21.5);
+                wait_for(&context, || received.borrow().len() == 1);
+
+                // Object changes and signals go to the bus
+                thermostat.set_display_name(Some("Kitchen"));
+                thermostat.emit_by_name::<()>("overheated", &[&30.0, &"too hot"]);
+                wait_for(&context, || received.borrow().len() == 3);
+
+                let received = received.borrow();
+                assert_eq!(received[0].0, "PropertiesChanged");
+                let (_, changed, _) = received[0]
+                    .1
+                    .get::<(String, HashMap<String, glib::Variant>, Vec<String>)>()
+                    .unwrap();
+                assert_eq!(changed["TargetTemperature"].get::<f64>(), Some(21.5));
+
+                // The signal is emitted right away, the property change when idle
+                assert_eq!(received[1].0, "Overheated");
+                assert_eq!(
+                    received[1].1.get::<(f64, String)>(),
+                    Some((30.0, "too hot".to_owned()))
+                );
+                assert_eq!(received[2].0, "PropertiesChanged");
+                let (_, changed, _) = received[2]
+                    .1
+                    .get::<(String, HashMap<String, glib::Variant>, Vec<String>)>()
+                    .unwrap();
+                assert_eq!(changed["Label"].get::<String>().as_deref(), Some("Kitchen"));
+
+                // Removing the interface disconnects from the object
+                assert!(tree.remove_object("/org/gtk_rs/Thermostat"));
+                thermostat.set_display_name(Some("Garage"));
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,396 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+#[cfg(unix)]
+mod object_tree {
+    use std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use gio::{
+        DBusCallFlags, DBusConnection, DBusExportedInterface, DBusInterfaceInfo, DBusNodeInfo,
+        DBusObjectTree, DBusSignalFlags, glib, prelude::*, test_util::DBusBroker,
+    };
+
+    const XML: &str = r#"
+    <node>
+      <interface name="org.gtk_rs.Counter">
+        <method name="Increment"/>
+        <property name="Name" type="s" access="read"/>
+        <property name="Count" type="i" access="readwrite"/>
+        <property name="Cookie" type="s" access="read">
+          <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="invalidates"/>
+        </property>
+      </interface>
+    </node>
+    "#;
+
+    #[derive(Default)]
+    struct Counter {
+        count: Cell<i32>,
+    }
+
+    impl DBusExportedInterface for Counter {
+        fn interface_info(&self) -> DBusInterfaceInfo {
+            DBusNodeInfo::for_xml(XML)
+                .unwrap()
+                .lookup_interface("org.gtk_rs.Counter")
+                .unwrap()
+        }
+
+        fn method_call(
+            &self,
+            handle: &gio::DBusInterfaceHandle,
+            _sender: Option<&str>,
+            method_name: &str,
+            _parameters: glib::Variant,
+            invocation: gio::DBusMethodInvocation,
+        ) {
+            assert_eq!(method_name, "Increment");
+            self.count.set(self.count.get() + 1);
+            handle.notify_property_changed("Count");
+            handle.notify_property_changed("Cookie");
+            invocation.return_value(None);
+        }
+
+        fn property(&self, name: &str) -> Result<glib::Variant, glib::Error> {
+            Ok(match name {
+                "Name" => "counter".to_variant(),
+                "Count" => self.count.get().to_variant(),
+                "Cookie" => format!("cookie-{}", self.count.get()).to_variant(),
+                _ => unreachable!(),
+            })
+        }
+
+        fn set_property(&self, name: &str, value: glib::Variant) -> Result<(), glib::Error> {
+            assert_eq!(name, "Count");
+            self.count.set(value.get().unwrap());
+            Ok(())
+        }
+    }
+
+    fn call(
+        context: &glib::MainContext,
+        connection: &DBusConnection,
+        path: &str,
+        interface_name: &str,
+        method_name: &str,
+        parameters: impl ToVariant,
+    ) -> Result<glib::Variant, glib::Error> {
+        context.block_on(connection.call_future(
+            connection.unique_name().as_deref(),
+            path,
+            interface_name,
+            method_name,
+            Some(&parameters.to_variant()),
+            None,
+            DBusCallFlags::NONE,
+            -1,
+        ))
+    }
+
+    fn introspect(
+        context: &glib::MainContext,
+        connection: &DBusConnection,
+        path: &str,
+    ) -> DBusNodeInfo {
+        let xml = call(
+            context,
+            connection,
+            path,
+            "org.freedesktop.DBus.Introspectable",
+            "Introspect",
+            (),
+        )
+        .unwrap();
+        DBusNodeInfo::for_xml(xml.child_value(0).str().unwrap()).unwrap()
+    }
+
+    fn node_names(node: &DBusNodeInfo) -> Vec<String> {
+        node.nodes()
+            .iter()
+            .map(|n| n.path().unwrap().to_owned())
+            .collect()
+    }
+
+    fn wait_for(context: &glib::MainContext, mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            if !context.iteration(false) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    fn subscribe(
+        connection: &DBusConnection,
+        interface_name: &str,
+        signal_name: &str,
+    ) -> (gio::SignalSubscription, Rc<RefCell<Vec<glib::Variant>>>) {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let subscription = connection.subscribe_to_signal(
+            None,
+            Some(interface_name),
+            Some(signal_name),
+            None,
+            None,
+            DBusSignalFlags::NONE,
+            glib::clone!(
+                #[strong]
+                received,
+                move |signal| received.borrow_mut().push(signal.parameters.clone())
+            ),
+        );
+        (subscription, received)
+    }
+
+    #[test]
+    fn properties() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let connection = bus.connect().unwrap();
+                let tree = DBusObjectTree::new(&connection);
+                let handle = tree
+                    .add_interface("/org/gtk_rs/Counter", Counter::default())
+                    .unwrap();
+                assert!(handle.is_exported());
+                assert!(
+                    tree.add_interface("/org/gtk_rs/Counter", Counter::default())
+                        .is_err()
+                );
+                let (_subscription, changes) = subscribe(
+                    &connection,
+                    "org.freedesktop.DBus.Properties",
+                    "PropertiesChanged",
+                );
+
+                let all = call(
+                    &context,
+                    &connection,
+                    "/org/gtk_rs/Counter",
+                    "org.freedesktop.DBus.Properties",
+                    "GetAll",
+                    ("org.gtk_rs.Counter",),
+                )
+                .unwrap();
+                let (all,) = all.get::<(HashMap<String, glib::Variant>,)>().unwrap();
+                assert_eq!(all["Name"].get::<String>().as_deref(), Some("counter"));
+                assert_eq!(all["Count"].get::<i32>(), Some(0));
+
+                call(
+                    &context,
+                    &connection,
+                    "/org/gtk_rs/Counter",
+                    "org.freedesktop.DBus.Properties",
+                    "Set",
+                    ("org.gtk_rs.Counter", "Count", 5.to_variant()),
+                )
+                .unwrap();
+                wait_for(&context, || changes.borrow().len() == 1);
+
+                // Both notifications of the method call end up in one signal
+                call(
+                    &context,
+                    &connection,
+                    "/org/gtk_rs/Counter",
+                    "org.gtk_rs.Counter",
+                    "Increment",
+                    (),
+                )
+                .unwrap();
+                wait_for(&context, || changes.borrow().len() == 2);
+                // Nothing is pending anymore, and a round trip makes sure no further signal is
+                // on its way
+                tree.flush_property_changes();
+                call(
+                    &context,
+                    &connection,
+                    "/",
+                    "org.freedesktop.DBus.Peer",
+                    "Ping",
+                    (),
+                )
+                .unwrap();
+                assert_eq!(changes.borrow().len(), 2);
+
+                let changes = changes.borrow();
+                let (interface_name, changed, invalidated) = changes[0]
+                    .get::<(String, HashMap<String, glib::Variant>, Vec<String>)>()
+                    .unwrap();
+                assert_eq!(interface_name, "org.gtk_rs.Counter");
+                assert_eq!(changed.len(), 1);
+                assert_eq!(changed["Count"].get::<i32>(), Some(5));
+                assert!(invalidated.is_empty());
+
+                let (_, changed, invalidated) = changes[1]
+                    .get::<(String, HashMap<String, glib::Variant>, Vec<String>)>()
+                    .unwrap();
+                assert_eq!(changed.len(), 1);
+                assert_eq!(changed["Count"].get::<i32>(), Some(6));
+                assert_eq!(invalidated, ["Cookie"]);
+
+                assert!(tree.remove_interface("/org/gtk_rs/Counter", "org.gtk_rs.Counter"));
+                assert!(!handle.is_exported());
+                assert!(tree.object_paths().is_empty());
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn object_manager() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let connection = bus.connect().unwrap();
+                let tree = DBusObjectTree::with_object_manager(&connection, "/org/gtk_rs").unwrap();
+                let (_added_subscription, added) = subscribe(
+                    &connection,
+                    "org.freedesktop.DBus.ObjectManager",
+                    "InterfacesAdded",
+                );
+                let (_removed_subscription, removed) = subscribe(
+                    &connection,
+                    "org.freedesktop.DBus.ObjectManager",
+                    "InterfacesRemoved",
+                );
+
+                tree.add_interface("/org/gtk_rs/a/first", Counter::default())
+                    .unwrap();
+                tree.add_interface("/org/gtk_rs/b", Counter::default())
+                    .unwrap();
+                assert_eq!(tree.children("/"), ["org"]);
+                assert_eq!(tree.children("/org/gtk_rs"), ["a", "b"]);
+                assert_eq!(tree.children("/org/gtk_rs/a"), ["first"]);
+
+                let objects = call(
+                    &context,
+                    &connection,
+                    "/org/gtk_rs",
+                    "org.freedesktop.DBus.ObjectManager",
+                    "GetManagedObjects",
+                    (),
+                )
+                .unwrap();
+                assert_eq!(objects.type_().as_str(), "(a{oa{sa{sv}}})");
+                let (objects,) = objects
+                    .get::<(HashMap<String, HashMap<String, HashMap<String, glib::Variant>>>,)>()
+                    .unwrap();
+                assert_eq!(objects.len(), 2);
+                assert_eq!(
+                    objects["/org/gtk_rs/b"]["org.gtk_rs.Counter"]["Name"]
+                        .get::<String>()
+                        .as_deref(),
+                    Some("counter")
+                );
+
+                let node = introspect(&context, &connection, "/org/gtk_rs");
+                assert!(
+                    node.lookup_interface("org.freedesktop.DBus.ObjectManager")
+                        .is_some()
+                );
+                assert_eq!(node_names(&node), ["a", "b"]);
+
+                let node = tree.introspect("/org/gtk_rs/b");
+                assert!(node.lookup_interface("org.gtk_rs.Counter").is_some());
+                assert!(node.nodes().is_empty());
+
+                // Objects and intermediate nodes are introspectable remotely
+                let node = introspect(&context, &connection, "/org/gtk_rs/b");
+                assert!(node.lookup_interface("org.gtk_rs.Counter").is_some());
+                assert!(
+                    node.lookup_interface("org.freedesktop.DBus.Properties")
+                        .is_some()
+                );
+                assert!(node.nodes().is_empty());
+
+                let node = introspect(&context, &connection, "/org/gtk_rs/a");
+                assert!(
+                    node.interfaces()
+                        .iter()
+                        .all(|i| i.name().starts_with("org.freedesktop.DBus."))
+                );
+                assert_eq!(node_names(&node), ["first"]);
+
+                let node = introspect(&context, &connection, "/");
+                assert_eq!(node_names(&node), ["org"]);
+
+                assert!(tree.remove_object("/org/gtk_rs/b"));
+                assert!(!tree.remove_object("/org/gtk_rs/b"));
+                wait_for(&context, || {
+                    added.borrow().len() == 2 && removed.borrow().len() == 1
+                });
+
+                let (path, interfaces) = removed.borrow()[0]
+                    .get::<(glib::variant::ObjectPath, Vec<String>)>()
+                    .unwrap();
+                assert_eq!(path.as_str(), "/org/gtk_rs/b");
+                assert_eq!(interfaces, ["org.gtk_rs.Counter"]);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn introspection_with_other_objects() {
+        const OTHER_XML: &str = r#"
+        <node>
+          <interface name="org.gtk_rs.Other">
+            <method name="Poke"/>
+          </interface>
+        </node>
+        "#;
+
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let connection = bus.connect().unwrap();
+                let info = DBusNodeInfo::for_xml(OTHER_XML)
+                    .unwrap()
+                    .lookup_interface("org.gtk_rs.Other")
+                    .unwrap();
+                let register = |path| {
+                    connection
+                        .register_object(path, &info)
+                        .method_call(|_, _, _, _, _, _, invocation| invocation.return_value(None))
+                        .build()
+                        .unwrap()
+                };
+                let root = register("/");
+                let sibling = register("/org/gtk_rs/other");
+
+                let tree = DBusObjectTree::new(&connection);
+                tree.add_interface("/org/gtk_rs/counter", Counter::default())
+                    .unwrap();
+
+                // Objects outside the tree stay visible next to the tree's objects
+                let node = introspect(&context, &connection, "/");
+                assert!(node.lookup_interface("org.gtk_rs.Other").is_some());
+                assert_eq!(node_names(&node), ["org"]);
+
+                let node = introspect(&context, &connection, "/org/gtk_rs");
+                assert_eq!(node_names(&node), ["counter", "other"]);
+
+                let node = introspect(&context, &connection, "/org/gtk_rs/other");
+                assert!(node.lookup_interface("org.gtk_rs.Other").is_some());
+                assert!(node.lookup_interface("org.gtk_rs.Counter").is_none());
+
+                let node = introspect(&context, &connection, "/org/gtk_rs/counter");
+                assert!(node.lookup_interface("org.gtk_rs.Counter").is_some());
+                assert!(node.lookup_interface("org.gtk_rs.Other").is_none());
+
+                drop(tree);
+                let node = introspect(&context, &connection, "/org/gtk_rs");
+                assert_eq!(node_names(&node), ["other"]);
+
+                connection.unregister_object(sibling).unwrap();
+                connection.unregister_object(root).unwrap();
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+#[cfg(unix)]
+mod typed_call {
+    use std::os::unix::fs::MetadataExt;
+
+    use gio::{
+        DBusCallFlags, DBusConnection, DBusError, DBusNodeInfo, IOErrorEnum, glib, prelude::*,
+        test_util::DBusBroker,
+    };
+
+    const XML: &str = r#"
+    <node>
+      <interface name="org.gtk_rs.Files">
+        <method name="Describe">
+          <arg type="h" direction="in"/>
+          <arg type="h" direction="out"/>
+          <arg type="s" direction="out"/>
+        </method>
+        <method name="Fail"/>
+      </interface>
+    </node>
+    "#;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, glib::ErrorDomain)]
+    #[error_domain(name = "gio-test-typed-call-error")]
+    enum TestError {
+        Broken,
+    }
+
+    fn serve(connection: &DBusConnection) -> gio::RegistrationId {
+        let info = DBusNodeInfo::for_xml(XML)
+            .unwrap()
+            .lookup_interface("org.gtk_rs.Files")
+            .unwrap();
+        connection
+            .register_object("/org/gtk_rs/Files", &info)
+            .method_call(|_, _, _, _, method_name, parameters, invocation| {
+                match method_name {
+                    // Send the file descriptor back together with the size of the file
+                    "Describe" => {
+                        let fd_list = invocation.message().unix_fd_list().unwrap();
+                        let (fd,) = parameters.get::<(glib::variant::Handle,)>().unwrap();
+                        let file = std::fs::File::from(fd_list.get(fd.0).unwrap());
+                        let size = file.metadata().unwrap().len();
+                        let fd_list = gio::UnixFDList::from_array([file]);
+                        invocation.return_value_with_unix_fd_list(
+                            Some(&(glib::variant::Handle(0), size.to_string()).to_variant()),
+                            Some(&fd_list),
+                        );
+                    }
+                    _ => invocation.return_dbus_error("org.gtk_rs.Error.Broken", "it broke"),
+                }
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn typed_call() {
+        let bus = DBusBroker::new().unwrap();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let server = bus.connect().unwrap();
+                let client = bus.connect().unwrap();
+                let _registration = serve(&server);
+                let server_name = server.unique_name().unwrap();
+
+                // File descriptors are passed in both directions
+                let file = std::fs::File::open(file!()).unwrap();
+                let metadata = file.metadata().unwrap();
+                let (fd, size) = context
+                    .block_on(client.call_typed_future::<_, (gio::FdHandle, String)>(
+                        Some(&server_name),
+                        "/org/gtk_rs/Files",
+                        "org.gtk_rs.Files",
+                        "Describe",
+                        (gio::FdHandle::from(file),),
+                        DBusCallFlags::NONE,
+                        -1,
+                    ))
+                    .unwrap();
+                assert_eq!(size, metadata.len().to_string());
+                let received = std::fs::File::from(fd.0).metadata().unwrap();
+                assert_eq!(
+                    (received.dev(), received.ino()),
+                    (metadata.dev(), metadata.ino())
+                );
+
+                // Mismatching reply types are errors
+                let err = context
+                    .block_on(client.call_typed_future::<_, (String,)>(
+                        Some(&server_name),
+                        "/org/gtk_rs/Files",
+                        "org.gtk_rs.Files",
+                        "Fail",
+                        ("unexpected",),
+                        DBusCallFlags::NONE,
+                        -1,
+                    ))
+                    .unwrap_err();
+                assert!(err.matches(IOErrorEnum::InvalidArgument), "{err}");
+
+                // Unregistered remote errors keep their name
+                let fail = || {
+                    context.block_on(client.call_typed_future::<_, ()>(
+                        Some(&server_name),
+                        "/org/gtk_rs/Files",
+                        "org.gtk_rs.Files",
+                        "Fail",
+                        (),
+                        DBusCallFlags::NONE,
+                        -1,
+                    ))
+                };
+                let err = fail().unwrap_err();
+                assert!(err.matches(IOErrorEnum::DbusError));
+                assert_eq!(
+                    DBusError::remote_error(&err).as_deref(),
+                    Some("org.gtk_rs.Error.Broken")
+                );
+
+                // Registered ones are mapped to their error domain
+                assert!(DBusError::register_error(
+                    <TestError as glib::error::ErrorDomain>::domain(),
+                    TestError::Broken as i32,
+                    "org.gtk_rs.Error.Broken",
+                ));
+                let err = fail().unwrap_err();
+                assert_eq!(err.kind::<TestError>(), Some(TestError::Broken));
+                assert_eq!(err.message(), "it broke");
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let bus = DBusBroker::new().unwrap();
+        let client = bus.connect().unwrap();
+        let err = glib::MainContext::default()
+            .block_on(client.call_typed_future::<_, ()>(
+                None,
+                "/",
+                "org.gtk_rs.Files",
+                "Describe",
+                "not a tuple".to_variant(),
+                DBusCallFlags::NONE,
+                -1,
+            ))
+            .unwrap_err();
+        assert!(err.matches(IOErrorEnum::InvalidArgument));
+    }
+}
@@ -0,0 +1,187 @@
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use heck::ToKebabCase;
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{Data, Fields, spanned::Spanned};
+
+use crate::utils::{NestedMetaItem, crate_ident_new, gio_crate_ident_new, parse_nested_meta_items};
+
+struct ActionVariant<'a> {
+    ident: &'a syn::Ident,
+    name: String,
+    parameter_type: Option<&'a syn::Type>,
+    state: Option<syn::Expr>,
+}
+
+// Same rules as `g_action_name_is_valid()`.
+fn is_valid_action_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+}
+
+fn parse_variant(variant: &syn::Variant) -> syn::Result<ActionVariant<'_>> {
+    let parameter_type = match variant.fields {
+        Fields::Unit => None,
+        Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            Some(&fields.unnamed.first().unwrap().ty)
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "#[derive(glib::Actions)] only supports unit variants and variants with a single unnamed field",
+            ));
+        }
+    };
+
+    let mut name = NestedMetaItem::<syn::LitStr>::new("name").value_required();
+    let mut state = NestedMetaItem::<syn::Expr>::new("state").value_required();
+    parse_nested_meta_items(&variant.attrs, "action", &mut [&mut name, &mut state])?;
+
+    let name = match name.value {
+        Some(name) => {
+            if !is_valid_action_name(&name.value()) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "action names may only contain alphanumeric characters, '-' and '.'",
+                ));
+            }
+            name.value()
+        }
+        None => variant.ident.to_string().to_kebab_case(),
+    };
+
+    Ok(ActionVariant {
+        ident: &variant.ident,
+        name,
+        parameter_type,
+        state: state.value,
+    })
+}
+
+pub fn impl_actions(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+
+    let enum_variants = match input.data {
+        Data::Enum(ref e) => &e.variants,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "#[derive(glib::Actions)] only supports enums",
+            ));
+        }
+    };
+
+    let variants = enum_variants
+        .iter()
+        .map(parse_variant)
+        .collect::<syn::Result<Vec<_>>>()?;
+    for (i, variant) in variants.iter().enumerate() {
+        if variants[..i].iter().any(|v| v.name == variant.name) {
+            return Err(syn::Error::new_spanned(
+                variant.ident,
+                format!("duplicate action name \"{}\"", variant.name),
+            ));
+        }
+    }
+
+    let glib = crate_ident_new();
+    let gio = gio_crate_ident_new();
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let descriptors = variants.iter().map(|v| {
+        let action_name = &v.name;
+        let parameter_type = match v.parameter_type {
+            Some(ty) => quote_spanned! { ty.span() =>
+                ::core::option::Option::Some(
+                    <#ty as #glib::variant::StaticVariantType>::static_variant_type().into_owned()
+                )
+            },
+            None => quote! { ::core::option::Option::None },
+        };
+        // The state of an action with parameter has to be of the parameter's type.
+        let state = match (&v.state, v.parameter_type) {
+            (Some(state), Some(ty)) => quote_spanned! { state.span() =>
+                ::core::option::Option::Some({
+                    let state: #ty = #state;
+                    #glib::variant::ToVariant::to_variant(&state)
+                })
+            },
+            (Some(state), None) => quote_spanned! { state.span() =>
+                ::core::option::Option::Some(#glib::variant::ToVariant::to_variant(&(#state)))
+            },
+            (None, _) => quote! { ::core::option::Option::None },
+        };
+        quote! {
+            #gio::ActionDescriptor::new(#action_name, #parameter_type, #state)
+        }
+    });
+
+    let names = variants.iter().map(|v| {
+        let ident = v.ident;
+        let action_name = &v.name;
+        match v.parameter_type {
+            Some(_) => quote! { Self::#ident(_) => #action_name },
+            None => quote! { Self::#ident => #action_name },
+        }
+    });
+
+    let targets = variants.iter().map(|v| {
+        let ident = v.ident;
+        match v.parameter_type {
+            Some(_) => quote! {
+                Self::#ident(ref value) => ::core::option::Option::Some(
+                    #glib::variant::ToVariant::to_variant(value)
+                )
+            },
+            None => quote! { Self::#ident => ::core::option::Option::None },
+        }
+    });
+
+    let from_actions = variants.iter().map(|v| {
+        let ident = v.ident;
+        let action_name = &v.name;
+        match v.parameter_type {
+            Some(ty) => quote_spanned! { ty.span() =>
+                #action_name => ::core::option::Option::Some(
+                    Self::#ident(<#ty as #glib::variant::FromVariant>::from_variant(parameter?)?)
+                )
+            },
+            None => quote! {
+                #action_name if parameter.is_none() => ::core::option::Option::Some(Self::#ident)
+            },
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #gio::Actions for #name #type_generics #where_clause {
+            fn action_descriptors() -> ::std::vec::Vec<#gio::ActionDescriptor> {
+                ::std::vec![#(#descriptors),*]
+            }
+
+            fn action_name(&self) -> &'static str {
+                match *self {
+                    #(#names,)*
+                }
+            }
+
+            fn action_target(&self) -> ::core::option::Option<#glib::Variant> {
+                match *self {
+                    #(#targets,)*
+                }
+            }
+
+            fn from_action(
+                name: &str,
+                parameter: ::core::option::Option<&#glib::Variant>,
+            ) -> ::core::option::Option<Self> {
+                match name {
+                    #(#from_actions,)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    })
+}
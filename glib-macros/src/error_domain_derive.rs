@@ -4,7 +4,10 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Data;
 
-use crate::utils::{NestedMetaItem, crate_ident_new, gen_enum_from_glib, parse_nested_meta_items};
+use crate::utils::{
+    NestedMetaItem, crate_ident_new, gen_enum_from_glib, gio_crate_ident_new,
+    parse_nested_meta_items,
+};
 
 pub fn impl_error_domain(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
     let name = &input.ident;
@@ -22,7 +25,12 @@ pub fn impl_error_domain(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
     let mut domain_name = NestedMetaItem::<syn::LitStr>::new("name")
         .required()
         .value_required();
-    let found = parse_nested_meta_items(&input.attrs, "error_domain", &mut [&mut domain_name])?;
+    let mut dbus = NestedMetaItem::<syn::LitStr>::new("dbus").value_required();
+    let found = parse_nested_meta_items(
+        &input.attrs,
+        "error_domain",
+        &mut [&mut domain_name, &mut dbus],
+    )?;
 
     if found.is_none() {
         return Err(syn::Error::new_spanned(
@@ -35,6 +43,42 @@ pub fn impl_error_domain(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
 
     let from_glib = gen_enum_from_glib(name, enum_variants);
 
+    let dbus_names = match dbus.value {
+        Some(prefix) => Some(dbus_error_names(&prefix, enum_variants)?),
+        None => None,
+    };
+
+    let register_dbus_errors = dbus_names.as_ref().map(|names| {
+        let gio = gio_crate_ident_new();
+        let (variants, names): (Vec<_>, Vec<_>) = names.iter().cloned().unzip();
+        quote! {
+            #(
+                #gio::DBusError::register_error(quark, #name::#variants as i32, #names);
+            )*
+        }
+    });
+
+    let dbus_error_name_fns = dbus_names.as_ref().map(|names| {
+        let (variants, names): (Vec<_>, Vec<_>) = names.iter().cloned().unzip();
+        quote! {
+            fn dbus_error_name(self) -> ::core::option::Option<&'static str> {
+                match self {
+                    #(#name::#variants => ::core::option::Option::Some(#names),)*
+                }
+            }
+
+            fn from_dbus_error_name(name: &str) -> ::core::option::Option<Self>
+            where
+                Self: ::std::marker::Sized
+            {
+                match name {
+                    #(#names => ::core::option::Option::Some(#name::#variants),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    });
+
     Ok(quote! {
         impl #crate_ident::error::ErrorDomain for #name {
             #[inline]
@@ -42,8 +86,12 @@ pub fn impl_error_domain(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
                 use #crate_ident::translate::from_glib;
 
                 static QUARK: ::std::sync::OnceLock<#crate_ident::Quark> = ::std::sync::OnceLock::new();
-                *QUARK.get_or_init(|| unsafe {
-                    from_glib(#crate_ident::ffi::g_quark_from_static_string(concat!(#domain_name, "\0") as *const ::core::primitive::str as *const _))
+                *QUARK.get_or_init(|| {
+                    let quark: #crate_ident::Quark = unsafe {
+                        from_glib(#crate_ident::ffi::g_quark_from_static_string(concat!(#domain_name, "\0") as *const ::core::primitive::str as *const _))
+                    };
+                    #register_dbus_errors
+                    quark
                 })
             }
 
@@ -59,6 +107,54 @@ pub fn impl_error_domain(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
             {
                 #from_glib
             }
+
+            #dbus_error_name_fns
         }
     })
 }
+
+// Returns the D-Bus error name of each variant, `<prefix>.<Variant>` unless overridden with
+// `#[error_domain(dbus_name = "...")]` on the variant.
+fn dbus_error_names(
+    prefix: &syn::LitStr,
+    enum_variants: &syn::punctuated::Punctuated<syn::Variant, syn::Token![,]>,
+) -> syn::Result<Vec<(syn::Ident, syn::LitStr)>> {
+    let mut names = Vec::new();
+    for variant in enum_variants {
+        if !matches!(variant.fields, syn::Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "#[error_domain(dbus = ...)] only supports unit variants",
+            ));
+        }
+        let mut dbus_name = NestedMetaItem::<syn::LitStr>::new("dbus_name").value_required();
+        parse_nested_meta_items(&variant.attrs, "error_domain", &mut [&mut dbus_name])?;
+        let dbus_name = dbus_name.value.unwrap_or_else(|| {
+            syn::LitStr::new(
+                &format!("{}.{}", prefix.value(), variant.ident),
+                prefix.span(),
+            )
+        });
+        if !is_valid_dbus_error_name(&dbus_name.value()) {
+            return Err(syn::Error::new_spanned(
+                &dbus_name,
+                format!("`{}` is not a valid D-Bus error name", dbus_name.value()),
+            ));
+        }
+        names.push((variant.ident.clone(), dbus_name));
+    }
+    Ok(names)
+}
+
+// D-Bus error names follow the rules of interface names.
+fn is_valid_dbus_error_name(name: &str) -> bool {
+    name.len() <= 255
+        && name.split('.').count() >= 2
+        && name.split('.').all(|element| {
+            !element.is_empty()
+                && !element.starts_with(|c: char| c.is_ascii_digit())
+                && element
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
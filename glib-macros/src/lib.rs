@@ -1,5 +1,6 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
+mod actions_derive;
 mod async_test;
 mod boxed_derive;
 mod clone;
@@ -807,6 +808,23 @@ pub fn flags(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// With `dbus = "..."`, every variant also gets a D-Bus error name, `<prefix>.<Variant>` by
+/// default or the one given with `#[error_domain(dbus_name = "...")]` on the variant. The names
+/// are registered with `gio::DBusError::register_error()` when the error domain is first used,
+/// so errors of this domain are sent over D-Bus under their name and mapped back to the enum
+/// when received. This requires the `gio` crate.
+///
+/// ```ignore
+/// #[derive(Debug, Copy, Clone, glib::ErrorDomain)]
+/// #[error_domain(name = "ex-foo", dbus = "org.example.Foo.Error")]
+/// enum Foo {
+///     // org.example.Foo.Error.Blah
+///     Blah,
+///     #[error_domain(dbus_name = "org.freedesktop.DBus.Error.Failed")]
+///     Baaz,
+/// }
+/// ```
+///
 /// [`ErrorDomain`]: ../glib/error/trait.ErrorDomain.html
 #[proc_macro_derive(ErrorDomain, attributes(error_domain))]
 pub fn error_domain_derive(input: TokenStream) -> TokenStream {
@@ -816,6 +834,60 @@ pub fn error_domain_derive(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Derive macro for describing the actions of an action map with an enum, implementing
+/// [`gio::Actions`].
+///
+/// Each variant describes one action. Unit variants are actions without parameter, and
+/// variants with a single field are actions whose parameter is that field, converted with
+/// [`ToVariant`] and [`FromVariant`]. The parameter type is taken from the field's
+/// [`StaticVariantType`], so mismatches are caught at compile time.
+///
+/// Action names default to the [kebab case] variant name. The following attributes are
+/// supported on variants:
+///
+/// - `#[action(name = "...")]` overrides the action name.
+/// - `#[action(state = ...)]` makes the action stateful with the given initial state. If the
+///   action has a parameter, the state has to be of the parameter's type.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use gio::prelude::*;
+///
+/// #[derive(glib::Actions)]
+/// enum WindowAction {
+///     Close,
+///     Open(String),
+///     #[action(name = "fullscreen", state = false)]
+///     ToggleFullscreen,
+///     #[action(state = 100)]
+///     Zoom(i32),
+/// }
+///
+/// let group = gio::SimpleActionGroup::new();
+/// group.add_actions(|group: &gio::SimpleActionGroup, action: WindowAction| match action {
+///     WindowAction::Close => println!("Closing"),
+///     WindowAction::Open(path) => println!("Opening {path}"),
+///     WindowAction::ToggleFullscreen => (),
+///     WindowAction::Zoom(zoom) => group.change_action_state("zoom", &zoom.to_variant()),
+/// });
+///
+/// WindowAction::Zoom(150).activate_action(&group);
+/// ```
+///
+/// [`gio::Actions`]: ../gio/trait.Actions.html
+/// [`ToVariant`]: ../glib/variant/trait.ToVariant.html
+/// [`FromVariant`]: ../glib/variant/trait.FromVariant.html
+/// [`StaticVariantType`]: ../glib/variant/trait.StaticVariantType.html
+/// [kebab case]: https://docs.rs/heck/0.4.0/heck/trait.ToKebabCase.html
+#[proc_macro_derive(Actions, attributes(action))]
+pub fn actions_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    actions_derive::impl_actions(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 /// Derive macro for defining a [`BoxedType`]`::type_` function and
 /// the [`glib::Value`] traits. Optionally, the type can be marked as
 /// `nullable` to get an implementation of `glib::value::ToValueOptional`.
@@ -1338,6 +1410,7 @@ pub fn cstr_bytes(item: TokenStream) -> TokenStream {
 /// | `builder(<required-params>)[.ident]*` | Used to input required params or add optional Param Spec builder fields | | `#[property(builder(SomeEnum::default()))]`, `#[builder().default_value(1).minimum(0).maximum(5)]`, etc.  |
 /// | `default` | Sets the param spec builder field to the default value | | `#[property(default)]` |
 /// | `default = expr` | Sets the `default_value` field of the Param Spec builder | | `#[property(default = 1)]` |
+/// | `dbus [= "literal"]` | Exports the property with `gio::DBusObjectBridge`, under the given name or the property name in D-Bus style (`icon-name` becomes `IconName`). Implements `gio::DBusExportedProperties` for the wrapper type | | `#[property(get, dbus)]`, `#[property(get, dbus = "Icon")]` |
 /// | `<optional-pspec-builder-fields> = expr` | Used to add optional Param Spec builder fields | | `#[property(minimum = 0)` , `#[property(minimum = 0, maximum = 1)]`, etc. |
 /// | `<optional-pspec-builder-fields>` | Used to add optional Param Spec builder fields | | `#[property(explicit_notify)]` , `#[property(construct_only)]`, etc. |
 ///
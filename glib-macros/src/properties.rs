@@ -1,6 +1,7 @@
 // Take a look at the license at the top of the repository in the LICENSE file.
 
-use crate::utils::crate_ident_new;
+use crate::utils::{crate_ident_new, gio_crate_ident_new};
+use heck::ToUpperCamelCase;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::format_ident;
@@ -141,6 +142,9 @@ enum PropAttr {
 
     // ident
     Default,
+
+    // ident [= "literal"]
+    DBus(Option<syn::LitStr>),
 }
 
 impl Parse for PropAttr {
@@ -159,6 +163,7 @@ impl Parse for PropAttr {
                 "override_interface" => PropAttr::OverrideInterface(input.parse()?),
                 "type" => PropAttr::Type(input.parse()?),
                 "member" => PropAttr::Member(input.parse()?),
+                "dbus" => PropAttr::DBus(Some(input.parse()?)),
                 // Special case "default = ..." and map it to .default_value(...)
                 "default" => PropAttr::BuilderField((
                     syn::Ident::new("default_value", name.span()),
@@ -198,6 +203,7 @@ impl Parse for PropAttr {
                     ));
                 }
                 "default" => PropAttr::Default,
+                "dbus" => PropAttr::DBus(None),
                 _ => PropAttr::BuilderField((name, None)),
             }
         };
@@ -218,6 +224,7 @@ struct ReceivedAttrs {
     builder: Option<(Punctuated<syn::Expr, Token![,]>, TokenStream2)>,
     builder_fields: BTreeMap<syn::Ident, Option<syn::Expr>>,
     use_default: bool,
+    dbus: Option<Option<syn::LitStr>>,
 }
 
 impl Parse for ReceivedAttrs {
@@ -252,6 +259,7 @@ impl ReceivedAttrs {
             PropAttr::Default => {
                 self.use_default = true;
             }
+            PropAttr::DBus(name) => self.dbus = Some(name),
         }
     }
 }
@@ -274,6 +282,7 @@ struct PropDesc {
     builder_fields: BTreeMap<syn::Ident, Option<syn::Expr>>,
     is_construct_only: bool,
     use_default: bool,
+    dbus: Option<Option<syn::LitStr>>,
 }
 
 impl PropDesc {
@@ -296,6 +305,7 @@ impl PropDesc {
             builder,
             builder_fields,
             use_default,
+            dbus,
         } = attrs;
 
         let is_construct_only = builder_fields.iter().any(|(k, _)| *k == "construct_only");
@@ -344,6 +354,7 @@ impl PropDesc {
             builder_fields,
             is_construct_only,
             use_default,
+            dbus,
         })
     }
     fn is_overriding(&self) -> bool {
@@ -799,6 +810,32 @@ fn expand_properties_enum(props: &[PropDesc]) -> TokenStream2 {
     }
 }
 
+fn expand_dbus_properties(wrapper_type: &syn::Path, props: &[PropDesc]) -> TokenStream2 {
+    let dbus_props = props
+        .iter()
+        .filter_map(|p| {
+            let name = strip_raw_prefix_from_name(&p.name);
+            let dbus_name =
+                p.dbus.as_ref()?.clone().unwrap_or_else(|| {
+                    LitStr::new(&name.value().to_upper_camel_case(), name.span())
+                });
+            Some(quote!((#name, #dbus_name)))
+        })
+        .collect::<Vec<_>>();
+    if dbus_props.is_empty() {
+        return quote!();
+    }
+
+    let gio = gio_crate_ident_new();
+    quote! {
+        impl #gio::DBusExportedProperties for #wrapper_type {
+            fn dbus_properties() -> &'static [(&'static str, &'static str)] {
+                &[#(#dbus_props,)*]
+            }
+        }
+    }
+}
+
 pub fn impl_derive_props(input: PropsMacroInput) -> TokenStream {
     let struct_ident = &input.ident;
     let crate_ident = crate_ident_new();
@@ -810,6 +847,7 @@ pub fn impl_derive_props(input: PropsMacroInput) -> TokenStream {
     let connect_prop_notify = expand_impl_connect_prop_notify(&input.props);
     let notify_prop = expand_impl_notify_prop(&wrapper_type, &input.props);
     let properties_enum = expand_properties_enum(&input.props);
+    let dbus_properties = expand_dbus_properties(&wrapper_type, &input.props);
 
     let rust_interface = if let Some(ext_trait) = input.ext_trait {
         let trait_ident = if let Some(ext_trait) = ext_trait {
@@ -855,6 +893,8 @@ pub fn impl_derive_props(input: PropsMacroInput) -> TokenStream {
         }
 
         #rust_interface
+
+        #dbus_properties
     };
     proc_macro::TokenStream::from(expanded)
 }
@@ -220,6 +220,24 @@ pub fn crate_ident_new() -> TokenStream {
     quote!(#glib)
 }
 
+#[cfg(feature = "proc_macro_crate")]
+pub fn gio_crate_ident_new() -> TokenStream {
+    use proc_macro_crate::{FoundCrate, crate_name};
+
+    let gio = match crate_name("gio") {
+        Ok(FoundCrate::Name(name)) => name,
+        _ => "gio".to_string(),
+    };
+    let gio = Ident::new(&gio, Span::call_site());
+    quote!(#gio)
+}
+
+#[cfg(not(feature = "proc_macro_crate"))]
+pub fn gio_crate_ident_new() -> TokenStream {
+    let gio = Ident::new("gio", Span::call_site());
+    quote!(#gio)
+}
+
 // Generate i32 to enum mapping, used to implement
 // glib::translate::TryFromGlib<i32>, such as:
 //
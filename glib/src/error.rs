@@ -179,6 +179,25 @@ pub trait ErrorDomain: Copy {
     fn from(code: i32) -> Option<Self>
     where
         Self: Sized;
+
+    // rustdoc-stripper-ignore-next
+    /// Returns the D-Bus error name of the variant, if the error domain has D-Bus error names.
+    fn dbus_error_name(self) -> Option<&'static str> {
+        None
+    }
+
+    // rustdoc-stripper-ignore-next
+    /// Tries to convert a D-Bus error name to an enum variant.
+    ///
+    /// Errors received before the error domain was first used are not mapped to it yet, see
+    /// `gio::prelude::DBusErrorExt::dbus_kind()` to match these as well.
+    fn from_dbus_error_name(name: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let _ = name;
+        None
+    }
 }
 
 // rustdoc-stripper-ignore-next